@@ -0,0 +1,78 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_ALTERNATIVE_AFTER_HELP: &str = "\
+Examples:
+  wright alternative list
+  wright alternative list vi
+  wright alternative pin vi vim
+  wright alternative unpin vi";
+
+#[derive(Args)]
+#[command(
+    long_about = "List alternatives and pin which installed part provides them.\n\n\
+                  Outputs declare the names they can provide with \
+                  [[output.alternatives]]. While a candidate is installed, the \
+                  alternative's link points at the target of the pinned part, or \
+                  else of the candidate with the highest priority. Links follow \
+                  installs, upgrades, and removals automatically.",
+    after_help = WRIGHT_ALTERNATIVE_AFTER_HELP
+)]
+pub struct AlternativeArgs {
+    #[command(subcommand)]
+    pub command: AlternativeCommand,
+
+    /// Alternate root directory for file operations
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum AlternativeCommand {
+    /// List alternatives with their candidates
+    List {
+        /// Alternative to list (default: every alternative)
+        name: Option<String>,
+    },
+
+    /// Select a part for an alternative, regardless of priority
+    Pin {
+        /// Alternative name (e.g. vi)
+        name: String,
+        /// Installed candidate part to select
+        part: String,
+    },
+
+    /// Let priority select the part for an alternative again
+    Unpin {
+        /// Alternative name
+        name: String,
+    },
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: AlternativeArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::alternatives;
+
+    match args.command {
+        AlternativeCommand::List { name } => {
+            let db = ctx.open_db().await?;
+            alternatives::execute_list_alternatives(&db, name.as_deref(), ctx.format).await
+        }
+        AlternativeCommand::Pin { name, part } => {
+            let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let db = ctx.open_db().await?;
+            alternatives::execute_pin_alternative(&db, &ctx.root_dir, &name, Some(&part)).await
+        }
+        AlternativeCommand::Unpin { name } => {
+            let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let db = ctx.open_db().await?;
+            alternatives::execute_pin_alternative(&db, &ctx.root_dir, &name, None).await
+        }
+    }
+}
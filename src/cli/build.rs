@@ -11,6 +11,9 @@ Examples:
   wright build zlib --force --clean
   wright build freetype --mvp --stage=configure
   wright build freetype --until-stage=staging
+  wright build freetype --shell=configure
+  wright build freetype --shell-on-failure
+  SOURCE_DATE_EPOCH=1700000000 wright build zlib --verify-reproducible
   wright resolve openssl --rdeps | wright build
   echo -e 'curl\nwget' | wright build --force
 
@@ -72,6 +75,44 @@ pub struct BuildArgs {
     #[arg(long, conflicts_with_all = ["fetch", "checksum", "until_stage"])]
     pub seal: bool,
 
+    /// Forge each target twice in fresh, differently named build roots and
+    /// compare the output trees file by file. Differing paths are listed
+    /// with a binary diff summary and both build roots are kept; exits
+    /// non-zero when any path differs.
+    #[arg(
+        long,
+        conflicts_with_all = ["stage", "until_stage", "fetch", "checksum", "seal", "mvp"]
+    )]
+    pub verify_reproducible: bool,
+
+    /// When a pipeline stage fails, open an interactive shell in that
+    /// stage's isolation — same layers, mounts, and environment — before
+    /// its work is discarded. Plans are forged one at a time.
+    #[arg(
+        long,
+        conflicts_with_all = ["fetch", "checksum", "verify_reproducible"]
+    )]
+    pub shell_on_failure: bool,
+
+    /// Forge the stages before STAGE, then open an interactive shell in
+    /// STAGE's isolation instead of running it. Takes a single target
+    /// whose dependencies are already deployed; the forge stops when the
+    /// shell exits.
+    #[arg(
+        long,
+        value_name = "STAGE",
+        conflicts_with_all = [
+            "stage",
+            "until_stage",
+            "fetch",
+            "checksum",
+            "seal",
+            "verify_reproducible",
+            "shell_on_failure"
+        ]
+    )]
+    pub shell: Option<String>,
+
     /// Compute and update SHA256 checksums in plan.toml
     #[arg(long, conflicts_with = "fetch")]
     pub checksum: bool,
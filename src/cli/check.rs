@@ -66,6 +66,7 @@ pub async fn run(args: CheckArgs, ctx: &Context<'_>) -> Result<()> {
         args.deep,
         args.integrity_only,
         args.check_files,
+        ctx.format,
     )
     .await
 }
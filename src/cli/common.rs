@@ -24,6 +24,27 @@ pub enum DomainArg {
     All,
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum FormatArg {
+    /// Human-readable text.
+    Human,
+    /// One JSON document with a `schema_version` field.
+    Json,
+    /// Tab-separated rows after a `# schema_version=N` line and a header.
+    Tsv,
+}
+
+#[cfg(with_handlers)]
+impl From<FormatArg> for crate::util::output::OutputFormat {
+    fn from(arg: FormatArg) -> Self {
+        match arg {
+            FormatArg::Human => Self::Human,
+            FormatArg::Json => Self::Json,
+            FormatArg::Tsv => Self::Tsv,
+        }
+    }
+}
+
 // The items below reference crate::operations / util / resolve / delivery and
 // are only visible when the main crate is compiled. build.rs `#[path]`-includes
 // this file but does NOT see the `with_handlers` cfg (only the main crate
@@ -50,6 +71,7 @@ pub struct Context<'a> {
     pub root_dir: PathBuf,
     pub verbose: u8,
     pub quiet: bool,
+    pub format: crate::util::output::OutputFormat,
 }
 
 #[cfg(with_handlers)]
@@ -70,6 +92,17 @@ impl<'a> Context<'a> {
         let part_store = crate::resolve::setup_part_store(self.config)?;
         Ok((part_store, lock))
     }
+
+    /// Take the lock `wright build` holds, so cache cleanup never races a
+    /// standalone build (which does not take the `wright` command lock).
+    pub fn ensure_build_lock(&self) -> Result<ProcessLock> {
+        crate::util::lock::acquire_lock(
+            &crate::util::lock::lock_dir_from_db(&self.db_path),
+            crate::util::lock::LockIdentity::Command("build"),
+            crate::util::lock::LockMode::Exclusive,
+        )
+        .map_err(|e| WrightError::LockError(format!("failed to acquire build command lock: {}", e)))
+    }
 }
 
 #[cfg(with_handlers)]
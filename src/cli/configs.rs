@@ -0,0 +1,120 @@
+use clap::{ArgGroup, Args, Subcommand};
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::{Result, WrightError};
+
+const WRIGHT_CONFIG_AFTER_HELP: &str = "\
+Examples:
+  wright config list
+  wright config list --modified
+  wright config diff /etc/nginx/nginx.conf
+  wright config resolve --merge /etc/nginx/nginx.conf
+  wright config resolve --use-new /etc/ssh/sshd_config
+  wright config resolve --keep --all
+
+A merge with conflicts leaves the local file untouched and writes the
+marked-up result to <path>.wright-merge.";
+
+#[derive(Args)]
+#[command(
+    long_about = "List, review, and resolve pending config updates.\n\n\
+                  When an upgrade ships a new version of a [backup] file that was \
+                  edited locally, the live file is left alone and the packaged \
+                  version is installed beside it as <path>.wright-new. Each such \
+                  update stays pending until it is resolved: keep the local file, \
+                  take the new one, or three-way merge the local edits into it.",
+    after_help = WRIGHT_CONFIG_AFTER_HELP
+)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+
+    /// Alternate root directory for file operations
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// List pending config updates
+    List {
+        /// Also list config files that differ from their packaged version
+        #[arg(long)]
+        modified: bool,
+    },
+
+    /// Show how pending updates differ from the local files
+    Diff {
+        /// Config paths (default: every pending update)
+        paths: Vec<String>,
+    },
+
+    /// Settle pending updates
+    #[command(group(
+        ArgGroup::new("resolution")
+            .required(true)
+            .args(["keep", "use_new", "merge"])
+    ))]
+    Resolve {
+        /// Config paths to resolve
+        paths: Vec<String>,
+
+        /// Resolve every pending update
+        #[arg(long, conflicts_with = "paths")]
+        all: bool,
+
+        /// Keep the local file and discard the update
+        #[arg(long)]
+        keep: bool,
+
+        /// Replace the local file with the update
+        #[arg(long)]
+        use_new: bool,
+
+        /// Merge the local edits into the update
+        #[arg(long)]
+        merge: bool,
+    },
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: ConfigArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::configs::{self, Resolution};
+
+    match args.command {
+        ConfigCommand::List { modified } => {
+            let db = ctx.open_db().await?;
+            configs::execute_list_configs(&db, &ctx.root_dir, modified, ctx.format).await
+        }
+        ConfigCommand::Diff { paths } => {
+            let db = ctx.open_db().await?;
+            configs::execute_diff_configs(&db, &ctx.root_dir, &paths).await
+        }
+        ConfigCommand::Resolve {
+            paths,
+            all,
+            keep,
+            use_new,
+            merge: _,
+        } => {
+            if paths.is_empty() && !all {
+                return Err(WrightError::ValidationError(
+                    "no config paths specified (pass paths or --all)".into(),
+                ));
+            }
+            let resolution = if keep {
+                Resolution::Keep
+            } else if use_new {
+                Resolution::UseNew
+            } else {
+                Resolution::Merge
+            };
+            let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let db = ctx.open_db().await?;
+            configs::execute_resolve_configs(&db, &ctx.root_dir, &paths, resolution).await
+        }
+    }
+}
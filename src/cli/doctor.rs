@@ -29,5 +29,5 @@ pub struct DoctorArgs {
 #[cfg(with_handlers)]
 pub async fn run(_args: DoctorArgs, ctx: &Context<'_>) -> Result<()> {
     let db = ctx.open_db().await?;
-    crate::operations::doctor::execute_doctor(&db, &ctx.root_dir, ctx.config).await
+    crate::operations::doctor::execute_doctor(&db, &ctx.root_dir, ctx.config, ctx.format).await
 }
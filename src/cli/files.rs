@@ -23,5 +23,5 @@ pub struct FilesArgs {
 #[cfg(with_handlers)]
 pub async fn run(args: FilesArgs, ctx: &Context<'_>) -> Result<()> {
     let db = ctx.open_db().await?;
-    crate::operations::files::execute_files(&db, &args.part).await
+    crate::operations::files::execute_files(&db, &args.part, ctx.format).await
 }
@@ -0,0 +1,120 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_GENERATION_AFTER_HELP: &str = "\
+Examples:
+  wright generation snapshot pre-maintenance
+  wright generation list
+  wright generation diff pre-maintenance
+  wright generation diff pre-maintenance post-maintenance
+  wright generation switch pre-maintenance --dry-run
+  wright generation switch pre-maintenance
+  wright generation delete pre-maintenance";
+
+#[derive(Args)]
+#[command(
+    long_about = "Tag, compare, and restore named generations of the installed registry.\n\n\
+                  A generation records every deployed part's name, archive hash, and \
+                  origin. Switching to a generation computes the minimal change set \
+                  against the current registry: parts whose hash differs are redeployed \
+                  from the matching archive (found by hash in the CAS store and \
+                  parts_dir), parts the generation lacks are removed, and recorded \
+                  origins are restored. The switch runs as one delivery transaction, \
+                  so `wright rollback` can undo it.",
+    after_help = WRIGHT_GENERATION_AFTER_HELP
+)]
+pub struct GenerationArgs {
+    #[command(subcommand)]
+    pub command: GenerationCommand,
+
+    /// Alternate root directory for file operations
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum GenerationCommand {
+    /// Record the installed registry as a named generation
+    Snapshot {
+        /// Generation name
+        name: String,
+    },
+
+    /// List recorded generations
+    List,
+
+    /// Show parts added, removed, or changed between two generations
+    Diff {
+        /// Generation to compare from
+        from: String,
+        /// Generation to compare to (default: the installed registry)
+        to: Option<String>,
+    },
+
+    /// Bring the installed registry back to a generation
+    Switch {
+        /// Generation name
+        name: String,
+
+        /// Show what would be redeployed and removed without changing anything
+        #[arg(long, short = 'n')]
+        dry_run: bool,
+
+        /// Remove parts even if others outside the generation depend on them
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Delete a recorded generation
+    Delete {
+        /// Generation name
+        name: String,
+    },
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: GenerationArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::generation;
+
+    match args.command {
+        GenerationCommand::Snapshot { name } => {
+            let db = ctx.open_db().await?;
+            generation::execute_snapshot(&db, &name).await
+        }
+        GenerationCommand::List => {
+            let db = ctx.open_db().await?;
+            generation::execute_list_generations(&db, ctx.format).await
+        }
+        GenerationCommand::Diff { from, to } => {
+            let db = ctx.open_db().await?;
+            generation::execute_diff(&db, &from, to.as_deref(), ctx.format).await
+        }
+        GenerationCommand::Switch {
+            name,
+            dry_run,
+            force,
+        } => {
+            let (part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let db = ctx.open_db().await?;
+            generation::execute_switch(
+                &db,
+                ctx.config,
+                &ctx.root_dir,
+                &part_store,
+                &name,
+                dry_run,
+                force,
+            )
+            .await
+        }
+        GenerationCommand::Delete { name } => {
+            let db = ctx.open_db().await?;
+            generation::execute_delete(&db, &name).await
+        }
+    }
+}
@@ -24,5 +24,5 @@ pub struct HistoryArgs {
 #[cfg(with_handlers)]
 pub async fn run(args: HistoryArgs, ctx: &Context<'_>) -> Result<()> {
     let db = ctx.open_db().await?;
-    crate::operations::history::execute_history(&db, args.part.as_deref()).await
+    crate::operations::history::execute_history(&db, args.part.as_deref(), ctx.format).await
 }
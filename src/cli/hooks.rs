@@ -0,0 +1,78 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::{Result, WrightError};
+
+const WRIGHT_HOOK_AFTER_HELP: &str = "\
+Examples:
+  wright hook list
+  wright hook list man-db
+  wright hook run man-db
+  wright hook run fontconfig --hook post_install
+  wright hook run --all";
+
+#[derive(Args)]
+#[command(
+    long_about = "List and re-run deploy hooks that failed.\n\n\
+                  A hook whose on_failure policy is \"warn\" (the default) or \
+                  \"defer\" does not fail its operation; its failure is recorded \
+                  instead. Deferred hooks run once more at the end of their \
+                  delivery. Both stay recorded until a run of the hook succeeds.",
+    after_help = WRIGHT_HOOK_AFTER_HELP
+)]
+pub struct HookArgs {
+    #[command(subcommand)]
+    pub command: HookCommand,
+
+    /// Alternate root directory for file operations
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum HookCommand {
+    /// List failed and deferred hooks
+    List {
+        /// Parts to list (default: every part)
+        parts: Vec<String>,
+    },
+
+    /// Re-run failed and deferred hooks
+    Run {
+        /// Parts whose hooks to re-run
+        parts: Vec<String>,
+
+        /// Re-run the hooks of every part
+        #[arg(long, conflicts_with = "parts")]
+        all: bool,
+
+        /// Only re-run this hook (e.g. post_install)
+        #[arg(long)]
+        hook: Option<String>,
+    },
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: HookArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::hooks;
+
+    match args.command {
+        HookCommand::List { parts } => {
+            let db = ctx.open_db().await?;
+            hooks::execute_list_hooks(&db, &parts, ctx.format).await
+        }
+        HookCommand::Run { parts, all, hook } => {
+            if parts.is_empty() && !all {
+                return Err(WrightError::ValidationError(
+                    "no parts specified (pass part names or --all)".into(),
+                ));
+            }
+            let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let db = ctx.open_db().await?;
+            hooks::execute_run_hooks(&db, &ctx.root_dir, &parts, hook.as_deref()).await
+        }
+    }
+}
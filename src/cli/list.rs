@@ -39,6 +39,13 @@ pub struct ListArgs {
 #[cfg(with_handlers)]
 pub async fn run(args: ListArgs, ctx: &Context<'_>) -> Result<()> {
     let db = ctx.open_db().await?;
-    crate::operations::list::execute_list(&db, args.long, args.roots, args.provided, args.orphans)
-        .await
+    crate::operations::list::execute_list(
+        &db,
+        args.long,
+        args.roots,
+        args.provided,
+        args.orphans,
+        ctx.format,
+    )
+    .await
 }
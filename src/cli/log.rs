@@ -0,0 +1,62 @@
+use clap::Args;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_LOG_AFTER_HELP: &str = "\
+Examples:
+  wright log
+  wright log zlib
+  wright log zlib compile
+  wright log zlib compile --build 1
+  wright log zlib compile --tail 50
+  wright log zlib compile --follow
+  wright log --grep 'undefined reference'
+  wright log zlib --grep 'error:'";
+
+#[derive(Args)]
+#[command(
+    long_about = "Browse, tail, and search the logs of past builds.\n\n\
+                  Every forge stage writes its script, stdout, stderr, exit code, and \
+                  duration to a log in the plan's build root. Logs of the latest build \
+                  are kept in logs/, and those of the previous build.log_retention \
+                  builds (default 5) in log-history/.\n\n\
+                  Without arguments, the latest build of every build root is listed. \
+                  With a PLAN, its retained builds are listed, newest (#0) first, with \
+                  when each ran, how it ended, how long it took, and how each stage \
+                  ended. With a PLAN and STAGE, that stage's log is printed. --grep \
+                  searches the stage logs of every retained build instead.",
+    after_help = WRIGHT_LOG_AFTER_HELP
+)]
+pub struct LogArgs {
+    /// Plan name or path to a plan directory (default: every build root)
+    #[arg(value_name = "PLAN")]
+    pub plan: Option<String>,
+
+    /// Stage whose log to print, e.g. `compile` or `pre_configure`
+    #[arg(value_name = "STAGE", requires = "plan")]
+    pub stage: Option<String>,
+
+    /// Build to show: 0 is the latest, 1 the one before, and so on
+    #[arg(long, value_name = "N")]
+    pub build: Option<usize>,
+
+    /// Print only the last N lines of the stage log
+    #[arg(long, short = 'n', value_name = "N", requires = "stage")]
+    pub tail: Option<usize>,
+
+    /// Keep printing the stage log as it grows, until the build finishes
+    #[arg(long, short = 'f', requires = "stage", conflicts_with = "build")]
+    pub follow: bool,
+
+    /// Print the stage-log lines matching a regular expression
+    #[arg(long, value_name = "PATTERN", conflicts_with_all = ["tail", "follow"])]
+    pub grep: Option<String>,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: LogArgs, ctx: &Context<'_>) -> Result<()> {
+    crate::operations::log::execute_log(ctx.config, args, ctx.format).await
+}
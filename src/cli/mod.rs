@@ -1,18 +1,31 @@
+pub mod alternatives;
 pub mod build;
 pub mod check;
 pub mod common;
+pub mod configs;
 pub mod doctor;
 pub mod files;
+pub mod generation;
 pub mod history;
+pub mod hooks;
 pub mod install;
 pub mod launch;
 pub mod lint;
 pub mod list;
+pub mod log;
 pub mod merge;
 pub mod owner;
 pub mod provide;
+pub mod prune;
 pub mod remove;
+pub mod repair;
+pub mod rollback;
+pub mod sources;
+pub mod stats;
+pub mod tree;
 pub mod upgrade;
+pub mod verify;
+pub mod workshop;
 
 use clap::{ArgAction, Parser, Subcommand};
 use std::path::PathBuf;
@@ -21,6 +34,8 @@ use std::path::PathBuf;
 use crate::config::GlobalConfig;
 #[cfg(with_handlers)]
 use crate::error::Result;
+#[cfg(with_handlers)]
+use crate::util::output::OutputFormat;
 
 #[cfg(with_handlers)]
 use self::common::{Context, crash_recover, resolve_db};
@@ -53,6 +68,18 @@ pub struct Cli {
     /// Reduce log output (show warnings/errors only)
     #[arg(long, global = true, help_heading = "Global Options")]
     pub quiet: bool,
+
+    /// Output format for query commands (list, files, owner, history, check, doctor, verify,
+    /// generation list/diff, config list, hook list, alternative list, sources list,
+    /// workshop list, log, stats)
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value = "human",
+        help_heading = "Global Options"
+    )]
+    pub format: common::FormatArg,
 }
 
 #[derive(Subcommand)]
@@ -78,31 +105,63 @@ pub enum Commands {
     #[command(display_order = 5)]
     Provide(provide::ProvideArgs),
 
+    /// Reverse a completed delivery transaction
+    #[command(display_order = 6)]
+    Rollback(rollback::RollbackArgs),
+
+    /// Snapshot, compare, and switch between named generations of the registry
+    #[command(display_order = 7)]
+    Generation(generation::GenerationArgs),
+
+    /// List, review, and resolve pending config updates
+    #[command(display_order = 8)]
+    Config(configs::ConfigArgs),
+
+    /// Restore damaged files of deployed parts from their archives
+    #[command(display_order = 9)]
+    Repair(repair::RepairArgs),
+
+    /// List and re-run failed and deferred deploy hooks
+    #[command(display_order = 10)]
+    Hook(hooks::HookArgs),
+
+    /// List alternatives and pin which installed part provides them
+    #[command(display_order = 11, visible_alias = "alt")]
+    Alternative(alternatives::AlternativeArgs),
+
     // ── Query & Inspection ─────────────────────────────────────────
     /// List deployed parts
-    #[command(display_order = 11)]
+    #[command(display_order = 12)]
     List(list::ListArgs),
 
     /// List files owned by a part
-    #[command(display_order = 12)]
+    #[command(display_order = 13)]
     Files(files::FilesArgs),
 
     /// Show which part owns the given file(s)
-    #[command(display_order = 13)]
+    #[command(display_order = 14)]
     Owner(owner::OwnerArgs),
 
     /// Perform system health checks
-    #[command(display_order = 14)]
+    #[command(display_order = 15)]
     Check(check::CheckArgs),
 
     /// Show part transaction history (deploy, upgrade, remove)
-    #[command(display_order = 15)]
+    #[command(display_order = 16)]
     History(history::HistoryArgs),
 
     /// Diagnose system and archive health issues
-    #[command(display_order = 16)]
+    #[command(display_order = 17)]
     Doctor(doctor::DoctorArgs),
 
+    /// Render forward, reverse, or whole-system dependency trees
+    #[command(display_order = 18, visible_alias = "deps")]
+    Tree(tree::TreeArgs),
+
+    /// Audit deployed files and restore damaged ones from their archives
+    #[command(display_order = 19)]
+    Verify(verify::VerifyArgs),
+
     // ── Build & Packaging ──────────────────────────────────────────
     /// Forge parts from plans
     #[command(display_order = 21)]
@@ -115,6 +174,27 @@ pub enum Commands {
     /// Fill a target root from a folio manifest or from plans
     #[command(display_order = 23)]
     Launch(launch::LaunchArgs),
+
+    /// Browse, tail, and search the logs of past builds
+    #[command(display_order = 24)]
+    Log(log::LogArgs),
+
+    /// Show build times and resource usage of past builds
+    #[command(display_order = 25)]
+    Stats(stats::StatsArgs),
+
+    // ── Cache & Maintenance ────────────────────────────────────────
+    /// Delete unreferenced archives from parts_dir and the CAS store
+    #[command(display_order = 31)]
+    Prune(prune::PruneArgs),
+
+    /// Inspect, verify, and clean the source cache
+    #[command(display_order = 32)]
+    Sources(sources::SourcesArgs),
+
+    /// Inspect and clean build roots in the forge workshop
+    #[command(display_order = 33)]
+    Workshop(workshop::WorkshopArgs),
 }
 
 /// Build a Context for a command that has a `--root` option.
@@ -127,6 +207,7 @@ async fn ctx_with_root<'a>(
     config: &'a GlobalConfig,
     verbose: u8,
     quiet: bool,
+    format: OutputFormat,
 ) -> Context<'a> {
     let root_dir = root.unwrap_or_else(|| PathBuf::from("/"));
     let db_path = resolve_db(Some(&root_dir), top_db, config);
@@ -137,6 +218,7 @@ async fn ctx_with_root<'a>(
         root_dir,
         verbose,
         quiet,
+        format,
     }
 }
 
@@ -147,6 +229,7 @@ async fn ctx_default<'a>(
     config: &'a GlobalConfig,
     verbose: u8,
     quiet: bool,
+    format: OutputFormat,
 ) -> Context<'a> {
     let db_path = top_db.unwrap_or_else(|| config.general.db_path.clone());
     crash_recover(&db_path).await;
@@ -156,6 +239,7 @@ async fn ctx_default<'a>(
         root_dir: PathBuf::from("/"),
         verbose,
         quiet,
+        format,
     }
 }
 
@@ -165,65 +249,120 @@ pub async fn dispatch(cli: Cli, config: &GlobalConfig) -> Result<()> {
     let top_db = cli.db.clone();
     let verbose = cli.verbose;
     let quiet = cli.quiet;
+    let format = cli.format.into();
 
     match cli.command {
         // ── System Management ──────────────────────────────────────
         Commands::Merge(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             merge::run(args, &ctx).await
         }
         Commands::Install(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             install::run(args, &ctx).await
         }
         Commands::Upgrade(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             upgrade::run(args, &ctx).await
         }
         Commands::Remove(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             remove::run(args, &ctx).await
         }
         Commands::Provide(args) => {
-            let ctx = ctx_default(top_db, config, verbose, quiet).await;
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
             provide::run(args, &ctx).await
         }
+        Commands::Rollback(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            rollback::run(args, &ctx).await
+        }
+        Commands::Generation(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            generation::run(args, &ctx).await
+        }
+        Commands::Config(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            configs::run(args, &ctx).await
+        }
+        Commands::Repair(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            repair::run(args, &ctx).await
+        }
+        Commands::Hook(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            hooks::run(args, &ctx).await
+        }
+        Commands::Alternative(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            alternatives::run(args, &ctx).await
+        }
 
         // ── Query & Inspection ─────────────────────────────────────
         Commands::List(args) => {
-            let ctx = ctx_default(top_db, config, verbose, quiet).await;
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
             list::run(args, &ctx).await
         }
         Commands::Files(args) => {
-            let ctx = ctx_default(top_db, config, verbose, quiet).await;
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
             files::run(args, &ctx).await
         }
         Commands::Owner(args) => {
-            let ctx = ctx_default(top_db, config, verbose, quiet).await;
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
             owner::run(args, &ctx).await
         }
         Commands::Check(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             check::run(args, &ctx).await
         }
         Commands::History(args) => {
-            let ctx = ctx_default(top_db, config, verbose, quiet).await;
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
             history::run(args, &ctx).await
         }
         Commands::Doctor(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             doctor::run(args, &ctx).await
         }
+        Commands::Tree(args) => {
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
+            tree::run(args, &ctx).await
+        }
+        Commands::Verify(mut args) => {
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
+            verify::run(args, &ctx).await
+        }
 
         // ── Build & Packaging ──────────────────────────────────────
         Commands::Build(args) => {
-            let ctx = ctx_default(top_db, config, verbose, quiet).await;
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
             build::run(args, &ctx).await
         }
         Commands::Lint(args) => lint::run(args, config).await,
         Commands::Launch(mut args) => {
-            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet).await;
+            let ctx = ctx_with_root(args.root.take(), top_db, config, verbose, quiet, format).await;
             launch::run(args, &ctx).await
         }
+        Commands::Log(args) => {
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
+            log::run(args, &ctx).await
+        }
+        Commands::Stats(args) => {
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
+            stats::run(args, &ctx).await
+        }
+
+        // ── Cache & Maintenance ────────────────────────────────────
+        Commands::Prune(args) => {
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
+            prune::run(args, &ctx).await
+        }
+        Commands::Sources(args) => {
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
+            sources::run(args, &ctx).await
+        }
+        Commands::Workshop(args) => {
+            let ctx = ctx_default(top_db, config, verbose, quiet, format).await;
+            workshop::run(args, &ctx).await
+        }
     }
 }
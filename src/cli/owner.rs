@@ -27,5 +27,5 @@ pub struct OwnerArgs {
 #[cfg(with_handlers)]
 pub async fn run(args: OwnerArgs, ctx: &Context<'_>) -> Result<()> {
     let db = ctx.open_db().await?;
-    crate::operations::owner::execute_owner(&db, &args.paths).await
+    crate::operations::owner::execute_owner(&db, &args.paths, ctx.format).await
 }
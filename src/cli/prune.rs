@@ -0,0 +1,99 @@
+use clap::Args;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_PRUNE_AFTER_HELP: &str = "\
+Examples:
+  wright prune
+  wright prune --latest --apply
+  wright prune --keep-latest 3 --max-age 30d --apply
+  wright prune --max-size 50G --apply
+
+Without --apply, prune only reports what it would delete and how many
+bytes that reclaims. Defaults come from the [prune] section of wright.toml.";
+
+#[derive(Args)]
+#[command(
+    long_about = "Delete archives from parts_dir and the CAS store that no retention rule keeps.\n\n\
+                  Always kept: archives of deployed parts, archives recorded in a \
+                  generation, and archives touched by the most recent delivery \
+                  transactions (so `wright rollback` keeps working), plus the newest \
+                  --keep-latest revisions of each plan. Everything else is prunable; \
+                  --max-age limits pruning to older archives, and --max-size evicts \
+                  the oldest prunable archives until the inventory fits.\n\n\
+                  Records of finished delivery transactions beyond the newest \
+                  --keep-delivery-records are dropped too; those deliveries can no \
+                  longer be rolled back.",
+    after_help = WRIGHT_PRUNE_AFTER_HELP
+)]
+pub struct PruneArgs {
+    /// Keep only the newest revision of each plan (same as --keep-latest 1)
+    #[arg(long, conflicts_with = "keep_latest")]
+    pub latest: bool,
+
+    /// Number of newest revisions kept per plan (0 keeps none beyond references)
+    #[arg(long, value_name = "N")]
+    pub keep_latest: Option<usize>,
+
+    /// Keep archives touched by the N most recent delivery transactions
+    #[arg(long, value_name = "N")]
+    pub keep_deliveries: Option<i64>,
+
+    /// Keep the records of the N most recent delivery transactions (0 keeps all)
+    #[arg(long, value_name = "N")]
+    pub keep_delivery_records: Option<i64>,
+
+    /// Only prune archives older than AGE (e.g. 30d, 12h, 2w)
+    #[arg(long, value_name = "AGE")]
+    pub max_age: Option<String>,
+
+    /// Evict the oldest prunable archives until the inventory fits in SIZE (e.g. 50G)
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<String>,
+
+    /// Delete the prunable archives instead of reporting them
+    #[arg(long)]
+    pub apply: bool,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: PruneArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::prune::PrunePolicy;
+    use crate::util::units::{parse_age, parse_size};
+
+    let defaults = &ctx.config.prune;
+    let keep_latest = if args.latest {
+        1
+    } else {
+        args.keep_latest.unwrap_or(defaults.keep_latest)
+    };
+    let policy = PrunePolicy {
+        keep_latest,
+        keep_deliveries: args.keep_deliveries.unwrap_or(defaults.keep_deliveries),
+        keep_delivery_records: args
+            .keep_delivery_records
+            .unwrap_or(defaults.keep_delivery_records),
+        max_age: args
+            .max_age
+            .as_deref()
+            .or(defaults.max_age.as_deref())
+            .map(parse_age)
+            .transpose()?,
+        max_size: args
+            .max_size
+            .as_deref()
+            .or(defaults.max_size.as_deref())
+            .map(parse_size)
+            .transpose()?,
+    };
+
+    // Builds seal into parts_dir and deliveries deploy from it; hold both
+    // command locks so an archive is never deleted while in use.
+    let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+    let _build_lock = ctx.ensure_build_lock()?;
+    let db = ctx.open_db().await?;
+    crate::operations::prune::execute_prune(&db, ctx.config, &policy, args.apply).await
+}
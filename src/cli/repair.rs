@@ -0,0 +1,53 @@
+use clap::Args;
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_REPAIR_AFTER_HELP: &str = "\
+Examples:
+  wright repair
+  wright repair llvm gcc
+  wright repair --dry-run";
+
+#[derive(Args)]
+#[command(
+    long_about = "Restore damaged files of deployed parts from their archives.\n\n\
+                  Each part is audited as `wright verify` does, and only the files whose \
+                  content, size, mode, owner, or symlink target no longer match the \
+                  registry are re-extracted from the archive the part was deployed from \
+                  (found by hash in the CAS store or parts_dir). Hooks do not run. The \
+                  repair is one delivery transaction, and each part's overwrites are \
+                  journaled, so a failure restores what was on disk before.\n\n\
+                  Extra files and local edits to [backup] config files are left alone.",
+    after_help = WRIGHT_REPAIR_AFTER_HELP
+)]
+pub struct RepairArgs {
+    /// Parts to repair (default: all deployed parts)
+    #[arg(value_name = "PART")]
+    pub parts: Vec<String>,
+
+    /// Show the files that would be restored without changing anything
+    #[arg(long, short = 'n')]
+    pub dry_run: bool,
+
+    /// Alternate root directory for file operations
+    #[arg(long)]
+    pub root: Option<PathBuf>,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: RepairArgs, ctx: &Context<'_>) -> Result<()> {
+    let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+    let db = ctx.open_db().await?;
+    crate::operations::repair::execute_repair(
+        &db,
+        ctx.config,
+        &ctx.root_dir,
+        &args.parts,
+        args.dry_run,
+    )
+    .await
+}
@@ -0,0 +1,71 @@
+use clap::Args;
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_ROLLBACK_AFTER_HELP: &str = "\
+Examples:
+  wright rollback --list
+  wright rollback 42 --dry-run
+  wright rollback 42";
+
+#[derive(Args)]
+#[command(
+    long_about = "Reverse a completed delivery transaction.\n\n\
+                  Every install, upgrade, merge, and remove runs as a delivery \
+                  transaction that records, per part, the archive hash it \
+                  replaced. Rolling back redeploys those previous archives \
+                  (located by hash in the CAS store and parts_dir) and removes \
+                  parts the delivery installed, all as one new delivery \
+                  transaction. If any step fails, the steps already applied \
+                  are reverted.\n\n\
+                  Rollback refuses when a later delivery changed the same \
+                  parts; use --force to roll back anyway.",
+    after_help = WRIGHT_ROLLBACK_AFTER_HELP
+)]
+pub struct RollbackArgs {
+    /// Delivery transaction id (see --list)
+    #[arg(value_name = "TX_ID", required_unless_present = "list")]
+    pub tx_id: Option<i64>,
+
+    /// List recent delivery transactions
+    #[arg(long, conflicts_with_all = ["dry_run", "force"])]
+    pub list: bool,
+
+    /// Show what would be redeployed and removed without changing anything
+    #[arg(long, short = 'n')]
+    pub dry_run: bool,
+
+    /// Roll back even if later deliveries changed the same parts, and
+    /// remove installed parts that others now depend on
+    #[arg(long)]
+    pub force: bool,
+
+    /// Alternate root directory for file operations
+    #[arg(long)]
+    pub root: Option<PathBuf>,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: RollbackArgs, ctx: &Context<'_>) -> Result<()> {
+    if args.list {
+        let db = ctx.open_db().await?;
+        return crate::operations::rollback::execute_list_deliveries(&db).await;
+    }
+    let tx_id = args.tx_id.expect("clap requires TX_ID without --list");
+    let (part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+    let db = ctx.open_db().await?;
+    crate::operations::rollback::execute_rollback(
+        &db,
+        ctx.config,
+        &ctx.root_dir,
+        &part_store,
+        tx_id,
+        args.dry_run,
+        args.force,
+    )
+    .await
+}
@@ -0,0 +1,75 @@
+use clap::{Args, Subcommand};
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_SOURCES_AFTER_HELP: &str = "\
+Examples:
+  wright sources list
+  wright sources list --orphans
+  wright sources verify
+  wright sources verify zlib openssl
+  wright sources clean
+  wright sources clean --apply
+
+Without --apply, clean only reports the orphans it would delete.";
+
+#[derive(Args)]
+#[command(
+    long_about = "Inspect, verify, and clean the source cache (general.source_dir).\n\n\
+                  Every cached download and git mirror is mapped back to the plans \
+                  whose [[sources]] resolve to it. Entries no current plan references \
+                  are orphans; `clean` removes them. `verify` re-hashes cached HTTP \
+                  downloads against the sha256 their plans declare.",
+    after_help = WRIGHT_SOURCES_AFTER_HELP
+)]
+pub struct SourcesArgs {
+    #[command(subcommand)]
+    pub command: SourcesCommand,
+}
+
+#[derive(Subcommand)]
+pub enum SourcesCommand {
+    /// List cached sources with their size and referencing plans
+    List {
+        /// Show only entries no plan references
+        #[arg(long)]
+        orphans: bool,
+    },
+
+    /// Re-check cached downloads against their declared sha256
+    Verify {
+        /// Plans to verify (default: all plans)
+        plans: Vec<String>,
+    },
+
+    /// Delete cache entries no plan references
+    Clean {
+        /// Delete the orphans instead of reporting them
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: SourcesArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::sources;
+
+    match args.command {
+        SourcesCommand::List { orphans } => {
+            sources::execute_list_sources(ctx.config, orphans, ctx.format).await
+        }
+        SourcesCommand::Verify { plans } => {
+            sources::execute_verify_sources(ctx.config, &plans).await
+        }
+        SourcesCommand::Clean { apply } => {
+            // Builds fetch into source_dir; hold both command locks so a
+            // download in progress is never mistaken for an orphan.
+            let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let _build_lock = ctx.ensure_build_lock()?;
+            sources::execute_clean_sources(ctx.config, apply).await
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use clap::Args;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_STATS_AFTER_HELP: &str = "\
+Examples:
+  wright stats
+  wright stats --limit 10
+  wright stats gcc
+  wright stats gcc --stages";
+
+#[derive(Args)]
+#[command(
+    long_about = "Show what forging plans cost.\n\n\
+                  Every build that forges a plan's whole pipeline records, per stage, \
+                  the wall time, CPU time, peak memory (RSS) of the largest process, \
+                  and the disk space the stage added. Builds that resume from \
+                  checkpoints or run selected stages are not recorded.\n\n\
+                  Without a PLAN, the latest build of every plan is listed, slowest \
+                  first. With a PLAN, each of its recorded builds is listed newest \
+                  first, with how its wall time changed from the build before.\n\n\
+                  wright build starts the longest forges of each dependency batch \
+                  first, using the mean wall time of each plan's latest builds.",
+    after_help = WRIGHT_STATS_AFTER_HELP
+)]
+pub struct StatsArgs {
+    /// Plan name; omit to list the slowest plans
+    #[arg(value_name = "PLAN")]
+    pub plan: Option<String>,
+
+    /// Show at most N builds
+    #[arg(long, short = 'n', value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Break each build down by stage
+    #[arg(long)]
+    pub stages: bool,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: StatsArgs, ctx: &Context<'_>) -> Result<()> {
+    let db = ctx.open_db().await?;
+    crate::operations::stats::execute_stats(&db, args, ctx.format).await
+}
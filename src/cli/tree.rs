@@ -0,0 +1,113 @@
+use clap::{Args, ValueEnum};
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+use crate::cli::common::DomainArg;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_TREE_AFTER_HELP: &str = "\
+Examples:
+  wright tree
+  wright tree gtk4
+  wright tree gtk4 --depth=2
+  wright tree zlib --reverse
+  wright tree gtk4 --plans --deps=link
+  wright deps openssl --reverse --prefix=none";
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum PrefixArg {
+    /// Tree-drawing characters
+    Indent,
+    /// Flat list with a depth number prefix
+    Depth,
+    /// Bare names, deduplicated, suitable for piping
+    None,
+}
+
+#[derive(Args)]
+#[command(
+    long_about = "Render dependency trees.\n\n\
+                  By default the tree is read from the installed registry, which \
+                  records runtime dependencies only (ADR-0016). With --plans, \
+                  the tree is built from plan sources instead, so build and \
+                  link edges can be followed as well. Omit PART to render every \
+                  root part (or, with --plans, every plan nothing depends on).\n\n\
+                  Use --reverse to answer \"why is this installed\": it walks \
+                  dependents instead of dependencies.",
+    after_help = WRIGHT_TREE_AFTER_HELP
+)]
+pub struct TreeArgs {
+    /// Part (or plan, with --plans) to root the tree at; omit for the whole system
+    #[arg(value_name = "PART")]
+    pub part: Option<String>,
+
+    /// Walk dependents instead of dependencies
+    #[arg(long, short)]
+    pub reverse: bool,
+
+    /// Maximum depth. `0` means unlimited.
+    #[arg(long, default_value_t = 0)]
+    pub depth: usize,
+
+    /// Dependency domain to follow. The installed registry only knows
+    /// `runtime`; `link` and `build` require --plans.
+    #[arg(long = "deps", short = 'd', value_enum)]
+    pub deps: Option<DomainArg>,
+
+    /// Build the tree from plan sources instead of the installed registry
+    #[arg(long)]
+    pub plans: bool,
+
+    /// Only show children whose name contains this substring
+    #[arg(long, value_name = "TEXT")]
+    pub filter: Option<String>,
+
+    /// Show these parts but do not expand their subtrees
+    #[arg(long, value_name = "PART")]
+    pub prune: Vec<String>,
+
+    /// Line prefix style
+    #[arg(long, value_enum, default_value = "indent")]
+    pub prefix: PrefixArg,
+
+    /// Print a summary line (node count, depth, gaps, cycles)
+    #[arg(long)]
+    pub stats: bool,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: TreeArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::query::PrefixMode;
+    use crate::resolve::DepDomain;
+
+    let domain = args.deps.map(|d| match d {
+        DomainArg::Link => DepDomain::LINK,
+        DomainArg::Runtime => DepDomain::RUNTIME,
+        DomainArg::Forge => DepDomain::BUILD,
+        DomainArg::All => DepDomain::ALL,
+    });
+    let prefix_mode = match args.prefix {
+        PrefixArg::Indent => PrefixMode::Indent,
+        PrefixArg::Depth => PrefixMode::Depth,
+        PrefixArg::None => PrefixMode::None,
+    };
+
+    let db = ctx.open_db().await?;
+    crate::operations::tree::execute_tree(
+        &db,
+        ctx.config,
+        crate::operations::tree::TreeRequest {
+            target: args.part.as_deref(),
+            reverse: args.reverse,
+            depth: args.depth,
+            domain,
+            plans: args.plans,
+            filter: args.filter.as_deref(),
+            prune: &args.prune,
+            prefix_mode,
+            stats: args.stats,
+        },
+    )
+    .await
+}
@@ -0,0 +1,61 @@
+use clap::Args;
+use std::path::PathBuf;
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_VERIFY_AFTER_HELP: &str = "\
+Examples:
+  wright verify
+  wright verify zlib openssl
+  wright verify --restore
+  wright --format json verify";
+
+#[derive(Args)]
+#[command(
+    long_about = "Audit deployed files against what was recorded when they were merged.\n\n\
+                  Every file of each part is checked for presence, type, size, content \
+                  hash, permission bits, owner, and symlink target. Unrecorded files in \
+                  directories only that part owns are reported as extra. Content changes \
+                  to [backup] config files are shown but not counted as damage; see \
+                  `wright config`.\n\n\
+                  With --restore, damaged files are put back from the archive the part \
+                  was deployed from (found by hash in the CAS store or parts_dir). The \
+                  report ends with the parts that still need a re-merge. Exit code is 1 \
+                  while any damage remains.",
+    after_help = WRIGHT_VERIFY_AFTER_HELP
+)]
+pub struct VerifyArgs {
+    /// Parts to audit (default: all deployed parts)
+    #[arg(value_name = "PART")]
+    pub parts: Vec<String>,
+
+    /// Restore damaged files from the part archive
+    #[arg(long)]
+    pub restore: bool,
+
+    /// Alternate root directory for file operations
+    #[arg(long)]
+    pub root: Option<PathBuf>,
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: VerifyArgs, ctx: &Context<'_>) -> Result<()> {
+    let _guard = if args.restore {
+        Some(ctx.ensure_lock_and_part_store()?)
+    } else {
+        None
+    };
+    let db = ctx.open_db().await?;
+    crate::operations::verify::execute_verify(
+        &db,
+        ctx.config,
+        &ctx.root_dir,
+        &args.parts,
+        args.restore,
+        ctx.format,
+    )
+    .await
+}
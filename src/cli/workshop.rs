@@ -0,0 +1,69 @@
+use clap::{Args, Subcommand};
+
+#[cfg(with_handlers)]
+use crate::cli::common::Context;
+#[cfg(with_handlers)]
+use crate::error::Result;
+
+const WRIGHT_WORKSHOP_AFTER_HELP: &str = "\
+Examples:
+  wright workshop list
+  wright workshop clean
+  wright workshop clean --apply
+  wright workshop clean --older-than 14d --apply
+
+Without --apply, clean only reports what it would remove.";
+
+#[derive(Args)]
+#[command(
+    long_about = "Inspect and clean the forge workshop (build.forge_dir).\n\n\
+                  Each plan builds in its own root under the workshop, holding the \
+                  extracted sources, per-stage OverlayFS layers, logs, and the stage \
+                  checkpoint that lets a later build resume. Roots survive crashes and \
+                  version bumps. `list` shows each root's plan, checkpoint progress, \
+                  last use, and disk usage; `clean` removes roots whose plan no longer \
+                  exists or has moved to another version, and detaches stale overlay \
+                  mounts left by interrupted builds.",
+    after_help = WRIGHT_WORKSHOP_AFTER_HELP
+)]
+pub struct WorkshopArgs {
+    #[command(subcommand)]
+    pub command: WorkshopCommand,
+}
+
+#[derive(Subcommand)]
+pub enum WorkshopCommand {
+    /// List build roots with their plan, checkpoint state, and disk usage
+    List,
+
+    /// Remove stale build roots and detach leftover mounts
+    Clean {
+        /// Also remove current roots unused for longer than AGE (e.g. 14d, 2w)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Remove instead of reporting
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[cfg(with_handlers)]
+pub async fn run(args: WorkshopArgs, ctx: &Context<'_>) -> Result<()> {
+    use crate::operations::workshop;
+
+    match args.command {
+        WorkshopCommand::List => workshop::execute_list_workshop(ctx.config, ctx.format).await,
+        WorkshopCommand::Clean { older_than, apply } => {
+            let older_than = older_than
+                .as_deref()
+                .map(crate::util::units::parse_age)
+                .transpose()?;
+            // A root is only safe to unmount and delete while no build,
+            // standalone or inside install, is using it.
+            let (_part_store, _lock) = ctx.ensure_lock_and_part_store()?;
+            let _build_lock = ctx.ensure_build_lock()?;
+            workshop::execute_clean_workshop(ctx.config, older_than, apply).await
+        }
+    }
+}
@@ -3,6 +3,7 @@ use figment::{
     providers::{Env, Format, Toml},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use crate::error::{Result, WrightError};
@@ -15,6 +16,13 @@ pub struct GlobalConfig {
     pub build: ForgeConfig,
     #[serde(default)]
     pub network: NetworkConfig,
+    #[serde(default)]
+    pub prune: PruneConfig,
+    /// Preferred providers of virtual dependencies, most preferred first:
+    /// `sh = ["bash", "dash"]`.  Entries name plans or outputs.  Consulted
+    /// only when more than one plan provides the name.
+    #[serde(default)]
+    pub providers: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -60,6 +68,24 @@ pub struct ForgeConfig {
     pub cpu_time_limit: Option<u64>,
     #[serde(default)]
     pub timeout: Option<u64>,
+    /// cgroup v2 `memory.max` per isolation task, in MB: resident memory of
+    /// the whole build tree. Exceeding it is reported as out of memory.
+    #[serde(default)]
+    pub memory_max: Option<u64>,
+    /// cgroup v2 `pids.max` per isolation task.
+    #[serde(default)]
+    pub pids_max: Option<u64>,
+    /// cgroup v2 `cpu.max` per isolation task, in CPUs (e.g. `4` or `2.5`).
+    #[serde(default)]
+    pub cpu_max: Option<f64>,
+    /// cgroup v2 `io.max` rules per isolation task, e.g.
+    /// `"/dev/nvme0n1 wbps=209715200"`.
+    #[serde(default)]
+    pub io_max: Vec<String>,
+    /// cgroup under which per-task cgroups are created. Unset = `wright`
+    /// under the cgroup2 mount, which needs root or a delegated subtree.
+    #[serde(default)]
+    pub cgroup_parent: Option<PathBuf>,
     /// Static per-isolation compiler thread budget. When set, overrides the
     /// dynamic `total_cpus / active_isolations` calculation in the scheduler.
     /// When unset, the scheduler divides CPUs evenly across active isolations.
@@ -76,6 +102,10 @@ pub struct ForgeConfig {
     /// distribution uses different package names.
     #[serde(default = "default_stable_toolchain")]
     pub stable_toolchain: Vec<String>,
+    /// Logs of this many previous builds are kept per build root, beside
+    /// the latest build's. `0` keeps only the latest.
+    #[serde(default = "default_log_retention")]
+    pub log_retention: usize,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -89,6 +119,44 @@ pub struct NetworkConfig {
     /// triggers many parallel fetches.
     #[serde(default = "default_max_concurrent_downloads")]
     pub max_concurrent_downloads: usize,
+    /// Where `network = "proxy"` stages' fetches are cached for offline
+    /// replay. Defaults to `proxy/` under `general.source_dir`.
+    #[serde(default)]
+    pub proxy_cache: Option<PathBuf>,
+    /// Serve proxied stages from the proxy cache only, never contacting
+    /// the upstream.
+    #[serde(default)]
+    pub proxy_offline: bool,
+    /// Host names, addresses, and CIDR networks on loopback, link-local,
+    /// or private ranges that proxied stages may still reach, such as a
+    /// LAN mirror. Everything else on those ranges is refused.
+    #[serde(default)]
+    pub proxy_allow: Vec<String>,
+}
+
+/// Default retention policy for `wright prune`; command-line flags override
+/// each field.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PruneConfig {
+    /// Newest plan revisions kept per plan. `0` disables the rule.
+    #[serde(default = "default_keep_latest")]
+    pub keep_latest: usize,
+    /// Archives referenced by this many most recent delivery transactions
+    /// are kept, so `wright rollback` still finds them.
+    #[serde(default = "default_keep_deliveries")]
+    pub keep_deliveries: i64,
+    /// Records of this many most recent delivery transactions are kept for
+    /// `wright rollback`; older finished ones are dropped. `0` keeps all.
+    #[serde(default = "default_keep_delivery_records")]
+    pub keep_delivery_records: i64,
+    /// Only prune archives older than this age (e.g. `"30d"`).
+    #[serde(default)]
+    pub max_age: Option<String>,
+    /// Evict the oldest prunable archives until the inventory fits in this
+    /// size (e.g. `"50G"`).
+    #[serde(default)]
+    pub max_size: Option<String>,
 }
 
 fn default_general() -> GeneralConfig {
@@ -212,6 +280,19 @@ fn default_retry() -> u32 {
 fn default_max_concurrent_downloads() -> usize {
     8
 }
+fn default_keep_latest() -> usize {
+    2
+}
+fn default_keep_deliveries() -> i64 {
+    10
+}
+fn default_keep_delivery_records() -> i64 {
+    100
+}
+
+fn default_log_retention() -> usize {
+    5
+}
 
 impl Default for GlobalConfig {
     fn default() -> Self {
@@ -219,6 +300,8 @@ impl Default for GlobalConfig {
             general: default_general(),
             build: ForgeConfig::default(),
             network: NetworkConfig::default(),
+            prune: PruneConfig::default(),
+            providers: BTreeMap::new(),
         }
     }
 }
@@ -232,9 +315,15 @@ impl Default for ForgeConfig {
             memory_limit: None,
             cpu_time_limit: None,
             timeout: None,
+            memory_max: None,
+            pids_max: None,
+            cpu_max: None,
+            io_max: Vec::new(),
+            cgroup_parent: None,
             nproc_per_isolation: None,
             max_cpus: None,
             stable_toolchain: default_stable_toolchain(),
+            log_retention: default_log_retention(),
         }
     }
 }
@@ -245,6 +334,21 @@ impl Default for NetworkConfig {
             download_timeout: default_timeout(),
             retry_count: default_retry(),
             max_concurrent_downloads: default_max_concurrent_downloads(),
+            proxy_cache: None,
+            proxy_offline: false,
+            proxy_allow: Vec::new(),
+        }
+    }
+}
+
+impl Default for PruneConfig {
+    fn default() -> Self {
+        Self {
+            keep_latest: default_keep_latest(),
+            keep_deliveries: default_keep_deliveries(),
+            keep_delivery_records: default_keep_delivery_records(),
+            max_age: None,
+            max_size: None,
         }
     }
 }
@@ -0,0 +1,112 @@
+use super::{InstalledAlternative, InstalledDb};
+use crate::error::{Result, WrightError};
+use crate::plan::manifest::Alternative;
+use sqlx::{Row, query, query_as};
+
+const ALTERNATIVE_COLUMNS: &str = "a.name, p.name AS part_name, a.link, a.target, a.priority";
+
+impl InstalledDb {
+    /// Replace the alternatives a part is a candidate for.
+    pub async fn replace_alternatives(
+        &self,
+        part_id: i64,
+        alternatives: &[Alternative],
+    ) -> Result<()> {
+        query("DELETE FROM alternatives WHERE part_id = ?")
+            .bind(part_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to delete old alternatives: {}", e))
+            })?;
+        for alt in alternatives {
+            query(
+                "INSERT INTO alternatives (name, part_id, link, target, priority)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&alt.name)
+            .bind(part_id)
+            .bind(&alt.link)
+            .bind(&alt.target)
+            .bind(alt.priority)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to insert alternative: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Candidates for alternative `name`, or for every name, highest
+    /// priority first within a name.
+    pub async fn list_alternatives(&self, name: Option<&str>) -> Result<Vec<InstalledAlternative>> {
+        let sql = format!(
+            "SELECT {} FROM alternatives a JOIN parts p ON p.id = a.part_id
+             WHERE ?1 IS NULL OR a.name = ?1
+             ORDER BY a.name, a.priority DESC, p.name",
+            ALTERNATIVE_COLUMNS
+        );
+        query_as::<_, InstalledAlternative>(&sql)
+            .bind(name)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to list alternatives: {}", e)))
+    }
+
+    /// The alternatives part `part_name` is a candidate for.
+    pub async fn get_part_alternatives(
+        &self,
+        part_name: &str,
+    ) -> Result<Vec<InstalledAlternative>> {
+        let sql = format!(
+            "SELECT {} FROM alternatives a JOIN parts p ON p.id = a.part_id
+             WHERE p.name = ? ORDER BY a.name",
+            ALTERNATIVE_COLUMNS
+        );
+        query_as::<_, InstalledAlternative>(&sql)
+            .bind(part_name)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to get part alternatives: {}", e))
+            })
+    }
+
+    pub async fn get_alternative_pin(&self, name: &str) -> Result<Option<String>> {
+        let row = query("SELECT part_name FROM alternative_pins WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to get alternative pin: {}", e))
+            })?;
+        row.map(|r| r.try_get(0)).transpose().map_err(|e| {
+            WrightError::DatabaseError(format!("failed to read alternative pin: {}", e))
+        })
+    }
+
+    pub async fn pin_alternative(&self, name: &str, part_name: &str) -> Result<()> {
+        query(
+            "INSERT INTO alternative_pins (name, part_name) VALUES (?, ?)
+             ON CONFLICT(name) DO UPDATE SET part_name = excluded.part_name",
+        )
+        .bind(name)
+        .bind(part_name)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to pin alternative: {}", e)))?;
+        Ok(())
+    }
+
+    pub async fn unpin_alternative(&self, name: &str) -> Result<bool> {
+        let res = query("DELETE FROM alternative_pins WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to unpin alternative: {}", e))
+            })?;
+        Ok(res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use super::{BuildRun, BuildStage, InstalledDb, NewBuildRun};
+use crate::error::{Result, WrightError};
+use sqlx::{query, query_as};
+
+/// Recent forges averaged into a plan's expected build time.
+const EXPECTED_TIME_RUNS: i64 = 3;
+
+const BUILD_RUN_COLUMNS: &str =
+    "id, plan_name, version, release, built_at, wall_secs, cpu_secs, max_rss_kb, disk_bytes";
+
+impl InstalledDb {
+    /// Record a forge of a plan and what each of its stages cost.
+    pub async fn record_build(&self, run: NewBuildRun<'_>) -> Result<i64> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            WrightError::DatabaseError(format!("failed to begin transaction: {}", e))
+        })?;
+
+        let stages = run.stages;
+        let res = query(
+            "INSERT INTO build_runs
+                 (plan_name, version, release, wall_secs, cpu_secs, max_rss_kb, disk_bytes)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(run.plan_name)
+        .bind(run.version)
+        .bind(run.release as i64)
+        .bind(stages.iter().map(|s| s.wall_secs).sum::<f64>())
+        .bind(
+            stages
+                .iter()
+                .map(|s| s.user_secs + s.system_secs)
+                .sum::<f64>(),
+        )
+        .bind(stages.iter().map(|s| s.max_rss_kb).max().unwrap_or(0))
+        .bind(stages.iter().map(|s| s.disk_bytes).sum::<i64>())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to record build: {}", e)))?;
+        let id = res.last_insert_rowid();
+
+        for (seq, stage) in stages.iter().enumerate() {
+            query(
+                "INSERT INTO build_stages
+                     (run_id, seq, stage, wall_secs, user_secs, system_secs, max_rss_kb, disk_bytes)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(seq as i64)
+            .bind(&stage.stage)
+            .bind(stage.wall_secs)
+            .bind(stage.user_secs)
+            .bind(stage.system_secs)
+            .bind(stage.max_rss_kb)
+            .bind(stage.disk_bytes)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to record build stage: {}", e))
+            })?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to commit build: {}", e)))?;
+        Ok(id)
+    }
+
+    /// Recorded forges of `plan_name`, newest first.
+    pub async fn build_runs(&self, plan_name: &str) -> Result<Vec<BuildRun>> {
+        let sql = format!(
+            "SELECT {} FROM build_runs WHERE plan_name = ? ORDER BY id DESC",
+            BUILD_RUN_COLUMNS
+        );
+        query_as::<_, BuildRun>(&sql)
+            .bind(plan_name)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to query builds: {}", e)))
+    }
+
+    /// The latest recorded forge of every plan, slowest first.
+    pub async fn latest_build_runs(&self) -> Result<Vec<BuildRun>> {
+        let sql = format!(
+            "SELECT {} FROM build_runs
+             WHERE id IN (SELECT MAX(id) FROM build_runs GROUP BY plan_name)
+             ORDER BY wall_secs DESC, plan_name",
+            BUILD_RUN_COLUMNS
+        );
+        query_as::<_, BuildRun>(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to query builds: {}", e)))
+    }
+
+    pub async fn build_stages(&self, run_id: i64) -> Result<Vec<BuildStage>> {
+        query_as(
+            "SELECT stage, wall_secs, user_secs, system_secs, max_rss_kb, disk_bytes
+             FROM build_stages WHERE run_id = ? ORDER BY seq",
+        )
+        .bind(run_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to query build stages: {}", e)))
+    }
+
+    /// Expected wall time of each recorded plan: the mean of its latest
+    /// few forges.
+    pub async fn expected_build_secs(&self) -> Result<HashMap<String, f64>> {
+        let rows: Vec<(String, f64)> = query_as(
+            "SELECT plan_name, AVG(wall_secs) FROM (
+                 SELECT plan_name, wall_secs,
+                        ROW_NUMBER() OVER (PARTITION BY plan_name ORDER BY id DESC) AS n
+                 FROM build_runs
+             ) WHERE n <= ? GROUP BY plan_name",
+        )
+        .bind(EXPECTED_TIME_RUNS)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to query expected build times: {}", e))
+        })?;
+        Ok(rows.into_iter().collect())
+    }
+}
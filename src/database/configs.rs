@@ -0,0 +1,89 @@
+use super::{ConfigFile, InstalledDb, PendingConfig};
+use crate::error::{Result, WrightError};
+use sqlx::{query, query_as};
+
+const PENDING_SQL: &str = "
+    SELECT pc.path, p.name AS part_name, pc.new_hash, pc.base_hash, pc.created_at
+    FROM pending_configs pc
+    INNER JOIN parts p ON pc.part_id = p.id
+";
+
+impl InstalledDb {
+    /// Record (or refresh) a pending config update for `path`. An existing
+    /// record keeps its `base_hash`: the local edits still started there.
+    pub async fn upsert_pending_config(
+        &self,
+        path: &str,
+        part_id: i64,
+        new_hash: &str,
+        base_hash: Option<&str>,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        query(
+            "INSERT INTO pending_configs (path, part_id, new_hash, base_hash, created_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(path) DO UPDATE SET
+                 part_id = excluded.part_id,
+                 new_hash = excluded.new_hash,
+                 base_hash = COALESCE(pending_configs.base_hash, excluded.base_hash),
+                 created_at = excluded.created_at",
+        )
+        .bind(path)
+        .bind(part_id)
+        .bind(new_hash)
+        .bind(base_hash)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to record pending config: {}", e))
+        })?;
+        Ok(())
+    }
+
+    pub async fn get_pending_config(&self, path: &str) -> Result<Option<PendingConfig>> {
+        let sql = format!("{} WHERE pc.path = ?", PENDING_SQL);
+        query_as::<_, PendingConfig>(&sql)
+            .bind(path)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to query pending config: {}", e))
+            })
+    }
+
+    pub async fn list_pending_configs(&self) -> Result<Vec<PendingConfig>> {
+        let sql = format!("{} ORDER BY pc.path", PENDING_SQL);
+        query_as::<_, PendingConfig>(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to list pending configs: {}", e))
+            })
+    }
+
+    pub async fn delete_pending_config(&self, path: &str) -> Result<bool> {
+        let res = query("DELETE FROM pending_configs WHERE path = ?")
+            .bind(path)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to delete pending config: {}", e))
+            })?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Every `[backup]` file of every installed part, by path.
+    pub async fn list_config_files(&self) -> Result<Vec<ConfigFile>> {
+        query_as(
+            "SELECT f.path, p.name AS part_name, f.file_hash
+             FROM files f
+             INNER JOIN parts p ON f.part_id = p.id
+             WHERE f.is_config = 1 AND f.file_type = 'file'
+             ORDER BY f.path",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to list config files: {}", e)))
+    }
+}
@@ -36,6 +36,36 @@ impl InstalledDb {
         Ok(())
     }
 
+    /// Record that delivery `tx_id` reverts delivery `target`.
+    pub async fn set_delivery_reverts(&self, tx_id: i64, target: i64) -> Result<()> {
+        query("UPDATE delivery_transactions SET reverts = ? WHERE id = ?")
+            .bind(target)
+            .bind(tx_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to record reverted delivery: {}", e))
+            })?;
+        Ok(())
+    }
+
+    /// The completed delivery that reverted delivery `target`, if any.
+    pub async fn find_reverting_delivery(&self, target: i64) -> Result<Option<i64>> {
+        query_as::<_, (i64,)>(
+            "SELECT id FROM delivery_transactions
+             WHERE reverts = ? AND status = 'completed'
+             ORDER BY id DESC
+             LIMIT 1",
+        )
+        .bind(target)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|row| row.map(|(id,)| id))
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to query reverting delivery: {}", e))
+        })
+    }
+
     /// Insert a single operation into the transaction ops table.
     pub async fn insert_transaction_op(
         &self,
@@ -64,6 +94,63 @@ impl InstalledDb {
         Ok(res.last_insert_rowid())
     }
 
+    /// Insert an operation after every op already recorded for `tx_id`.
+    pub async fn append_transaction_op(
+        &self,
+        tx_id: i64,
+        part_name: &str,
+        part_hash: &str,
+        action_type: &str,
+        old_hash: Option<&str>,
+    ) -> Result<i64> {
+        let res = query(
+            "INSERT INTO transaction_ops (transaction_id, part_name, part_hash, action_type, execution_order, status, old_hash)
+             VALUES (?, ?, ?, ?,
+                     (SELECT COALESCE(MAX(execution_order), -1) + 1 FROM transaction_ops WHERE transaction_id = ?),
+                     'pending', ?)",
+        )
+        .bind(tx_id)
+        .bind(part_name)
+        .bind(part_hash)
+        .bind(action_type)
+        .bind(tx_id)
+        .bind(old_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to insert transaction op: {}", e))
+        })?;
+        Ok(res.last_insert_rowid())
+    }
+
+    /// Append an op that changes an external placeholder of `version`.
+    /// Placeholders have no archive, so `part_hash` is left empty.
+    pub async fn append_placeholder_op(
+        &self,
+        tx_id: i64,
+        part_name: &str,
+        action_type: &str,
+        version: &str,
+    ) -> Result<i64> {
+        let res = query(
+            "INSERT INTO transaction_ops (transaction_id, part_name, part_hash, action_type, execution_order, status, placeholder_version)
+             VALUES (?, ?, '', ?,
+                     (SELECT COALESCE(MAX(execution_order), -1) + 1 FROM transaction_ops WHERE transaction_id = ?),
+                     'pending', ?)",
+        )
+        .bind(tx_id)
+        .bind(part_name)
+        .bind(action_type)
+        .bind(tx_id)
+        .bind(version)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to insert transaction op: {}", e))
+        })?;
+        Ok(res.last_insert_rowid())
+    }
+
     /// Insert multiple operations in a batch.
     pub async fn insert_transaction_ops(
         &self,
@@ -111,7 +198,7 @@ impl InstalledDb {
     /// Find any delivery transaction that is not yet complete (leftover from a crash).
     pub async fn get_active_delivery(&self) -> Result<Option<DeliveryTransaction>> {
         let result: Option<DeliveryTransaction> = query_as(
-            "SELECT id, command, status, created_at, updated_at
+            "SELECT id, command, status, created_at, updated_at, reverts
              FROM delivery_transactions
              WHERE status IN ('planning', 'ready', 'applying')
              ORDER BY id DESC
@@ -128,7 +215,8 @@ impl InstalledDb {
     /// Get all operations for a delivery transaction, ordered by execution_order.
     pub async fn get_ops_for_delivery(&self, tx_id: i64) -> Result<Vec<TransactionOp>> {
         let ops: Vec<TransactionOp> = query_as(
-            "SELECT id, transaction_id, part_name, part_hash, action_type, execution_order, status, old_hash, error_msg
+            "SELECT id, transaction_id, part_name, part_hash, action_type, execution_order, status, old_hash, error_msg,
+                    placeholder_version
              FROM transaction_ops
              WHERE transaction_id = ?
              ORDER BY execution_order",
@@ -140,6 +228,118 @@ impl InstalledDb {
         Ok(ops)
     }
 
+    /// Look up a delivery transaction by id.
+    pub async fn get_delivery(&self, tx_id: i64) -> Result<Option<DeliveryTransaction>> {
+        query_as(
+            "SELECT id, command, status, created_at, updated_at, reverts
+             FROM delivery_transactions
+             WHERE id = ?",
+        )
+        .bind(tx_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to query delivery: {}", e)))
+    }
+
+    /// List recorded delivery transactions, newest first.
+    pub async fn list_deliveries(&self, limit: i64) -> Result<Vec<DeliveryTransaction>> {
+        query_as(
+            "SELECT id, command, status, created_at, updated_at, reverts
+             FROM delivery_transactions
+             ORDER BY id DESC
+             LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to list deliveries: {}", e)))
+    }
+
+    /// Completed or rolled back deliveries older than the `keep` most recent
+    /// ones, oldest first.
+    pub async fn deliveries_beyond(&self, keep: i64) -> Result<Vec<i64>> {
+        sqlx::query_scalar(
+            "SELECT id FROM delivery_transactions
+             WHERE status IN ('completed', 'rolled_back')
+               AND id NOT IN (SELECT id FROM delivery_transactions ORDER BY id DESC LIMIT ?)
+             ORDER BY id",
+        )
+        .bind(keep)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to list old deliveries: {}", e)))
+    }
+
+    /// Archive hashes (incoming and replaced) touched by the `limit` most
+    /// recent delivery transactions.
+    pub async fn recent_delivery_hashes(&self, limit: i64) -> Result<Vec<String>> {
+        sqlx::query_scalar(
+            "SELECT part_hash FROM transaction_ops
+             WHERE transaction_id IN (SELECT id FROM delivery_transactions ORDER BY id DESC LIMIT ?)
+               AND part_hash != ''
+             UNION
+             SELECT old_hash FROM transaction_ops
+             WHERE transaction_id IN (SELECT id FROM delivery_transactions ORDER BY id DESC LIMIT ?)
+               AND old_hash IS NOT NULL",
+        )
+        .bind(limit)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to query delivery hashes: {}", e)))
+    }
+
+    /// Record the paths an operation installed, replaced, or removed.
+    pub async fn insert_op_paths(&self, op_id: i64, paths: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            WrightError::DatabaseError(format!("failed to begin transaction: {}", e))
+        })?;
+        for path in paths {
+            query("INSERT OR IGNORE INTO transaction_paths (op_id, path) VALUES (?, ?)")
+                .bind(op_id)
+                .bind(path)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    WrightError::DatabaseError(format!("failed to record op path: {}", e))
+                })?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to commit op paths: {}", e)))?;
+        Ok(())
+    }
+
+    /// `(action_type, path)` for every path touched by a DONE op of `tx_id`.
+    pub async fn get_delivery_paths(&self, tx_id: i64) -> Result<Vec<(String, String)>> {
+        query_as(
+            "SELECT o.action_type, p.path
+             FROM transaction_paths p
+             JOIN transaction_ops o ON o.id = p.op_id
+             WHERE o.transaction_id = ? AND o.status = 'done'
+             ORDER BY o.execution_order, p.path",
+        )
+        .bind(tx_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to query delivery paths: {}", e)))
+    }
+
+    /// Drop the touched paths recorded for `tx_id`'s ops.
+    pub async fn clear_delivery_paths(&self, tx_id: i64) -> Result<()> {
+        query(
+            "DELETE FROM transaction_paths
+             WHERE op_id IN (SELECT id FROM transaction_ops WHERE transaction_id = ?)",
+        )
+        .bind(tx_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to clear delivery paths: {}", e))
+        })?;
+        Ok(())
+    }
+
     /// Reset an operation back to PENDING status (during crash recovery).
     pub async fn reset_op_to_pending(&self, op_id: i64) -> Result<()> {
         query("UPDATE transaction_ops SET status = 'pending', error_msg = NULL WHERE id = ?")
@@ -152,6 +352,7 @@ impl InstalledDb {
 
     /// Delete a delivery transaction and all its operations (called after successful completion or rollback).
     pub async fn cleanup_delivery(&self, tx_id: i64) -> Result<()> {
+        self.clear_delivery_paths(tx_id).await?;
         // transaction_ops has a foreign key with ON DELETE CASCADE?
         // Let's check migration 015.
         query("DELETE FROM transaction_ops WHERE transaction_id = ?")
@@ -252,4 +252,39 @@ impl InstalledDb {
         }
         Ok(result)
     }
+
+    /// Record the `provides` entries of a part (`name` or `name=version`).
+    pub async fn insert_provides(&self, part_id: i64, entries: &[String]) -> Result<()> {
+        for entry in entries {
+            let (name, version) = match entry.split_once('=') {
+                Some((name, version)) => (name.trim(), Some(version.trim())),
+                None => (entry.trim(), None),
+            };
+            query("INSERT OR REPLACE INTO provides (part_id, name, version) VALUES (?, ?, ?)")
+                .bind(part_id)
+                .bind(name)
+                .bind(version)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    WrightError::DatabaseError(format!("failed to insert provides: {}", e))
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Installed parts that provide `name`, with the provided version if
+    /// the entry has one.
+    pub async fn find_providers(&self, name: &str) -> Result<Vec<(String, Option<String>)>> {
+        query_as::<_, (String, Option<String>)>(
+            "SELECT p.name, pr.version FROM parts p
+             JOIN provides pr ON p.id = pr.part_id
+             WHERE pr.name = ?
+             ORDER BY p.name",
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to find providers: {}", e)))
+    }
 }
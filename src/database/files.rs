@@ -27,9 +27,9 @@ impl InstalledDb {
             WrightError::DatabaseError(format!("failed to begin transaction: {}", e))
         })?;
 
-        for chunk in files.chunks(999 / 7) {
+        for chunk in files.chunks(999 / 9) {
             let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(
-                "INSERT INTO files (part_id, path, file_hash, file_type, file_mode, file_size, is_config) ",
+                "INSERT INTO files (part_id, path, file_hash, file_type, file_mode, file_size, is_config, file_uid, file_gid) ",
             );
 
             query_builder.push_values(chunk, |mut b, file: &FileEntry| {
@@ -39,7 +39,9 @@ impl InstalledDb {
                     .push_bind(file.file_type)
                     .push_bind(file.file_mode)
                     .push_bind(file.file_size)
-                    .push_bind(file.is_config);
+                    .push_bind(file.is_config)
+                    .push_bind(file.file_uid)
+                    .push_bind(file.file_gid);
             });
 
             let query = query_builder.build();
@@ -147,7 +149,7 @@ impl InstalledDb {
 
     pub async fn get_files(&self, part_id: i64) -> Result<Vec<FileEntry>> {
         query_as::<_, FileEntry>(
-            "SELECT path, file_hash, file_type, file_mode, file_size, is_config
+            "SELECT path, file_hash, file_type, file_mode, file_size, is_config, file_uid, file_gid
              FROM files WHERE part_id = ? ORDER BY path",
         )
         .bind(part_id)
@@ -156,6 +158,59 @@ impl InstalledDb {
         .map_err(|e| WrightError::DatabaseError(format!("failed to get files: {}", e)))
     }
 
+    /// Directories recorded for `part_id` and for no other part.
+    pub async fn get_exclusive_dirs(&self, part_id: i64) -> Result<Vec<String>> {
+        let rows = query(
+            "SELECT f.path FROM files f
+             WHERE f.part_id = ? AND f.file_type = 'dir'
+               AND NOT EXISTS (
+                   SELECT 1 FROM files o WHERE o.path = f.path AND o.part_id != f.part_id
+               )
+             ORDER BY f.path",
+        )
+        .bind(part_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to get exclusive dirs: {}", e)))?;
+
+        rows.iter()
+            .map(|row| {
+                use sqlx::Row;
+                row.try_get(0)
+                    .map_err(|e| WrightError::DatabaseError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Paths of `original_owner_id` shadowed by another part, with where
+    /// the original file was diverted to, if anywhere.
+    pub async fn get_shadowed_paths(
+        &self,
+        original_owner_id: i64,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let rows =
+            query("SELECT path, diverted_to FROM shadowed_files WHERE original_owner_id = ?")
+                .bind(original_owner_id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| {
+                    WrightError::DatabaseError(format!("failed to get shadowed paths: {}", e))
+                })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            use sqlx::Row;
+            let path: String = row
+                .try_get(0)
+                .map_err(|e| WrightError::DatabaseError(e.to_string()))?;
+            let diverted_to: Option<String> = row
+                .try_get(1)
+                .map_err(|e| WrightError::DatabaseError(e.to_string()))?;
+            result.push((path, diverted_to));
+        }
+        Ok(result)
+    }
+
     pub async fn find_all_owners(&self, path: &str) -> Result<Vec<String>> {
         let rows = query(
             "SELECT p.name FROM parts p
@@ -0,0 +1,123 @@
+use super::{Generation, GenerationPart, InstalledDb};
+use crate::error::{Result, WrightError};
+use sqlx::{query, query_as};
+
+const GENERATION_SQL: &str = "
+    SELECT g.id, g.name, g.created_at,
+           (SELECT COUNT(*) FROM generation_parts gp WHERE gp.generation_id = g.id) AS part_count
+    FROM generations g
+";
+
+impl InstalledDb {
+    /// Record the current installed registry as generation `name`.
+    pub async fn create_generation(&self, name: &str) -> Result<i64> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            WrightError::DatabaseError(format!("failed to begin transaction: {}", e))
+        })?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let res = query("INSERT INTO generations (name, created_at) VALUES (?, ?)")
+            .bind(name)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                if let sqlx::Error::Database(ref db_err) = e
+                    && db_err.is_unique_violation()
+                {
+                    return WrightError::ValidationError(format!(
+                        "generation '{}' already exists",
+                        name
+                    ));
+                }
+                WrightError::DatabaseError(format!("failed to create generation: {}", e))
+            })?;
+        let id = res.last_insert_rowid();
+
+        query(
+            "INSERT INTO generation_parts (generation_id, name, part_hash, origin, version, release)
+             SELECT ?, p.name, p.part_hash, p.origin, pl.version, pl.release
+             FROM parts p
+             INNER JOIN plans pl ON p.plan_id = pl.id",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to record generation parts: {}", e))
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            WrightError::DatabaseError(format!("failed to commit generation: {}", e))
+        })?;
+        Ok(id)
+    }
+
+    /// List generations, oldest first.
+    pub async fn list_generations(&self) -> Result<Vec<Generation>> {
+        let sql = format!("{} ORDER BY g.id", GENERATION_SQL);
+        query_as::<_, Generation>(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to list generations: {}", e)))
+    }
+
+    pub async fn get_generation(&self, name: &str) -> Result<Option<Generation>> {
+        let sql = format!("{} WHERE g.name = ?", GENERATION_SQL);
+        query_as::<_, Generation>(&sql)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to query generation: {}", e)))
+    }
+
+    pub async fn get_generation_parts(&self, generation_id: i64) -> Result<Vec<GenerationPart>> {
+        query_as(
+            "SELECT name, part_hash, origin, version, release
+             FROM generation_parts
+             WHERE generation_id = ?
+             ORDER BY name",
+        )
+        .bind(generation_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to query generation parts: {}", e)))
+    }
+
+    /// The installed registry in the same shape as a generation's parts.
+    pub async fn current_generation_parts(&self) -> Result<Vec<GenerationPart>> {
+        query_as(
+            "SELECT p.name, p.part_hash, p.origin, pl.version, pl.release
+             FROM parts p
+             INNER JOIN plans pl ON p.plan_id = pl.id
+             ORDER BY p.name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to query parts: {}", e)))
+    }
+
+    /// Delete generation `name`. Returns `false` if it did not exist.
+    pub async fn delete_generation(&self, name: &str) -> Result<bool> {
+        let res = query("DELETE FROM generations WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to delete generation: {}", e))
+            })?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Archive hashes recorded in any generation.
+    pub async fn generation_part_hashes(&self) -> Result<Vec<String>> {
+        sqlx::query_scalar(
+            "SELECT DISTINCT part_hash FROM generation_parts WHERE part_hash IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            WrightError::DatabaseError(format!("failed to query generation hashes: {}", e))
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use super::{HookFailure, InstalledDb};
+use crate::error::{Result, WrightError};
+use sqlx::{query, query_as};
+
+const HOOK_FAILURE_COLUMNS: &str = "part_name, hook, status, hooks, error, delivery_id, failed_at";
+
+impl InstalledDb {
+    /// Record (or refresh) the failure of `hook` of `part_name`.
+    pub async fn record_hook_failure(
+        &self,
+        part_name: &str,
+        hook: &str,
+        status: &str,
+        hooks: &str,
+        error: &str,
+        delivery_id: Option<i64>,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        query(
+            "INSERT INTO hook_failures
+                 (part_name, hook, status, hooks, error, delivery_id, failed_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(part_name, hook) DO UPDATE SET
+                 status = excluded.status,
+                 hooks = excluded.hooks,
+                 error = excluded.error,
+                 delivery_id = excluded.delivery_id,
+                 failed_at = excluded.failed_at",
+        )
+        .bind(part_name)
+        .bind(hook)
+        .bind(status)
+        .bind(hooks)
+        .bind(error)
+        .bind(delivery_id)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to record hook failure: {}", e)))?;
+        Ok(())
+    }
+
+    /// Recorded hook failures, of `part_name` or of every part.
+    pub async fn list_hook_failures(&self, part_name: Option<&str>) -> Result<Vec<HookFailure>> {
+        let sql = format!(
+            "SELECT {} FROM hook_failures
+             WHERE ?1 IS NULL OR part_name = ?1
+             ORDER BY part_name, hook",
+            HOOK_FAILURE_COLUMNS
+        );
+        query_as::<_, HookFailure>(&sql)
+            .bind(part_name)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to list hook failures: {}", e)))
+    }
+
+    /// Hooks deferred by the ops of delivery `tx_id`.
+    pub async fn get_deferred_hooks(&self, tx_id: i64) -> Result<Vec<HookFailure>> {
+        let sql = format!(
+            "SELECT {} FROM hook_failures
+             WHERE status = 'deferred' AND delivery_id = ?
+             ORDER BY failed_at, part_name, hook",
+            HOOK_FAILURE_COLUMNS
+        );
+        query_as::<_, HookFailure>(&sql)
+            .bind(tx_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to query deferred hooks: {}", e))
+            })
+    }
+
+    pub async fn delete_hook_failure(&self, part_name: &str, hook: &str) -> Result<bool> {
+        let res = query("DELETE FROM hook_failures WHERE part_name = ? AND hook = ?")
+            .bind(part_name)
+            .bind(hook)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                WrightError::DatabaseError(format!("failed to delete hook failure: {}", e))
+            })?;
+        Ok(res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,23 @@
+-- V18: Named generations — tagged snapshots of the installed registry.
+--
+-- A generation copies, per part, the facts needed to put the system back:
+-- the archive hash (resolved against the CAS store and parts_dir when
+-- switching), the origin, and the plan version for display.  It records
+-- state, not files: switching redeploys archives through the normal
+-- delivery path.  External parts have no archive, so `part_hash` is NULL.
+
+CREATE TABLE generations (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    name        TEXT    NOT NULL UNIQUE,
+    created_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE generation_parts (
+    generation_id  INTEGER NOT NULL REFERENCES generations(id) ON DELETE CASCADE,
+    name           TEXT    NOT NULL,
+    part_hash      TEXT,
+    origin         TEXT    NOT NULL,
+    version        TEXT    NOT NULL,
+    release        INTEGER NOT NULL,
+    PRIMARY KEY (generation_id, name)
+);
@@ -0,0 +1,16 @@
+-- V19: Pending config updates.
+--
+-- When an upgrade ships a new version of a `[backup]` file the admin has
+-- edited locally, the new version is written beside it as
+-- `<path>.wright-new` instead of overwriting it, and recorded here until
+-- `wright config resolve` settles it.  `new_hash` is the packaged version
+-- waiting in the sidecar; `base_hash` is the packaged version the local
+-- edits started from (the merge base), kept across repeated upgrades.
+
+CREATE TABLE pending_configs (
+    path        TEXT    PRIMARY KEY,
+    part_id     INTEGER NOT NULL REFERENCES parts(id) ON DELETE CASCADE,
+    new_hash    TEXT    NOT NULL,
+    base_hash   TEXT,
+    created_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
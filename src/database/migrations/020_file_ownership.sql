@@ -0,0 +1,10 @@
+-- V20: Record the owner of each deployed file.
+--
+-- `wright verify` audits content, mode, size, symlink target and owner
+-- against what was deployed.  The uid/gid are those the file carried
+-- when the archive was extracted for deployment, i.e. what the merge
+-- left on disk.  Rows deployed before this migration have NULL owners
+-- and are not audited for ownership until the part is redeployed.
+
+ALTER TABLE files ADD COLUMN file_uid INTEGER;
+ALTER TABLE files ADD COLUMN file_gid INTEGER;
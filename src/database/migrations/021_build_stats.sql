@@ -0,0 +1,35 @@
+-- V21: Resource accounting of forges.
+--
+-- Every forge that ran a plan's whole pipeline records what each stage
+-- cost: wall time, CPU time (user and system, of the stage's scripts and
+-- every process they reaped), the peak RSS of the largest process, and the
+-- bytes the stage added to its layer.  A run row carries the totals so
+-- `wright stats` and the build scheduler need not aggregate stages.
+-- Builds are keyed by plan name, not `plans.id`: plans are forged long
+-- before (and whether or not) their parts are installed.
+
+CREATE TABLE build_runs (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_name   TEXT    NOT NULL,
+    version     TEXT    NOT NULL,
+    release     INTEGER NOT NULL,
+    built_at    DATETIME DEFAULT CURRENT_TIMESTAMP,
+    wall_secs   REAL    NOT NULL,
+    cpu_secs    REAL    NOT NULL,
+    max_rss_kb  INTEGER NOT NULL,
+    disk_bytes  INTEGER NOT NULL
+);
+
+CREATE INDEX idx_build_runs_plan ON build_runs(plan_name, id);
+
+CREATE TABLE build_stages (
+    run_id       INTEGER NOT NULL REFERENCES build_runs(id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    stage        TEXT    NOT NULL,
+    wall_secs    REAL    NOT NULL,
+    user_secs    REAL    NOT NULL,
+    system_secs  REAL    NOT NULL,
+    max_rss_kb   INTEGER NOT NULL,
+    disk_bytes   INTEGER NOT NULL,
+    PRIMARY KEY (run_id, seq)
+);
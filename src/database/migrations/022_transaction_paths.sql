@@ -0,0 +1,14 @@
+-- V22: Paths touched by delivery operations.
+--
+-- Each part operation records the files and symlinks it installed,
+-- replaced, or removed.  When every op of a delivery is done, the paths of
+-- its DONE ops are matched against the system's transaction triggers
+-- (`/etc/wright/triggers/*.toml`), each matching trigger runs once, and
+-- the rows are dropped: the ops themselves stay as the rollback ledger,
+-- their paths are only needed until the triggers have run.
+
+CREATE TABLE transaction_paths (
+    op_id  INTEGER NOT NULL REFERENCES transaction_ops(id) ON DELETE CASCADE,
+    path   TEXT    NOT NULL,
+    PRIMARY KEY (op_id, path)
+);
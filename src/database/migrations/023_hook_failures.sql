@@ -0,0 +1,20 @@
+-- V23: Deploy hooks that failed and were not allowed to abort their op.
+--
+-- A hook with `on_failure = "warn"` (the default) is recorded as `failed`,
+-- one with `on_failure = "defer"` as `deferred`: deferred hooks run again
+-- once the rest of their delivery is done, and both stay here until a run
+-- succeeds, on its own or through `wright hook run`.  `hooks` keeps the
+-- part's `.HOOKS` content so a hook can be re-run after its part is
+-- upgraded or removed.  Keyed by part name, not `parts.id`, for the same
+-- reason; `delivery_id` is not a foreign key since deliveries get pruned.
+
+CREATE TABLE hook_failures (
+    part_name    TEXT    NOT NULL,
+    hook         TEXT    NOT NULL,
+    status       TEXT    NOT NULL CHECK (status IN ('failed', 'deferred')),
+    hooks        TEXT    NOT NULL,
+    error        TEXT    NOT NULL,
+    delivery_id  INTEGER,
+    failed_at    DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (part_name, hook)
+);
@@ -0,0 +1,22 @@
+-- V24: Alternatives.
+--
+-- Every installed part that declares `[[output.alternatives]]` is a
+-- candidate for those names.  The link of a name points at the target of
+-- the pinned candidate, or of the candidate with the highest priority
+-- (ties go to the first part name).  A pin is keyed by part name, not
+-- `parts.id`, so it outlives the part being upgraded or removed and
+-- reinstalled; while the pinned part is not a candidate, priority decides.
+
+CREATE TABLE alternatives (
+    name        TEXT    NOT NULL,
+    part_id     INTEGER NOT NULL REFERENCES parts(id) ON DELETE CASCADE,
+    link        TEXT    NOT NULL,
+    target      TEXT    NOT NULL,
+    priority    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (name, part_id)
+);
+
+CREATE TABLE alternative_pins (
+    name        TEXT    PRIMARY KEY,
+    part_name   TEXT    NOT NULL
+);
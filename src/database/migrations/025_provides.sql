@@ -0,0 +1,15 @@
+-- V25: Provides.
+--
+-- The virtual names an installed part provides, so a runtime dependency
+-- on `http-server` is satisfied by whichever part declares
+-- `provides = ["http-server=2.4"]`.  `version` is NULL for an unversioned
+-- provide, which satisfies only dependencies without a constraint.
+
+CREATE TABLE provides (
+    part_id     INTEGER NOT NULL REFERENCES parts(id) ON DELETE CASCADE,
+    name        TEXT    NOT NULL,
+    version     TEXT,
+    PRIMARY KEY (part_id, name)
+);
+
+CREATE INDEX idx_provides_name ON provides(name);
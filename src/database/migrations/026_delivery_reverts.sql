@@ -0,0 +1,7 @@
+-- V26: Record which delivery a `wright rollback` reverted.
+--
+-- `rolled_back` means a delivery failed and the WAL reverted it.  A
+-- completed delivery undone later by `wright rollback` stays `completed`;
+-- the rollback's own delivery points at it with `reverts`.
+
+ALTER TABLE delivery_transactions ADD COLUMN reverts INTEGER;
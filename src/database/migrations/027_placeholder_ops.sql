@@ -0,0 +1,9 @@
+-- V27: Record the version of external placeholders a delivery drops or
+-- provides.
+--
+-- `wright generation switch` journals placeholder changes as `unprovide`
+-- and `provide` ops so undo and `wright rollback` can reverse them.  They
+-- have no archive, so `part_hash` stays empty and the placeholder's
+-- version lives here.
+
+ALTER TABLE transaction_ops ADD COLUMN placeholder_version TEXT;
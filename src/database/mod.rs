@@ -1,7 +1,12 @@
+mod alternatives;
+mod builds;
+mod configs;
 mod core;
 mod delivery_db;
 mod dependencies;
 mod files;
+mod generations;
+mod hook_failures;
 mod meta;
 mod migrations;
 mod parts;
@@ -13,9 +18,10 @@ pub use core::InstalledDb;
 use core::PART_COLUMNS;
 pub use plans::PlanRecord;
 pub use types::{
-    DeliveryStatus, DeliveryTransaction, Dependency, FileEntry, FileType, HistoryAction,
-    HistoryRecord, HistoryStatus, InstalledPart, NewPart, NewPlan, OpStatus, Origin, PartWithPlan,
-    SessionContext, TransactionOp,
+    BuildRun, BuildStage, ConfigFile, DeliveryStatus, DeliveryTransaction, Dependency, FileEntry,
+    FileType, Generation, GenerationPart, HistoryAction, HistoryRecord, HistoryStatus, HookFailure,
+    InstalledAlternative, InstalledPart, NewBuildRun, NewPart, NewPlan, OpStatus, Origin,
+    PartWithPlan, PendingConfig, SessionContext, TransactionOp,
 };
 
 #[cfg(test)]
@@ -112,6 +118,8 @@ mod tests {
                 file_mode: Some(0o755),
                 file_size: Some(1024),
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             }],
         )
         .await
@@ -142,6 +150,8 @@ mod tests {
                 file_mode: Some(0o755),
                 file_size: Some(1024),
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             },
             FileEntry {
                 path: "/usr/share/hello/README".to_string(),
@@ -150,6 +160,8 @@ mod tests {
                 file_mode: Some(0o644),
                 file_size: Some(512),
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             },
         ];
         db.insert_files(id, &files).await.unwrap();
@@ -188,6 +200,8 @@ mod tests {
                 file_mode: None,
                 file_size: None,
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             }],
         )
         .await
@@ -201,6 +215,8 @@ mod tests {
                 file_mode: None,
                 file_size: None,
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             }],
         )
         .await
@@ -322,6 +338,8 @@ mod tests {
                 file_mode: Some(0o755),
                 file_size: Some(1024),
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             }],
         )
         .await
@@ -336,6 +354,8 @@ mod tests {
                 file_mode: Some(0o755),
                 file_size: Some(2048),
                 is_config: false,
+                file_uid: None,
+                file_gid: None,
             }],
         )
         .await
@@ -514,4 +534,54 @@ mod tests {
         // not exposed at the CLI layer to keep the user-facing interface
         // part-centric.
     }
+
+    #[tokio::test]
+    async fn test_build_runs_and_expected_times() {
+        let db = InstalledDb::open_in_memory().await.unwrap();
+        let stage = |name: &str, wall: f64, rss: i64| BuildStage {
+            stage: name.to_string(),
+            wall_secs: wall,
+            user_secs: wall * 2.0,
+            system_secs: 1.0,
+            max_rss_kb: rss,
+            disk_bytes: 1000,
+        };
+        for (plan, version, compile) in [
+            ("gcc", "14.1", 3000.0),
+            ("zlib", "1.3", 10.0),
+            ("gcc", "14.2", 3200.0),
+        ] {
+            let stages = [
+                stage("configure", 100.0, 50),
+                stage("compile", compile, 900),
+            ];
+            db.record_build(NewBuildRun {
+                plan_name: plan,
+                version,
+                release: 1,
+                stages: &stages,
+            })
+            .await
+            .unwrap();
+        }
+
+        let latest = db.latest_build_runs().await.unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].plan_name, "gcc");
+        assert_eq!(latest[0].version, "14.2");
+        assert_eq!(latest[0].wall_secs, 3300.0);
+        assert_eq!(latest[0].cpu_secs, 6602.0);
+        assert_eq!(latest[0].max_rss_kb, 900);
+        assert_eq!(latest[0].disk_bytes, 2000);
+
+        let gcc = db.build_runs("gcc").await.unwrap();
+        assert_eq!(gcc[1].version, "14.1");
+        let stages = db.build_stages(gcc[0].id).await.unwrap();
+        assert_eq!(stages[0].stage, "configure");
+        assert_eq!(stages[1], stage("compile", 3200.0, 900));
+
+        let expected = db.expected_build_secs().await.unwrap();
+        assert_eq!(expected["gcc"], 3200.0);
+        assert_eq!(expected["zlib"], 110.0);
+    }
 }
@@ -156,6 +156,14 @@ impl InstalledDb {
             .map_err(|e| WrightError::DatabaseError(format!("failed to query part: {}", e)))
     }
 
+    /// Archive hashes of every deployed part.
+    pub async fn installed_part_hashes(&self) -> Result<Vec<String>> {
+        sqlx::query_scalar("SELECT part_hash FROM parts WHERE part_hash IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to query part hashes: {}", e)))
+    }
+
     pub async fn list_parts(&self) -> Result<Vec<PartWithPlan>> {
         let sql = format!("{} ORDER BY p.name", PART_WITH_PLAN_SQL);
         query_as::<_, PartWithPlan>(&sql)
@@ -194,6 +202,19 @@ impl InstalledDb {
         Ok(())
     }
 
+    /// Set a part's origin unconditionally, bypassing the upgrade-only rule
+    /// of [`set_origin`](Self::set_origin). Used to restore a recorded
+    /// origin; external parts are still left alone.
+    pub async fn reset_origin(&self, name: &str, origin: Origin) -> Result<()> {
+        query("UPDATE parts SET origin = ? WHERE name = ? AND origin != 'external'")
+            .bind(origin)
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| WrightError::DatabaseError(format!("failed to reset origin: {}", e)))?;
+        Ok(())
+    }
+
     pub async fn get_orphan_parts(&self) -> Result<Vec<PartWithPlan>> {
         let sql = format!(
             "{} WHERE p.origin = 'dependency' AND p.name NOT IN (
@@ -1,11 +1,15 @@
+use serde::Serialize;
+
 use crate::error::{Result, WrightError};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize)]
 #[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum FileType {
     File,
     Symlink,
     #[sqlx(rename = "dir")]
+    #[serde(rename = "dir")]
     Directory,
 }
 
@@ -41,8 +45,9 @@ impl TryFrom<&str> for FileType {
 /// higher variants are never silently downgraded to lower ones.
 /// `External` sits above `Manual` so that `set_origin(name, Manual)` is
 /// always a no-op for externally provided parts.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type, Serialize)]
 #[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum Origin {
     Dependency,
     Forge,
@@ -88,13 +93,16 @@ impl TryFrom<&str> for Origin {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize)]
 #[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum HistoryAction {
     Install,
     Upgrade,
     Remove,
     Rollback,
+    /// Damaged files put back from the part's own archive.
+    Repair,
 }
 
 impl std::fmt::Display for HistoryAction {
@@ -104,12 +112,14 @@ impl std::fmt::Display for HistoryAction {
             Self::Upgrade => "upgrade",
             Self::Remove => "remove",
             Self::Rollback => "rollback",
+            Self::Repair => "repair",
         })
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize)]
 #[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum HistoryStatus {
     Pending,
     Completed,
@@ -146,6 +156,8 @@ pub struct DeliveryTransaction {
     pub status: DeliveryStatus,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    /// The delivery this one reverted (`wright rollback`).
+    pub reverts: Option<i64>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -159,6 +171,9 @@ pub struct TransactionOp {
     pub status: OpStatus,
     pub old_hash: Option<String>,
     pub error_msg: Option<String>,
+    /// Version of the external placeholder an `unprovide` or `provide` op
+    /// dropped or created.
+    pub placeholder_version: Option<String>,
 }
 
 impl std::fmt::Display for HistoryStatus {
@@ -184,13 +199,19 @@ pub struct InstalledPart {
 }
 
 /// Part combined with its plan metadata for display queries.
-#[derive(Debug, Clone, sqlx::FromRow)]
+///
+/// Serialized for `wright list --format json`; row ids and hook bodies are
+/// internal and left out of the schema.
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
 pub struct PartWithPlan {
+    #[serde(skip)]
     pub id: i64,
     pub name: String,
+    #[serde(skip)]
     pub plan_id: i64,
     pub installed_at: Option<String>,
     pub part_hash: Option<String>,
+    #[serde(skip)]
     pub deploy_scripts: Option<String>,
     pub origin: Origin,
     pub plan_name: String,
@@ -200,7 +221,7 @@ pub struct PartWithPlan {
     pub arch: String,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
 pub struct FileEntry {
     pub path: String,
     pub file_hash: Option<String>,
@@ -208,6 +229,8 @@ pub struct FileEntry {
     pub file_mode: Option<i64>,
     pub file_size: Option<i64>,
     pub is_config: bool,
+    pub file_uid: Option<i64>,
+    pub file_gid: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -251,9 +274,12 @@ pub struct Dependency {
 pub struct SessionContext {
     pub id: String,
     pub command: String,
+    /// Delivery transaction this session's part operations belong to.
+    /// When set, every `TransactionContext` records a `transaction_ops` row.
+    pub delivery_id: Option<i64>,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
 pub struct HistoryRecord {
     pub timestamp: Option<String>,
     pub session_id: String,
@@ -267,3 +293,102 @@ pub struct HistoryRecord {
     pub status: HistoryStatus,
     pub details: Option<String>,
 }
+
+/// What forging one stage, with its pre/post hooks, cost.
+#[derive(Debug, Clone, Default, PartialEq, sqlx::FromRow, Serialize)]
+pub struct BuildStage {
+    pub stage: String,
+    pub wall_secs: f64,
+    pub user_secs: f64,
+    pub system_secs: f64,
+    /// Peak resident set size of the largest process, in KiB.
+    pub max_rss_kb: i64,
+    /// Bytes the stage added to its layer.
+    pub disk_bytes: i64,
+}
+
+/// A recorded forge of a plan, with totals over its stages.
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct BuildRun {
+    #[serde(skip)]
+    pub id: i64,
+    pub plan_name: String,
+    pub version: String,
+    pub release: i64,
+    pub built_at: Option<String>,
+    pub wall_secs: f64,
+    pub cpu_secs: f64,
+    pub max_rss_kb: i64,
+    pub disk_bytes: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewBuildRun<'a> {
+    pub plan_name: &'a str,
+    pub version: &'a str,
+    pub release: u32,
+    pub stages: &'a [BuildStage],
+}
+
+/// A named snapshot of the installed registry.
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct Generation {
+    #[serde(skip)]
+    pub id: i64,
+    pub name: String,
+    pub created_at: Option<String>,
+    pub part_count: i64,
+}
+
+/// One part as recorded in a generation (or as installed right now).
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize)]
+pub struct GenerationPart {
+    pub name: String,
+    pub part_hash: Option<String>,
+    pub origin: Origin,
+    pub version: String,
+    pub release: i64,
+}
+
+/// A packaged config update waiting beside a locally modified file.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize)]
+pub struct PendingConfig {
+    pub path: String,
+    pub part_name: String,
+    pub new_hash: String,
+    pub base_hash: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// A deploy hook whose failure was recorded instead of failing its op.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize)]
+pub struct HookFailure {
+    pub part_name: String,
+    pub hook: String,
+    /// `failed` or `deferred`.
+    pub status: String,
+    /// The `.HOOKS` content the hook came from.
+    #[serde(skip)]
+    pub hooks: String,
+    pub error: String,
+    pub delivery_id: Option<i64>,
+    pub failed_at: Option<String>,
+}
+
+/// An installed part's candidacy for an alternative name.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize)]
+pub struct InstalledAlternative {
+    pub name: String,
+    pub part_name: String,
+    pub link: String,
+    pub target: String,
+    pub priority: i64,
+}
+
+/// A `[backup]` file as recorded for an installed part.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow, Serialize)]
+pub struct ConfigFile {
+    pub path: String,
+    pub part_name: String,
+    pub file_hash: Option<String>,
+}
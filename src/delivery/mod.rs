@@ -45,15 +45,27 @@ pub async fn begin_applying(db: &InstalledDb, tx_id: i64) -> Result<()> {
 }
 
 /// Mark the delivery as COMPLETED — all operations finished successfully.
+///
+/// A completed delivery that mutated the system is kept until `wright
+/// prune` drops old records: its ops are the ledger `wright rollback`
+/// reverses.  One that recorded no ops is dropped.
+/// The paths its ops touched are dropped either way; callers that run
+/// transaction triggers do so before completing.
 pub async fn complete_delivery(db: &InstalledDb, tx_id: i64) -> Result<()> {
     db.set_delivery_status(tx_id, DeliveryStatus::Completed)
-        .await
+        .await?;
+    db.clear_delivery_paths(tx_id).await?;
+    if db.get_ops_for_delivery(tx_id).await?.is_empty() {
+        db.cleanup_delivery(tx_id).await?;
+    }
+    Ok(())
 }
 
 /// Mark the delivery as ROLLED_BACK after a failure.
 pub async fn rollback_delivery(db: &InstalledDb, tx_id: i64) -> Result<()> {
     db.set_delivery_status(tx_id, DeliveryStatus::RolledBack)
-        .await
+        .await?;
+    db.clear_delivery_paths(tx_id).await
 }
 
 /// Cleanup a delivery transaction after it has been fully processed (committed or rolled back).
@@ -72,6 +84,40 @@ pub async fn register_ops(
     db.insert_transaction_ops(tx_id, ops).await
 }
 
+/// Record a part operation as it starts, appended after the delivery's
+/// existing ops, and return its id.  The op enters EXTRACTING directly:
+/// it is registered at the moment its files start to move.
+pub async fn begin_op(
+    db: &InstalledDb,
+    tx_id: i64,
+    part_name: &str,
+    part_hash: &str,
+    action_type: &str,
+    old_hash: Option<&str>,
+) -> Result<i64> {
+    let op_id = db
+        .append_transaction_op(tx_id, part_name, part_hash, action_type, old_hash)
+        .await?;
+    db.set_op_status(op_id, OpStatus::Extracting).await?;
+    Ok(op_id)
+}
+
+/// Record an op on an external placeholder of `version` as it starts, the
+/// way [`begin_op`] does for parts with an archive.
+pub async fn begin_placeholder_op(
+    db: &InstalledDb,
+    tx_id: i64,
+    part_name: &str,
+    version: &str,
+    action_type: &str,
+) -> Result<i64> {
+    let op_id = db
+        .append_placeholder_op(tx_id, part_name, action_type, version)
+        .await?;
+    db.set_op_status(op_id, OpStatus::Extracting).await?;
+    Ok(op_id)
+}
+
 /// Update a single operation to EXTRACTING (about to copy files).
 pub async fn op_extracting(db: &InstalledDb, op_id: i64) -> Result<()> {
     db.set_op_status(op_id, OpStatus::Extracting).await
@@ -119,8 +165,7 @@ pub async fn recover_if_needed(db: &InstalledDb) -> Result<bool> {
                 tx_id = active.id,
                 "Delivery was still in PLANNING phase — discarding"
             );
-            db.set_delivery_status(active.id, DeliveryStatus::RolledBack)
-                .await?;
+            rollback_delivery(db, active.id).await?;
         }
         DeliveryStatus::Ready | DeliveryStatus::Applying => {
             recover_from_applying(db, &active).await?;
@@ -216,9 +261,7 @@ async fn recover_from_applying(db: &InstalledDb, tx: &DeliveryTransaction) -> Re
 
     // Mark the transaction as ROLLED_BACK so the user knows to re-run.
     // The re-run will pick up from where we left off (CAS store will have
-    // the pre-built parts, and the WAL will be re-created fresh).
-    db.set_delivery_status(tx.id, DeliveryStatus::RolledBack)
-        .await?;
-
-    Ok(())
+    // the pre-built parts, and the WAL will be re-created fresh).  The
+    // re-run records its own touched paths, so drop this delivery's.
+    rollback_delivery(db, tx.id).await
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use sha2::{Digest, Sha256};
@@ -18,9 +18,9 @@ use crate::error::{Result, WrightError};
 /// A part's CAS fingerprint captures the full transitive build closure:
 ///
 ///   sha256(
-///     plan.build_key()           // sources + pipeline scripts
-///     + dep1.fingerprint         // fingerprint of build dependency 1
-///     + dep2.fingerprint         // fingerprint of build dependency 2
+///     plan.build_key()           // sources, scripts, env, executors, isolation
+///     + dep1.digest              // deployed part hashes of build/link dep 1
+///     + dep2.digest              // deployed part hashes of build/link dep 2
 ///     + ...
 ///   )
 ///
@@ -147,6 +147,49 @@ impl CasStore {
         Ok(dest)
     }
 
+    /// Find archives by the sha256 of the archive file — the `part_hash`
+    /// the installed registry and the delivery WAL record.
+    ///
+    /// Searches the store first, then `extra_dirs` (typically `parts_dir`),
+    /// and stops hashing as soon as every wanted hash is found.  Hashes with
+    /// no matching archive are absent from the result.
+    pub fn locate_by_part_hash(
+        &self,
+        extra_dirs: &[&Path],
+        wanted: &HashSet<String>,
+    ) -> Result<HashMap<String, PathBuf>> {
+        let mut found: HashMap<String, PathBuf> = HashMap::new();
+        if wanted.is_empty() {
+            return Ok(found);
+        }
+
+        let dirs = std::iter::once(self.store_dir.as_path()).chain(extra_dirs.iter().copied());
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let is_archive = path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .is_some_and(|n| n.ends_with(".part") || n.ends_with(".wright.tar.zst"));
+                if !is_archive || !path.is_file() {
+                    continue;
+                }
+                let hash = crate::util::checksum::sha256_file(&path)?;
+                if wanted.contains(&hash) && !found.contains_key(&hash) {
+                    debug!(event = "cas.hash_hit", path = %path.display(), "archive matched part hash");
+                    found.insert(hash, path);
+                    if found.len() == wanted.len() {
+                        return Ok(found);
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
     /// Remove a part from the CAS store.
     #[allow(dead_code)]
     pub fn remove(&self, name: &str, fingerprint: &str) -> Result<()> {
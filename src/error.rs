@@ -54,6 +54,10 @@ pub enum WrightError {
     #[error("isolation error: {0}")]
     IsolationError(String),
 
+    /// A build was killed for exceeding its cgroup `memory.max`.
+    #[error("out of memory: {0}")]
+    OutOfMemory(String),
+
     #[error("network error: {0}")]
     NetworkError(String),
 
@@ -66,6 +70,17 @@ pub enum WrightError {
 
 pub type Result<T> = std::result::Result<T, WrightError>;
 
+impl WrightError {
+    /// Prefix the error with `context` as a forge error. An out-of-memory
+    /// kill stays one, so callers can tell it from other stage failures.
+    pub fn in_forge(self, context: impl std::fmt::Display) -> WrightError {
+        match self {
+            WrightError::OutOfMemory(msg) => WrightError::OutOfMemory(format!("{context}: {msg}")),
+            e => WrightError::ForgeError(format!("{context}: {e}")),
+        }
+    }
+}
+
 /// Extension trait that adds `.context()` to any Result,
 /// converting errors into WrightError::ForgeError with a context message.
 pub trait WrightResultExt<T> {
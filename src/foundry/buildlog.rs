@@ -0,0 +1,287 @@
+//! Retained build logs.
+//!
+//! Each build writes its stage logs to `logs/` in the build root, together
+//! with a [`BuildRecord`] of when it ran and how its stages ended. Before
+//! the next build starts, `logs/` is moved to `log-history/<started>/`, and
+//! only the newest `build.log_retention` of those are kept.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, WrightError};
+use crate::foundry::checkpoint::{Checkpoint, StageRecord};
+
+/// Record of one build, kept beside its stage logs.
+pub const BUILD_RECORD: &str = "build.json";
+
+/// Directory in the build root holding the logs of previous builds.
+pub const LOG_HISTORY_DIR: &str = "log-history";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub plan_name: String,
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// `None` while the build runs (or when it was killed).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub succeeded: Option<bool>,
+    /// Checkpoint records of the stages as the build left them.
+    #[serde(default)]
+    pub stages: BTreeMap<String, StageRecord>,
+}
+
+impl BuildRecord {
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.finished_at
+            .map(|end| (end - self.started_at).num_milliseconds() as f64 / 1000.0)
+    }
+
+    pub fn outcome(&self) -> &'static str {
+        match (self.succeeded, self.finished_at) {
+            (Some(true), _) => "success",
+            (Some(false), _) => "failed",
+            (None, Some(_)) => "unknown",
+            (None, None) => "running",
+        }
+    }
+}
+
+/// The logs of one build: the latest in `logs/`, older ones in
+/// `log-history/`.
+#[derive(Debug)]
+pub struct BuildLogs {
+    pub dir: PathBuf,
+    pub record: Option<BuildRecord>,
+}
+
+impl BuildLogs {
+    /// Stage logs in this build, by stage (file stem), sorted.
+    pub fn stage_logs(&self) -> Vec<(String, PathBuf)> {
+        let mut logs: Vec<(String, PathBuf)> = std::fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+            .filter_map(|p| {
+                let stage = p.file_stem()?.to_string_lossy().into_owned();
+                Some((stage, p))
+            })
+            .collect();
+        logs.sort();
+        logs
+    }
+}
+
+/// Move the previous build's `logs/` into `log-history/` and drop the
+/// oldest retained builds beyond `keep`.
+pub fn rotate(build_root: &Path, keep: usize) -> Result<()> {
+    let logs_dir = build_root.join("logs");
+    let is_empty = std::fs::read_dir(&logs_dir).map_or(true, |mut d| d.next().is_none());
+    if !is_empty && keep > 0 {
+        let started = read_record(&logs_dir)
+            .map(|r| r.started_at)
+            .or_else(|| {
+                std::fs::metadata(&logs_dir)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .map(DateTime::<Utc>::from)
+            })
+            .unwrap_or_else(Utc::now);
+        let history = build_root.join(LOG_HISTORY_DIR);
+        std::fs::create_dir_all(&history).map_err(|e| {
+            WrightError::ForgeError(format!("failed to create {}: {e}", history.display()))
+        })?;
+        // Nanoseconds, so builds started in quick succession do not share
+        // a stem and then sort out of order by their collision suffix.
+        let stem = started.format("%Y%m%dT%H%M%S%.9fZ").to_string();
+        let mut dest = history.join(&stem);
+        let mut n = 1;
+        while dest.exists() {
+            dest = history.join(format!("{stem}.{n}"));
+            n += 1;
+        }
+        std::fs::rename(&logs_dir, &dest).map_err(|e| {
+            WrightError::ForgeError(format!(
+                "failed to retain {} as {}: {e}",
+                logs_dir.display(),
+                dest.display()
+            ))
+        })?;
+    }
+
+    let mut retained = history_dirs(build_root);
+    while retained.len() > keep {
+        let oldest = retained.remove(0);
+        if let Err(e) = std::fs::remove_dir_all(&oldest) {
+            tracing::warn!(event = "buildlog.prune_failed", path = %oldest.display(), error = %e, "Failed to drop old build logs");
+        }
+    }
+    Ok(())
+}
+
+/// The record of a running build. Dropping it before [`Recorder::finish`]
+/// records the build as failed.
+pub struct Recorder {
+    logs_dir: PathBuf,
+    build_root: PathBuf,
+    open: bool,
+}
+
+impl Recorder {
+    /// Start the record of a build whose logs go to `logs/` in `build_root`.
+    pub fn begin(build_root: &Path, plan_name: &str, version: &str) -> Result<Self> {
+        let logs_dir = build_root.join("logs");
+        write_record(
+            &logs_dir,
+            &BuildRecord {
+                plan_name: plan_name.to_string(),
+                version: version.to_string(),
+                started_at: Utc::now(),
+                finished_at: None,
+                succeeded: None,
+                stages: BTreeMap::new(),
+            },
+        )?;
+        Ok(Self {
+            logs_dir,
+            build_root: build_root.to_path_buf(),
+            open: true,
+        })
+    }
+
+    /// Record the build as succeeded.
+    pub fn finish(mut self) {
+        self.close(true);
+    }
+
+    /// Close the record with the outcome and the checkpoint's stage records.
+    fn close(&mut self, succeeded: bool) {
+        if !std::mem::take(&mut self.open) {
+            return;
+        }
+        let Some(mut record) = read_record(&self.logs_dir) else {
+            return;
+        };
+        record.finished_at = Some(Utc::now());
+        record.succeeded = Some(succeeded);
+        if let Ok(Some(state)) = Checkpoint::read_state(&self.build_root) {
+            record.stages = state.stages;
+        }
+        if let Err(e) = write_record(&self.logs_dir, &record) {
+            tracing::warn!(event = "buildlog.record_failed", error = %e, "Failed to record build outcome");
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.close(false);
+    }
+}
+
+/// Every retained build of a build root, newest first.
+pub fn list(build_root: &Path) -> Vec<BuildLogs> {
+    let mut dirs = history_dirs(build_root);
+    let current = build_root.join("logs");
+    if current.is_dir() {
+        dirs.push(current);
+    }
+    dirs.into_iter()
+        .rev()
+        .map(|dir| BuildLogs {
+            record: read_record(&dir),
+            dir,
+        })
+        .collect()
+}
+
+/// Wall-clock time a stage took, from the trailer its log ends with.
+pub fn stage_duration(log: &Path) -> Option<f64> {
+    let mut file = std::fs::File::open(log).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(256))).ok()?;
+    let mut tail = String::new();
+    file.read_to_string(&mut tail).ok()?;
+    tail.lines()
+        .rev()
+        .find_map(|l| l.strip_prefix("=== Duration: "))
+        .and_then(|rest| rest.trim_end_matches(" ===").strip_suffix('s'))
+        .and_then(|secs| secs.parse().ok())
+}
+
+fn history_dirs(build_root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(build_root.join(LOG_HISTORY_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    // Directory names are UTC timestamps, so name order is age order.
+    dirs.sort();
+    dirs
+}
+
+fn read_record(dir: &Path) -> Option<BuildRecord> {
+    let raw = std::fs::read_to_string(dir.join(BUILD_RECORD)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn write_record(dir: &Path, record: &BuildRecord) -> Result<()> {
+    let path = dir.join(BUILD_RECORD);
+    let raw = serde_json::to_string_pretty(record)
+        .map_err(|e| WrightError::ForgeError(format!("failed to serialize build record: {e}")))?;
+    std::fs::write(&path, raw)
+        .map_err(|e| WrightError::ForgeError(format!("failed to write {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_retains_newest_builds() {
+        let root = tempfile::tempdir().unwrap();
+        let logs = root.path().join("logs");
+        for i in 0..4 {
+            std::fs::create_dir_all(&logs).unwrap();
+            let recorder = Recorder::begin(root.path(), "zlib", "1.3").unwrap();
+            std::fs::write(logs.join("compile.log"), format!("build {i}")).unwrap();
+            if i == 3 {
+                recorder.finish();
+            } else {
+                drop(recorder);
+            }
+            rotate(root.path(), 2).unwrap();
+            assert!(!logs.exists());
+        }
+        let builds = list(root.path());
+        assert_eq!(builds.len(), 2);
+        let newest = std::fs::read_to_string(builds[0].dir.join("compile.log")).unwrap();
+        assert_eq!(newest, "build 3");
+        assert_eq!(builds[0].record.as_ref().unwrap().outcome(), "success");
+        assert_eq!(builds[1].record.as_ref().unwrap().outcome(), "failed");
+        assert_eq!(builds[0].stage_logs()[0].0, "compile");
+    }
+
+    #[test]
+    fn test_stage_duration_reads_log_trailer() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("compile.log");
+        std::fs::write(
+            &log,
+            "=== Stage: compile ===\n--- stderr ---\n\n=== Exit code: 0 ===\n=== Duration: 12.5s ===\n",
+        )
+        .unwrap();
+        assert_eq!(stage_duration(&log), Some(12.5));
+        std::fs::write(&log, "truncated").unwrap();
+        assert_eq!(stage_duration(&log), None);
+    }
+}
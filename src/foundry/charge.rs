@@ -6,9 +6,11 @@ use tracing::{debug, info, warn};
 
 use crate::config::GlobalConfig;
 use crate::error::{Result, WrightError};
+use crate::foundry::patch;
 use crate::foundry::variables;
+use crate::foundry::vendor::{self, Download};
 use crate::part::store::sanitize_cache_filename;
-use crate::plan::manifest::{PlanManifest, Source};
+use crate::plan::manifest::{Patch, PlanManifest, Source};
 use crate::util::{checksum, compress, download, progress};
 
 /// The built-in stages of source preparation, executed in order by
@@ -55,7 +57,7 @@ impl Charge {
     ) -> Result<ChargeResult> {
         let source_dir = build_root.join("source");
         let marker = build_root.join(".charge_prepared");
-        let fingerprint = self.fingerprint(manifest);
+        let fingerprint = self.fingerprint(manifest, plan_dir);
 
         if marker.exists() {
             if let Ok(stored) = tokio::fs::read_to_string(&marker).await
@@ -89,8 +91,8 @@ impl Charge {
             .map_err(|e| WrightError::ForgeError(format!("failed to create source dir: {e}")))?;
 
         self.fetch(manifest, plan_dir).await?;
-        self.verify(manifest).await?;
-        self.extract(manifest, &source_dir).await?;
+        self.verify(manifest, plan_dir).await?;
+        self.extract(manifest, plan_dir, &source_dir).await?;
 
         tokio::fs::write(&marker, &fingerprint)
             .await
@@ -102,8 +104,9 @@ impl Charge {
         })
     }
 
-    /// Compute a fingerprint of the manifest's sources section.
-    pub fn fingerprint(&self, manifest: &PlanManifest) -> String {
+    /// Compute a fingerprint of the manifest's sources and patches,
+    /// including the content of vendoring lockfiles and local patches.
+    pub fn fingerprint(&self, manifest: &PlanManifest, plan_dir: &Path) -> String {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
         for source in &manifest.sources.entries {
@@ -139,6 +142,53 @@ impl Charge {
                         hasher.update(ext.as_bytes());
                     }
                 }
+                Source::CargoVendor(cargo) => {
+                    hasher.update(b"cargo_vendor");
+                    hasher.update(cargo.registry.as_bytes());
+                    if let Some(ref ext) = cargo.extract_to {
+                        hasher.update(ext.as_bytes());
+                    }
+                }
+                Source::GoMod(go) => {
+                    hasher.update(b"go_mod");
+                    hasher.update(go.proxy.as_bytes());
+                    if let Some(ref ext) = go.extract_to {
+                        hasher.update(ext.as_bytes());
+                    }
+                }
+                Source::PipRequirements(pip) => {
+                    hasher.update(b"pip_requirements");
+                    hasher.update(pip.index.as_bytes());
+                    if let Some(ref ext) = pip.extract_to {
+                        hasher.update(ext.as_bytes());
+                    }
+                }
+            }
+            // A changed lockfile pins different dependencies.
+            if let Some(lockfile) = lockfile_of(source) {
+                hasher.update(lockfile.as_bytes());
+                if let Ok(content) = read_lockfile(plan_dir, lockfile, manifest) {
+                    hasher.update(content.as_bytes());
+                }
+            }
+        }
+        for patch in applicable_patches(manifest) {
+            hasher.update(b"patch");
+            hasher.update(patch.locator().as_bytes());
+            if let Some(ref sha256) = patch.sha256 {
+                hasher.update(sha256.as_bytes());
+            }
+            hasher.update(patch.strip.to_le_bytes());
+            if let Some(ref target) = patch.target {
+                hasher.update(target.as_bytes());
+            }
+            if let Some(ref path) = patch.path {
+                let processed = variables::process_uri(path, manifest);
+                if let Ok(content) = validate_local_path(plan_dir, &processed)
+                    .and_then(|file| std::fs::read(file).map_err(WrightError::IoError))
+                {
+                    hasher.update(&content);
+                }
             }
         }
         format!("{:x}", hasher.finalize())
@@ -161,6 +211,14 @@ impl Charge {
             .iter()
             .map(|source| self.fetch_one(manifest, plan_dir, source));
         futures_util::future::try_join_all(futs).await?;
+
+        let patches: Vec<Download> = applicable_patches(manifest)
+            .filter_map(|patch| patch_download(manifest, patch))
+            .collect();
+        let futs = patches
+            .iter()
+            .map(|download| self.fetch_download(download, &manifest.metadata.name));
+        futures_util::future::try_join_all(futs).await?;
         Ok(())
     }
 
@@ -269,7 +327,129 @@ impl Charge {
                     ))
                 })?;
             }
+            Source::CargoVendor(_) | Source::GoMod(_) => {
+                let downloads = vendor_downloads(manifest, plan_dir, source)?;
+                let futs = downloads
+                    .iter()
+                    .map(|download| self.fetch_download(download, &manifest.metadata.name));
+                futures_util::future::try_join_all(futs).await?;
+            }
+            Source::PipRequirements(pip) => {
+                let content = read_lockfile(plan_dir, &pip.lockfile, manifest)?;
+                let index = variables::process_uri(&pip.index, manifest);
+                let futs = vendor::parse_requirements(&content)?
+                    .into_iter()
+                    .map(|req| self.fetch_requirement(req, &index, &manifest.metadata.name));
+                futures_util::future::try_join_all(futs).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Download one pinned artifact (a vendored dependency or a patch)
+    /// unless the cache already holds a copy matching its checksum.
+    /// Downloads land in a temporary file and are renamed into place only
+    /// once verified.
+    async fn fetch_download(&self, download: &Download, scope: &str) -> Result<()> {
+        let dest = self.cache_dir.join(&download.rel_path);
+        if tokio::fs::metadata(&dest).await.is_ok() {
+            if download.digest.check(&dest)?.is_ok() {
+                debug!(
+                    "{} already cached and verified",
+                    download.rel_path.display()
+                );
+                return Ok(());
+            }
+            warn!(
+                "Cached {} hash mismatch, re-downloading...",
+                download.rel_path.display()
+            );
+            let _ = tokio::fs::remove_file(&dest).await;
+        }
+
+        let _permit = self
+            .network_pool
+            .acquire()
+            .await
+            .expect("network semaphore closed");
+        let file_name = dest
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let partial = dest.with_file_name(format!(".{file_name}.part"));
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(WrightError::IoError)?;
+        }
+        let url = download.url.clone();
+        let partial_owned = partial.clone();
+        let timeout = self.download_timeout;
+        let scope_owned = scope.to_string();
+        tokio::task::spawn_blocking(move || {
+            download::download_file(&url, &partial_owned, timeout, &scope_owned)
+        })
+        .await
+        .map_err(|e| WrightError::ForgeError(format!("download join: {e}")))??;
+        if let Err(actual) = download.digest.check(&partial)? {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(WrightError::ValidationError(format!(
+                "Downloaded file {} failed verification!\n  Expected: {}\n  Actual:   {}",
+                download.url,
+                download.digest.expected(),
+                actual
+            )));
+        }
+        tokio::fs::rename(&partial, &dest)
+            .await
+            .map_err(WrightError::IoError)?;
+        Ok(())
+    }
+
+    /// Resolve a pinned requirement to its release files through the index
+    /// and download them, unless a verified file is already cached.
+    async fn fetch_requirement(
+        &self,
+        req: vendor::Requirement,
+        index: &str,
+        scope: &str,
+    ) -> Result<()> {
+        let cache_dir = self.cache_dir.join(req.cache_dir());
+        if !cached_requirement_files(&cache_dir, &req)?.is_empty() {
+            debug!("Requirement {}=={} already cached", req.name, req.version);
+            return Ok(());
+        }
+        tokio::fs::create_dir_all(&cache_dir)
+            .await
+            .map_err(WrightError::IoError)?;
+
+        let metadata_path = cache_dir.join(".metadata.json");
+        {
+            let _permit = self
+                .network_pool
+                .acquire()
+                .await
+                .expect("network semaphore closed");
+            let url = req.metadata_url(index);
+            let dest = metadata_path.clone();
+            let timeout = self.download_timeout;
+            let scope_owned = scope.to_string();
+            tokio::task::spawn_blocking(move || {
+                download::download_file(&url, &dest, timeout, &scope_owned)
+            })
+            .await
+            .map_err(|e| WrightError::ForgeError(format!("download join: {e}")))??;
         }
+        let metadata = tokio::fs::read_to_string(&metadata_path)
+            .await
+            .map_err(WrightError::IoError)?;
+        let _ = tokio::fs::remove_file(&metadata_path).await;
+
+        let downloads = req.downloads(&metadata)?;
+        let futs = downloads
+            .iter()
+            .map(|download| self.fetch_download(download, scope));
+        futures_util::future::try_join_all(futs).await?;
         Ok(())
     }
 
@@ -489,10 +669,29 @@ impl Charge {
     // Verify
     // ------------------------------------------------------------------
 
-    async fn verify(&self, manifest: &PlanManifest) -> Result<()> {
+    async fn verify(&self, manifest: &PlanManifest, plan_dir: &Path) -> Result<()> {
         for (i, source) in manifest.sources.entries.iter().enumerate() {
             let http = match source {
                 Source::Http(h) => h,
+                Source::CargoVendor(_) | Source::GoMod(_) => {
+                    for download in vendor_downloads(manifest, plan_dir, source)? {
+                        self.verify_download(&download.rel_path, &download.digest)?;
+                    }
+                    continue;
+                }
+                Source::PipRequirements(pip) => {
+                    let content = read_lockfile(plan_dir, &pip.lockfile, manifest)?;
+                    for req in vendor::parse_requirements(&content)? {
+                        let cache_dir = self.cache_dir.join(req.cache_dir());
+                        if cached_requirement_files(&cache_dir, &req)?.is_empty() {
+                            return Err(WrightError::ValidationError(format!(
+                                "source file missing: {}",
+                                req.cache_dir().display()
+                            )));
+                        }
+                    }
+                    continue;
+                }
                 _ => {
                     debug!("Skipping verification for non-HTTP source {}", i);
                     continue;
@@ -522,6 +721,39 @@ impl Charge {
             }
             debug!("Verified source: {}", filename);
         }
+        for patch in applicable_patches(manifest) {
+            if let Some(download) = patch_download(manifest, patch) {
+                self.verify_download(&download.rel_path, &download.digest)?;
+            } else if let (Some(path), Some(sha256)) = (&patch.path, &patch.sha256) {
+                let processed = variables::process_uri(path, manifest);
+                let file = validate_local_path(plan_dir, &processed)?;
+                let actual = checksum::sha256_file(&file)?;
+                if actual != *sha256 {
+                    return Err(WrightError::ValidationError(format!(
+                        "SHA256 mismatch for patch {processed}:\n  expected: {sha256}\n  actual:   {actual}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_download(&self, rel_path: &Path, digest: &vendor::Digest) -> Result<()> {
+        let path = self.cache_dir.join(rel_path);
+        if !path.is_file() {
+            return Err(WrightError::ValidationError(format!(
+                "source file missing: {}",
+                rel_path.display()
+            )));
+        }
+        if let Err(actual) = digest.check(&path)? {
+            return Err(WrightError::ValidationError(format!(
+                "checksum mismatch for {}:\n  expected: {}\n  actual:   {}",
+                rel_path.display(),
+                digest.expected(),
+                actual
+            )));
+        }
         Ok(())
     }
 
@@ -529,7 +761,12 @@ impl Charge {
     // Extract
     // ------------------------------------------------------------------
 
-    async fn extract(&self, manifest: &PlanManifest, dest_dir: &Path) -> Result<PathBuf> {
+    async fn extract(
+        &self,
+        manifest: &PlanManifest,
+        plan_dir: &Path,
+        dest_dir: &Path,
+    ) -> Result<PathBuf> {
         let mut placed: HashSet<PathBuf> = HashSet::new();
         for source in &manifest.sources.entries {
             match source {
@@ -695,11 +932,102 @@ impl Charge {
                         })?;
                     }
                 }
+                Source::CargoVendor(cargo) => {
+                    let final_dest = vendor_dest(manifest, dest_dir, &cargo.extract_to).await?;
+                    let content = read_lockfile(plan_dir, &cargo.lockfile, manifest)?;
+                    let vendor_dir = final_dest.join(vendor::VENDOR_DIR);
+                    tokio::fs::create_dir_all(&vendor_dir)
+                        .await
+                        .map_err(WrightError::IoError)?;
+                    let _span = crate::cli_span!(
+                        "Extracting",
+                        "{} ({})",
+                        cargo.lockfile,
+                        manifest.metadata.name
+                    );
+                    for krate in vendor::parse_cargo_lock(&content)? {
+                        let crate_file = self.cache_dir.join(krate.cache_path());
+                        vendor::unpack_crate(&crate_file, &krate, &vendor_dir)?;
+                    }
+                    vendor::write_cargo_config(&final_dest)?;
+                }
+                Source::GoMod(go) => {
+                    let final_dest = vendor_dest(manifest, dest_dir, &go.extract_to).await?;
+                    let content = read_lockfile(plan_dir, &go.lockfile, manifest)?;
+                    let goproxy_dir = final_dest.join(vendor::VENDOR_DIR).join("goproxy");
+                    for module in vendor::parse_go_sum(&content)? {
+                        vendor::place_go_module(&self.cache_dir, &module, &goproxy_dir)?;
+                    }
+                }
+                Source::PipRequirements(pip) => {
+                    let final_dest = vendor_dest(manifest, dest_dir, &pip.extract_to).await?;
+                    let content = read_lockfile(plan_dir, &pip.lockfile, manifest)?;
+                    let wheelhouse = final_dest.join(vendor::VENDOR_DIR).join("pypi");
+                    tokio::fs::create_dir_all(&wheelhouse)
+                        .await
+                        .map_err(WrightError::IoError)?;
+                    for req in vendor::parse_requirements(&content)? {
+                        let cache_dir = self.cache_dir.join(req.cache_dir());
+                        for file in cached_requirement_files(&cache_dir, &req)? {
+                            let dest = wheelhouse.join(file.file_name().unwrap_or_default());
+                            claim_workdir_dest(&mut placed, &dest)?;
+                            tokio::fs::copy(&file, &dest)
+                                .await
+                                .map_err(WrightError::IoError)?;
+                        }
+                    }
+                }
             }
         }
+        self.apply_patches(manifest, plan_dir, dest_dir)?;
         Ok(dest_dir.to_path_buf())
     }
 
+    /// Apply the plan's `[[patches]]` that match its version, in order.
+    fn apply_patches(
+        &self,
+        manifest: &PlanManifest,
+        plan_dir: &Path,
+        dest_dir: &Path,
+    ) -> Result<()> {
+        for patch in applicable_patches(manifest) {
+            let file = match patch_download(manifest, patch) {
+                Some(download) => self.cache_dir.join(download.rel_path),
+                None => {
+                    let processed = variables::process_uri(patch.locator(), manifest);
+                    validate_local_path(plan_dir, &processed)?
+                }
+            };
+            let content = std::fs::read(&file).map_err(|e| {
+                WrightError::ForgeError(format!("failed to read patch {}: {e}", patch.locator()))
+            })?;
+            let root = match patch.target {
+                Some(ref sub) => {
+                    let sub = variables::process_uri(sub, manifest);
+                    patch::beneath(dest_dir, &sub).map_err(|msg| {
+                        WrightError::ForgeError(format!(
+                            "patch {}: target {sub}: {msg}",
+                            patch.locator()
+                        ))
+                    })?
+                }
+                None => dest_dir.to_path_buf(),
+            };
+            if !root.is_dir() {
+                return Err(WrightError::ForgeError(format!(
+                    "patch {}: target directory {} does not exist after extract",
+                    patch.locator(),
+                    root.strip_prefix(dest_dir).unwrap_or(&root).display()
+                )));
+            }
+            let label = progress::source_label(patch.locator());
+            let _span = crate::cli_span!("Patching", "{} ({})", label, manifest.metadata.name);
+            let files = patch::apply(&content, patch.locator(), &root, patch.strip)?;
+            debug!("Applied patch {} to {} file(s)", patch.locator(), files);
+        }
+        Ok(())
+    }
+
     // ------------------------------------------------------------------
     // Hash update utility
     // ------------------------------------------------------------------
@@ -737,6 +1065,8 @@ impl Charge {
                 Source::Git(_) | Source::Local(_) => {
                     new_hashes.push("SKIP".to_string());
                 }
+                // Pinned by their lockfile; no `sha256` field to rewrite.
+                Source::CargoVendor(_) | Source::GoMod(_) | Source::PipRequirements(_) => {}
             }
         }
         if new_hashes.is_empty() {
@@ -801,6 +1131,104 @@ impl Charge {
     }
 }
 
+/// A source-cache entry a plan's sources resolve to.
+#[derive(Debug, Clone)]
+pub struct CachedSource {
+    /// Path relative to `general.source_dir`: the cache filename for HTTP
+    /// and local sources, `git/<mirror>` for git sources.
+    pub rel_path: PathBuf,
+    /// Source URL or local path, with `${VAR}` expanded.
+    pub locator: String,
+    /// Declared sha256 of an HTTP source; `None` for `SKIP`, git, and local.
+    pub sha256: Option<String>,
+}
+
+/// The source-cache entries `Charge::prepare` reads and writes for
+/// `manifest`, named exactly as `fetch` names them. Vendoring sources
+/// contribute one entry per dependency their lockfile pins, so reading
+/// the lockfile from `plan_dir` can fail.
+pub fn cached_sources(manifest: &PlanManifest, plan_dir: &Path) -> Result<Vec<CachedSource>> {
+    let mut cached = Vec::new();
+    for source in &manifest.sources.entries {
+        match source {
+            Source::Http(http) => {
+                let url = variables::process_uri(&http.url, manifest);
+                cached.push(CachedSource {
+                    rel_path: PathBuf::from(
+                        http.r#as.clone().unwrap_or_else(|| {
+                            source_cache_filename(&manifest.metadata.name, &url)
+                        }),
+                    ),
+                    sha256: (http.sha256 != "SKIP").then(|| http.sha256.clone()),
+                    locator: url,
+                });
+            }
+            Source::Git(git) => {
+                let url = variables::process_uri(&git.url, manifest);
+                cached.push(CachedSource {
+                    rel_path: Path::new("git").join(git_cache_dir_name(&url)),
+                    sha256: None,
+                    locator: url,
+                });
+            }
+            Source::Local(local) => {
+                let path = variables::process_uri(&local.path, manifest);
+                cached.push(CachedSource {
+                    rel_path: PathBuf::from(
+                        local.r#as.clone().unwrap_or_else(|| {
+                            source_cache_filename(&manifest.metadata.name, &path)
+                        }),
+                    ),
+                    sha256: None,
+                    locator: path,
+                });
+            }
+            Source::CargoVendor(cargo) => {
+                let content = read_lockfile(plan_dir, &cargo.lockfile, manifest)?;
+                let registry = variables::process_uri(&cargo.registry, manifest);
+                for krate in vendor::parse_cargo_lock(&content)? {
+                    let download = krate.download(&registry);
+                    cached.push(CachedSource {
+                        rel_path: download.rel_path,
+                        sha256: Some(krate.checksum),
+                        locator: download.url,
+                    });
+                }
+            }
+            Source::GoMod(go) => {
+                let content = read_lockfile(plan_dir, &go.lockfile, manifest)?;
+                for module in vendor::parse_go_sum(&content)? {
+                    cached.push(CachedSource {
+                        rel_path: module.cache_dir(),
+                        sha256: None,
+                        locator: format!("{}@{}", module.path, module.version),
+                    });
+                }
+            }
+            Source::PipRequirements(pip) => {
+                let content = read_lockfile(plan_dir, &pip.lockfile, manifest)?;
+                for req in vendor::parse_requirements(&content)? {
+                    cached.push(CachedSource {
+                        rel_path: req.cache_dir(),
+                        sha256: None,
+                        locator: format!("{}=={}", req.name, req.version),
+                    });
+                }
+            }
+        }
+    }
+    for patch in &manifest.patches {
+        if let Some(download) = patch_download(manifest, patch) {
+            cached.push(CachedSource {
+                rel_path: download.rel_path,
+                sha256: patch.sha256.clone(),
+                locator: download.url,
+            });
+        }
+    }
+    Ok(cached)
+}
+
 // ------------------------------------------------------------------
 // Helpers
 // ------------------------------------------------------------------
@@ -810,6 +1238,109 @@ fn source_cache_filename(part_name: &str, uri: &str) -> String {
     sanitize_cache_filename(&format!("{}-{}", part_name, basename))
 }
 
+/// The `[[patches]]` whose `versions` the plan version satisfies.
+fn applicable_patches(manifest: &PlanManifest) -> impl Iterator<Item = &Patch> {
+    let version = manifest.metadata.version.as_deref();
+    manifest
+        .patches
+        .iter()
+        .filter(move |patch| patch.applies_to(version))
+}
+
+/// Where a `url` patch is downloaded to; `None` for `path` patches.
+fn patch_download(manifest: &PlanManifest, patch: &Patch) -> Option<Download> {
+    let url = variables::process_uri(patch.url.as_deref()?, manifest);
+    Some(Download {
+        rel_path: PathBuf::from(source_cache_filename(&manifest.metadata.name, &url)),
+        digest: vendor::Digest::Sha256(patch.sha256.clone().unwrap_or_default()),
+        url,
+    })
+}
+
+/// The lockfile a vendoring source is driven by.
+fn lockfile_of(source: &Source) -> Option<&str> {
+    match source {
+        Source::CargoVendor(cargo) => Some(&cargo.lockfile),
+        Source::GoMod(go) => Some(&go.lockfile),
+        Source::PipRequirements(pip) => Some(&pip.lockfile),
+        Source::Http(_) | Source::Git(_) | Source::Local(_) => None,
+    }
+}
+
+/// Read a vendoring lockfile, which like a `local` source must live
+/// inside the plan directory.
+fn read_lockfile(plan_dir: &Path, lockfile: &str, manifest: &PlanManifest) -> Result<String> {
+    let processed = variables::process_uri(lockfile, manifest);
+    let path = validate_local_path(plan_dir, &processed)?;
+    std::fs::read_to_string(&path).map_err(|e| {
+        WrightError::ValidationError(format!("failed to read lockfile {processed}: {e}"))
+    })
+}
+
+/// The artifacts a `cargo_vendor` or `go_mod` source pins. Downloads of
+/// `pip_requirements` are only known after asking the index.
+fn vendor_downloads(
+    manifest: &PlanManifest,
+    plan_dir: &Path,
+    source: &Source,
+) -> Result<Vec<Download>> {
+    match source {
+        Source::CargoVendor(cargo) => {
+            let content = read_lockfile(plan_dir, &cargo.lockfile, manifest)?;
+            let registry = variables::process_uri(&cargo.registry, manifest);
+            Ok(vendor::parse_cargo_lock(&content)?
+                .iter()
+                .map(|krate| krate.download(&registry))
+                .collect())
+        }
+        Source::GoMod(go) => {
+            let content = read_lockfile(plan_dir, &go.lockfile, manifest)?;
+            let proxy = variables::process_uri(&go.proxy, manifest);
+            Ok(vendor::parse_go_sum(&content)?
+                .iter()
+                .flat_map(|module| module.downloads(&proxy))
+                .collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Files cached for a pip requirement whose sha256 it pins.
+fn cached_requirement_files(cache_dir: &Path, req: &vendor::Requirement) -> Result<Vec<PathBuf>> {
+    let listing = match std::fs::read_dir(cache_dir) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(WrightError::IoError(e)),
+    };
+    let digest = vendor::Digest::AnySha256(req.hashes.clone());
+    let mut files = Vec::new();
+    for item in listing.flatten() {
+        let path = item.path();
+        let hidden = item.file_name().to_string_lossy().starts_with('.');
+        if !hidden && path.is_file() && digest.check(&path)?.is_ok() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// `${WORKDIR}`, or its `extract_to` subdirectory, created.
+async fn vendor_dest(
+    manifest: &PlanManifest,
+    dest_dir: &Path,
+    extract_to: &Option<String>,
+) -> Result<PathBuf> {
+    let Some(sub) = extract_to else {
+        return Ok(dest_dir.to_path_buf());
+    };
+    let p = dest_dir.join(variables::process_uri(sub, manifest));
+    tokio::fs::create_dir_all(&p)
+        .await
+        .map_err(WrightError::IoError)?;
+    Ok(p)
+}
+
 /// Destination filename for a non-archive source placed in the work
 /// directory: the source's own basename. The part-name prefix only exists to
 /// namespace the shared source cache and must not leak into ${WORKDIR}.
@@ -1013,7 +1544,10 @@ extract_to = "source"
         let dest_dir = root.path().join("work");
         std::fs::create_dir_all(&dest_dir).unwrap();
 
-        charge.extract(&manifest, &dest_dir).await.unwrap();
+        charge
+            .extract(&manifest, root.path(), &dest_dir)
+            .await
+            .unwrap();
 
         assert_eq!(
             std::fs::read_to_string(dest_dir.join("source/payload.txt")).unwrap(),
@@ -1061,7 +1595,7 @@ path = "demo.service"
         std::fs::create_dir_all(&dest_dir).unwrap();
 
         test_charge(sources_dir)
-            .extract(&manifest, &dest_dir)
+            .extract(&manifest, root.path(), &dest_dir)
             .await
             .unwrap();
 
@@ -1091,7 +1625,7 @@ url = "https://example.invalid/data.bin"
         std::fs::create_dir_all(&dest_dir).unwrap();
 
         test_charge(sources_dir)
-            .extract(&manifest, &dest_dir)
+            .extract(&manifest, root.path(), &dest_dir)
             .await
             .unwrap();
 
@@ -1120,7 +1654,7 @@ as = "renamed.conf"
         std::fs::create_dir_all(&dest_dir).unwrap();
 
         test_charge(sources_dir)
-            .extract(&manifest, &dest_dir)
+            .extract(&manifest, root.path(), &dest_dir)
             .await
             .unwrap();
 
@@ -1152,7 +1686,7 @@ path = "b/app.json"
         std::fs::create_dir_all(&dest_dir).unwrap();
 
         let err = test_charge(sources_dir)
-            .extract(&manifest, &dest_dir)
+            .extract(&manifest, root.path(), &dest_dir)
             .await
             .unwrap_err();
 
@@ -1161,4 +1695,186 @@ path = "b/app.json"
             "unexpected error: {err}"
         );
     }
+
+    #[tokio::test]
+    async fn prepare_applies_patches_matching_the_version() {
+        let root = tempfile::tempdir().unwrap();
+        let plan_dir = root.path().join("plan");
+        std::fs::create_dir_all(plan_dir.join("patches")).unwrap();
+        std::fs::write(
+            plan_dir.join("hello.c"),
+            "int main(void) {\n    return 1;\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            plan_dir.join("patches/fix.patch"),
+            "--- a/hello.c\n+++ b/hello.c\n@@ -1,3 +1,3 @@\n int main(void) {\n-    return 1;\n+    return 0;\n }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            plan_dir.join("patches/legacy.patch"),
+            "--- a/missing.c\n+++ b/missing.c\n@@ -1 +1 @@\n-a\n+b\n",
+        )
+        .unwrap();
+        let manifest = test_manifest(
+            r#"
+[[sources]]
+type = "local"
+path = "hello.c"
+
+[[patches]]
+path = "patches/fix.patch"
+
+[[patches]]
+path = "patches/legacy.patch"
+versions = ["< 1.0"]
+"#,
+        );
+        let charge = test_charge(root.path().join("sources"));
+
+        let before = charge.fingerprint(&manifest, &plan_dir);
+        let result = charge
+            .prepare(&manifest, &plan_dir, &root.path().join("build"))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(result.dir.join("hello.c")).unwrap(),
+            "int main(void) {\n    return 0;\n}\n"
+        );
+
+        std::fs::write(
+            plan_dir.join("patches/fix.patch"),
+            "--- a/hello.c\n+++ b/hello.c\n@@ -1,3 +1,3 @@\n int main(void) {\n-    return 2;\n+    return 0;\n }\n",
+        )
+        .unwrap();
+        assert_ne!(charge.fingerprint(&manifest, &plan_dir), before);
+        let Err(err) = charge
+            .prepare(&manifest, &plan_dir, &root.path().join("build"))
+            .await
+        else {
+            panic!("a stale patch applied");
+        };
+        let err = err.to_string();
+        assert!(
+            err.contains("patch patches/fix.patch: hunk #1 of hello.c did not apply"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn prepare_vendors_cargo_lock_crates() {
+        let root = tempfile::tempdir().unwrap();
+        let registry = root.path().join("registry");
+        std::fs::create_dir_all(registry.join("dep")).unwrap();
+        let crate_file = registry.join("dep/dep-0.1.0.crate");
+        {
+            let gz = flate2::write::GzEncoder::new(
+                std::fs::File::create(&crate_file).unwrap(),
+                flate2::Compression::default(),
+            );
+            let mut tar = tar::Builder::new(gz);
+            let body = b"pub fn dep() {}\n";
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, "dep-0.1.0/src/lib.rs", &body[..])
+                .unwrap();
+            tar.into_inner().unwrap().finish().unwrap();
+        }
+        let good = checksum::sha256_file(&crate_file).unwrap();
+
+        let plan_dir = root.path().join("plan");
+        std::fs::create_dir_all(&plan_dir).unwrap();
+        let write_lock = |sum: &str| {
+            std::fs::write(
+                plan_dir.join("Cargo.lock"),
+                format!(
+                    "version = 3\n\n[[package]]\nname = \"demo\"\nversion = \"1.0.0\"\n\n\
+                     [[package]]\nname = \"dep\"\nversion = \"0.1.0\"\n\
+                     source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+                     checksum = \"{sum}\"\n"
+                ),
+            )
+            .unwrap();
+        };
+        let manifest = test_manifest(&format!(
+            r#"
+[[sources]]
+type = "cargo_vendor"
+lockfile = "Cargo.lock"
+registry = "file://{}"
+"#,
+            registry.display()
+        ));
+        let sources_dir = root.path().join("sources");
+        let charge = test_charge(sources_dir.clone());
+
+        write_lock(&"0".repeat(64));
+        let Err(err) = charge
+            .prepare(&manifest, &plan_dir, &root.path().join("bad"))
+            .await
+        else {
+            panic!("a crate with the wrong checksum was vendored");
+        };
+        let err = err.to_string();
+        assert!(err.contains("failed verification"), "{err}");
+        assert!(!sources_dir.join("vendor/crates/dep-0.1.0.crate").exists());
+
+        write_lock(&good);
+        let result = charge
+            .prepare(&manifest, &plan_dir, &root.path().join("build"))
+            .await
+            .unwrap();
+        assert!(sources_dir.join("vendor/crates/dep-0.1.0.crate").is_file());
+        assert_eq!(
+            std::fs::read_to_string(result.dir.join("vendor/dep-0.1.0/src/lib.rs")).unwrap(),
+            "pub fn dep() {}\n"
+        );
+        let checksum_json =
+            std::fs::read_to_string(result.dir.join("vendor/dep-0.1.0/.cargo-checksum.json"))
+                .unwrap();
+        assert!(checksum_json.contains(&good), "{checksum_json}");
+        let config = std::fs::read_to_string(result.dir.join(".cargo/config.toml")).unwrap();
+        assert!(config.contains("directory = \"vendor\""), "{config}");
+    }
+
+    #[test]
+    fn cached_sources_match_fetch_naming() {
+        let manifest = test_manifest(
+            r#"
+[[sources]]
+type = "http"
+url = "https://example.com/app-${VERSION}.tar.gz"
+sha256 = "abc123"
+
+[[sources]]
+type = "http"
+url = "https://example.com/extra.patch"
+sha256 = "SKIP"
+as = "extra.patch"
+
+[[sources]]
+type = "git"
+url = "https://example.com/app.git"
+"#,
+        );
+
+        let sources = cached_sources(&manifest, Path::new(".")).unwrap();
+        let paths: Vec<PathBuf> = sources.iter().map(|s| s.rel_path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from(source_cache_filename(
+                    "demo",
+                    "https://example.com/app-1.0.0.tar.gz"
+                )),
+                PathBuf::from("extra.patch"),
+                Path::new("git").join(git_cache_dir_name("https://example.com/app.git")),
+            ]
+        );
+        assert_eq!(sources[0].locator, "https://example.com/app-1.0.0.tar.gz");
+        assert_eq!(sources[0].sha256.as_deref(), Some("abc123"));
+        assert_eq!(sources[1].sha256, None);
+    }
 }
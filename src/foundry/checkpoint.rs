@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
 use crate::error::{Result, WrightError};
+use crate::foundry::inputs::BuildInputs;
+use crate::resolve::RebuildReason;
+
+/// Checkpoint state file at the top of every build root.
+pub const CHECKPOINT_FILE: &str = ".wright-checkpoint.json";
 
 /// Build stage order for checkpoint rewind. Source stages are NOT included.
 pub const STAGE_ORDER: &[&str] = &["prepare", "configure", "compile", "check", "staging"];
@@ -14,6 +19,12 @@ pub struct ForgeState {
     pub plan_name: String,
     pub version: String,
     pub stages: BTreeMap<String, StageRecord>,
+    /// Build key the recorded stages were forged under.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub build_key: String,
+    /// Per-input digests behind `build_key`, kept to explain a rebuild.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,27 +57,14 @@ pub struct Checkpoint {
 
 impl Checkpoint {
     pub fn load(work_dir: PathBuf, plan_name: &str, version: &str) -> Result<Self> {
-        let state_path = work_dir.join(".wright-checkpoint.json");
-        let state = if state_path.exists() {
-            let raw = std::fs::read_to_string(&state_path).map_err(|e| {
-                WrightError::ForgeError(format!(
-                    "failed to read checkpoint state {}: {e}",
-                    state_path.display()
-                ))
-            })?;
-            serde_json::from_str::<ForgeState>(&raw).map_err(|e| {
-                WrightError::ForgeError(format!(
-                    "failed to parse checkpoint state {}: {e}",
-                    state_path.display()
-                ))
-            })?
-        } else {
-            ForgeState {
-                plan_name: plan_name.to_string(),
-                version: version.to_string(),
-                stages: BTreeMap::new(),
-            }
-        };
+        let state_path = work_dir.join(CHECKPOINT_FILE);
+        let state = Self::read_state(&work_dir)?.unwrap_or_else(|| ForgeState {
+            plan_name: plan_name.to_string(),
+            version: version.to_string(),
+            stages: BTreeMap::new(),
+            build_key: String::new(),
+            inputs: BTreeMap::new(),
+        });
         Ok(Self {
             state_path,
             state,
@@ -74,6 +72,29 @@ impl Checkpoint {
         })
     }
 
+    /// Read the checkpoint state of a build root without taking ownership
+    /// of it; `None` when no stage has run there yet.
+    pub fn read_state(work_dir: &Path) -> Result<Option<ForgeState>> {
+        let state_path = work_dir.join(CHECKPOINT_FILE);
+        if !state_path.exists() {
+            return Ok(None);
+        }
+        let raw = std::fs::read_to_string(&state_path).map_err(|e| {
+            WrightError::ForgeError(format!(
+                "failed to read checkpoint state {}: {e}",
+                state_path.display()
+            ))
+        })?;
+        serde_json::from_str::<ForgeState>(&raw)
+            .map(Some)
+            .map_err(|e| {
+                WrightError::ForgeError(format!(
+                    "failed to parse checkpoint state {}: {e}",
+                    state_path.display()
+                ))
+            })
+    }
+
     fn save(&self) -> Result<()> {
         if let Some(parent) = self.state_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| {
@@ -256,6 +277,27 @@ impl Checkpoint {
         self.state.stages.clear();
     }
 
+    /// Why stages recorded here are stale for `inputs`: `None` when nothing
+    /// was forged yet or it was forged under the same build key.
+    pub fn stale_reason(&self, inputs: &BuildInputs) -> Option<RebuildReason> {
+        if self.state.stages.is_empty() && self.state.build_key.is_empty() {
+            return None;
+        }
+        if self.state.build_key == inputs.key() {
+            return None;
+        }
+        Some(RebuildReason::InputsChanged(
+            inputs.changed_since(&self.state.inputs),
+        ))
+    }
+
+    /// Record `inputs` as the ones the stages are being forged under.
+    pub fn record_inputs(&mut self, inputs: &BuildInputs) -> Result<()> {
+        self.state.build_key = inputs.key();
+        self.state.inputs = inputs.digests();
+        self.save()
+    }
+
     pub fn state(&self) -> &ForgeState {
         &self.state
     }
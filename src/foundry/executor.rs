@@ -5,8 +5,10 @@ use tracing::debug;
 
 use crate::error::{Result, WrightError};
 use crate::foundry::variables;
+use crate::isolation::harden::Hardening;
 use crate::isolation::{
-    IsolationConfig, IsolationLevel, IsolationOutput, ResourceLimits, run_in_isolation,
+    IsolationConfig, IsolationLevel, IsolationOutput, NetworkProxy, ResourceLimits,
+    run_in_isolation,
 };
 
 #[derive(Debug, Deserialize, Clone)]
@@ -115,6 +117,8 @@ pub struct ExecutorOptions {
     pub cpu_count: Option<u32>,
     pub log_stdout: Option<std::fs::File>,
     pub dep_mounts: Vec<(PathBuf, PathBuf)>,
+    pub hardening: Hardening,
+    pub proxy: Option<NetworkProxy>,
 }
 
 pub async fn execute_script(
@@ -125,7 +129,86 @@ pub async fn execute_script(
     vars: &HashMap<String, String>,
     options: &mut ExecutorOptions,
 ) -> Result<IsolationOutput> {
-    let effective_vars = if options.level != IsolationLevel::None {
+    let effective_vars = effective_vars(vars, options);
+
+    let expanded = variables::substitute(script, &effective_vars);
+    let script_name = format!(".wright_script{}", executor.tempfile_extension);
+    let script_path = working_dir.join(&script_name);
+    tokio::fs::write(&script_path, &expanded)
+        .await
+        .map_err(|e| WrightError::ForgeError(format!("failed to write forge script: {}", e)))?;
+
+    // Defensive sync: on some kernels/fs configs, a file written via async I/O
+    // may briefly appear busy to execve.  Ensure the script is fully persisted
+    // before we hand it to the executor.
+    if let Ok(file) = tokio::fs::File::open(&script_path).await {
+        let _ = file.sync_all().await;
+    }
+
+    let mut config = isolation_config(env_vars, vars, &effective_vars, options);
+
+    let mut args = executor.args.clone();
+    if executor.delivery == "tempfile" {
+        if options.level == IsolationLevel::None {
+            args.push(script_path.to_string_lossy().to_string());
+        } else {
+            args.push(format!("/build/{}", script_name));
+        }
+    }
+
+    let command = executor.command.clone();
+    let mut output =
+        tokio::task::spawn_blocking(move || run_in_isolation(&mut config, &command, &args))
+            .await
+            .map_err(|e| WrightError::ForgeError(format!("spawn_blocking failed: {}", e)))??;
+
+    if output.status.code() != Some(0) {
+        let mut remapped_stderr = output.stderr.tail.clone();
+        remapped_stderr = remapped_stderr.replace("/main-part", "${MAIN_STAGING_DIR}");
+        remapped_stderr = remapped_stderr.replace("/output", "${STAGING_DIR}");
+        remapped_stderr = remapped_stderr.replace("/build", "${WORKDIR}");
+        output.stderr.tail = remapped_stderr;
+    }
+
+    Ok(output)
+}
+
+/// Launches bash when the isolation root has it, plain sh otherwise.
+const SHELL_LAUNCHER: &str =
+    "if command -v bash >/dev/null 2>&1; then exec bash -i; else exec sh -i; fi";
+
+/// Open an interactive shell on the caller's terminal in the environment
+/// `execute_script` would give a stage: the same isolation level, mounts,
+/// variables, and stage `env_vars`. `PS1` is set to `prompt`. The stage
+/// timeout does not apply.
+pub async fn open_shell(
+    env_vars: &HashMap<String, String>,
+    vars: &HashMap<String, String>,
+    prompt: &str,
+    options: &mut ExecutorOptions,
+) -> Result<std::process::ExitStatus> {
+    let effective_vars = effective_vars(vars, options);
+    let mut config = isolation_config(env_vars, vars, &effective_vars, options);
+    config.interactive = true;
+    config.rlimits.timeout_secs = None;
+    config.env.retain(|(k, _)| k != "PS1");
+    config.env.push(("PS1".to_string(), prompt.to_string()));
+
+    let args = vec!["-c".to_string(), SHELL_LAUNCHER.to_string()];
+    let output =
+        tokio::task::spawn_blocking(move || run_in_isolation(&mut config, "/bin/sh", &args))
+            .await
+            .map_err(|e| WrightError::ForgeError(format!("spawn_blocking failed: {}", e)))??;
+    Ok(output.status)
+}
+
+/// Stage variables as seen inside the isolation: `WORKDIR` and the staging
+/// directories point at their mount points unless isolation is off.
+fn effective_vars(
+    vars: &HashMap<String, String>,
+    options: &ExecutorOptions,
+) -> HashMap<String, String> {
+    if options.level != IsolationLevel::None {
         let mut v = vars.clone();
         v.insert("WORKDIR".to_string(), "/build".to_string());
         v.insert("STAGING_DIR".to_string(), "/output".to_string());
@@ -140,22 +223,15 @@ pub async fn execute_script(
         v
     } else {
         vars.clone()
-    };
-
-    let expanded = variables::substitute(script, &effective_vars);
-    let script_name = format!(".wright_script{}", executor.tempfile_extension);
-    let script_path = working_dir.join(&script_name);
-    tokio::fs::write(&script_path, &expanded)
-        .await
-        .map_err(|e| WrightError::ForgeError(format!("failed to write forge script: {}", e)))?;
-
-    // Defensive sync: on some kernels/fs configs, a file written via async I/O
-    // may briefly appear busy to execve.  Ensure the script is fully persisted
-    // before we hand it to the executor.
-    if let Ok(file) = tokio::fs::File::open(&script_path).await {
-        let _ = file.sync_all().await;
     }
+}
 
+fn isolation_config(
+    env_vars: &HashMap<String, String>,
+    vars: &HashMap<String, String>,
+    effective_vars: &HashMap<String, String>,
+    options: &mut ExecutorOptions,
+) -> IsolationConfig {
     let task_id = format!(
         "{}-{}",
         vars.get("NAME")
@@ -179,6 +255,8 @@ pub async fn execute_script(
     config.cpu_count = options.cpu_count;
     config.log_stdout = options.log_stdout.take();
     config.dep_mounts = std::mem::take(&mut options.dep_mounts);
+    config.hardening = options.hardening.clone();
+    config.proxy = options.proxy.clone();
 
     if let Some(ref main_part) = options.main_part_dir {
         config
@@ -187,11 +265,11 @@ pub async fn execute_script(
     }
 
     for (key, value) in env_vars {
-        let expanded_value = variables::substitute(value, &effective_vars);
+        let expanded_value = variables::substitute(value, effective_vars);
         config.env.push((key.clone(), expanded_value));
     }
 
-    for (key, value) in &effective_vars {
+    for (key, value) in effective_vars {
         if !config.env.iter().any(|(k, _)| k == key) {
             config.env.push((key.clone(), value.clone()));
         }
@@ -227,32 +305,5 @@ pub async fn execute_script(
         }
     }
 
-    let mut args = executor.args.clone();
-    if executor.delivery == "tempfile" {
-        if options.level == IsolationLevel::None {
-            args.push(script_path.to_string_lossy().to_string());
-        } else {
-            args.push(format!("/build/{}", script_name));
-        }
-    }
-
-    let command = executor.command.clone();
-    let mut output =
-        tokio::task::spawn_blocking(move || run_in_isolation(&mut config, &command, &args))
-            .await
-            .map_err(|e| WrightError::ForgeError(format!("spawn_blocking failed: {}", e)))??;
-
-    if output.status.code() != Some(0) {
-        let mut remapped_stderr = output.stderr.tail.clone();
-        remapped_stderr = remapped_stderr.replace("/main-part", "${MAIN_STAGING_DIR}");
-        remapped_stderr = remapped_stderr.replace("/output", "${STAGING_DIR}");
-        remapped_stderr = remapped_stderr.replace("/build", "${WORKDIR}");
-        output.stderr.tail = remapped_stderr;
-    }
-
-    Ok(IsolationOutput {
-        stdout: output.stdout,
-        stderr: output.stderr,
-        status: output.status,
-    })
+    config
 }
@@ -4,13 +4,17 @@ use std::sync::Arc;
 use tokio::sync::Semaphore;
 use tracing::{debug, info, warn};
 
+use crate::database::BuildStage;
 use crate::error::{Result, WrightError};
+use crate::foundry::buildlog::{self, Recorder};
 use crate::foundry::checkpoint::Checkpoint;
 use crate::foundry::executor::{self, ExecutorOptions, ExecutorRegistry};
+use crate::foundry::inputs::BuildInputs;
 use crate::foundry::layers::LayerManager;
 use crate::foundry::logging;
-use crate::isolation::IsolationLevel;
-use crate::isolation::ResourceLimits;
+use crate::foundry::proxy::{FETCHES_DIR, Proxy, ProxyOptions, write_fetches};
+use crate::isolation::{IsolationLevel, NetworkMode};
+use crate::isolation::{ResourceLimits, ResourceUsage};
 use crate::plan::manifest::{PipelineStage, PlanManifest};
 
 pub const STAGES: &[&str] = &["prepare", "configure", "compile", "check", "staging"];
@@ -53,6 +57,16 @@ pub fn compute_expected_hashes(
     results
 }
 
+/// Where `wright build` opens an interactive shell in a stage's environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugShell {
+    /// In the failing stage, before its layer is discarded.
+    OnFailure,
+    /// In place of running this stage, over the layers of the stages before
+    /// it. The forge stops after the shell exits.
+    Stage(String),
+}
+
 pub struct ForgeContext<'a> {
     pub manifest: &'a PlanManifest,
     pub source_dir: PathBuf,
@@ -74,7 +88,11 @@ pub struct ForgeContext<'a> {
     pub configure_lock: Option<Arc<Semaphore>>,
     pub compile_cpu_count: Option<u32>,
     pub compile_lock: Option<Arc<Semaphore>>,
-    pub build_key: String,
+    pub inputs: BuildInputs,
+    pub debug_shell: Option<DebugShell>,
+    pub proxy: ProxyOptions,
+    /// Previous builds' logs kept when a stage runs, see [`buildlog::rotate`].
+    pub log_retention: usize,
 }
 
 pub struct Forge<'a> {
@@ -83,6 +101,7 @@ pub struct Forge<'a> {
     vars: HashMap<String, String>,
     logs_dir: &'a Path,
     base_root: PathBuf,
+    work_dir: PathBuf,
     output_dir: PathBuf,
     stages: Vec<String>,
     force_stage: Vec<String>,
@@ -99,6 +118,13 @@ pub struct Forge<'a> {
     checkpoint: Checkpoint,
     layers: LayerManager,
     build_phase: Option<String>,
+    inputs: BuildInputs,
+    debug_shell: Option<DebugShell>,
+    proxy: ProxyOptions,
+    usage: Vec<BuildStage>,
+    resumed: bool,
+    log_retention: usize,
+    recorder: Option<Recorder>,
 }
 
 impl<'a> Forge<'a> {
@@ -116,6 +142,7 @@ impl<'a> Forge<'a> {
             vars: ctx.vars,
             logs_dir: ctx.logs_dir,
             base_root: ctx.base_root,
+            work_dir: ctx.work_dir,
             output_dir: ctx.output_dir,
             stages: ctx.stages,
             force_stage: ctx.force_stage,
@@ -132,9 +159,49 @@ impl<'a> Forge<'a> {
             checkpoint,
             layers,
             build_phase,
+            inputs: ctx.inputs,
+            debug_shell: ctx.debug_shell,
+            proxy: ctx.proxy,
+            usage: Vec::new(),
+            resumed: false,
+            log_retention: ctx.log_retention,
+            recorder: None,
         })
     }
 
+    /// Resource usage of the stages forged by [`Forge::run`], in order.
+    pub fn usage(&self) -> &[BuildStage] {
+        &self.usage
+    }
+
+    /// Whether [`Forge::run`] kept any stage from a previous forge instead
+    /// of running it.
+    pub fn resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// The record of this build, once [`Forge::run`] ran a stage. A forge
+    /// with every stage up to date leaves the previous build's logs alone.
+    pub fn take_recorder(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    }
+
+    /// Retire the previous build's logs and start this build's record,
+    /// before the first stage that actually runs.
+    async fn begin_recording(&mut self) -> Result<()> {
+        if self.recorder.is_some() {
+            return Ok(());
+        }
+        buildlog::rotate(&self.work_dir, self.log_retention)?;
+        super::ensure_clean_dir(self.logs_dir).await?;
+        self.recorder = Some(Recorder::begin(
+            &self.work_dir,
+            &self.manifest.metadata.name,
+            self.manifest.metadata.version.as_deref().unwrap_or(""),
+        )?);
+        Ok(())
+    }
+
     fn can_checkpoint(&self) -> bool {
         self.stages.is_empty() && !self.force
     }
@@ -153,7 +220,15 @@ impl<'a> Forge<'a> {
             }
             for stage_name in &order {
                 if self.stages.contains(stage_name) {
-                    self.run_ordered_stage(stage_name).await?;
+                    self.begin_recording().await?;
+                    let result = self.run_ordered_stage(stage_name).await;
+                    if result.is_err()
+                        && self.debug_shell == Some(DebugShell::OnFailure)
+                        && let Err(e) = self.open_debug_shell(stage_name).await
+                    {
+                        warn!("[{}] debug shell: {e}", self.manifest.metadata.name);
+                    }
+                    result?;
                 }
             }
             return Ok(());
@@ -167,8 +242,31 @@ impl<'a> Forge<'a> {
             None
         };
 
+        let shell_index = match self.debug_shell {
+            Some(DebugShell::Stage(ref stage_name)) => {
+                Some(order.iter().position(|p| p == stage_name).ok_or_else(|| {
+                    WrightError::ForgeError(format!(
+                        "stage '{stage_name}' not found in forge order"
+                    ))
+                })?)
+            }
+            _ => None,
+        };
+
         let checkpoint_enabled = self.can_checkpoint();
 
+        // Stages forged under other build inputs are stale as a whole: a new
+        // dependency or executor can change the output of every stage.
+        if checkpoint_enabled && let Some(reason) = self.checkpoint.stale_reason(&self.inputs) {
+            let plan_name = &self.manifest.metadata.name;
+            info!(event = "build.inputs_changed", plan_name = %plan_name, reason = %reason, "Rebuilding {}: {}", plan_name, reason);
+            self.checkpoint.invalidate_all();
+            self.layers.clear_layers_from(&order[0]);
+        }
+        if self.stages.is_empty() {
+            self.checkpoint.record_inputs(&self.inputs)?;
+        }
+
         // --- Smart resume: find where to start ---
         let start_index: usize = if checkpoint_enabled {
             let expected = compute_expected_hashes(
@@ -199,8 +297,11 @@ impl<'a> Forge<'a> {
                     .rewind_from(&checkpoint_stages, rewind_idx)?;
                 self.layers.clear_layers_from(rewind_stage);
                 start_idx
+            } else if let Some(idx) = shell_index {
+                idx
             } else {
                 info!(event = "stage.all_up_to_date", plan_name = %self.manifest.metadata.name, "All stages up-to-date — nothing to do");
+                self.resumed = true;
                 return Ok(());
             }
         } else {
@@ -232,9 +333,32 @@ impl<'a> Forge<'a> {
 
         // --- Execute stages from `start_index` forward ---
         for (idx, stage_name) in order.iter().enumerate() {
+            if shell_index == Some(idx) {
+                // The shell replaces this stage's layer, so it and every
+                // stage stacked on it must be forged again.
+                if checkpoint_enabled {
+                    self.checkpoint.rewind_from(&order, idx)?;
+                }
+                let prev_stages: Vec<String> = order[..idx].to_vec();
+                self.layers.prepare_upper_layer(stage_name)?;
+                let overlay_mounted =
+                    self.layers
+                        .mount_overlay(stage_name, &self.source_dir, &prev_stages)?;
+                if !overlay_mounted {
+                    self.layers
+                        .populate_target(&self.source_dir, &prev_stages)?;
+                }
+                let result = self.open_debug_shell(stage_name).await;
+                self.layers.unmount_overlay();
+                // Whatever the shell changed is not a forged layer.
+                self.layers.clear_layer(stage_name);
+                return result;
+            }
+
             if idx < start_index {
                 let plan_name = &self.manifest.metadata.name;
                 debug!(event = "stage.skipped", plan_name = %plan_name, stage_name = %stage_name, reason = "before_start_index", "Stage skipped");
+                self.resumed = true;
                 if stop_after_index == Some(idx) {
                     return Ok(());
                 }
@@ -263,6 +387,7 @@ impl<'a> Forge<'a> {
                 {
                     let plan_name = &self.manifest.metadata.name;
                     info!(event = "stage.skipped", plan_name = %plan_name, stage_name = %stage_name, reason = "checkpoint_up_to_date", "Stage skipped (up-to-date)");
+                    self.resumed = true;
                     if stop_after_index == Some(idx) {
                         return Ok(());
                     }
@@ -271,6 +396,7 @@ impl<'a> Forge<'a> {
             }
 
             // --- Prepare layer and working directory for this stage ---
+            self.begin_recording().await?;
             let prev_stages: Vec<String> = order[..idx].to_vec();
 
             self.layers.prepare_upper_layer(stage_name)?;
@@ -286,14 +412,29 @@ impl<'a> Forge<'a> {
 
             let result = self.run_ordered_stage_in_target(stage_name).await;
 
+            if result.is_err()
+                && self.debug_shell == Some(DebugShell::OnFailure)
+                && let Err(e) = self.open_debug_shell(stage_name).await
+            {
+                warn!("[{}] debug shell: {e}", self.manifest.metadata.name);
+            }
+
             self.layers.unmount_overlay();
 
             match result {
-                Ok(()) => {
+                Ok(mut usage) => {
                     if !overlay_mounted {
                         self.layers
                             .commit_layer(stage_name, &self.source_dir, &prev_stages)?;
                     }
+                    let defined = ["pre_", "", "post_"]
+                        .iter()
+                        .any(|prefix| self.get_stage(&format!("{prefix}{stage_name}")).is_some());
+                    if defined {
+                        usage.disk_bytes =
+                            crate::util::disk_usage(&self.layers.layer_dir(stage_name)) as i64;
+                        self.usage.push(usage);
+                    }
                     if checkpoint_enabled {
                         let expected = compute_expected_hashes(
                             self.manifest,
@@ -330,7 +471,7 @@ impl<'a> Forge<'a> {
         Ok(())
     }
 
-    async fn run_ordered_stage_in_target(&self, stage_name: &str) -> Result<()> {
+    async fn run_ordered_stage_in_target(&self, stage_name: &str) -> Result<BuildStage> {
         if stage_name == "configure" {
             let _permit = if let Some(ref s) = self.configure_lock {
                 Some(s.acquire().await.expect("configure semaphore closed"))
@@ -358,19 +499,34 @@ impl<'a> Forge<'a> {
         }
     }
 
-    async fn run_stage_with_hooks_in_target(&self, stage_name: &str, cpu_count: u32) -> Result<()> {
+    async fn run_stage_with_hooks_in_target(
+        &self,
+        stage_name: &str,
+        cpu_count: u32,
+    ) -> Result<BuildStage> {
         let plan_name = &self.manifest.metadata.name;
+        let started = std::time::Instant::now();
+        let mut usage = BuildStage {
+            stage: stage_name.to_string(),
+            ..Default::default()
+        };
         let pre_hook = format!("pre_{stage_name}");
         if let Some(stage) = self.get_stage(&pre_hook) {
             debug!(event = "hook.running", plan_name = %plan_name, hook = %pre_hook, "Running pre-hook");
-            self.run_stage_in_target(&pre_hook, stage, cpu_count)
-                .await?;
+            add_usage(
+                &mut usage,
+                self.run_stage_in_target(&pre_hook, stage, cpu_count)
+                    .await?,
+            );
         }
 
         if let Some(stage) = self.get_stage(stage_name) {
             let t0 = std::time::Instant::now();
-            self.run_stage_in_target(stage_name, stage, cpu_count)
-                .await?;
+            add_usage(
+                &mut usage,
+                self.run_stage_in_target(stage_name, stage, cpu_count)
+                    .await?,
+            );
             let elapsed = t0.elapsed().as_secs_f64();
             info!(event = "stage.completed", plan_name = %plan_name, stage_name = %stage_name, elapsed_secs = elapsed, "Stage completed");
         } else {
@@ -380,13 +536,78 @@ impl<'a> Forge<'a> {
         let post_hook = format!("post_{stage_name}");
         if let Some(stage) = self.get_stage(&post_hook) {
             debug!(event = "hook.running", plan_name = %plan_name, hook = %post_hook, "Running post-hook");
-            self.run_stage_in_target(&post_hook, stage, cpu_count)
-                .await?;
+            add_usage(
+                &mut usage,
+                self.run_stage_in_target(&post_hook, stage, cpu_count)
+                    .await?,
+            );
         }
 
+        usage.wall_secs = started.elapsed().as_secs_f64();
+        Ok(usage)
+    }
+
+    /// Open an interactive shell in the working tree as `stage_name` sees
+    /// it, with that stage's isolation, executor environment, and variables.
+    async fn open_debug_shell(&self, stage_name: &str) -> Result<()> {
+        let plan_name = &self.manifest.metadata.name;
+        let fallback = PipelineStage {
+            executor: "shell".to_string(),
+            isolation: "strict".to_string(),
+            network: "isolation".to_string(),
+            env: HashMap::new(),
+            script: String::new(),
+        };
+        let stage = self.get_stage(stage_name).unwrap_or(&fallback);
+        let proxy = self.start_proxy(stage)?;
+        let mut options = ExecutorOptions {
+            level: stage.isolation.parse()?,
+            base_root: self.base_root.clone(),
+            work_dir: self.layers.target_dir().to_path_buf(),
+            output_dir: self.output_dir.clone(),
+            rlimits: self.rlimits.clone(),
+            main_part_dir: None,
+            verbose: self.verbose,
+            cpu_count: Some(self.cpu_count),
+            log_stdout: None,
+            dep_mounts: Vec::new(),
+            hardening: self.manifest.options.hardening(),
+            proxy: proxy.as_ref().map(Proxy::endpoint),
+        };
+        crate::cli_action!(
+            "Debugging",
+            "{} stage {} (exit the shell to continue)",
+            plan_name,
+            stage_name
+        );
+        let prompt = format!("[{plan_name}:{stage_name}]$ ");
+        let status = executor::open_shell(&stage.env, &self.vars, &prompt, &mut options).await?;
+        debug!(event = "stage.debug_shell_exited", plan_name = %plan_name, stage_name = %stage_name, ?status, "Debug shell exited");
         Ok(())
     }
 
+    /// Start the proxy a `network = "proxy"` stage reaches the network
+    /// through; `None` for other stages.
+    fn start_proxy(&self, stage: &PipelineStage) -> Result<Option<Proxy>> {
+        if stage.network.parse::<NetworkMode>()? != NetworkMode::Proxy {
+            return Ok(None);
+        }
+        Proxy::start(&self.proxy).map(Some)
+    }
+
+    /// Stop the stage's proxy and keep what it fetched in the build root
+    /// for sealing. A stage without one drops any record left by an earlier
+    /// proxied version of it.
+    fn finish_proxy(&self, stage_name: &str, proxy: Option<Proxy>) -> Result<Vec<String>> {
+        let Some(proxy) = proxy else {
+            let _ = std::fs::remove_file(self.work_dir.join(FETCHES_DIR).join(stage_name));
+            return Ok(Vec::new());
+        };
+        let fetches = proxy.finish();
+        write_fetches(&self.work_dir, stage_name, &fetches)?;
+        Ok(fetches)
+    }
+
     fn get_stage_order(&self) -> Vec<String> {
         stage_order_for_manifest(self.manifest, self.build_phase.as_deref())
     }
@@ -467,10 +688,10 @@ impl<'a> Forge<'a> {
         stage_name: &str,
         stage: &PipelineStage,
         cpu_count: u32,
-    ) -> Result<()> {
+    ) -> Result<ResourceUsage> {
         if stage.script.is_empty() {
             debug!("Stage {stage_name} has empty script, skipping");
-            return Ok(());
+            return Ok(ResourceUsage::default());
         }
 
         let working_dir = self.layers.target_dir();
@@ -509,6 +730,7 @@ impl<'a> Forge<'a> {
             "stage started"
         );
 
+        let proxy = self.start_proxy(stage)?;
         let max_etxtbsy_retries: u32 = 10;
         let mut attempt: u32 = 0;
         let (result, final_attempt) = loop {
@@ -532,6 +754,8 @@ impl<'a> Forge<'a> {
                 cpu_count: Some(cpu_count),
                 log_stdout,
                 dep_mounts: Vec::new(),
+                hardening: self.manifest.options.hardening(),
+                proxy: proxy.as_ref().map(Proxy::endpoint),
             };
 
             let res = executor::execute_script(
@@ -573,17 +797,31 @@ impl<'a> Forge<'a> {
 
         let elapsed = t0.elapsed().as_secs_f64();
         let exit_code = result.status.code().unwrap_or(-1);
+        let fetches = self.finish_proxy(stage_name, proxy)?;
 
         if let Ok(mut log_file) = std::fs::OpenOptions::new().append(true).open(&log_path) {
             use std::io::Write;
             let _ = log_file.write_all(b"\n--- stderr ---\n");
             let _ = std::io::copy(&mut result.stderr.file, &mut log_file);
+            if !fetches.is_empty() {
+                let _ = write!(log_file, "\n--- fetches ---\n{}\n", fetches.join("\n"));
+            }
+            if result.oom_killed {
+                let _ = log_file.write_all(b"\n=== Killed: out of memory (memory_max) ===");
+            }
             let _ = write!(
                 log_file,
                 "\n=== Exit code: {exit_code} ===\n=== Duration: {elapsed:.1}s ===\n",
             );
         }
 
+        if exit_code != 0 && result.oom_killed {
+            return Err(WrightError::OutOfMemory(format!(
+                "stage '{stage_name}' exceeded memory_max of {} MB and was killed (see log: {})",
+                self.rlimits.memory_max.unwrap_or_default(),
+                log_path.display()
+            )));
+        }
         if exit_code != 0 {
             return Err(WrightError::ForgeError(format!(
                 "stage '{stage_name}' failed with exit code {exit_code} (see log: {})",
@@ -591,7 +829,7 @@ impl<'a> Forge<'a> {
             )));
         }
 
-        Ok(())
+        Ok(result.usage)
     }
 
     async fn run_stage_legacy(
@@ -640,6 +878,7 @@ impl<'a> Forge<'a> {
             "stage started"
         );
 
+        let proxy = self.start_proxy(stage)?;
         let max_etxtbsy_retries: u32 = 10;
         let mut attempt: u32 = 0;
         let (result, final_attempt) = loop {
@@ -663,6 +902,8 @@ impl<'a> Forge<'a> {
                 cpu_count: Some(cpu_count),
                 log_stdout,
                 dep_mounts: Vec::new(),
+                hardening: self.manifest.options.hardening(),
+                proxy: proxy.as_ref().map(Proxy::endpoint),
             };
 
             let res = executor::execute_script(
@@ -704,17 +945,31 @@ impl<'a> Forge<'a> {
 
         let elapsed = t0.elapsed().as_secs_f64();
         let exit_code = result.status.code().unwrap_or(-1);
+        let fetches = self.finish_proxy(stage_name, proxy)?;
 
         if let Ok(mut log_file) = std::fs::OpenOptions::new().append(true).open(&log_path) {
             use std::io::Write;
             let _ = log_file.write_all(b"\n--- stderr ---\n");
             let _ = std::io::copy(&mut result.stderr.file, &mut log_file);
+            if !fetches.is_empty() {
+                let _ = write!(log_file, "\n--- fetches ---\n{}\n", fetches.join("\n"));
+            }
+            if result.oom_killed {
+                let _ = log_file.write_all(b"\n=== Killed: out of memory (memory_max) ===");
+            }
             let _ = write!(
                 log_file,
                 "\n=== Exit code: {exit_code} ===\n=== Duration: {elapsed:.1}s ===\n",
             );
         }
 
+        if exit_code != 0 && result.oom_killed {
+            return Err(WrightError::OutOfMemory(format!(
+                "stage '{stage_name}' exceeded memory_max of {} MB and was killed (see log: {})",
+                self.rlimits.memory_max.unwrap_or_default(),
+                log_path.display()
+            )));
+        }
         if exit_code != 0 {
             return Err(WrightError::ForgeError(format!(
                 "stage '{stage_name}' failed with exit code {exit_code} (see log: {})",
@@ -726,6 +981,14 @@ impl<'a> Forge<'a> {
     }
 }
 
+/// Fold one script's usage into its stage's: CPU times add up, the peak is
+/// the largest of the scripts'.
+fn add_usage(stage: &mut BuildStage, usage: ResourceUsage) {
+    stage.user_secs += usage.user_secs;
+    stage.system_secs += usage.system_secs;
+    stage.max_rss_kb = stage.max_rss_kb.max(usage.max_rss_kb as i64);
+}
+
 fn jitter_ms(max_ms: u64) -> u64 {
     if max_ms == 0 {
         return 0;
@@ -0,0 +1,382 @@
+//! Build inputs — everything a forge result depends on, hashed into the
+//! build key that invalidates checkpoints and keys the CAS store.
+//!
+//! Each input is kept under its own label (`plan`, `env`, `isolation`,
+//! `executor:<name>`, `mvp`, `dep:<plan>`) so that when the key changes the
+//! forge can say which inputs moved.
+
+use std::collections::{BTreeMap, HashMap};
+
+use sha2::{Digest, Sha256};
+
+use crate::database::InstalledDb;
+use crate::delivery::store::CasStore;
+use crate::error::Result;
+use crate::foundry::executor::ExecutorRegistry;
+use crate::foundry::variables;
+use crate::plan::manifest::{PipelineStage, PlanManifest, Source};
+use crate::resolve::DepDomain;
+
+/// Digest recorded for a dependency with nothing deployed.
+const NOT_DEPLOYED: &str = "none";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildInputs {
+    /// The plan's own inputs, label -> digest.
+    own: BTreeMap<String, String>,
+    /// Build/link dependency plan name -> digest of its deployed parts.
+    deps: HashMap<String, String>,
+}
+
+impl BuildInputs {
+    /// Hash the plan's own inputs. `mvp` selects the bootstrap phase, which
+    /// adds the `mvp.toml` overlay and keys the build apart from a full one.
+    pub fn for_manifest(manifest: &PlanManifest, executors: &ExecutorRegistry, mvp: bool) -> Self {
+        let mut own = BTreeMap::new();
+        own.insert("plan".to_string(), plan_digest(manifest));
+
+        let mut stages: Vec<(&String, &PipelineStage)> = manifest.pipeline.iter().collect();
+        let overlay = manifest.mvp.as_ref().filter(|_| mvp);
+        if let Some(overlay) = overlay {
+            stages.extend(overlay.pipeline.iter());
+        }
+
+        let mut env = Sha256::new();
+        hash_env(&mut env, &manifest.options.env);
+        let mut isolation = Sha256::new();
+        let mut executor_names: Vec<&str> = Vec::new();
+        for (name, stage) in sorted(&stages) {
+            env.update(name.as_bytes());
+            env.update(b"\n");
+            hash_env(&mut env, &stage.env);
+            isolation.update(format!("{}={}\n", name, stage.isolation).as_bytes());
+            if stage.network != "isolation" {
+                isolation.update(format!("{}.network={}\n", name, stage.network).as_bytes());
+            }
+            executor_names.push(&stage.executor);
+        }
+        own.insert("env".to_string(), format!("{:x}", env.finalize()));
+        own.insert(
+            "isolation".to_string(),
+            format!("{:x}", isolation.finalize()),
+        );
+
+        executor_names.sort_unstable();
+        executor_names.dedup();
+        for name in executor_names {
+            let digest = match executors.get(name) {
+                Some(exec) => {
+                    let mut h = Sha256::new();
+                    for field in [
+                        &exec.command,
+                        &exec.delivery,
+                        &exec.tempfile_extension,
+                        &exec.default_isolation,
+                    ] {
+                        h.update(field.as_bytes());
+                        h.update(b"\n");
+                    }
+                    for arg in exec.args.iter().chain(&exec.required_paths) {
+                        h.update(arg.as_bytes());
+                        h.update(b"\n");
+                    }
+                    format!("{:x}", h.finalize())
+                }
+                None => "missing".to_string(),
+            };
+            own.insert(format!("executor:{}", name), digest);
+        }
+
+        if mvp {
+            let mut h = Sha256::new();
+            if let Some(overlay) = overlay {
+                for dep in overlay
+                    .build_deps
+                    .iter()
+                    .chain(&overlay.link_deps)
+                    .chain(&overlay.runtime_deps)
+                {
+                    h.update(dep.as_bytes());
+                    h.update(b"\n");
+                }
+                for (name, stage) in sorted(&overlay.pipeline.iter().collect::<Vec<_>>()) {
+                    h.update(name.as_bytes());
+                    h.update(stage.script.as_bytes());
+                    h.update(stage.executor.as_bytes());
+                }
+                if let Some(ref order) = overlay.pipeline_order {
+                    h.update(order.stages.join(",").as_bytes());
+                }
+            }
+            own.insert("mvp".to_string(), format!("{:x}", h.finalize()));
+        }
+
+        Self {
+            own,
+            deps: HashMap::new(),
+        }
+    }
+
+    /// Add the deployed parts of every build and link dependency of
+    /// `manifest` (its mvp overrides when `mvp`), as recorded in `db`.
+    pub async fn add_dependencies(
+        &mut self,
+        db: &InstalledDb,
+        manifest: &PlanManifest,
+        mvp: bool,
+    ) -> Result<()> {
+        let deps = crate::foundry::mvp::collect_phase_deps(
+            manifest,
+            &HashMap::new(),
+            mvp,
+            None,
+            DepDomain::BUILD | DepDomain::LINK,
+        );
+        for dep in deps {
+            let digest = deployed_digest(db, &dep).await?;
+            self.deps.insert(dep, digest);
+        }
+        Ok(())
+    }
+
+    /// The build key: the plan's own inputs folded with its dependency
+    /// digests into a closure fingerprint.
+    pub fn key(&self) -> String {
+        let mut h = Sha256::new();
+        for (label, digest) in &self.own {
+            h.update(format!("{} {}\n", label, digest).as_bytes());
+        }
+        CasStore::compute_closure_fingerprint(&format!("{:x}", h.finalize()), &self.deps)
+    }
+
+    /// Every input as label -> digest, dependencies labelled `dep:<plan>`.
+    pub fn digests(&self) -> BTreeMap<String, String> {
+        let mut all = self.own.clone();
+        for (dep, digest) in &self.deps {
+            all.insert(format!("dep:{}", dep), digest.clone());
+        }
+        all
+    }
+
+    /// Labels of inputs that differ from `previous`, including ones added
+    /// or dropped since.
+    pub fn changed_since(&self, previous: &BTreeMap<String, String>) -> Vec<String> {
+        let current = self.digests();
+        let mut changed: Vec<String> = current
+            .iter()
+            .filter(|(label, digest)| previous.get(*label) != Some(*digest))
+            .map(|(label, _)| label.clone())
+            .collect();
+        changed.extend(
+            previous
+                .keys()
+                .filter(|label| !current.contains_key(*label))
+                .cloned(),
+        );
+        changed.sort();
+        changed
+    }
+}
+
+/// Name, version, sources, patches, and stage scripts — what `plan.toml`
+/// says to build.
+fn plan_digest(manifest: &PlanManifest) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(manifest.metadata.name.as_bytes());
+    hasher.update(
+        manifest
+            .metadata
+            .version
+            .as_deref()
+            .unwrap_or("")
+            .as_bytes(),
+    );
+    hasher.update(manifest.metadata.release.to_string().as_bytes());
+    for source in &manifest.sources.entries {
+        match source {
+            Source::Http(http) => {
+                hasher.update(b"http");
+                hasher.update(http.url.as_bytes());
+                hasher.update(http.sha256.as_bytes());
+                if let Some(ref r#as) = http.r#as {
+                    hasher.update(r#as.as_bytes());
+                }
+                if let Some(ref ext) = http.extract_to {
+                    hasher.update(ext.as_bytes());
+                }
+            }
+            Source::Git(git) => {
+                hasher.update(b"git");
+                hasher.update(git.url.as_bytes());
+                if let Some(ref r#ref) = git.r#ref {
+                    hasher.update(variables::process_uri(r#ref, manifest).as_bytes());
+                }
+                if let Some(depth) = git.depth {
+                    hasher.update(depth.to_le_bytes());
+                }
+                if let Some(ref ext) = git.extract_to {
+                    hasher.update(ext.as_bytes());
+                }
+            }
+            Source::Local(local) => {
+                hasher.update(b"local");
+                hasher.update(local.path.as_bytes());
+                if let Some(ref ext) = local.extract_to {
+                    hasher.update(ext.as_bytes());
+                }
+            }
+            Source::CargoVendor(cargo) => {
+                hasher.update(b"cargo_vendor");
+                hasher.update(cargo.lockfile.as_bytes());
+                hasher.update(cargo.registry.as_bytes());
+                if let Some(ref ext) = cargo.extract_to {
+                    hasher.update(ext.as_bytes());
+                }
+            }
+            Source::GoMod(go) => {
+                hasher.update(b"go_mod");
+                hasher.update(go.lockfile.as_bytes());
+                hasher.update(go.proxy.as_bytes());
+                if let Some(ref ext) = go.extract_to {
+                    hasher.update(ext.as_bytes());
+                }
+            }
+            Source::PipRequirements(pip) => {
+                hasher.update(b"pip_requirements");
+                hasher.update(pip.lockfile.as_bytes());
+                hasher.update(pip.index.as_bytes());
+                if let Some(ref ext) = pip.extract_to {
+                    hasher.update(ext.as_bytes());
+                }
+            }
+        }
+    }
+    for patch in &manifest.patches {
+        hasher.update(b"patch");
+        hasher.update(patch.locator().as_bytes());
+        hasher.update(patch.sha256.as_deref().unwrap_or("").as_bytes());
+        hasher.update(patch.file_checksum.as_deref().unwrap_or("").as_bytes());
+        hasher.update(patch.strip.to_le_bytes());
+        hasher.update(patch.target.as_deref().unwrap_or("").as_bytes());
+        hasher.update(patch.versions.join(",").as_bytes());
+    }
+    let stages: Vec<_> = manifest.pipeline.iter().collect();
+    for (name, stage) in sorted(&stages) {
+        hasher.update(name.as_bytes());
+        hasher.update(stage.script.as_bytes());
+        hasher.update(stage.executor.as_bytes());
+    }
+    if let Some(ref order) = manifest.pipeline_order {
+        hasher.update(order.stages.join(",").as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Digest of what is deployed for dependency `dep`: every part of plan
+/// `dep` or, when no plan has that name, the part called `dep`.
+async fn deployed_digest(db: &InstalledDb, dep: &str) -> Result<String> {
+    let mut parts: Vec<(String, Option<String>)> = db
+        .get_parts_by_plan(dep)
+        .await?
+        .into_iter()
+        .map(|p| (p.name, p.part_hash))
+        .collect();
+    if parts.is_empty()
+        && let Some(part) = db.get_part(dep).await?
+    {
+        parts.push((part.name, part.part_hash));
+    }
+    if parts.is_empty() {
+        return Ok(NOT_DEPLOYED.to_string());
+    }
+    parts.sort();
+    let mut h = Sha256::new();
+    for (name, hash) in parts {
+        h.update(format!("{}={}\n", name, hash.as_deref().unwrap_or("-")).as_bytes());
+    }
+    Ok(format!("{:x}", h.finalize()))
+}
+
+fn sorted<'a>(stages: &[(&'a String, &'a PipelineStage)]) -> Vec<(&'a String, &'a PipelineStage)> {
+    let mut stages = stages.to_vec();
+    stages.sort_by(|a, b| a.0.cmp(b.0));
+    stages
+}
+
+fn hash_env(h: &mut Sha256, env: &HashMap<String, String>) {
+    let mut vars: Vec<_> = env.iter().collect();
+    vars.sort();
+    for (k, v) in vars {
+        h.update(format!("{}={}\n", k, v).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::manifest::PlanManifest;
+
+    fn manifest(extra: &str) -> PlanManifest {
+        PlanManifest::parse(&format!(
+            r#"
+name = "hello"
+version = "1.0.0"
+release = 1
+description = "test"
+license = "MIT"
+arch = "x86_64"
+
+[pipeline.compile]
+script = "make"
+{extra}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_key_tracks_env_and_isolation() {
+        let executors = ExecutorRegistry::new();
+        let base = BuildInputs::for_manifest(&manifest(""), &executors, false);
+        let env = BuildInputs::for_manifest(
+            &manifest("[options.env]\nCFLAGS = \"-O3\""),
+            &executors,
+            false,
+        );
+        assert_ne!(base.key(), env.key());
+        assert_eq!(env.changed_since(&base.digests()), vec!["env"]);
+
+        let iso = BuildInputs::for_manifest(&manifest("isolation = \"none\""), &executors, false);
+        assert_eq!(iso.changed_since(&base.digests()), vec!["isolation"]);
+        let proxied =
+            BuildInputs::for_manifest(&manifest("network = \"proxy\""), &executors, false);
+        assert_eq!(proxied.changed_since(&base.digests()), vec!["isolation"]);
+    }
+
+    #[test]
+    fn test_key_tracks_patch_content() {
+        let executors = ExecutorRegistry::new();
+        let mut patched = manifest("[[patches]]\npath = \"fix.patch\"");
+        patched.patches[0].file_checksum = Some("aaa".to_string());
+        let before = BuildInputs::for_manifest(&patched, &executors, false);
+        patched.patches[0].file_checksum = Some("bbb".to_string());
+        let edited = BuildInputs::for_manifest(&patched, &executors, false);
+        assert_eq!(edited.changed_since(&before.digests()), vec!["plan"]);
+    }
+
+    #[test]
+    fn test_key_tracks_dependencies_and_phase() {
+        let executors = ExecutorRegistry::new();
+        let base = BuildInputs::for_manifest(&manifest(""), &executors, false);
+        let mut dep = base.clone();
+        dep.deps.insert("zlib".to_string(), "aaa".to_string());
+        let mut rebuilt = base.clone();
+        rebuilt.deps.insert("zlib".to_string(), "bbb".to_string());
+        assert_ne!(dep.key(), rebuilt.key());
+        assert_eq!(rebuilt.changed_since(&dep.digests()), vec!["dep:zlib"]);
+
+        let mvp = BuildInputs::for_manifest(&manifest(""), &executors, true);
+        assert_ne!(base.key(), mvp.key());
+        assert_eq!(mvp.changed_since(&base.digests()), vec!["mvp"]);
+    }
+}
@@ -59,10 +59,11 @@ fn force_clean_dir_blocking(path: &Path) -> Result<()> {
     )))
 }
 
-pub(crate) fn detach_mounts_under(path: &Path) -> usize {
+/// Mount points at or below `path`, deepest first.
+pub fn mounts_under(path: &Path) -> Vec<PathBuf> {
     let mounts = match std::fs::read_to_string("/proc/self/mounts") {
         Ok(s) => s,
-        Err(_) => return 0,
+        Err(_) => return Vec::new(),
     };
     let mut targets: Vec<PathBuf> = mounts
         .lines()
@@ -75,9 +76,12 @@ pub(crate) fn detach_mounts_under(path: &Path) -> usize {
         .filter(|t| t == path || t.starts_with(path))
         .collect();
     targets.sort_by_key(|t| std::cmp::Reverse(t.components().count()));
+    targets
+}
 
+pub(crate) fn detach_mounts_under(path: &Path) -> usize {
     let mut detached = 0;
-    for target in targets {
+    for target in mounts_under(path) {
         match nix::mount::umount2(&target, nix::mount::MntFlags::MNT_DETACH) {
             Ok(()) => {
                 detached += 1;
@@ -1,12 +1,18 @@
+pub mod buildlog;
 pub mod charge;
 pub mod checkpoint;
 pub mod executor;
 pub mod forge;
+pub mod inputs;
 pub mod layers;
 pub mod logging;
 pub mod mold;
 pub mod mvp;
+pub mod patch;
+pub mod proxy;
+pub mod reproducible;
 pub mod variables;
+pub mod vendor;
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -14,6 +20,7 @@ use tokio::sync::Semaphore;
 use tracing::info;
 
 use crate::config::GlobalConfig;
+use crate::database::BuildStage;
 use crate::error::{Result, WrightError};
 use crate::foundry::charge::Charge;
 use crate::foundry::executor::ExecutorRegistry;
@@ -21,7 +28,8 @@ use crate::foundry::mold::Mold;
 use crate::plan::manifest::PlanManifest;
 
 pub use crate::foundry::charge::ChargeResult;
-pub use crate::foundry::forge::{Forge, ForgeContext};
+pub use crate::foundry::forge::{DebugShell, Forge, ForgeContext};
+pub use crate::foundry::inputs::BuildInputs;
 pub use crate::foundry::mold::MoldResult;
 
 #[derive(Debug)]
@@ -30,6 +38,10 @@ pub struct FoundryResult {
     pub build_root: PathBuf,
     pub logs_dir: PathBuf,
     pub output_dirs: HashMap<String, PathBuf>,
+    /// What each stage cost, when this build forged the whole pipeline;
+    /// `None` when stages were selected, skipped, or kept from a previous
+    /// forge.
+    pub usage: Option<Vec<BuildStage>>,
 }
 
 /// Options that control a single build invocation.
@@ -47,6 +59,14 @@ pub struct BuildOptions {
     pub nproc_per_isolation: Option<u32>,
     pub configure_lock: Option<Arc<Semaphore>>,
     pub compile_lock: Option<Arc<Semaphore>>,
+    /// Build inputs including dependencies. When `None`, only the plan's
+    /// own inputs key the build.
+    pub inputs: Option<BuildInputs>,
+    /// Forge in this directory instead of the plan's build root under
+    /// `forge_dir`.
+    pub build_root: Option<PathBuf>,
+    /// Open an interactive shell in a stage's isolation.
+    pub debug_shell: Option<DebugShell>,
 }
 
 /// The foundry — the workshop where raw materials are transformed into
@@ -90,80 +110,14 @@ impl Foundry {
         }
     }
 
-    pub fn compute_build_key(&self, manifest: &PlanManifest) -> Result<String> {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(manifest.metadata.name.as_bytes());
-        hasher.update(
-            manifest
-                .metadata
-                .version
-                .as_deref()
-                .unwrap_or("")
-                .as_bytes(),
-        );
-        hasher.update(manifest.metadata.release.to_string().as_bytes());
-        for source in &manifest.sources.entries {
-            match source {
-                crate::plan::manifest::Source::Http(http) => {
-                    hasher.update(b"http");
-                    hasher.update(http.url.as_bytes());
-                    hasher.update(http.sha256.as_bytes());
-                    if let Some(ref r#as) = http.r#as {
-                        hasher.update(r#as.as_bytes());
-                    }
-                    if let Some(ref ext) = http.extract_to {
-                        hasher.update(ext.as_bytes());
-                    }
-                }
-                crate::plan::manifest::Source::Git(git) => {
-                    hasher.update(b"git");
-                    hasher.update(git.url.as_bytes());
-                    if let Some(ref r#ref) = git.r#ref {
-                        hasher.update(variables::process_uri(r#ref, manifest).as_bytes());
-                    }
-                    if let Some(depth) = git.depth {
-                        hasher.update(depth.to_le_bytes());
-                    }
-                    if let Some(ref ext) = git.extract_to {
-                        hasher.update(ext.as_bytes());
-                    }
-                }
-                crate::plan::manifest::Source::Local(local) => {
-                    hasher.update(b"local");
-                    hasher.update(local.path.as_bytes());
-                    if let Some(ref ext) = local.extract_to {
-                        hasher.update(ext.as_bytes());
-                    }
-                }
-            }
-        }
-        let mut stage_names: Vec<_> = manifest.pipeline.keys().collect();
-        stage_names.sort();
-        for name in stage_names {
-            if let Some(stage) = manifest.pipeline.get(name) {
-                hasher.update(name.as_bytes());
-                hasher.update(stage.script.as_bytes());
-                hasher.update(stage.executor.as_bytes());
-            }
-        }
-        Ok(format!("{:x}", hasher.finalize()))
+    /// The plan's own build inputs; callers add dependencies with
+    /// [`BuildInputs::add_dependencies`].
+    pub fn build_inputs(&self, manifest: &PlanManifest, mvp: bool) -> BuildInputs {
+        BuildInputs::for_manifest(manifest, &self.executors, mvp)
     }
 
     pub fn build_root(&self, manifest: &PlanManifest) -> Result<PathBuf> {
-        let forge_dir = if self.config.build.forge_dir.is_absolute() {
-            self.config.build.forge_dir.clone()
-        } else {
-            std::env::current_dir()
-                .map_err(|e| WrightError::ForgeError(format!("failed to get cwd: {e}")))?
-                .join(&self.config.build.forge_dir)
-        };
-        let ver = manifest.metadata.version.as_deref().unwrap_or("");
-        if ver.is_empty() {
-            Ok(forge_dir.join(format!("{}-noversion", manifest.metadata.name)))
-        } else {
-            Ok(forge_dir.join(format!("{}-{}", manifest.metadata.name, ver)))
-        }
+        Ok(forge_dir(&self.config)?.join(build_root_name(manifest)))
     }
 
     pub async fn clean(&self, manifest: &PlanManifest) -> Result<()> {
@@ -191,7 +145,10 @@ impl Foundry {
             self.clean(manifest).await?;
         }
 
-        let build_root = self.build_root(manifest)?;
+        let build_root = match opts.build_root {
+            Some(ref root) => root.clone(),
+            None => self.build_root(manifest)?,
+        };
 
         // Reap any stale overlay mounts left behind by a prior crash or
         // forced termination.  This prevents EBUSY when the user later
@@ -204,7 +161,10 @@ impl Foundry {
         let staging_dir = build_root.join("staging");
         let logs_dir = build_root.join("logs");
         let output_dir = staging_dir.clone();
-        let partial = !opts.stages.is_empty() || opts.fetch_only;
+        let partial = !opts.stages.is_empty()
+            || opts.fetch_only
+            || matches!(opts.debug_shell, Some(DebugShell::Stage(_)));
+        let whole_pipeline = !partial && opts.until_stage.is_none() && !opts.skip_check;
 
         if !opts.stages.is_empty() && opts.until_stage.is_some() {
             return Err(WrightError::ForgeError(
@@ -212,7 +172,11 @@ impl Foundry {
             ));
         }
 
-        if let Some(ref stage_name) = opts.until_stage {
+        let shell_stage = match opts.debug_shell {
+            Some(DebugShell::Stage(ref stage_name)) => Some(stage_name),
+            _ => None,
+        };
+        for stage_name in opts.until_stage.iter().chain(shell_stage) {
             let order = crate::foundry::forge::stage_order_for_manifest(
                 manifest,
                 opts.extra_env.get("WRIGHT_BUILD_PHASE").map(|s| s.as_str()),
@@ -231,10 +195,8 @@ impl Foundry {
                 ));
             }
             ensure_clean_dir(&staging_dir).await?;
-            ensure_clean_dir(&logs_dir).await?;
         } else {
             ensure_clean_dir(&staging_dir).await?;
-            ensure_clean_dir(&logs_dir).await?;
         }
 
         // ------------------------------------------------------------------
@@ -249,6 +211,7 @@ impl Foundry {
                 build_root,
                 logs_dir,
                 output_dirs: HashMap::new(),
+                usage: None,
             });
         }
 
@@ -260,6 +223,7 @@ impl Foundry {
                 build_root,
                 logs_dir,
                 output_dirs: HashMap::new(),
+                usage: None,
             });
         }
 
@@ -276,6 +240,15 @@ impl Foundry {
                 .cpu_time_limit
                 .or(self.config.build.cpu_time_limit),
             timeout_secs: manifest.options.timeout.or(self.config.build.timeout),
+            memory_max: manifest.options.memory_max.or(self.config.build.memory_max),
+            pids_max: manifest.options.pids_max.or(self.config.build.pids_max),
+            cpu_max: manifest.options.cpu_max.or(self.config.build.cpu_max),
+            io_max: manifest
+                .options
+                .io_max
+                .clone()
+                .unwrap_or_else(|| self.config.build.io_max.clone()),
+            cgroup_parent: self.config.build.cgroup_parent.clone(),
         };
 
         let available = std::thread::available_parallelism()
@@ -309,7 +282,13 @@ impl Foundry {
         }
         vars.extend(opts.extra_env.iter().map(|(k, v)| (k.clone(), v.clone())));
 
-        let build_key = self.compute_build_key(manifest)?;
+        let inputs = match opts.inputs {
+            Some(inputs) => inputs,
+            None => self.build_inputs(
+                manifest,
+                opts.extra_env.get("WRIGHT_BUILD_PHASE").map(|s| s.as_str()) == Some("mvp"),
+            ),
+        };
 
         let mut forge = crate::foundry::forge::Forge::new(ForgeContext {
             manifest,
@@ -332,7 +311,20 @@ impl Foundry {
             configure_lock: opts.configure_lock,
             compile_cpu_count: Some(total_cpus),
             compile_lock: opts.compile_lock,
-            build_key,
+            inputs,
+            debug_shell: opts.debug_shell,
+            proxy: crate::foundry::proxy::ProxyOptions {
+                cache_dir: self
+                    .config
+                    .network
+                    .proxy_cache
+                    .clone()
+                    .unwrap_or_else(|| self.config.general.source_dir.join("proxy")),
+                offline: self.config.network.proxy_offline,
+                timeout: self.config.network.download_timeout,
+                allow: self.config.network.proxy_allow.clone(),
+            },
+            log_retention: self.config.build.log_retention,
         })?;
 
         let plan_name = &manifest.metadata.name;
@@ -348,6 +340,9 @@ impl Foundry {
             info!(event = "build.failed", plan_name = %plan_name, error = %e, "Forge failed");
             return Err(e);
         }
+        // Only a forge that actually ran a stage retired the previous
+        // build's logs and started a record.
+        let recorder = forge.take_recorder();
         let forge_elapsed = forge_t0.elapsed().as_secs_f64();
         info!(
             event = "build.completed",
@@ -368,17 +363,56 @@ impl Foundry {
             }
         };
 
+        if let Some(recorder) = recorder {
+            recorder.finish();
+        }
+        let usage = (whole_pipeline && !forge.resumed() && !forge.usage().is_empty())
+            .then(|| forge.usage().to_vec());
         Ok(FoundryResult {
             staging_dir,
             build_root,
             logs_dir,
             output_dirs: mold_result.split_dirs,
+            usage,
         })
     }
 }
 
 use std::collections::HashMap;
 
+/// The workshop directory holding every build root, `build.forge_dir`
+/// resolved against the current directory when relative.
+pub fn forge_dir(config: &GlobalConfig) -> Result<PathBuf> {
+    if config.build.forge_dir.is_absolute() {
+        Ok(config.build.forge_dir.clone())
+    } else {
+        Ok(std::env::current_dir()
+            .map_err(|e| WrightError::ForgeError(format!("failed to get cwd: {e}")))?
+            .join(&config.build.forge_dir))
+    }
+}
+
+/// Separators `wright build --verify-reproducible` puts between a build root
+/// name and a stamp to name its two roots.
+pub const REPRODUCIBLE_ROOT_MARKERS: [&str; 2] = [".repro-", ".reproducible-check-"];
+
+/// Whether `dir_name` is a root of `wright build --verify-reproducible`.
+/// Those are kept for inspection when the check fails, so `wright workshop`
+/// leaves them alone.
+pub fn is_reproducible_root(dir_name: &str) -> bool {
+    REPRODUCIBLE_ROOT_MARKERS
+        .iter()
+        .any(|marker| dir_name.contains(marker))
+}
+
+/// Directory name of a plan's build root under [`forge_dir`].
+pub fn build_root_name(manifest: &PlanManifest) -> String {
+    match manifest.metadata.version.as_deref().unwrap_or("") {
+        "" => format!("{}-noversion", manifest.metadata.name),
+        ver => format!("{}-{}", manifest.metadata.name, ver),
+    }
+}
+
 async fn ensure_clean_dir(dir: &Path) -> Result<()> {
     if tokio::fs::metadata(dir).await.is_ok() {
         match tokio::fs::remove_dir_all(dir).await {
@@ -0,0 +1,546 @@
+//! Unified-diff application for `[[patches]]`.
+//!
+//! Hunks must match exactly; like `patch(1)` without fuzz, a hunk may
+//! apply at an offset from the line numbers it records, but not with
+//! altered context. A hunk that does not apply aborts the whole series
+//! and is quoted in the error.
+//!
+//! Patches and files are compared as bytes, line by line on `\n`; a `\r`
+//! before it is part of the line on both sides, so a diff of a CRLF file
+//! applies to it, and sources need not be UTF-8.
+//!
+//! Patches are applied on the host, outside any isolation, so every file
+//! they name must stay inside the patched tree: names that leave it and
+//! paths through symbolic links (which the extracted sources may contain)
+//! are refused, and files are opened without following links.
+
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Result, WrightError};
+
+/// Longest excerpt of a failed hunk quoted in an error.
+const HUNK_EXCERPT_LINES: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Context(Vec<u8>),
+    Remove(Vec<u8>),
+    Add(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct Hunk {
+    /// The `@@ -a,b +c,d @@` line.
+    header: String,
+    old_start: usize,
+    old_len: usize,
+    lines: Vec<Line>,
+    /// The new side ends without a trailing newline.
+    new_missing_newline: bool,
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&[u8]> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                Line::Context(s) | Line::Remove(s) => Some(s.as_slice()),
+                Line::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<Vec<u8>> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                Line::Context(s) | Line::Add(s) => Some(s.clone()),
+                Line::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    fn excerpt(&self) -> String {
+        let mut out = self.header.clone();
+        for line in self.lines.iter().take(HUNK_EXCERPT_LINES) {
+            let (mark, text) = match line {
+                Line::Context(s) => (' ', s),
+                Line::Remove(s) => ('-', s),
+                Line::Add(s) => ('+', s),
+            };
+            out.push('\n');
+            out.push(mark);
+            out.push_str(String::from_utf8_lossy(text).trim_end_matches('\r'));
+        }
+        if self.lines.len() > HUNK_EXCERPT_LINES {
+            out.push_str("\n...");
+        }
+        out
+    }
+}
+
+#[derive(Debug)]
+struct FilePatch {
+    /// `None` for `/dev/null` (a created file).
+    old_path: Option<String>,
+    /// `None` for `/dev/null` (a deleted file).
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// Apply every file of the unified diff `content` under `root`, dropping
+/// `strip` leading path components from the names it records. `name`
+/// identifies the patch in errors. Returns the number of files changed.
+pub fn apply(content: &[u8], name: &str, root: &Path, strip: u32) -> Result<usize> {
+    let files =
+        parse(content).map_err(|msg| WrightError::ForgeError(format!("patch {name}: {msg}")))?;
+    if files.is_empty() {
+        return Err(WrightError::ForgeError(format!(
+            "patch {name}: no file changes found"
+        )));
+    }
+    for file in &files {
+        apply_file(file, name, root, strip)?;
+    }
+    Ok(files.len())
+}
+
+fn apply_file(file: &FilePatch, name: &str, root: &Path, strip: u32) -> Result<()> {
+    let recorded = file
+        .new_path
+        .as_deref()
+        .or(file.old_path.as_deref())
+        .unwrap_or_default();
+    let rel = strip_path(recorded, strip).ok_or_else(|| {
+        WrightError::ForgeError(format!(
+            "patch {name}: cannot strip {strip} component(s) from '{recorded}'"
+        ))
+    })?;
+    let display = rel.display().to_string();
+    let target = beneath(root, &display)
+        .map_err(|msg| WrightError::ForgeError(format!("patch {name}: {display}: {msg}")))?;
+
+    let (mut lines, mut trailing_newline) = if file.old_path.is_none() {
+        if target.exists() {
+            return Err(WrightError::ForgeError(format!(
+                "patch {name}: {display} already exists but the patch creates it"
+            )));
+        }
+        (Vec::new(), true)
+    } else {
+        let mut text = Vec::new();
+        OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(&target)
+            .and_then(|mut f| f.read_to_end(&mut text))
+            .map_err(|e| {
+                WrightError::ForgeError(format!("patch {name}: cannot read {display}: {e}"))
+            })?;
+        split_lines(&text)
+    };
+
+    let mut offset: isize = 0;
+    let mut min_pos = 0usize;
+    for (i, hunk) in file.hunks.iter().enumerate() {
+        let old = hunk.old_lines();
+        // An empty old side (`-5,0`) inserts after line 5, not at it.
+        let recorded_pos = if hunk.old_len == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (recorded_pos as isize + offset).max(0) as usize;
+        let Some(pos) = find_hunk(&lines, &old, expected, min_pos) else {
+            return Err(WrightError::ForgeError(format!(
+                "patch {name}: hunk #{} of {display} did not apply\n{}",
+                i + 1,
+                hunk.excerpt()
+            )));
+        };
+        let reaches_end = pos + old.len() == lines.len();
+        let new = hunk.new_lines();
+        let new_len = new.len();
+        lines.splice(pos..pos + old.len(), new);
+        if reaches_end {
+            trailing_newline = !hunk.new_missing_newline;
+        }
+        // Later hunks record old-file positions; shift them by where this
+        // one landed and by the lines it added or removed.
+        offset = pos as isize - recorded_pos as isize + new_len as isize - old.len() as isize;
+        min_pos = pos + new_len;
+    }
+
+    if file.new_path.is_none() {
+        if !lines.is_empty() {
+            return Err(WrightError::ForgeError(format!(
+                "patch {name}: {display} is not empty after removing the patched lines"
+            )));
+        }
+        return std::fs::remove_file(&target).map_err(|e| {
+            WrightError::ForgeError(format!("patch {name}: cannot remove {display}: {e}"))
+        });
+    }
+
+    let mut text = lines.join(&b'\n');
+    if trailing_newline && !lines.is_empty() {
+        text.push(b'\n');
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(&target)
+        .and_then(|mut f| f.write_all(&text))
+        .map_err(|e| WrightError::ForgeError(format!("patch {name}: cannot write {display}: {e}")))
+}
+
+/// `rel` under `base`, refusing names that would leave it and existing
+/// components that are symbolic links.
+pub fn beneath(base: &Path, rel: &str) -> std::result::Result<PathBuf, String> {
+    let mut path = base.to_path_buf();
+    for component in Path::new(rel).components() {
+        match component {
+            Component::CurDir => continue,
+            Component::Normal(part) => path.push(part),
+            _ => return Err("path leaves the source tree".to_string()),
+        }
+        if std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink()) {
+            return Err(format!(
+                "{} is a symbolic link",
+                path.strip_prefix(base).unwrap_or(&path).display()
+            ));
+        }
+    }
+    Ok(path)
+}
+
+/// Where `old` occurs in `lines`, searching outward from `expected` but
+/// never before `min_pos` (the end of the previous hunk).
+fn find_hunk(lines: &[Vec<u8>], old: &[&[u8]], expected: usize, min_pos: usize) -> Option<usize> {
+    if old.len() > lines.len() {
+        return None;
+    }
+    let last = lines.len() - old.len();
+    let matches = |pos: usize| {
+        pos >= min_pos
+            && pos <= last
+            && lines[pos..pos + old.len()]
+                .iter()
+                .zip(old)
+                .all(|(a, b)| a == b)
+    };
+    let expected = expected.min(last);
+    for distance in 0..=lines.len() {
+        if matches(expected + distance) {
+            return Some(expected + distance);
+        }
+        if distance <= expected && matches(expected - distance) {
+            return Some(expected - distance);
+        }
+    }
+    None
+}
+
+/// The lines of `text` without their `\n`, and whether the last one had
+/// it.
+fn split_lines(text: &[u8]) -> (Vec<Vec<u8>>, bool) {
+    let trailing_newline = text.ends_with(b"\n");
+    let body = text.strip_suffix(b"\n").unwrap_or(text);
+    if body.is_empty() && !trailing_newline {
+        return (Vec::new(), true);
+    }
+    (
+        body.split(|&b| b == b'\n').map(<[u8]>::to_vec).collect(),
+        trailing_newline,
+    )
+}
+
+/// Drop `strip` leading components from a path recorded in a diff header,
+/// refusing names that would leave the patched tree.
+fn strip_path(recorded: &str, strip: u32) -> Option<PathBuf> {
+    let mut components = Path::new(recorded)
+        .components()
+        .filter(|c| *c != Component::CurDir);
+    for _ in 0..strip {
+        components.next()?;
+    }
+    let rest: PathBuf = components.collect();
+    let confined = rest.components().all(|c| matches!(c, Component::Normal(_)));
+    (confined && !rest.as_os_str().is_empty()).then_some(rest)
+}
+
+/// The path of a `---`/`+++` header, without any timestamp.
+fn header_path(rest: &str) -> Option<String> {
+    let path = rest.split('\t').next().unwrap_or(rest).trim_end();
+    (path != "/dev/null").then(|| path.to_string())
+}
+
+fn parse(content: &[u8]) -> std::result::Result<Vec<FilePatch>, String> {
+    let (lines, _) = split_lines(content);
+    let lines: Vec<&[u8]> = lines.iter().map(Vec::as_slice).collect();
+    let text = |line: &[u8]| String::from_utf8_lossy(line).into_owned();
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with(b"GIT binary patch") || line.starts_with(b"Binary files ") {
+            return Err(format!("line {}: binary patches are not supported", i + 1));
+        }
+        let Some(old) = line.strip_prefix(b"--- ") else {
+            i += 1;
+            continue;
+        };
+        let Some(new) = lines.get(i + 1).and_then(|l| l.strip_prefix(b"+++ ")) else {
+            i += 1;
+            continue;
+        };
+        let mut file = FilePatch {
+            old_path: header_path(&text(old)),
+            new_path: header_path(&text(new)),
+            hunks: Vec::new(),
+        };
+        i += 2;
+        while let Some(header) = lines.get(i).filter(|l| l.starts_with(b"@@ ")) {
+            let (hunk, next) = parse_hunk(&lines, i, text(header).trim_end())?;
+            file.hunks.push(hunk);
+            i = next;
+        }
+        if file.hunks.is_empty() {
+            return Err(format!("line {}: file header without hunks", i));
+        }
+        files.push(file);
+    }
+    Ok(files)
+}
+
+fn parse_hunk(
+    lines: &[&[u8]],
+    start: usize,
+    header: &str,
+) -> std::result::Result<(Hunk, usize), String> {
+    let bad = || format!("line {}: malformed hunk header '{header}'", start + 1);
+    let ranges = header
+        .strip_prefix("@@ -")
+        .and_then(|r| r.split_once(" @@"))
+        .map(|(r, _)| r)
+        .ok_or_else(bad)?;
+    let (old, new) = ranges.split_once(" +").ok_or_else(bad)?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((s, n)) => Some((s.parse().ok()?, n.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = range(old).ok_or_else(bad)?;
+    let (_, new_len) = range(new).ok_or_else(bad)?;
+
+    let mut hunk = Hunk {
+        header: header.to_string(),
+        old_start,
+        old_len,
+        lines: Vec::new(),
+        new_missing_newline: false,
+    };
+    let (mut old_seen, mut new_seen) = (0, 0);
+    let mut i = start + 1;
+    while old_seen < old_len || new_seen < new_len {
+        let Some(&line) = lines.get(i) else {
+            return Err(format!("hunk at line {} is truncated", start + 1));
+        };
+        let parsed = match line.first() {
+            Some(b' ') => Line::Context(line[1..].to_vec()),
+            // Some tools drop the space of empty context lines.
+            None => Line::Context(Vec::new()),
+            Some(b'\r') if line.len() == 1 => Line::Context(line.to_vec()),
+            Some(b'-') => Line::Remove(line[1..].to_vec()),
+            Some(b'+') => Line::Add(line[1..].to_vec()),
+            Some(b'\\') => {
+                note_missing_newline(&mut hunk);
+                i += 1;
+                continue;
+            }
+            _ => return Err(format!("line {}: unexpected line in hunk", i + 1)),
+        };
+        match parsed {
+            Line::Context(_) => {
+                old_seen += 1;
+                new_seen += 1;
+            }
+            Line::Remove(_) => old_seen += 1,
+            Line::Add(_) => new_seen += 1,
+        }
+        hunk.lines.push(parsed);
+        i += 1;
+    }
+    while lines.get(i).is_some_and(|l| l.starts_with(b"\\")) {
+        note_missing_newline(&mut hunk);
+        i += 1;
+    }
+    Ok((hunk, i))
+}
+
+/// `\ No newline at end of file` qualifies the line before it; only a
+/// line the new side keeps matters when writing the result.
+fn note_missing_newline(hunk: &mut Hunk) {
+    if matches!(hunk.lines.last(), Some(Line::Context(_) | Line::Add(_))) {
+        hunk.new_missing_newline = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "\
+diff --git a/src/main.c b/src/main.c
+--- a/src/main.c\t2024-01-01 00:00:00
++++ b/src/main.c\t2024-01-02 00:00:00
+@@ -2,3 +2,3 @@
+ int main(void) {
+-    return 1;
++    return 0;
+ }
+--- /dev/null
++++ b/NEWS
+@@ -0,0 +1 @@
++patched
+";
+
+    #[test]
+    fn applies_at_an_offset_and_creates_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/main.c"),
+            "/* added upstream */\n#include <stdio.h>\nint main(void) {\n    return 1;\n}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            apply(PATCH.as_bytes(), "fix.patch", dir.path(), 1).unwrap(),
+            2
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/main.c")).unwrap(),
+            "/* added upstream */\n#include <stdio.h>\nint main(void) {\n    return 0;\n}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("NEWS")).unwrap(),
+            "patched\n"
+        );
+    }
+
+    #[test]
+    fn reports_the_hunk_that_did_not_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/main.c"),
+            "#include <stdio.h>\nint main(void) {\n    return 2;\n}\n",
+        )
+        .unwrap();
+
+        let err = apply(PATCH.as_bytes(), "fix.patch", dir.path(), 1)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("patch fix.patch: hunk #1 of src/main.c did not apply"),
+            "{err}"
+        );
+        assert!(err.contains("@@ -2,3 +2,3 @@\n int main(void) {\n-    return 1;"));
+    }
+
+    #[test]
+    fn honours_strip_level_and_missing_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("VERSION"), "1.0\n").unwrap();
+        let patch = "\
+--- VERSION
++++ VERSION
+@@ -1 +1 @@
+-1.0
++1.0-wright
+\\ No newline at end of file
+";
+        apply(patch.as_bytes(), "version.patch", dir.path(), 0).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("VERSION")).unwrap(),
+            "1.0-wright"
+        );
+
+        let escaping = "--- a/../x\n+++ b/../x\n@@ -0,0 +1 @@\n+x\n";
+        assert!(apply(escaping.as_bytes(), "bad.patch", dir.path(), 1).is_err());
+    }
+
+    #[test]
+    fn applies_to_crlf_and_non_utf8_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("dos.txt"), b"one\r\ntwo\r\n").unwrap();
+        std::fs::write(dir.path().join("latin1.txt"), b"caf\xe9\nold\n").unwrap();
+        let patch = b"\
+--- a/dos.txt\r
++++ b/dos.txt\r
+@@ -1,2 +1,2 @@\r
+ one\r
+-two\r
++three\r
+--- a/latin1.txt
++++ b/latin1.txt
+@@ -1,2 +1,2 @@
+ caf\xe9
+-old
++new
+";
+        assert_eq!(apply(patch, "mixed.patch", dir.path(), 1).unwrap(), 2);
+        assert_eq!(
+            std::fs::read(dir.path().join("dos.txt")).unwrap(),
+            b"one\r\nthree\r\n"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("latin1.txt")).unwrap(),
+            b"caf\xe9\nnew\n"
+        );
+
+        // An LF diff does not match CRLF lines.
+        std::fs::write(dir.path().join("dos.txt"), b"one\r\ntwo\r\n").unwrap();
+        let lf = b"--- a/dos.txt\n+++ b/dos.txt\n@@ -1 +1 @@\n-one\n+uno\n";
+        assert!(apply(lf, "lf.patch", dir.path(), 1).is_err());
+    }
+
+    #[test]
+    fn refuses_to_patch_through_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(
+            outside.path().join("main.c"),
+            "int main(void) {\n    return 1;\n}\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("src")).unwrap();
+
+        let err = apply(PATCH.as_bytes(), "fix.patch", dir.path(), 1)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("src is a symbolic link"), "{err}");
+        assert_eq!(
+            std::fs::read_to_string(outside.path().join("main.c")).unwrap(),
+            "int main(void) {\n    return 1;\n}\n"
+        );
+
+        std::fs::remove_file(dir.path().join("src")).unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("main.c"), dir.path().join("src/main.c"))
+            .unwrap();
+        assert!(apply(PATCH.as_bytes(), "fix.patch", dir.path(), 1).is_err());
+        assert!(beneath(dir.path(), "../x").is_err());
+        assert!(beneath(dir.path(), "/etc").is_err());
+    }
+}
@@ -0,0 +1,947 @@
+//! The HTTP(S) proxy behind `network = "proxy"` stages.
+//!
+//! A proxied stage runs in its own network namespace whose only way out is
+//! an HTTP proxy at [`PROXY_URL`](crate::isolation::PROXY_URL), relayed by
+//! the isolation to the Unix socket of a [`Proxy`] running here.  The proxy
+//! fetches every request itself: plain HTTP directly, and HTTPS by
+//! terminating the `CONNECT` tunnel with a certificate from a throwaway CA
+//! the stage is made to trust.  That way it sees every URL and body, and
+//!
+//! - records `<METHOD> <url> <status> sha256=<body hash>` for each request,
+//!   into the stage log and `fetches/<stage>` in the build root, from where
+//!   sealing copies them into `.PARTINFO` `[provenance] fetches`;
+//! - stores successful GET responses in the proxy cache
+//!   (`network.proxy_cache`), which serves them when the upstream cannot be
+//!   reached, and exclusively when `network.proxy_offline` is set.
+//!
+//! Upstreams on loopback, link-local, or private addresses (the host's own
+//! services, its LAN, cloud metadata) are refused with a 403 unless
+//! `network.proxy_allow` lists them.  Host names are checked against every
+//! address they resolve to, and the upstream client connects only to the
+//! addresses that passed.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::error::{Result, WrightError};
+use crate::isolation::NetworkProxy;
+use crate::util::x509::CertAuthority;
+
+/// Directory of the build root holding each proxied stage's fetch records.
+pub const FETCHES_DIR: &str = "fetches";
+
+/// Request headers not forwarded upstream: hop-by-hop, or set by the
+/// upstream client itself.
+const HOP_HEADERS: &[&str] = &[
+    "connection",
+    "proxy-connection",
+    "keep-alive",
+    "proxy-authorization",
+    "proxy-authenticate",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+];
+
+/// Largest request or response head accepted, in bytes.
+const MAX_HEAD: usize = 64 * 1024;
+
+/// Size of the reads a body is relayed in.
+const COPY_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ProxyOptions {
+    /// Where successful GET responses are kept for offline replay.
+    pub cache_dir: PathBuf,
+    /// Serve only from the cache; never contact the upstream.
+    pub offline: bool,
+    /// Upstream connect and response timeout in seconds.
+    pub timeout: u64,
+    /// Host names, addresses, and CIDR networks that may be reached even
+    /// though they are loopback, link-local, or private.
+    pub allow: Vec<String>,
+}
+
+/// A running proxy for one stage. Stops when finished or dropped.
+pub struct Proxy {
+    dir: tempfile::TempDir,
+    state: Arc<State>,
+    accept: Option<JoinHandle<()>>,
+}
+
+struct State {
+    ca: CertAuthority,
+    tls: Mutex<HashMap<String, Arc<rustls::ServerConfig>>>,
+    options: ProxyOptions,
+    guard: Guard,
+    fetches: Mutex<Vec<String>>,
+    stopped: AtomicBool,
+}
+
+struct Request {
+    method: String,
+    target: String,
+    close: bool,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+enum Body {
+    Bytes(Vec<u8>),
+    /// Relayed as it is read, from the upstream or the cache; of the given
+    /// length if known.
+    Stream(Box<dyn Read>, Option<u64>),
+}
+
+/// What a served body is copied to besides the client: the hash for its
+/// fetch record and, for a cacheable upstream response, the proxy cache.
+struct Capture {
+    hasher: Sha256,
+    cache: Option<tempfile::NamedTempFile>,
+}
+
+/// Which upstream addresses may be reached: everything but loopback,
+/// link-local, and private addresses, plus what `allow` lists.  Also the
+/// upstream client's resolver, so a name cannot be re-resolved to an
+/// address that was not checked.
+#[derive(Debug, Clone, Default)]
+struct Guard {
+    hosts: Vec<String>,
+    nets: Vec<(IpAddr, u8)>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CacheMeta {
+    url: String,
+    content_type: Option<String>,
+}
+
+impl Proxy {
+    /// Start a proxy listening on a fresh Unix socket.
+    pub fn start(options: &ProxyOptions) -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("wright-proxy")
+            .tempdir()
+            .map_err(WrightError::IoError)?;
+        let guard = Guard::new(&options.allow)?;
+        let ca = CertAuthority::generate("wright build proxy CA")?;
+        std::fs::write(dir.path().join("ca.pem"), ca.pem()).map_err(WrightError::IoError)?;
+        let listener =
+            UnixListener::bind(dir.path().join("proxy.sock")).map_err(WrightError::IoError)?;
+
+        let state = Arc::new(State {
+            ca,
+            tls: Mutex::new(HashMap::new()),
+            options: options.clone(),
+            guard,
+            fetches: Mutex::new(Vec::new()),
+            stopped: AtomicBool::new(false),
+        });
+        let accept_state = state.clone();
+        let accept = std::thread::spawn(move || {
+            // Built off the async runtime: the blocking client may not be
+            // created or dropped inside it.
+            let client =
+                match upstream_client(accept_state.options.timeout, accept_state.guard.clone()) {
+                    Ok(client) => client,
+                    Err(e) => {
+                        warn!(event = "proxy.failed", error = %e, "build proxy unavailable");
+                        return;
+                    }
+                };
+            for stream in listener.incoming() {
+                if accept_state.stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = accept_state.clone();
+                let client = client.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = serve(&state, &client, stream) {
+                        debug!(event = "proxy.connection_failed", error = %e, "proxy connection failed");
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            dir,
+            state,
+            accept: Some(accept),
+        })
+    }
+
+    /// What the isolation needs to route a stage through this proxy.
+    pub fn endpoint(&self) -> NetworkProxy {
+        NetworkProxy {
+            socket: self.dir.path().join("proxy.sock"),
+            ca_cert: self.dir.path().join("ca.pem"),
+        }
+    }
+
+    /// Stop the proxy and return its fetch records in request order.
+    pub fn finish(mut self) -> Vec<String> {
+        self.stop();
+        std::mem::take(&mut *self.state.fetches.lock().unwrap())
+    }
+
+    fn stop(&mut self) {
+        if let Some(accept) = self.accept.take() {
+            self.state.stopped.store(true, Ordering::Release);
+            // Wake the accept loop so it sees the flag.
+            let _ = UnixStream::connect(self.dir.path().join("proxy.sock"));
+            let _ = accept.join();
+        }
+    }
+}
+
+impl Drop for Proxy {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Replace the fetch records of `stage` in `build_root`.
+pub fn write_fetches(build_root: &Path, stage: &str, fetches: &[String]) -> Result<()> {
+    let dir = build_root.join(FETCHES_DIR);
+    std::fs::create_dir_all(&dir).map_err(WrightError::IoError)?;
+    let mut content = fetches.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    std::fs::write(dir.join(stage), content).map_err(WrightError::IoError)
+}
+
+/// Every fetch recorded by the proxied stages of the build in
+/// `build_root`, sorted and deduplicated so that sealing is reproducible.
+pub fn recorded_fetches(build_root: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(build_root.join(FETCHES_DIR)) else {
+        return Vec::new();
+    };
+    let mut fetches: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .flat_map(|content| content.lines().map(str::to_string).collect::<Vec<_>>())
+        .filter(|line| !line.is_empty())
+        .collect();
+    fetches.sort();
+    fetches.dedup();
+    fetches
+}
+
+fn upstream_client(timeout: u64, guard: Guard) -> Result<reqwest::blocking::Client> {
+    reqwest::blocking::Client::builder()
+        .user_agent("wright/0.1.0 (Linux; x86_64)")
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(guard))
+        .connect_timeout(std::time::Duration::from_secs(timeout))
+        .timeout(std::time::Duration::from_secs(timeout))
+        .build()
+        .map_err(|e| WrightError::NetworkError(format!("failed to create client: {}", e)))
+}
+
+fn io_error(e: impl std::fmt::Display) -> WrightError {
+    WrightError::NetworkError(e.to_string())
+}
+
+/// Serve one client connection: absolute-form requests directly, and a
+/// `CONNECT` tunnel by terminating its TLS and serving the requests inside.
+fn serve(state: &State, client: &reqwest::blocking::Client, stream: UnixStream) -> Result<()> {
+    let mut conn = BufReader::new(stream);
+    loop {
+        let Some(request) = read_request(&mut conn)? else {
+            return Ok(());
+        };
+        if request.method != "CONNECT" {
+            let close = request.close;
+            let url = request.target.clone();
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                write_response(
+                    conn.get_mut(),
+                    &request,
+                    &mut error_response(400, "absolute URL required"),
+                    &mut Capture::new(None),
+                )?;
+                return Ok(());
+            }
+            state.fetch(client, &request, &url, conn.get_mut())?;
+            if close {
+                return Ok(());
+            }
+            continue;
+        }
+
+        let authority = request.target.clone();
+        let host = host_of(&authority);
+        let tls = state.tls_config(&host)?;
+        conn.get_mut()
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .map_err(WrightError::IoError)?;
+        let base = format!(
+            "https://{}",
+            authority.strip_suffix(":443").unwrap_or(&authority)
+        );
+        // The client waits for the 200 before its ClientHello, so nothing
+        // is left buffered.
+        let stream = conn.into_inner();
+        let tls = rustls::ServerConnection::new(tls).map_err(io_error)?;
+        let mut conn = BufReader::new(rustls::StreamOwned::new(tls, stream));
+        while let Some(request) = read_request(&mut conn)? {
+            let url = format!("{base}{}", request.target);
+            state.fetch(client, &request, &url, conn.get_mut())?;
+            if request.close {
+                break;
+            }
+        }
+        conn.get_mut().conn.send_close_notify();
+        let _ = conn.get_mut().flush();
+        return Ok(());
+    }
+}
+
+impl State {
+    fn tls_config(&self, host: &str) -> Result<Arc<rustls::ServerConfig>> {
+        let mut configs = self.tls.lock().unwrap();
+        if let Some(config) = configs.get(host) {
+            return Ok(config.clone());
+        }
+        let issued = self.ca.issue(host)?;
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(io_error)?
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![issued.cert.into()],
+            rustls::pki_types::PrivatePkcs8KeyDer::from(issued.pkcs8_key).into(),
+        )
+        .map_err(io_error)?;
+        let config = Arc::new(config);
+        configs.insert(host.to_string(), config.clone());
+        Ok(config)
+    }
+
+    /// Serve `url` for `request` to `out`, from the upstream or the cache,
+    /// and record what was served.
+    fn fetch(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &Request,
+        url: &str,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        let cacheable = request.method == "GET";
+        let mut from_upstream = false;
+        let mut response = if self.options.offline {
+            match self.cached(url).filter(|_| cacheable) {
+                Some(response) => response,
+                None => error_response(502, "not in the proxy cache (network.proxy_offline)"),
+            }
+        } else if let Some(refusal) = self.guard.refusal(url) {
+            error_response(403, &format!("{refusal} (network.proxy_allow)"))
+        } else {
+            match self.upstream(client, request, url) {
+                Ok(response) => {
+                    from_upstream = true;
+                    response
+                }
+                Err(e) => match self.cached(url).filter(|_| cacheable) {
+                    Some(response) => {
+                        warn!(
+                            event = "proxy.cache_fallback",
+                            url,
+                            error = %e,
+                            "upstream unreachable, serving from the proxy cache"
+                        );
+                        response
+                    }
+                    None => error_response(502, &e.to_string()),
+                },
+            }
+        };
+
+        let cache = (from_upstream && cacheable && response.status == 200)
+            .then(|| self.cache_file())
+            .flatten();
+        let mut capture = Capture::new(cache);
+        let served = write_response(out, request, &mut response, &mut capture);
+        let record = format!(
+            "{} {} {} sha256={}",
+            request.method,
+            url,
+            response.status,
+            hex(&capture.hasher.finalize_reset())
+        );
+        debug!(event = "proxy.fetch", record = %record, "proxied fetch");
+        self.fetches.lock().unwrap().push(record);
+        if served.is_ok()
+            && let Some(file) = capture.cache
+        {
+            self.store(url, &response, file);
+        }
+        served
+    }
+
+    fn upstream(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &Request,
+        url: &str,
+    ) -> Result<Response> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes()).map_err(io_error)?;
+        let mut builder = client.request(method, url);
+        for (name, value) in &request.headers {
+            if !HOP_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                builder = builder.header(name, value);
+            }
+        }
+        if !request.body.is_empty() {
+            builder = builder.body(request.body.clone());
+        }
+        let upstream = builder.send().map_err(io_error)?;
+        let status = upstream.status();
+        let mut headers = Vec::new();
+        for (name, value) in upstream.headers() {
+            let name = name.as_str();
+            if HOP_HEADERS.contains(&name) {
+                continue;
+            }
+            headers.push((
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            ));
+        }
+        if request.method == "HEAD"
+            && let Some(len) = upstream.headers().get(reqwest::header::CONTENT_LENGTH)
+        {
+            headers.push((
+                "content-length".to_string(),
+                String::from_utf8_lossy(len.as_bytes()).into_owned(),
+            ));
+        }
+        let len = upstream.content_length();
+        Ok(Response {
+            status: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or("").to_string(),
+            headers,
+            body: Body::Stream(Box::new(upstream), len),
+        })
+    }
+
+    fn cache_path(&self, url: &str) -> PathBuf {
+        self.options
+            .cache_dir
+            .join(hex(&Sha256::digest(url.as_bytes())))
+    }
+
+    fn cached(&self, url: &str) -> Option<Response> {
+        let path = self.cache_path(url);
+        let meta: CacheMeta =
+            serde_json::from_slice(&std::fs::read(path.with_extension("json")).ok()?).ok()?;
+        if meta.url != url {
+            return None;
+        }
+        let file = std::fs::File::open(&path).ok()?;
+        let len = file.metadata().ok()?.len();
+        let mut headers = Vec::new();
+        if let Some(content_type) = meta.content_type {
+            headers.push(("content-type".to_string(), content_type));
+        }
+        Some(Response {
+            status: 200,
+            reason: "OK".to_string(),
+            headers,
+            body: Body::Stream(Box::new(file), Some(len)),
+        })
+    }
+
+    /// A file to copy a response into while it is served, if the cache can
+    /// be written.
+    fn cache_file(&self) -> Option<tempfile::NamedTempFile> {
+        std::fs::create_dir_all(&self.options.cache_dir)
+            .and_then(|_| tempfile::NamedTempFile::new_in(&self.options.cache_dir))
+            .inspect_err(|e| {
+                debug!(event = "proxy.cache_failed", error = %e, "failed to open proxy cache file")
+            })
+            .ok()
+    }
+
+    /// Keep a successful response, copied into `file`, for replay.
+    /// Best-effort: a cache that cannot be written only costs offline
+    /// builds.
+    fn store(&self, url: &str, response: &Response, file: tempfile::NamedTempFile) {
+        let path = self.cache_path(url);
+        let meta = CacheMeta {
+            url: url.to_string(),
+            content_type: response
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                .map(|(_, value)| value.clone()),
+        };
+        let stored = file.persist(&path).map_err(|e| e.error).and_then(|_| {
+            std::fs::write(
+                path.with_extension("json"),
+                serde_json::to_vec(&meta).unwrap_or_default(),
+            )
+        });
+        if let Err(e) = stored {
+            debug!(event = "proxy.cache_failed", url, error = %e, "failed to cache response");
+        }
+    }
+}
+
+/// Read one request, or `None` at a clean end of the connection.
+fn read_request(conn: &mut impl BufRead) -> Result<Option<Request>> {
+    let mut head = Vec::new();
+    loop {
+        let mut line = Vec::new();
+        let n = conn
+            .by_ref()
+            .take((MAX_HEAD - head.len()) as u64)
+            .read_until(b'\n', &mut line)
+            .map_err(WrightError::IoError)?;
+        if n == 0 {
+            if head.is_empty() {
+                return Ok(None);
+            }
+            return Err(io_error("connection closed inside a request head"));
+        }
+        if line == b"\r\n" || line == b"\n" {
+            if head.is_empty() {
+                continue;
+            }
+            break;
+        }
+        if !line.ends_with(b"\n") {
+            return Err(io_error("request head too large"));
+        }
+        head.extend_from_slice(&line);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target), Some(version)) = (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) else {
+        return Err(io_error("malformed request line"));
+    };
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+    let close = match header("connection").or(header("proxy-connection")) {
+        Some(value) => value.eq_ignore_ascii_case("close"),
+        None => version == "HTTP/1.0",
+    };
+
+    let mut body = Vec::new();
+    if header("transfer-encoding").is_some_and(|te| te.eq_ignore_ascii_case("chunked")) {
+        read_chunked(conn, &mut body)?;
+    } else if let Some(len) = header("content-length") {
+        let len: u64 = len.parse().map_err(io_error)?;
+        conn.by_ref()
+            .take(len)
+            .read_to_end(&mut body)
+            .map_err(WrightError::IoError)?;
+    }
+
+    Ok(Some(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        close,
+        headers,
+        body,
+    }))
+}
+
+fn read_chunked(conn: &mut impl BufRead, body: &mut Vec<u8>) -> Result<()> {
+    loop {
+        let mut size = String::new();
+        conn.read_line(&mut size).map_err(WrightError::IoError)?;
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(io_error)?;
+        if size == 0 {
+            // Trailers, up to the blank line.
+            loop {
+                let mut line = String::new();
+                if conn.read_line(&mut line).map_err(WrightError::IoError)? == 0
+                    || line.trim().is_empty()
+                {
+                    return Ok(());
+                }
+            }
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        conn.read_exact(&mut body[start..])
+            .map_err(WrightError::IoError)?;
+        let mut crlf = String::new();
+        conn.read_line(&mut crlf).map_err(WrightError::IoError)?;
+    }
+}
+
+/// Write `response` to `out`, relaying its body through `capture`.  A body
+/// of unknown length is chunked, or ends with the connection if the client
+/// asked for that.
+fn write_response(
+    out: &mut impl Write,
+    request: &Request,
+    response: &mut Response,
+    capture: &mut Capture,
+) -> Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, response.reason);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    let bodiless = request.method == "HEAD" || matches!(response.status, 204 | 304);
+    let len = match &response.body {
+        Body::Bytes(bytes) => Some(bytes.len() as u64),
+        Body::Stream(_, len) => *len,
+    };
+    let chunked = !bodiless && len.is_none() && !request.close;
+    match len {
+        Some(len) if !bodiless => head.push_str(&format!("content-length: {len}\r\n")),
+        _ if chunked => head.push_str("transfer-encoding: chunked\r\n"),
+        _ => {}
+    }
+    if request.close {
+        head.push_str("connection: close\r\n");
+    }
+    head.push_str("\r\n");
+    out.write_all(head.as_bytes())
+        .map_err(WrightError::IoError)?;
+    if !bodiless {
+        let reader: &mut dyn Read = match &mut response.body {
+            Body::Bytes(bytes) => &mut &bytes[..],
+            Body::Stream(reader, _) => reader,
+        };
+        let mut buf = vec![0u8; COPY_CHUNK];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(io_error(e)),
+            };
+            capture.feed(&buf[..n]);
+            if chunked {
+                write!(out, "{n:x}\r\n").map_err(WrightError::IoError)?;
+            }
+            out.write_all(&buf[..n]).map_err(WrightError::IoError)?;
+            if chunked {
+                out.write_all(b"\r\n").map_err(WrightError::IoError)?;
+            }
+        }
+        if chunked {
+            out.write_all(b"0\r\n\r\n").map_err(WrightError::IoError)?;
+        }
+    }
+    out.flush().map_err(WrightError::IoError)
+}
+
+impl Capture {
+    fn new(cache: Option<tempfile::NamedTempFile>) -> Self {
+        Self {
+            hasher: Sha256::new(),
+            cache,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        if let Some(file) = &mut self.cache
+            && let Err(e) = file.write_all(chunk)
+        {
+            debug!(event = "proxy.cache_failed", error = %e, "failed to write proxy cache file");
+            self.cache = None;
+        }
+    }
+}
+
+fn error_response(status: u16, message: &str) -> Response {
+    Response {
+        status,
+        reason: match status {
+            400 => "Bad Request",
+            403 => "Forbidden",
+            _ => "Bad Gateway",
+        }
+        .to_string(),
+        headers: vec![("content-type".to_string(), "text/plain".to_string())],
+        body: Body::Bytes(format!("wright proxy: {message}\n").into_bytes()),
+    }
+}
+
+impl Guard {
+    fn new(allow: &[String]) -> Result<Self> {
+        let mut guard = Self::default();
+        for entry in allow {
+            let (addr, prefix) = match entry.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(prefix)),
+                None => (entry.as_str(), None),
+            };
+            let Ok(addr) = addr.parse::<IpAddr>() else {
+                if prefix.is_some() {
+                    return Err(WrightError::ConfigError(format!(
+                        "network.proxy_allow: '{entry}' is not a CIDR network"
+                    )));
+                }
+                guard.hosts.push(entry.to_ascii_lowercase());
+                continue;
+            };
+            let width = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix.map(str::parse::<u8>) {
+                None => width,
+                Some(Ok(prefix)) if prefix <= width => prefix,
+                Some(_) => {
+                    return Err(WrightError::ConfigError(format!(
+                        "network.proxy_allow: '{entry}' has an invalid prefix length"
+                    )));
+                }
+            };
+            guard.nets.push((addr, prefix));
+        }
+        Ok(guard)
+    }
+
+    fn host_allowed(&self, host: &str) -> bool {
+        self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host))
+    }
+
+    fn addr_allowed(&self, ip: IpAddr) -> bool {
+        !is_internal(ip)
+            || self
+                .nets
+                .iter()
+                .any(|&(net, prefix)| in_network(ip, net, prefix))
+    }
+
+    /// Why `url` may not be fetched, if it may not.  A name that does not
+    /// resolve is left to fail upstream.
+    fn refusal(&self, url: &str) -> Option<String> {
+        let url = reqwest::Url::parse(url).ok()?;
+        let host = url.host_str()?;
+        if self.host_allowed(host) {
+            return None;
+        }
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<IpAddr> = match literal.parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => (host, url.port_or_known_default().unwrap_or(80))
+                .to_socket_addrs()
+                .ok()?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+        (!addrs.is_empty() && !addrs.iter().any(|&ip| self.addr_allowed(ip)))
+            .then(|| format!("{host} is a loopback, link-local, or private address"))
+    }
+}
+
+impl reqwest::dns::Resolve for Guard {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let guard = self.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let allowed: Vec<SocketAddr> = if guard.host_allowed(&host) {
+                addrs
+            } else {
+                addrs
+                    .into_iter()
+                    .filter(|a| guard.addr_allowed(a.ip()))
+                    .collect()
+            };
+            if allowed.is_empty() {
+                return Err(format!("{host} resolves to no address the proxy may reach").into());
+            }
+            Ok(Box::new(allowed.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Loopback, link-local, private, and other addresses that are not on the
+/// internet: the host itself, its LAN, and cloud metadata services.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Shared address space (carrier-grade NAT), 100.64.0.0/10.
+                || (a == 100 && b & 0xc0 == 64)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_internal(IpAddr::V4(v4)),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+            }
+        },
+    }
+}
+
+fn in_network(ip: IpAddr, net: IpAddr, prefix: u8) -> bool {
+    let bits = |ip: IpAddr| match ip {
+        IpAddr::V4(ip) => (u128::from(u32::from(ip)), 32),
+        IpAddr::V6(ip) => (u128::from(ip), 128),
+    };
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    };
+    let ((ip, width), (net, net_width)) = (bits(ip), bits(net));
+    width == net_width && (prefix == 0 || (ip ^ net) >> (width - u32::from(prefix)) == 0)
+}
+
+/// The host of a `CONNECT` authority, without port or IPv6 brackets.
+fn host_of(authority: &str) -> String {
+    if let Some(rest) = authority.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest).to_string();
+    }
+    authority
+        .rsplit_once(':')
+        .map(|(host, _)| host)
+        .unwrap_or(authority)
+        .to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request() {
+        let raw = b"POST http://example.org/x HTTP/1.1\r\nHost: example.org\r\n\
+                    Transfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;ext=1\r\nde\r\n0\r\n\r\n\
+                    GET /y HTTP/1.0\r\n\r\n";
+        let mut conn = BufReader::new(&raw[..]);
+        let first = read_request(&mut conn).unwrap().unwrap();
+        assert_eq!(first.method, "POST");
+        assert_eq!(first.target, "http://example.org/x");
+        assert_eq!(first.body, b"abcde");
+        assert!(!first.close);
+        let second = read_request(&mut conn).unwrap().unwrap();
+        assert_eq!(second.target, "/y");
+        assert!(second.close);
+        assert!(read_request(&mut conn).unwrap().is_none());
+
+        assert_eq!(host_of("example.org:443"), "example.org");
+        assert_eq!(host_of("[::1]:8443"), "::1");
+    }
+
+    #[test]
+    fn test_guard_refuses_internal_destinations() {
+        let guard = Guard::default();
+        for url in [
+            "http://127.0.0.1:8080/x",
+            "http://localhost/x",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.1.2.3/x",
+            "http://192.168.1.1/x",
+            "https://[::1]:8443/x",
+            "http://[fe80::1]/x",
+            "http://[::ffff:127.0.0.1]/x",
+            "http://100.64.0.1/x",
+        ] {
+            assert!(guard.refusal(url).is_some(), "{url}");
+        }
+        assert!(guard.refusal("https://93.184.215.14/x").is_none());
+        assert!(guard.refusal("http://[2606:2800::1]/x").is_none());
+
+        let guard =
+            Guard::new(&["127.0.0.1".into(), "10.1.0.0/16".into(), "LocalHost".into()]).unwrap();
+        assert!(guard.refusal("http://127.0.0.1:8080/x").is_none());
+        assert!(guard.refusal("http://localhost/x").is_none());
+        assert!(guard.refusal("http://10.1.200.3/x").is_none());
+        assert!(guard.refusal("http://10.2.0.1/x").is_some());
+        assert!(guard.refusal("http://127.0.0.2/x").is_some());
+
+        assert!(Guard::new(&["10.0.0.0/33".into()]).is_err());
+        assert!(Guard::new(&["mirror.lan/8".into()]).is_err());
+    }
+
+    #[test]
+    fn test_write_response_chunks_body_of_unknown_length() {
+        let mut conn = BufReader::new(&b"GET http://example.org/x HTTP/1.1\r\n\r\n"[..]);
+        let request = read_request(&mut conn).unwrap().unwrap();
+        let mut response = Response {
+            status: 200,
+            reason: "OK".to_string(),
+            headers: Vec::new(),
+            body: Body::Stream(Box::new(&b"hello"[..]), None),
+        };
+        let cache = tempfile::NamedTempFile::new().unwrap();
+        let mut capture = Capture::new(Some(cache));
+        let mut out = Vec::new();
+        write_response(&mut out, &request, &mut response, &mut capture).unwrap();
+        assert_eq!(
+            out,
+            b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n"
+        );
+        assert_eq!(
+            hex(&capture.hasher.finalize()),
+            hex(&Sha256::digest(b"hello"))
+        );
+        let cached = capture.cache.unwrap();
+        assert_eq!(std::fs::read(cached.path()).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_recorded_fetches_sorted_and_deduplicated() {
+        let root = tempfile::tempdir().unwrap();
+        write_fetches(root.path(), "prepare", &["GET b 200 sha256=2".into()]).unwrap();
+        write_fetches(
+            root.path(),
+            "compile",
+            &["GET b 200 sha256=2".into(), "GET a 200 sha256=1".into()],
+        )
+        .unwrap();
+        assert_eq!(
+            recorded_fetches(root.path()),
+            vec!["GET a 200 sha256=1", "GET b 200 sha256=2"]
+        );
+        write_fetches(root.path(), "compile", &[]).unwrap();
+        assert_eq!(recorded_fetches(root.path()), vec!["GET b 200 sha256=2"]);
+    }
+}
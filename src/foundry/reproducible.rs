@@ -0,0 +1,250 @@
+//! Output-tree comparison for `wright build --verify-reproducible`.
+//!
+//! Two forges of the same plan are compared path by path: entry type,
+//! permission bits, symlink target, and file content. Timestamps are not
+//! compared — sealing clamps them to `SOURCE_DATE_EPOCH`.
+
+use std::collections::BTreeMap;
+use std::io::{BufReader, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::error::{Result, WrightError};
+
+/// How a path differs between the first and second forge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difference {
+    OnlyInFirst,
+    OnlyInSecond,
+    Type,
+    Mode,
+    Target,
+    Content,
+}
+
+impl Difference {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Difference::OnlyInFirst => "only-first",
+            Difference::OnlyInSecond => "only-second",
+            Difference::Type => "type",
+            Difference::Mode => "mode",
+            Difference::Target => "target",
+            Difference::Content => "content",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathDifference {
+    /// Path relative to the compared build roots.
+    pub path: String,
+    pub difference: Difference,
+    /// Human-readable summary, e.g. the binary diff of differing content.
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct TreeComparison {
+    /// Paths present and identical in both trees.
+    pub identical: usize,
+    pub differences: Vec<PathDifference>,
+}
+
+enum Entry {
+    Dir(u32),
+    File(u32, PathBuf),
+    Symlink(PathBuf),
+    Other(u32),
+}
+
+impl Entry {
+    fn kind(&self) -> &'static str {
+        match self {
+            Entry::Dir(_) => "directory",
+            Entry::File(..) => "file",
+            Entry::Symlink(_) => "symlink",
+            Entry::Other(_) => "special",
+        }
+    }
+}
+
+/// Compare `subdirs` (e.g. `staging`, `outputs`) of two build roots.
+pub fn compare_trees(first: &Path, second: &Path, subdirs: &[&str]) -> Result<TreeComparison> {
+    let mut a = BTreeMap::new();
+    let mut b = BTreeMap::new();
+    for sub in subdirs {
+        collect(first, sub, &mut a)?;
+        collect(second, sub, &mut b)?;
+    }
+
+    let mut result = TreeComparison::default();
+    let mut paths: Vec<&String> = a.keys().chain(b.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    for path in paths {
+        let diff = match (a.get(path), b.get(path)) {
+            (Some(_), None) => Some((Difference::OnlyInFirst, String::new())),
+            (None, Some(_)) => Some((Difference::OnlyInSecond, String::new())),
+            (Some(x), Some(y)) => compare_entries(x, y)?,
+            (None, None) => None,
+        };
+        match diff {
+            Some((difference, detail)) => result.differences.push(PathDifference {
+                path: path.clone(),
+                difference,
+                detail,
+            }),
+            None => result.identical += 1,
+        }
+    }
+    Ok(result)
+}
+
+fn compare_entries(a: &Entry, b: &Entry) -> Result<Option<(Difference, String)>> {
+    let mode_diff =
+        |x: u32, y: u32| (x != y).then(|| (Difference::Mode, format!("{:04o} vs {:04o}", x, y)));
+    Ok(match (a, b) {
+        (Entry::Dir(x), Entry::Dir(y)) | (Entry::Other(x), Entry::Other(y)) => mode_diff(*x, *y),
+        (Entry::Symlink(x), Entry::Symlink(y)) => (x != y).then(|| {
+            (
+                Difference::Target,
+                format!("{} vs {}", x.display(), y.display()),
+            )
+        }),
+        (Entry::File(mx, px), Entry::File(my, py)) => match binary_diff(px, py)? {
+            Some(summary) => Some((Difference::Content, summary)),
+            None => mode_diff(*mx, *my),
+        },
+        _ => Some((Difference::Type, format!("{} vs {}", a.kind(), b.kind()))),
+    })
+}
+
+fn collect(root: &Path, sub: &str, entries: &mut BTreeMap<String, Entry>) -> Result<()> {
+    let base = root.join(sub);
+    if !base.exists() {
+        return Ok(());
+    }
+    for entry in WalkDir::new(&base).sort_by_file_name() {
+        let entry = entry.map_err(|e| {
+            WrightError::ForgeError(format!("failed to walk {}: {}", base.display(), e))
+        })?;
+        let rel = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let meta = entry
+            .path()
+            .symlink_metadata()
+            .map_err(WrightError::IoError)?;
+        let mode = meta.permissions().mode() & 0o7777;
+        let ft = meta.file_type();
+        let value = if ft.is_symlink() {
+            Entry::Symlink(std::fs::read_link(entry.path()).map_err(WrightError::IoError)?)
+        } else if ft.is_dir() {
+            Entry::Dir(mode)
+        } else if ft.is_file() {
+            Entry::File(mode, entry.path().to_path_buf())
+        } else {
+            Entry::Other(mode)
+        };
+        entries.insert(rel.to_string_lossy().into_owned(), value);
+    }
+    Ok(())
+}
+
+/// Summarize how two files differ byte for byte, or `None` when equal.
+fn binary_diff(a: &Path, b: &Path) -> Result<Option<String>> {
+    let open = |p: &Path| {
+        std::fs::File::open(p)
+            .map(BufReader::new)
+            .map_err(|e| WrightError::ForgeError(format!("failed to open {}: {}", p.display(), e)))
+    };
+    let (mut ra, mut rb) = (open(a)?, open(b)?);
+    let (mut size_a, mut size_b) = (0u64, 0u64);
+    let mut first: Option<u64> = None;
+    let mut differing = 0u64;
+    let (mut buf_a, mut buf_b) = (vec![0u8; 64 * 1024], vec![0u8; 64 * 1024]);
+    loop {
+        let na = read_full(&mut ra, &mut buf_a)?;
+        let nb = read_full(&mut rb, &mut buf_b)?;
+        if na == 0 && nb == 0 {
+            break;
+        }
+        for i in 0..na.min(nb) {
+            if buf_a[i] != buf_b[i] {
+                first.get_or_insert(size_a + i as u64);
+                differing += 1;
+            }
+        }
+        if na != nb {
+            first.get_or_insert(size_a.min(size_b) + na.min(nb) as u64);
+        }
+        size_a += na as u64;
+        size_b += nb as u64;
+    }
+    let Some(first) = first else {
+        return Ok(None);
+    };
+    let mut summary = format!("{} byte(s) differ, first at offset {:#x}", differing, first);
+    if size_a != size_b {
+        summary.push_str(&format!("; size {} vs {}", size_a, size_b));
+    }
+    Ok(Some(summary))
+}
+
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader
+            .read(&mut buf[filled..])
+            .map_err(WrightError::IoError)?
+        {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_trees_reports_each_difference() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        for root in [a.path(), b.path()] {
+            std::fs::create_dir_all(root.join("staging/usr/bin")).unwrap();
+            std::fs::write(root.join("staging/usr/bin/same"), b"same").unwrap();
+        }
+        std::fs::write(a.path().join("staging/usr/bin/tool"), b"build-1234").unwrap();
+        std::fs::write(b.path().join("staging/usr/bin/tool"), b"build-5678!").unwrap();
+        std::fs::write(a.path().join("staging/usr/bin/extra"), b"x").unwrap();
+        std::os::unix::fs::symlink("same", a.path().join("staging/usr/bin/link")).unwrap();
+        std::os::unix::fs::symlink("tool", b.path().join("staging/usr/bin/link")).unwrap();
+
+        let cmp = compare_trees(a.path(), b.path(), &["staging", "outputs"]).unwrap();
+        let got: Vec<(&str, Difference)> = cmp
+            .differences
+            .iter()
+            .map(|d| (d.path.as_str(), d.difference))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("staging/usr/bin/extra", Difference::OnlyInFirst),
+                ("staging/usr/bin/link", Difference::Target),
+                ("staging/usr/bin/tool", Difference::Content),
+            ]
+        );
+        assert_eq!(
+            cmp.differences[2].detail,
+            "4 byte(s) differ, first at offset 0x6; size 10 vs 11"
+        );
+        // staging, staging/usr, staging/usr/bin, staging/usr/bin/same
+        assert_eq!(cmp.identical, 4);
+    }
+}
@@ -0,0 +1,690 @@
+//! Lockfile-driven vendoring for `cargo_vendor`, `go_mod`, and
+//! `pip_requirements` sources.
+//!
+//! Each lockfile pins every dependency together with a checksum. `Charge`
+//! downloads the pinned artifacts into `<source_dir>/vendor/` during
+//! `fetch`, verifies them against those checksums, and lays them out in
+//! `${WORKDIR}` during `extract` in the form the language's own tooling
+//! reads offline: a cargo directory source, a `file://` Go module proxy,
+//! and a pip wheelhouse.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use sha2::{Digest as _, Sha256};
+
+use crate::error::{Result, WrightError};
+use crate::util::checksum;
+
+/// Root of vendored artifacts, both in `general.source_dir` and in the
+/// work directory.
+pub const VENDOR_DIR: &str = "vendor";
+
+/// crates.io's `source` in a `Cargo.lock`, in either index protocol.
+const CRATES_IO_SOURCES: &[&str] = &[
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+/// How a downloaded artifact is checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Digest {
+    /// Hex SHA-256 of the file.
+    Sha256(String),
+    /// Hex SHA-256 of the file, any one of several.
+    AnySha256(Vec<String>),
+    /// Go's `h1:` dirhash of a module zip.
+    GoZip(String),
+    /// Go's `h1:` dirhash of a `go.mod` file.
+    GoMod(String),
+}
+
+impl Digest {
+    /// Check `path`, returning the digest it actually has on mismatch.
+    pub fn check(&self, path: &Path) -> Result<std::result::Result<(), String>> {
+        let actual = match self {
+            Self::Sha256(_) | Self::AnySha256(_) => checksum::sha256_file(path)?,
+            Self::GoZip(_) => go_zip_hash(path)?,
+            Self::GoMod(_) => go_mod_hash(&std::fs::read(path)?),
+        };
+        let ok = match self {
+            Self::Sha256(expected) | Self::GoZip(expected) | Self::GoMod(expected) => {
+                actual == *expected
+            }
+            Self::AnySha256(accepted) => accepted.contains(&actual),
+        };
+        Ok(if ok { Ok(()) } else { Err(actual) })
+    }
+
+    /// The expected value, for error messages.
+    pub fn expected(&self) -> String {
+        match self {
+            Self::Sha256(h) | Self::GoZip(h) | Self::GoMod(h) => h.clone(),
+            Self::AnySha256(hs) => hs.join(" | "),
+        }
+    }
+}
+
+/// One artifact to download into the source cache.
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub url: String,
+    /// Path relative to `general.source_dir`.
+    pub rel_path: PathBuf,
+    pub digest: Digest,
+}
+
+// ------------------------------------------------------------------
+// Cargo
+// ------------------------------------------------------------------
+
+/// A registry crate pinned by `Cargo.lock`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crate {
+    pub name: String,
+    pub version: String,
+    /// Hex SHA-256 of the `.crate` file.
+    pub checksum: String,
+}
+
+impl Crate {
+    /// `vendor/crates/<name>-<version>.crate`
+    pub fn cache_path(&self) -> PathBuf {
+        Path::new(VENDOR_DIR)
+            .join("crates")
+            .join(format!("{}-{}.crate", self.name, self.version))
+    }
+
+    pub fn download(&self, registry: &str) -> Download {
+        Download {
+            url: format!(
+                "{}/{}/{}-{}.crate",
+                registry.trim_end_matches('/'),
+                self.name,
+                self.name,
+                self.version
+            ),
+            rel_path: self.cache_path(),
+            digest: Digest::Sha256(self.checksum.clone()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<CargoLockPackage>,
+}
+
+#[derive(serde::Deserialize)]
+struct CargoLockPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    checksum: Option<String>,
+}
+
+/// The crates.io crates a `Cargo.lock` pins. Workspace and path members
+/// (no `source`) need no download; git and alternate-registry
+/// dependencies are rejected.
+pub fn parse_cargo_lock(content: &str) -> Result<Vec<Crate>> {
+    let lock: CargoLock = toml::from_str(content)
+        .map_err(|e| WrightError::ParseError(format!("invalid Cargo.lock: {e}")))?;
+    let mut crates = Vec::new();
+    for package in lock.package {
+        let Some(source) = package.source else {
+            continue;
+        };
+        if !CRATES_IO_SOURCES.contains(&source.as_str()) {
+            return Err(WrightError::ValidationError(format!(
+                "Cargo.lock: {} {} comes from '{}'; only crates.io dependencies can be vendored",
+                package.name, package.version, source
+            )));
+        }
+        let checksum = package.checksum.ok_or_else(|| {
+            WrightError::ValidationError(format!(
+                "Cargo.lock: {} {} has no checksum; regenerate the lockfile with a current cargo",
+                package.name, package.version
+            ))
+        })?;
+        crates.push(Crate {
+            name: package.name,
+            version: package.version,
+            checksum,
+        });
+    }
+    Ok(crates)
+}
+
+/// Unpack a verified `.crate` into `<vendor_dir>/<name>-<version>/` as a
+/// cargo directory-source entry.
+pub fn unpack_crate(crate_file: &Path, krate: &Crate, vendor_dir: &Path) -> Result<()> {
+    let file = std::fs::File::open(crate_file)?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    archive.unpack(vendor_dir).map_err(|e| {
+        WrightError::ForgeError(format!("failed to unpack {}: {e}", crate_file.display()))
+    })?;
+    let crate_dir = vendor_dir.join(format!("{}-{}", krate.name, krate.version));
+    if !crate_dir.is_dir() {
+        return Err(WrightError::ForgeError(format!(
+            "{} did not unpack to {}-{}/",
+            crate_file.display(),
+            krate.name,
+            krate.version
+        )));
+    }
+    // The package checksum was verified at fetch; an empty file list tells
+    // cargo not to re-hash individual files.
+    let checksum_json = serde_json::json!({ "files": {}, "package": krate.checksum });
+    std::fs::write(
+        crate_dir.join(".cargo-checksum.json"),
+        checksum_json.to_string(),
+    )?;
+    Ok(())
+}
+
+/// Point cargo at `<dest>/vendor` instead of crates.io for builds run
+/// anywhere under `dest`.
+pub fn write_cargo_config(dest: &Path) -> Result<()> {
+    let cargo_dir = dest.join(".cargo");
+    std::fs::create_dir_all(&cargo_dir)?;
+    std::fs::write(
+        cargo_dir.join("config.toml"),
+        format!(
+            "[source.crates-io]\n\
+             replace-with = \"vendored-sources\"\n\
+             \n\
+             [source.vendored-sources]\n\
+             directory = \"{VENDOR_DIR}\"\n"
+        ),
+    )?;
+    Ok(())
+}
+
+// ------------------------------------------------------------------
+// Go
+// ------------------------------------------------------------------
+
+/// A module version pinned by `go.sum`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoModule {
+    pub path: String,
+    pub version: String,
+    /// `h1:` hash of the module zip; `None` when only its `go.mod` is
+    /// needed for version selection.
+    pub zip_hash: Option<String>,
+    /// `h1:` hash of the module's `go.mod`.
+    pub mod_hash: Option<String>,
+}
+
+impl GoModule {
+    /// `vendor/go/<path>@<version>`, with `/` in the module path spelled
+    /// `+` (which module paths cannot contain).
+    pub fn cache_dir(&self) -> PathBuf {
+        Path::new(VENDOR_DIR).join("go").join(format!(
+            "{}@{}",
+            self.path.replace('/', "+"),
+            self.version
+        ))
+    }
+
+    pub fn downloads(&self, proxy: &str) -> Vec<Download> {
+        let base = format!(
+            "{}/{}/@v/{}",
+            proxy.trim_end_matches('/'),
+            escape_go_path(&self.path),
+            escape_go_path(&self.version)
+        );
+        let dir = self.cache_dir();
+        let mut downloads = Vec::new();
+        if let Some(ref hash) = self.mod_hash {
+            downloads.push(Download {
+                url: format!("{base}.mod"),
+                rel_path: dir.join(format!("{}.mod", self.version)),
+                digest: Digest::GoMod(hash.clone()),
+            });
+        }
+        if let Some(ref hash) = self.zip_hash {
+            downloads.push(Download {
+                url: format!("{base}.zip"),
+                rel_path: dir.join(format!("{}.zip", self.version)),
+                digest: Digest::GoZip(hash.clone()),
+            });
+        }
+        downloads
+    }
+}
+
+/// Every module version `go.sum` lists, merging its zip and `go.mod` lines.
+pub fn parse_go_sum(content: &str) -> Result<Vec<GoModule>> {
+    let mut modules: Vec<GoModule> = Vec::new();
+    for (lineno, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [path, version, hash] = fields[..] else {
+            return Err(WrightError::ParseError(format!(
+                "go.sum line {}: expected '<module> <version> <hash>'",
+                lineno + 1
+            )));
+        };
+        if !hash.starts_with("h1:") {
+            return Err(WrightError::ParseError(format!(
+                "go.sum line {}: unsupported hash '{hash}'",
+                lineno + 1
+            )));
+        }
+        let (version, is_mod) = match version.strip_suffix("/go.mod") {
+            Some(v) => (v, true),
+            None => (version, false),
+        };
+        let index = match modules
+            .iter()
+            .position(|m| m.path == path && m.version == version)
+        {
+            Some(i) => i,
+            None => {
+                modules.push(GoModule {
+                    path: path.to_string(),
+                    version: version.to_string(),
+                    zip_hash: None,
+                    mod_hash: None,
+                });
+                modules.len() - 1
+            }
+        };
+        let slot = if is_mod {
+            &mut modules[index].mod_hash
+        } else {
+            &mut modules[index].zip_hash
+        };
+        *slot = Some(hash.to_string());
+    }
+    Ok(modules)
+}
+
+/// The module proxy's case encoding: each upper-case letter becomes `!`
+/// followed by its lower-case form.
+fn escape_go_path(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('!');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Go's `h1:` hash: SHA-256 over the sorted `"<sha256>  <name>\n"` lines
+/// of every file, base64-encoded.
+fn go_dirhash(mut files: Vec<(String, String)>) -> String {
+    files.sort();
+    let mut summary = Sha256::new();
+    for (name, hash) in files {
+        summary.update(format!("{hash}  {name}\n").as_bytes());
+    }
+    format!(
+        "h1:{}",
+        base64::engine::general_purpose::STANDARD.encode(summary.finalize())
+    )
+}
+
+pub fn go_mod_hash(content: &[u8]) -> String {
+    go_dirhash(vec![(
+        "go.mod".to_string(),
+        format!("{:x}", Sha256::digest(content)),
+    )])
+}
+
+pub fn go_zip_hash(path: &Path) -> Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| {
+        WrightError::ValidationError(format!("{} is not a zip: {e}", path.display()))
+    })?;
+    let mut files = Vec::with_capacity(zip.len());
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| {
+            WrightError::ValidationError(format!("failed to read {}: {e}", path.display()))
+        })?;
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        files.push((
+            entry.name().to_string(),
+            format!("{:x}", Sha256::digest(&content)),
+        ));
+    }
+    Ok(go_dirhash(files))
+}
+
+/// Lay a cached module out under `goproxy_dir` the way a `file://`
+/// `GOPROXY` serves it.
+pub fn place_go_module(source_dir: &Path, module: &GoModule, goproxy_dir: &Path) -> Result<()> {
+    let cached = source_dir.join(module.cache_dir());
+    let version_dir = goproxy_dir.join(escape_go_path(&module.path)).join("@v");
+    std::fs::create_dir_all(&version_dir)?;
+    let escaped_version = escape_go_path(&module.version);
+    for ext in ["mod", "zip"] {
+        let from = cached.join(format!("{}.{ext}", module.version));
+        if from.is_file() {
+            std::fs::copy(&from, version_dir.join(format!("{escaped_version}.{ext}")))?;
+        }
+    }
+    std::fs::write(
+        version_dir.join(format!("{escaped_version}.info")),
+        serde_json::json!({ "Version": module.version }).to_string(),
+    )?;
+    let list = version_dir.join("list");
+    let mut versions = std::fs::read_to_string(&list).unwrap_or_default();
+    if !versions.lines().any(|v| v == module.version) {
+        versions.push_str(&module.version);
+        versions.push('\n');
+        std::fs::write(&list, versions)?;
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------------
+// pip
+// ------------------------------------------------------------------
+
+/// A `name==version` requirement with its accepted `--hash=sha256:` values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    /// Normalized project name (PEP 503).
+    pub name: String,
+    pub version: String,
+    pub hashes: Vec<String>,
+}
+
+impl Requirement {
+    /// `vendor/pypi/<name>-<version>`
+    pub fn cache_dir(&self) -> PathBuf {
+        Path::new(VENDOR_DIR)
+            .join("pypi")
+            .join(format!("{}-{}", self.name, self.version))
+    }
+
+    /// The index's JSON description of this release.
+    pub fn metadata_url(&self, index: &str) -> String {
+        format!(
+            "{}/{}/{}/json",
+            index.trim_end_matches('/'),
+            self.name,
+            self.version
+        )
+    }
+
+    /// The release files to download, named in `metadata` (the index's
+    /// JSON release description): those whose sha256 is pinned, limited
+    /// to sdists and pure-Python wheels.
+    pub fn downloads(&self, metadata: &str) -> Result<Vec<Download>> {
+        let release: PypiRelease = serde_json::from_str(metadata).map_err(|e| {
+            WrightError::ParseError(format!(
+                "invalid index metadata for {} {}: {e}",
+                self.name, self.version
+            ))
+        })?;
+        let downloads: Vec<Download> = release
+            .urls
+            .into_iter()
+            .filter(|f| self.hashes.contains(&f.digests.sha256))
+            .filter(|f| f.packagetype == "sdist" || f.filename.ends_with("-none-any.whl"))
+            .map(|f| Download {
+                rel_path: self.cache_dir().join(&f.filename),
+                url: f.url,
+                digest: Digest::Sha256(f.digests.sha256),
+            })
+            .collect();
+        if downloads.is_empty() {
+            return Err(WrightError::ValidationError(format!(
+                "{}=={}: no sdist or pure-Python wheel on the index matches the pinned hashes",
+                self.name, self.version
+            )));
+        }
+        Ok(downloads)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PypiRelease {
+    urls: Vec<PypiFile>,
+}
+
+#[derive(serde::Deserialize)]
+struct PypiFile {
+    filename: String,
+    url: String,
+    packagetype: String,
+    digests: PypiDigests,
+}
+
+#[derive(serde::Deserialize)]
+struct PypiDigests {
+    sha256: String,
+}
+
+/// The requirements of a hash-pinned `requirements.txt`, as written by
+/// `pip-compile --generate-hashes` or `pip freeze` plus `pip hash`.
+pub fn parse_requirements(content: &str) -> Result<Vec<Requirement>> {
+    let mut requirements = Vec::new();
+    let mut logical = String::new();
+    for line in content.lines() {
+        let line = match line.find(" #") {
+            Some(i) => &line[..i],
+            None if line.trim_start().starts_with('#') => "",
+            None => line,
+        };
+        if let Some(continued) = line.trim_end().strip_suffix('\\') {
+            logical.push_str(continued);
+            logical.push(' ');
+            continue;
+        }
+        logical.push_str(line);
+        let entry = std::mem::take(&mut logical);
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        requirements.push(parse_requirement(entry)?);
+    }
+    Ok(requirements)
+}
+
+fn parse_requirement(entry: &str) -> Result<Requirement> {
+    let mut tokens = entry.split_whitespace();
+    let spec = tokens.next().unwrap_or_default();
+    if spec.starts_with('-') {
+        return Err(WrightError::ValidationError(format!(
+            "requirements: option '{spec}' is not supported; list pinned requirements only"
+        )));
+    }
+    let spec = spec.split(';').next().unwrap_or_default();
+    let Some((name, version)) = spec.split_once("==") else {
+        return Err(WrightError::ValidationError(format!(
+            "requirements: '{spec}' must pin an exact version with '=='"
+        )));
+    };
+    let name = name.split('[').next().unwrap_or_default();
+    let hashes: Vec<String> = tokens
+        .filter_map(|t| t.strip_prefix("--hash=sha256:"))
+        .map(str::to_string)
+        .collect();
+    if hashes.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "requirements: '{spec}' has no --hash=sha256: pin"
+        )));
+    }
+    Ok(Requirement {
+        name: normalize_project_name(name),
+        version: version.to_string(),
+        hashes,
+    })
+}
+
+/// PEP 503 normalization: lower-case, runs of `-`, `_`, `.` become `-`.
+fn normalize_project_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !out.ends_with('-') {
+                out.push('-');
+            }
+        } else {
+            out.push(c.to_ascii_lowercase());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_lock_pins_registry_crates_only() {
+        let lock = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["libc"]
+
+[[package]]
+name = "libc"
+version = "0.2.150"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "89d92a4743f9a61002fae18374ed11e7973f530cb3a3255fb354818118b2203c"
+"#;
+        let crates = parse_cargo_lock(lock).unwrap();
+        assert_eq!(
+            crates,
+            vec![Crate {
+                name: "libc".into(),
+                version: "0.2.150".into(),
+                checksum: "89d92a4743f9a61002fae18374ed11e7973f530cb3a3255fb354818118b2203c".into(),
+            }]
+        );
+        let download = crates[0].download("https://static.crates.io/crates/");
+        assert_eq!(
+            download.url,
+            "https://static.crates.io/crates/libc/libc-0.2.150.crate"
+        );
+        assert_eq!(
+            download.rel_path,
+            PathBuf::from("vendor/crates/libc-0.2.150.crate")
+        );
+
+        let git = r#"
+[[package]]
+name = "dep"
+version = "1.0.0"
+source = "git+https://example.invalid/dep.git#abc"
+"#;
+        let err = parse_cargo_lock(git).unwrap_err().to_string();
+        assert!(err.contains("only crates.io"), "{err}");
+    }
+
+    #[test]
+    fn go_sum_merges_zip_and_mod_lines() {
+        let sum = "\
+github.com/BurntSushi/toml v1.3.2 h1:zip=
+github.com/BurntSushi/toml v1.3.2/go.mod h1:mod=
+golang.org/x/sys v0.1.0/go.mod h1:sysmod=
+";
+        let modules = parse_go_sum(sum).unwrap();
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].zip_hash.as_deref(), Some("h1:zip="));
+        assert_eq!(modules[0].mod_hash.as_deref(), Some("h1:mod="));
+        assert_eq!(modules[1].zip_hash, None);
+
+        let urls: Vec<String> = modules[0]
+            .downloads("https://proxy.golang.org")
+            .into_iter()
+            .map(|d| d.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://proxy.golang.org/github.com/!burnt!sushi/toml/@v/v1.3.2.mod",
+                "https://proxy.golang.org/github.com/!burnt!sushi/toml/@v/v1.3.2.zip",
+            ]
+        );
+        assert_eq!(
+            modules[1].cache_dir(),
+            PathBuf::from("vendor/go/golang.org+x+sys@v0.1.0")
+        );
+    }
+
+    #[test]
+    fn go_mod_hash_matches_go_toolchain() {
+        // `go.sum` entry of golang.org/x/text v0.3.0/go.mod.
+        assert_eq!(
+            go_mod_hash(b"module golang.org/x/text\n"),
+            "h1:NqM8EUOU14njkJ3fqMW+pc6Ldnwhi/IjpwHt7yyuwOQ="
+        );
+    }
+
+    #[test]
+    fn requirements_need_pins_and_hashes() {
+        let txt = "\
+# generated
+Requests[socks]==2.31.0 ; python_version >= \"3.8\" \\
+    --hash=sha256:aaaa \\
+    --hash=sha256:bbbb
+zope.interface==6.0 --hash=sha256:cccc  # trailing comment
+";
+        let reqs = parse_requirements(txt).unwrap();
+        assert_eq!(reqs[0].name, "requests");
+        assert_eq!(reqs[0].version, "2.31.0");
+        assert_eq!(reqs[0].hashes, vec!["aaaa", "bbbb"]);
+        assert_eq!(reqs[1].name, "zope-interface");
+        assert_eq!(
+            reqs[1].cache_dir(),
+            PathBuf::from("vendor/pypi/zope-interface-6.0")
+        );
+
+        assert!(parse_requirements("flask>=2\n").is_err());
+        assert!(parse_requirements("flask==2.0\n").is_err());
+        assert!(parse_requirements("-r other.txt\n").is_err());
+    }
+
+    #[test]
+    fn pypi_downloads_select_pinned_portable_files() {
+        let req = Requirement {
+            name: "demo".into(),
+            version: "1.0".into(),
+            hashes: vec!["s1".into(), "w1".into(), "w2".into()],
+        };
+        let metadata = r#"{"urls": [
+            {"filename": "demo-1.0.tar.gz", "url": "https://files/demo-1.0.tar.gz",
+             "packagetype": "sdist", "digests": {"sha256": "s1"}},
+            {"filename": "demo-1.0-py3-none-any.whl", "url": "https://files/any.whl",
+             "packagetype": "bdist_wheel", "digests": {"sha256": "w1"}},
+            {"filename": "demo-1.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+             "url": "https://files/native.whl",
+             "packagetype": "bdist_wheel", "digests": {"sha256": "w2"}},
+            {"filename": "demo-1.0.zip", "url": "https://files/demo-1.0.zip",
+             "packagetype": "sdist", "digests": {"sha256": "unpinned"}}
+        ]}"#;
+        let names: Vec<PathBuf> = req
+            .downloads(metadata)
+            .unwrap()
+            .into_iter()
+            .map(|d| d.rel_path)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                PathBuf::from("vendor/pypi/demo-1.0/demo-1.0.tar.gz"),
+                PathBuf::from("vendor/pypi/demo-1.0/demo-1.0-py3-none-any.whl"),
+            ]
+        );
+    }
+}
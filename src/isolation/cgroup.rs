@@ -0,0 +1,274 @@
+//! cgroup v2 enforcement of build resource limits.
+//!
+//! Unlike `RLIMIT_AS`, which caps the virtual address space of each process
+//! and breaks runtimes that reserve large mappings (rustc, the JVM, Go), a
+//! cgroup accounts the resident memory, processes, CPU bandwidth, and block
+//! I/O of the whole build tree together.  Each isolation task with cgroup
+//! limits gets its own cgroup under the parent (`build.cgroup_parent`,
+//! default `wright` under the cgroup2 mount); the task's first process joins
+//! it between fork and exec, so every descendant is accounted from the start.
+
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use tracing::{debug, warn};
+
+use super::ResourceLimits;
+use crate::error::{Result, WrightError};
+
+/// Name of the parent cgroup created under the cgroup2 mount.
+const DEFAULT_PARENT: &str = "wright";
+
+/// `cpu.max` period in microseconds.
+const CPU_PERIOD_US: u64 = 100_000;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Set once the user has been told the limits are not enforced.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// The cgroup of one isolation task. Dropping it kills whatever is left in
+/// the cgroup and removes it.
+pub struct TaskCgroup {
+    dir: PathBuf,
+    procs: CString,
+}
+
+impl TaskCgroup {
+    /// The cgroup for a task with cgroup limits, or `None` when the limits
+    /// set none. When cgroups cannot be used (no cgroup2 mount, a required
+    /// controller missing, no permission), the task runs without them; the
+    /// first such task warns why.
+    pub fn for_task(limits: &ResourceLimits, task_id: &str) -> Option<Self> {
+        if !limits.has_cgroup_limits() {
+            return None;
+        }
+        match Self::create(limits, task_id) {
+            Ok(cgroup) => Some(cgroup),
+            Err(e) => {
+                if WARNED.swap(true, Ordering::Relaxed) {
+                    debug!(event = "isolation.cgroup_unavailable", error = %e, "cgroup limits not enforced");
+                } else {
+                    warn!(event = "isolation.cgroup_unavailable", error = %e, "cgroup limits not enforced");
+                }
+                None
+            }
+        }
+    }
+
+    fn create(limits: &ResourceLimits, task_id: &str) -> Result<Self> {
+        let parent = match limits.cgroup_parent {
+            Some(ref parent) => parent.clone(),
+            None => cgroup2_mount()
+                .ok_or_else(|| WrightError::IsolationError("no cgroup2 filesystem mounted".into()))?
+                .join(DEFAULT_PARENT),
+        };
+        let mut controllers = Vec::new();
+        if limits.memory_max.is_some() {
+            controllers.push("memory");
+        }
+        if limits.pids_max.is_some() {
+            controllers.push("pids");
+        }
+        if limits.cpu_max.is_some() {
+            controllers.push("cpu");
+        }
+        if !limits.io_max.is_empty() {
+            controllers.push("io");
+        }
+        std::fs::create_dir_all(&parent).map_err(|e| cgroup_error(&parent, e))?;
+        // Controllers reach the task's cgroup only when enabled in each
+        // ancestor's subtree_control; the parent's own parent is as far up
+        // as wright reaches.
+        for dir in [parent.parent(), Some(parent.as_path())]
+            .into_iter()
+            .flatten()
+        {
+            enable_controllers(dir, &controllers)?;
+        }
+
+        let name: String = task_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let dir = parent.join(format!(
+            "{name}-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir(&dir).map_err(|e| cgroup_error(&dir, e))?;
+        let procs = CString::new(dir.join("cgroup.procs").as_os_str().as_bytes())
+            .map_err(|e| WrightError::IsolationError(format!("cgroup path: {e}")))?;
+        let cgroup = Self { dir, procs };
+
+        if let Some(mb) = limits.memory_max {
+            cgroup.write("memory.max", &(mb * 1024 * 1024).to_string())?;
+            // An OOM kill takes the whole build down rather than leaving a
+            // `make` waiting on a killed compiler.
+            cgroup.write("memory.oom.group", "1")?;
+        }
+        if let Some(pids) = limits.pids_max {
+            cgroup.write("pids.max", &pids.to_string())?;
+        }
+        if let Some(cpus) = limits.cpu_max {
+            cgroup.write("cpu.max", &cpu_max(cpus))?;
+        }
+        for rule in &limits.io_max {
+            cgroup.write("io.max", &io_max(rule)?)?;
+        }
+        debug!(event = "isolation.cgroup_created", path = %cgroup.dir.display(), "Created task cgroup");
+        Ok(cgroup)
+    }
+
+    /// `cgroup.procs` of this cgroup, for [`join`] in a forked child.
+    pub fn procs(&self) -> &CStr {
+        &self.procs
+    }
+
+    /// Whether the kernel OOM-killed a process of the task for exceeding
+    /// `memory.max`.
+    pub fn oom_killed(&self) -> bool {
+        std::fs::read_to_string(self.dir.join("memory.events"))
+            .is_ok_and(|events| oom_kills(&events) > 0)
+    }
+
+    fn write(&self, file: &str, value: &str) -> Result<()> {
+        let path = self.dir.join(file);
+        std::fs::write(&path, value).map_err(|e| {
+            WrightError::IsolationError(format!(
+                "failed to set {} to '{}': {}",
+                path.display(),
+                value,
+                e
+            ))
+        })
+    }
+}
+
+impl Drop for TaskCgroup {
+    fn drop(&mut self) {
+        // Processes that outlived the task (e.g. daemons a `none`-isolation
+        // script left behind) would keep the cgroup busy.
+        let _ = std::fs::write(self.dir.join("cgroup.kill"), "1");
+        for _ in 0..50 {
+            match std::fs::remove_dir(&self.dir) {
+                Ok(()) => return,
+                Err(e) if e.raw_os_error() == Some(libc::EBUSY) => {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                }
+                Err(_) => break,
+            }
+        }
+        debug!(event = "isolation.cgroup_cleanup_failed", path = %self.dir.display(), "Failed to remove task cgroup");
+    }
+}
+
+/// Move the calling process into the cgroup whose `cgroup.procs` is
+/// `procs`. Async-signal-safe, so it may run between fork and exec.
+pub fn join(procs: &CStr) -> std::io::Result<()> {
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        let err = std::io::Error::last_os_error();
+        libc::close(fd);
+        if written != 1 {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+fn cgroup2_mount() -> Option<PathBuf> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let path = fields.nth(1)?;
+        (fields.next()? == "cgroup2").then(|| PathBuf::from(path))
+    })
+}
+
+fn enable_controllers(dir: &Path, controllers: &[&str]) -> Result<()> {
+    let available = std::fs::read_to_string(dir.join("cgroup.controllers"))
+        .map_err(|e| cgroup_error(dir, e))?;
+    let enabled = std::fs::read_to_string(dir.join("cgroup.subtree_control")).unwrap_or_default();
+    for controller in controllers {
+        if enabled.split_whitespace().any(|c| c == *controller) {
+            continue;
+        }
+        if !available.split_whitespace().any(|c| c == *controller) {
+            return Err(WrightError::IsolationError(format!(
+                "cgroup controller '{}' is not available in {}",
+                controller,
+                dir.display()
+            )));
+        }
+        std::fs::write(dir.join("cgroup.subtree_control"), format!("+{controller}"))
+            .map_err(|e| cgroup_error(dir, e))?;
+    }
+    Ok(())
+}
+
+fn cgroup_error(path: &Path, e: std::io::Error) -> WrightError {
+    WrightError::IsolationError(format!("cgroup {}: {}", path.display(), e))
+}
+
+/// `cpu.max` for a bandwidth of `cpus` CPUs.
+fn cpu_max(cpus: f64) -> String {
+    let quota = ((cpus * CPU_PERIOD_US as f64).round() as u64).max(1000);
+    format!("{quota} {CPU_PERIOD_US}")
+}
+
+/// An `io.max` line from a rule whose device is a `MAJ:MIN` number or a
+/// block device path, e.g. `/dev/nvme0n1 wbps=104857600`.
+fn io_max(rule: &str) -> Result<String> {
+    let (device, limits) = rule.trim().split_once(char::is_whitespace).ok_or_else(|| {
+        WrightError::IsolationError(format!(
+            "invalid io_max rule '{rule}' (expected '<device> <key>=<value>...')"
+        ))
+    })?;
+    if !device.starts_with('/') {
+        return Ok(format!("{device} {}", limits.trim()));
+    }
+    let meta = std::fs::metadata(device)
+        .map_err(|e| WrightError::IsolationError(format!("io_max device {device}: {e}")))?;
+    let rdev = meta.rdev();
+    Ok(format!(
+        "{}:{} {}",
+        libc::major(rdev),
+        libc::minor(rdev),
+        limits.trim()
+    ))
+}
+
+fn oom_kills(events: &str) -> u64 {
+    events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_values() {
+        assert_eq!(cpu_max(2.5), "250000 100000");
+        assert_eq!(cpu_max(0.0), "1000 100000");
+        assert_eq!(io_max("8:0 rbps=1048576").unwrap(), "8:0 rbps=1048576");
+        assert_eq!(io_max("/dev/null wiops=10").unwrap(), "1:3 wiops=10");
+        assert!(io_max("8:0").is_err());
+        assert_eq!(
+            oom_kills("low 0\nhigh 0\nmax 3\noom 1\noom_kill 1\noom_group_kill 1\n"),
+            1
+        );
+        assert_eq!(oom_kills(""), 0);
+    }
+}
@@ -0,0 +1,390 @@
+//! Capability and syscall hardening for strict isolation.
+//!
+//! Namespaces keep a strict stage away from the host's mounts, processes,
+//! and network, but the stage still runs as root inside them with every
+//! capability, so one kernel bug reachable through `mount`, `bpf`, or a
+//! keyring call is enough to escape.  Right before exec, a strict stage
+//! therefore drops every capability a build does not need from its bounding,
+//! permitted, effective, inheritable, and ambient sets, sets
+//! `no_new_privs`, and installs a seccomp filter that fails the syscalls in
+//! [`BLOCKED_SYSCALLS`] with `EPERM`.  A plan that needs one of them back
+//! lists it in `[options] allow_syscalls` or `[options] capabilities`.
+
+use crate::error::{Result, WrightError};
+
+/// Capabilities kept by default: what installing files as root needs
+/// (ownership, modes, file capabilities, device nodes), plus signalling
+/// and binding low ports in build tests.
+const DEFAULT_CAPABILITIES: &[&str] = &[
+    "CHOWN",
+    "DAC_OVERRIDE",
+    "FOWNER",
+    "FSETID",
+    "KILL",
+    "SETGID",
+    "SETUID",
+    "SETFCAP",
+    "MKNOD",
+    "SYS_CHROOT",
+    "NET_BIND_SERVICE",
+];
+
+/// Capability names by number, as in `<linux/capability.h>` without the
+/// `CAP_` prefix.
+const CAPABILITIES: &[&str] = &[
+    "CHOWN",
+    "DAC_OVERRIDE",
+    "DAC_READ_SEARCH",
+    "FOWNER",
+    "FSETID",
+    "KILL",
+    "SETGID",
+    "SETUID",
+    "SETPCAP",
+    "LINUX_IMMUTABLE",
+    "NET_BIND_SERVICE",
+    "NET_BROADCAST",
+    "NET_ADMIN",
+    "NET_RAW",
+    "IPC_LOCK",
+    "IPC_OWNER",
+    "SYS_MODULE",
+    "SYS_RAWIO",
+    "SYS_CHROOT",
+    "SYS_PTRACE",
+    "SYS_PACCT",
+    "SYS_ADMIN",
+    "SYS_BOOT",
+    "SYS_NICE",
+    "SYS_RESOURCE",
+    "SYS_TIME",
+    "SYS_TTY_CONFIG",
+    "MKNOD",
+    "LEASE",
+    "AUDIT_WRITE",
+    "AUDIT_CONTROL",
+    "SETFCAP",
+    "MAC_OVERRIDE",
+    "MAC_ADMIN",
+    "SYSLOG",
+    "WAKE_ALARM",
+    "BLOCK_SUSPEND",
+    "AUDIT_READ",
+    "PERFMON",
+    "BPF",
+    "CHECKPOINT_RESTORE",
+];
+
+/// Syscalls a strict stage may not make unless its plan allows them.
+/// `unshare` also covers creating namespaces through `clone` and `clone3`.
+pub const BLOCKED_SYSCALLS: &[(&str, libc::c_long)] = &[
+    // Mounting and the new mount API.
+    ("mount", libc::SYS_mount),
+    ("umount2", libc::SYS_umount2),
+    ("pivot_root", libc::SYS_pivot_root),
+    ("move_mount", libc::SYS_move_mount),
+    ("open_tree", libc::SYS_open_tree),
+    ("fsopen", libc::SYS_fsopen),
+    ("fsconfig", libc::SYS_fsconfig),
+    ("fsmount", libc::SYS_fsmount),
+    ("fspick", libc::SYS_fspick),
+    ("mount_setattr", libc::SYS_mount_setattr),
+    // New namespaces.
+    ("unshare", libc::SYS_unshare),
+    ("setns", libc::SYS_setns),
+    // Tracing and reading other processes.
+    ("ptrace", libc::SYS_ptrace),
+    ("process_vm_readv", libc::SYS_process_vm_readv),
+    ("process_vm_writev", libc::SYS_process_vm_writev),
+    ("perf_event_open", libc::SYS_perf_event_open),
+    // The kernel itself.
+    ("kexec_load", libc::SYS_kexec_load),
+    ("kexec_file_load", libc::SYS_kexec_file_load),
+    ("init_module", libc::SYS_init_module),
+    ("finit_module", libc::SYS_finit_module),
+    ("delete_module", libc::SYS_delete_module),
+    ("reboot", libc::SYS_reboot),
+    ("bpf", libc::SYS_bpf),
+    ("userfaultfd", libc::SYS_userfaultfd),
+    ("syslog", libc::SYS_syslog),
+    #[cfg(target_arch = "x86_64")]
+    ("iopl", libc::SYS_iopl),
+    #[cfg(target_arch = "x86_64")]
+    ("ioperm", libc::SYS_ioperm),
+    // Keyrings.
+    ("add_key", libc::SYS_add_key),
+    ("request_key", libc::SYS_request_key),
+    ("keyctl", libc::SYS_keyctl),
+    // Host-wide state.
+    ("swapon", libc::SYS_swapon),
+    ("swapoff", libc::SYS_swapoff),
+    ("acct", libc::SYS_acct),
+    ("quotactl", libc::SYS_quotactl),
+    ("settimeofday", libc::SYS_settimeofday),
+    ("clock_settime", libc::SYS_clock_settime),
+    ("clock_adjtime", libc::SYS_clock_adjtime),
+    ("adjtimex", libc::SYS_adjtimex),
+    ("open_by_handle_at", libc::SYS_open_by_handle_at),
+];
+
+/// `AUDIT_ARCH_*` of the native syscall ABI; syscalls made through any
+/// other ABI (i386 on x86_64, x32) are refused, since their numbers differ.
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: Option<u32> = Some(0xC000_003E);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: Option<u32> = Some(0xC000_00B7);
+#[cfg(target_arch = "riscv64")]
+const AUDIT_ARCH: Option<u32> = Some(0xC000_00F3);
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv64"
+)))]
+const AUDIT_ARCH: Option<u32> = None;
+
+/// Syscall numbers at or above this are the x32 ABI on x86_64.
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+const CLONE_NEW_FLAGS: u32 = (libc::CLONE_NEWNS
+    | libc::CLONE_NEWCGROUP
+    | libc::CLONE_NEWUTS
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUSER
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWNET) as u32;
+
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+/// What a plan lets its strict stages keep.
+#[derive(Debug, Clone, Default)]
+pub struct Hardening {
+    /// Names from [`BLOCKED_SYSCALLS`] to allow.
+    pub allow_syscalls: Vec<String>,
+    /// Capabilities to keep beyond the defaults, with or without `CAP_`.
+    pub capabilities: Vec<String>,
+}
+
+impl Hardening {
+    /// Check that every allowed syscall and kept capability is known.
+    pub fn validate(&self) -> Result<()> {
+        self.prepare().map(|_| ())
+    }
+
+    /// Resolve the names into the filter and capability mask [`Prepared::apply`]
+    /// installs, so nothing is allocated between fork and exec.
+    pub fn prepare(&self) -> Result<Prepared> {
+        for name in &self.allow_syscalls {
+            if !BLOCKED_SYSCALLS.iter().any(|(blocked, _)| blocked == name) {
+                return Err(WrightError::ValidationError(format!(
+                    "options.allow_syscalls lists '{name}', which strict isolation does not block"
+                )));
+            }
+        }
+        let mut keep_caps = 0u64;
+        for name in DEFAULT_CAPABILITIES
+            .iter()
+            .copied()
+            .chain(self.capabilities.iter().map(String::as_str))
+        {
+            let bare = name.to_ascii_uppercase();
+            let bare = bare.strip_prefix("CAP_").unwrap_or(&bare);
+            let cap = CAPABILITIES
+                .iter()
+                .position(|c| *c == bare)
+                .ok_or_else(|| {
+                    WrightError::ValidationError(format!(
+                        "[options] capabilities: unknown capability '{name}'"
+                    ))
+                })?;
+            keep_caps |= 1 << cap;
+        }
+        Ok(Prepared {
+            filter: self.filter(),
+            keep_caps,
+        })
+    }
+
+    fn allows(&self, syscall: &str) -> bool {
+        self.allow_syscalls.iter().any(|s| s == syscall)
+    }
+
+    fn filter(&self) -> Vec<libc::sock_filter> {
+        let Some(arch) = AUDIT_ARCH else {
+            return Vec::new();
+        };
+        let deny = libc::SECCOMP_RET_ERRNO | libc::EPERM as u32;
+        let insn = |code: u32, k: u32, jt: u8, jf: u8| libc::sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        };
+        let ret = |k: u32| insn(libc::BPF_RET | libc::BPF_K, k, 0, 0);
+        let load = |offset: u32| insn(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset, 0, 0);
+        let jump =
+            |op: u32, k: u32, jt: u8, jf: u8| insn(libc::BPF_JMP | op | libc::BPF_K, k, jt, jf);
+
+        // struct seccomp_data { int nr; __u32 arch; __u64 ip; __u64 args[6]; }
+        let mut prog = vec![load(4), jump(libc::BPF_JEQ, arch, 1, 0), ret(deny), load(0)];
+        #[cfg(target_arch = "x86_64")]
+        prog.extend([jump(libc::BPF_JGE, X32_SYSCALL_BIT, 0, 1), ret(deny)]);
+        for (name, nr) in BLOCKED_SYSCALLS {
+            if !self.allows(name) {
+                prog.extend([jump(libc::BPF_JEQ, *nr as u32, 0, 1), ret(deny)]);
+            }
+        }
+        if !self.allows("unshare") {
+            // clone3 passes its flags in memory the filter cannot read;
+            // ENOSYS makes libc fall back to clone, whose flags it can.
+            prog.extend([
+                jump(libc::BPF_JEQ, libc::SYS_clone3 as u32, 0, 1),
+                ret(libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+                jump(libc::BPF_JEQ, libc::SYS_clone as u32, 0, 3),
+                // Low half of args[0], the flags (all supported ABIs are
+                // little-endian).
+                load(16),
+                jump(libc::BPF_JSET, CLONE_NEW_FLAGS, 0, 1),
+                ret(deny),
+            ]);
+        }
+        prog.push(ret(libc::SECCOMP_RET_ALLOW));
+        prog
+    }
+}
+
+/// A resolved [`Hardening`], ready to apply in a forked child.
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    filter: Vec<libc::sock_filter>,
+    /// Bit `n` set keeps capability `n`.
+    keep_caps: u64,
+}
+
+#[repr(C)]
+struct CapHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct CapData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+impl Prepared {
+    /// Drop capabilities, set `no_new_privs`, and install the seccomp filter
+    /// in the calling process.  Async-signal-safe: runs between fork and
+    /// exec.
+    pub fn apply(&self) -> std::io::Result<()> {
+        let err = std::io::Error::last_os_error;
+        unsafe {
+            // Ambient capabilities do not exist before Linux 4.3.
+            if libc::prctl(
+                libc::PR_CAP_AMBIENT,
+                libc::PR_CAP_AMBIENT_CLEAR_ALL,
+                0,
+                0,
+                0,
+            ) != 0
+                && err().raw_os_error() != Some(libc::EINVAL)
+            {
+                return Err(err());
+            }
+
+            // Dropping from the bounding set needs CAP_SETPCAP, which an
+            // unprivileged caller without namespaces never has; its bounding
+            // set cannot matter once no_new_privs is set.
+            for cap in 0..64 {
+                if self.keep_caps & (1 << cap) != 0 {
+                    continue;
+                }
+                if libc::prctl(libc::PR_CAPBSET_DROP, cap, 0, 0, 0) != 0 {
+                    match err().raw_os_error() {
+                        Some(libc::EINVAL) => break,
+                        Some(libc::EPERM) => {}
+                        _ => return Err(err()),
+                    }
+                }
+            }
+
+            let mut header = CapHeader {
+                version: LINUX_CAPABILITY_VERSION_3,
+                pid: 0,
+            };
+            let mut data = [CapData::default(); 2];
+            if libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr()) != 0 {
+                return Err(err());
+            }
+            for (i, set) in data.iter_mut().enumerate() {
+                let keep = (self.keep_caps >> (32 * i)) as u32;
+                set.effective &= keep;
+                set.permitted &= keep;
+                set.inheritable &= keep;
+            }
+            if libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) != 0 {
+                return Err(err());
+            }
+
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(err());
+            }
+            if !self.filter.is_empty() {
+                let prog = libc::sock_fprog {
+                    len: self.filter.len() as u16,
+                    filter: self.filter.as_ptr() as *mut libc::sock_filter,
+                };
+                if libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &prog as *const libc::sock_fprog,
+                    0,
+                    0,
+                ) != 0
+                {
+                    return Err(err());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare() {
+        let default = Hardening::default().prepare().unwrap();
+        assert_eq!(default.keep_caps & (1 << 21), 0, "SYS_ADMIN dropped");
+        assert_ne!(default.keep_caps & 1, 0, "CHOWN kept");
+
+        let relaxed = Hardening {
+            allow_syscalls: vec!["ptrace".into(), "unshare".into()],
+            capabilities: vec!["cap_sys_ptrace".into(), "NET_RAW".into()],
+        }
+        .prepare()
+        .unwrap();
+        assert_ne!(relaxed.keep_caps & (1 << 19), 0);
+        assert_ne!(relaxed.keep_caps & (1 << 13), 0);
+        if AUDIT_ARCH.is_some() {
+            // Two instructions per blocked syscall, six for clone/clone3.
+            assert_eq!(default.filter.len() - relaxed.filter.len(), 2 * 2 + 6);
+        }
+
+        let unknown = Hardening {
+            allow_syscalls: vec!["read".into()],
+            ..Default::default()
+        };
+        assert!(unknown.validate().is_err());
+        let unknown = Hardening {
+            capabilities: vec!["CAP_EVERYTHING".into()],
+            ..Default::default()
+        };
+        assert!(unknown.validate().is_err());
+    }
+}
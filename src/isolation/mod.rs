@@ -1,3 +1,5 @@
+pub mod cgroup;
+pub mod harden;
 pub mod native;
 pub mod reaper;
 
@@ -21,6 +23,25 @@ pub struct IsolationOutput {
     pub status: ExitStatus,
     pub stdout: CapturedOutput,
     pub stderr: CapturedOutput,
+    pub usage: ResourceUsage,
+    /// The kernel killed the command for exceeding its cgroup `memory.max`.
+    pub oom_killed: bool,
+}
+
+/// CPU time and peak memory of a finished command and every descendant it
+/// reaped, as reported by `wait4(2)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub user_secs: f64,
+    pub system_secs: f64,
+    /// Peak resident set size of the largest process in the tree, in KiB.
+    pub max_rss_kb: u64,
+}
+
+impl ResourceUsage {
+    pub fn cpu_secs(&self) -> f64 {
+        self.user_secs + self.system_secs
+    }
 }
 
 const TAIL_BYTES: u64 = 16384;
@@ -89,6 +110,69 @@ pub struct ResourceLimits {
     pub cpu_time_secs: Option<u64>,
     /// Wall-clock timeout in seconds (enforced by parent, not rlimit).
     pub timeout_secs: Option<u64>,
+    /// cgroup `memory.max` in megabytes: resident memory (RSS and page
+    /// cache) of the whole task, enforced by the OOM killer.
+    pub memory_max: Option<u64>,
+    /// cgroup `pids.max`: processes and threads of the whole task.
+    pub pids_max: Option<u64>,
+    /// cgroup `cpu.max` as a number of CPUs' worth of bandwidth.
+    pub cpu_max: Option<f64>,
+    /// cgroup `io.max` rules, `<device> <key>=<value>...` with the device
+    /// as `MAJ:MIN` or a block device path.
+    pub io_max: Vec<String>,
+    /// cgroup under which task cgroups are created (default: `wright`
+    /// under the cgroup2 mount).
+    pub cgroup_parent: Option<PathBuf>,
+}
+
+impl ResourceLimits {
+    /// Whether any limit needs a cgroup to enforce.
+    pub fn has_cgroup_limits(&self) -> bool {
+        self.memory_max.is_some()
+            || self.pids_max.is_some()
+            || self.cpu_max.is_some()
+            || !self.io_max.is_empty()
+    }
+}
+
+/// Address of the HTTP proxy inside the isolation when
+/// [`IsolationConfig::proxy`] is set.
+pub const PROXY_URL: &str = "http://127.0.0.1:3128";
+
+/// Where the proxy's CA certificate appears inside the isolation.
+pub const PROXY_CA_PATH: &str = "/etc/wright/proxy-ca.pem";
+
+/// An HTTP proxy serving a stage's network access: its Unix socket and the
+/// CA certificate it issues HTTPS certificates from.
+#[derive(Debug, Clone)]
+pub struct NetworkProxy {
+    pub socket: PathBuf,
+    pub ca_cert: PathBuf,
+}
+
+/// How a stage reaches the network (`[pipeline.<stage>] network`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkMode {
+    /// Whatever the isolation level gives: no network in strict, the
+    /// host's in relaxed and none.
+    Isolation,
+    /// Only through a [`NetworkProxy`] started for the stage.
+    Proxy,
+}
+
+impl std::str::FromStr for NetworkMode {
+    type Err = crate::error::WrightError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "isolation" => Ok(Self::Isolation),
+            "proxy" => Ok(Self::Proxy),
+            _ => Err(crate::error::WrightError::IsolationError(format!(
+                "unknown network mode: '{}' (valid: isolation, proxy)",
+                s
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -98,6 +182,16 @@ pub enum IsolationLevel {
     Strict,
 }
 
+impl IsolationLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Relaxed => "relaxed",
+            Self::Strict => "strict",
+        }
+    }
+}
+
 impl std::str::FromStr for IsolationLevel {
     type Err = crate::error::WrightError;
 
@@ -135,6 +229,21 @@ pub struct IsolationConfig {
     /// Build-dependency mounts: (host_path, isolation_path).
     /// These are mounted read-only into the isolation environment.
     pub dep_mounts: Vec<(PathBuf, PathBuf)>,
+    /// Attach the command to the caller's terminal instead of capturing its
+    /// output: stdio is inherited and the command is made the terminal's
+    /// foreground process group. Used for debug shells.
+    pub interactive: bool,
+    /// Capabilities and syscalls a strict stage keeps beyond the defaults.
+    pub hardening: harden::Hardening,
+    /// Give the command a network namespace of its own whose only way out
+    /// is this proxy, reachable at [`PROXY_URL`] and trusted through the
+    /// usual CA bundle variables.
+    pub proxy: Option<NetworkProxy>,
+    /// Mount `base_root` itself read-write as `/` instead of an overlay
+    /// over it, and run the command from `/` without `/build`, `/output`,
+    /// or the host's `/etc` files.  Used for deploy hooks, whose changes
+    /// to the target root must stick.
+    pub writable_root: bool,
 }
 
 impl IsolationConfig {
@@ -158,6 +267,19 @@ impl IsolationConfig {
             log_stdout: None,
             log_stderr: None,
             dep_mounts: Vec::new(),
+            interactive: false,
+            hardening: harden::Hardening::default(),
+            proxy: None,
+            writable_root: false,
+        }
+    }
+
+    /// Where the command starts when it is not run inside the new root.
+    fn host_work_dir(&self) -> &std::path::Path {
+        if self.writable_root {
+            &self.base_root
+        } else {
+            &self.src_dir
         }
     }
 }
@@ -1,5 +1,7 @@
-use std::ffi::CString;
+use std::ffi::{CString, OsString};
+use std::net::{TcpListener, TcpStream};
 use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
 use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::{ExitStatus, Stdio};
@@ -10,9 +12,11 @@ use nix::sys::wait::{WaitStatus, waitpid};
 use nix::unistd::{ForkResult, Pid, chdir, execvp, fork, pivot_root, sethostname};
 use tracing::debug;
 
+use super::cgroup::{self, TaskCgroup};
+use super::harden::Prepared;
 use super::{
-    CapturedOutput, IsolationConfig, IsolationLevel, IsolationOutput, ResourceLimits,
-    spawn_stream_reader,
+    CapturedOutput, IsolationConfig, IsolationLevel, IsolationOutput, PROXY_CA_PATH, PROXY_URL,
+    ResourceLimits, ResourceUsage, spawn_stream_reader,
 };
 use crate::error::{Result, WrightError};
 
@@ -182,6 +186,22 @@ pub fn run_in_isolation(
         return Err(WrightError::ForgeError("cancelled by user".into()));
     }
 
+    // Resolved before forking: the child may not allocate.
+    let hardening = match config.level {
+        IsolationLevel::Strict => Some(config.hardening.prepare()?),
+        _ => None,
+    };
+
+    if config.proxy.is_some() && config.level == IsolationLevel::None {
+        return Err(WrightError::IsolationError(
+            "network proxy mode needs namespace isolation, not isolation level none".into(),
+        ));
+    }
+
+    // Dropped on return, after the command has been waited for.
+    let cgroup = TaskCgroup::for_task(&config.rlimits, &config.task_id);
+    let cgroup_procs = cgroup.as_ref().map(|c| c.procs().to_owned());
+
     if config.level == IsolationLevel::None {
         if config.base_root != Path::new("/") {
             return Err(WrightError::IsolationError(format!(
@@ -195,16 +215,22 @@ pub fn run_in_isolation(
         );
         let mut cmd = std::process::Command::new(command);
         cmd.args(args);
-        cmd.current_dir(&config.src_dir);
+        cmd.current_dir(config.host_work_dir());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
         for (key, value) in &config.env {
             cmd.env(key, value);
         }
+        if config.interactive {
+            return run_attached(cmd, config, cgroup, hardening);
+        }
         let rlimits = config.rlimits.clone();
         let cpu_count = config.cpu_count;
         unsafe {
             cmd.pre_exec(move || {
+                if let Some(ref procs) = cgroup_procs {
+                    cgroup::join(procs)?;
+                }
                 // New process group so timeout can kill all descendants.
                 libc::setpgid(0, 0);
                 // Pin to the scheduler's CPU share so `nproc` returns the
@@ -235,9 +261,7 @@ pub fn run_in_isolation(
             config.verbose,
             config.log_stderr.take(),
         );
-        let status = child
-            .wait()
-            .map_err(|e| WrightError::IsolationError(format!("failed to wait for command: {e}")))?;
+        let (status, usage) = wait_for_child(Pid::from_raw(child.id() as i32))?;
         if let Some(done) = watchdog {
             done.store(true, Ordering::Release);
         }
@@ -251,6 +275,8 @@ pub fn run_in_isolation(
             status,
             stdout,
             stderr,
+            usage,
+            oom_killed: cgroup.as_ref().is_some_and(TaskCgroup::oom_killed),
         });
     }
 
@@ -281,9 +307,18 @@ pub fn run_in_isolation(
     if need_userns {
         clone_flags |= CloneFlags::CLONE_NEWUSER;
     }
+    if config.proxy.is_some() {
+        clone_flags |= CloneFlags::CLONE_NEWNET;
+    }
 
     // Probe whether the required namespaces are available.
     if !can_unshare(clone_flags) {
+        if config.proxy.is_some() {
+            return Err(WrightError::IsolationError(
+                "namespace isolation unavailable, cannot confine network access to the proxy"
+                    .into(),
+            ));
+        }
         if config.base_root != Path::new("/") {
             return Err(WrightError::IsolationError(format!(
                 "namespace isolation unavailable, cannot run against base root {}",
@@ -296,21 +331,31 @@ pub fn run_in_isolation(
         );
         let mut cmd = std::process::Command::new(command);
         cmd.args(args);
-        cmd.current_dir(&config.src_dir);
+        cmd.current_dir(config.host_work_dir());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
         for (key, value) in &config.env {
             cmd.env(key, value);
         }
+        if config.interactive {
+            return run_attached(cmd, config, cgroup, hardening);
+        }
         let rlimits = config.rlimits.clone();
         let cpu_count = config.cpu_count;
         unsafe {
             cmd.pre_exec(move || {
+                if let Some(ref procs) = cgroup_procs {
+                    cgroup::join(procs)?;
+                }
                 libc::setpgid(0, 0);
                 if let Some(n) = cpu_count {
                     apply_cpu_affinity(n);
                 }
-                apply_rlimits(&rlimits).map_err(std::io::Error::other)
+                apply_rlimits(&rlimits).map_err(std::io::Error::other)?;
+                match hardening {
+                    Some(ref hardening) => hardening.apply(),
+                    None => Ok(()),
+                }
             });
         }
         let mut child = cmd
@@ -332,9 +377,7 @@ pub fn run_in_isolation(
             config.verbose,
             config.log_stderr.take(),
         );
-        let status = child
-            .wait()
-            .map_err(|e| WrightError::IsolationError(format!("failed to wait for command: {e}")))?;
+        let (status, usage) = wait_for_child(Pid::from_raw(child.id() as i32))?;
         if let Some(done) = watchdog {
             done.store(true, Ordering::Release);
         }
@@ -348,6 +391,8 @@ pub fn run_in_isolation(
             status,
             stdout,
             stderr,
+            usage,
+            oom_killed: cgroup.as_ref().is_some_and(TaskCgroup::oom_killed),
         });
     }
 
@@ -384,6 +429,13 @@ pub fn run_in_isolation(
                 libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
             }
 
+            // --- Join the task cgroup; every descendant inherits it ---
+            if let Some(ref procs) = cgroup_procs
+                && let Err(e) = cgroup::join(procs)
+            {
+                die(format!("join cgroup: {e}"));
+            }
+
             // --- Unshare namespaces ---
             if let Err(e) = unshare(clone_flags) {
                 die(format!("unshare: {e}"));
@@ -413,6 +465,31 @@ pub fn run_in_isolation(
                 die(format!("mount MS_PRIVATE /: {e}"));
             }
 
+            // --- Proxy relay: the namespace's only way out ---
+            // Listen inside the new network namespace; the relay is forked
+            // after the grandchild.  The grandchild's pivot_root also moves
+            // the relay's root, so the relay reaches the proxy socket through
+            // its directory, opened now, rather than by path.
+            let relay = config.proxy.as_ref().map(|proxy| {
+                if let Err(e) = bring_up_loopback() {
+                    die(format!("bring up lo: {e}"));
+                }
+                let addr = PROXY_URL.trim_start_matches("http://");
+                let listener = match TcpListener::bind(addr) {
+                    Ok(listener) => listener,
+                    Err(e) => die(format!("listen on {addr}: {e}")),
+                };
+                let socket_dir = proxy.socket.parent().unwrap_or(Path::new("/"));
+                match std::fs::File::open(socket_dir) {
+                    Ok(dir) => (
+                        listener,
+                        dir,
+                        proxy.socket.file_name().unwrap_or_default().to_owned(),
+                    ),
+                    Err(e) => die(format!("open {}: {e}", socket_dir.display())),
+                }
+            });
+
             // --- Double-fork: grandchild is PID 1 in new PID namespace ---
             // All mount setup + pivot_root happens in the grandchild so
             // that /proc can be mounted while we're still PID 1 with access
@@ -451,70 +528,91 @@ pub fn run_in_isolation(
                         die(format!("mkdir newroot: {e}"));
                     }
 
-                    let upper = scratch_base.join("upper");
-                    let work = scratch_base.join("work");
+                    if config.writable_root {
+                        debug!(
+                            event = "isolation.bind_root",
+                            root = %config.base_root.display(),
+                            "Mounting writable root"
+                        );
+                        if let Err(e) = mount(
+                            Some(config.base_root.as_path()),
+                            &newroot,
+                            None::<&str>,
+                            MsFlags::MS_BIND | MsFlags::MS_REC,
+                            None::<&str>,
+                        ) {
+                            die(format!(
+                                "bind mount {} -> {}: {e}",
+                                config.base_root.display(),
+                                newroot.display()
+                            ));
+                        }
+                    } else {
+                        let upper = scratch_base.join("upper");
+                        let work = scratch_base.join("work");
 
-                    if let Err(e) = std::fs::create_dir_all(&upper) {
-                        die(format!("mkdir overlay upper {}: {e}", upper.display()));
-                    }
-                    if let Err(e) = std::fs::create_dir_all(&work) {
-                        die(format!("mkdir overlay work {}: {e}", work.display()));
-                    }
+                        if let Err(e) = std::fs::create_dir_all(&upper) {
+                            die(format!("mkdir overlay upper {}: {e}", upper.display()));
+                        }
+                        if let Err(e) = std::fs::create_dir_all(&work) {
+                            die(format!("mkdir overlay work {}: {e}", work.display()));
+                        }
 
-                    let lowerdir = if config.base_root == Path::new("/") {
-                        let system_dirs = ["/usr", "/bin", "/sbin", "/lib", "/lib64"];
-                        let mut seen = std::collections::HashSet::new();
-                        let mut parts: Vec<PathBuf> = Vec::new();
-                        for d in system_dirs {
-                            let p = Path::new(d);
-                            if !p.exists() {
-                                continue;
-                            }
-                            let resolved =
-                                std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
-                            if seen.insert(resolved.clone()) {
-                                parts.push(resolved);
+                        let lowerdir = if config.base_root == Path::new("/") {
+                            let system_dirs = ["/usr", "/bin", "/sbin", "/lib", "/lib64"];
+                            let mut seen = std::collections::HashSet::new();
+                            let mut parts: Vec<PathBuf> = Vec::new();
+                            for d in system_dirs {
+                                let p = Path::new(d);
+                                if !p.exists() {
+                                    continue;
+                                }
+                                let resolved =
+                                    std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+                                if seen.insert(resolved.clone()) {
+                                    parts.push(resolved);
+                                }
                             }
-                        }
-                        // Drop subdirectories: on merged-/usr systems /bin→/usr/bin
-                        // sits under /usr, so /usr alone suffices.
-                        let mut keep: Vec<&PathBuf> = Vec::new();
-                        for r in &parts {
-                            if !keep.iter().any(|q| r.starts_with(q)) {
-                                keep.push(r);
+                            // Drop subdirectories: on merged-/usr systems /bin→/usr/bin
+                            // sits under /usr, so /usr alone suffices.
+                            let mut keep: Vec<&PathBuf> = Vec::new();
+                            for r in &parts {
+                                if !keep.iter().any(|q| r.starts_with(q)) {
+                                    keep.push(r);
+                                }
                             }
-                        }
-                        keep.iter()
-                            .map(|p| p.display().to_string())
-                            .collect::<Vec<_>>()
-                            .join(":")
-                    } else {
-                        config.base_root.display().to_string()
-                    };
+                            keep.iter()
+                                .map(|p| p.display().to_string())
+                                .collect::<Vec<_>>()
+                                .join(":")
+                        } else {
+                            config.base_root.display().to_string()
+                        };
+
+                        let opts = format!(
+                            "lowerdir={},upperdir={},workdir={}",
+                            lowerdir,
+                            upper.display(),
+                            work.display(),
+                        );
 
-                    let opts = format!(
-                        "lowerdir={},upperdir={},workdir={}",
-                        lowerdir,
-                        upper.display(),
-                        work.display(),
-                    );
-
-                    debug!(
-                        event = "isolation.mount_overlay",
-                        lowerdir = %lowerdir,
-                        upperdir = %upper.display(),
-                        workdir = %work.display(),
-                        "Mounting overlayfs"
-                    );
+                        debug!(
+                            event = "isolation.mount_overlay",
+                            lowerdir = %lowerdir,
+                            upperdir = %upper.display(),
+                            workdir = %work.display(),
+                            "Mounting overlayfs"
+                        );
 
-                    if let Err(e) = mount(
-                        Some("overlay"),
-                        &newroot,
-                        Some("overlay"),
-                        MsFlags::empty(),
-                        Some(opts.as_str()),
-                    ) {
-                        die(format!("overlayfs mount on {}: {e}", newroot.display(),));
+                        if let Err(e) = mount(
+                            Some("overlay"),
+                            &newroot,
+                            Some("overlay"),
+                            MsFlags::empty(),
+                            Some(opts.as_str()),
+                        ) {
+                            die(format!("overlayfs mount on {}: {e}", newroot.display(),));
+                        }
                     }
 
                     // Helper to bind-mount a path into the new root.
@@ -573,11 +671,13 @@ pub fn run_in_isolation(
                     };
 
                     // Build and output directories (read-write).
-                    if let Err(e) = bind(&config.src_dir, "/build", false) {
-                        die(e);
-                    }
-                    if let Err(e) = bind(&config.output_dir, "/output", false) {
-                        die(e);
+                    if !config.writable_root {
+                        if let Err(e) = bind(&config.src_dir, "/build", false) {
+                            die(e);
+                        }
+                        if let Err(e) = bind(&config.output_dir, "/output", false) {
+                            die(e);
+                        }
                     }
 
                     // Extra binds.
@@ -603,7 +703,8 @@ pub fn run_in_isolation(
                     // absolute paths like /usr/lib/..., which would fail to
                     // resolve.  Bind-mount the host /usr to restore the
                     // expected hierarchy.
-                    if newroot.join("usr").metadata().is_err()
+                    if !config.writable_root
+                        && newroot.join("usr").metadata().is_err()
                         && let Err(e) = bind(Path::new("/usr"), "/usr", true)
                     {
                         die(e);
@@ -656,48 +757,59 @@ pub fn run_in_isolation(
                         die(format!("bind mount /proc: {e}"));
                     }
 
-                    // /run
+                    // /run and /tmp.  A writable root keeps its own, so a
+                    // hook still reaches the sockets of the target's
+                    // services (systemd, D-Bus, udev) under /run.
                     let run_dir = newroot.join("run");
                     if let Err(e) = std::fs::create_dir_all(&run_dir) {
                         die(format!("mkdir {}: {e}", run_dir.display()));
                     }
-                    if let Err(e) = mount(
-                        Some("tmpfs"),
-                        &run_dir,
-                        Some("tmpfs"),
-                        MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
-                        Some("mode=0755"),
-                    ) {
+                    if !config.writable_root
+                        && let Err(e) = mount(
+                            Some("tmpfs"),
+                            &run_dir,
+                            Some("tmpfs"),
+                            MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
+                            Some("mode=0755"),
+                        )
+                    {
                         die(format!("mount tmpfs on /run: {e}"));
                     }
 
-                    // /tmp
                     let tmp = newroot.join("tmp");
                     if let Err(e) = std::fs::create_dir_all(&tmp) {
                         die(format!("mkdir {}: {e}", tmp.display()));
                     }
-                    if let Err(e) = mount(
-                        Some("tmpfs"),
-                        &tmp,
-                        Some("tmpfs"),
-                        MsFlags::empty(),
-                        None::<&str>,
-                    ) {
+                    if !config.writable_root
+                        && let Err(e) = mount(
+                            Some("tmpfs"),
+                            &tmp,
+                            Some("tmpfs"),
+                            MsFlags::empty(),
+                            None::<&str>,
+                        )
+                    {
                         die(format!("mount tmpfs on /tmp: {e}"));
                     }
 
                     // --- Essential /etc files ---
                     // Always bind-mount these to ensure they are available and correct,
                     // especially when /etc/resolv.conf is a symlink to /run which we masked.
-                    for etc_file in [
-                        "/etc/ld.so.conf",
-                        "/etc/ld.so.cache",
-                        "/etc/resolv.conf",
-                        "/etc/hosts",
-                        "/etc/passwd",
-                        "/etc/group",
-                        "/etc/ssl",
-                    ] {
+                    // A writable root keeps its own: hooks edit its passwd and group.
+                    let etc_files: &[&str] = if config.writable_root {
+                        &[]
+                    } else {
+                        &[
+                            "/etc/ld.so.conf",
+                            "/etc/ld.so.cache",
+                            "/etc/resolv.conf",
+                            "/etc/hosts",
+                            "/etc/passwd",
+                            "/etc/group",
+                            "/etc/ssl",
+                        ]
+                    };
+                    for etc_file in etc_files {
                         let p = Path::new(etc_file);
                         if p.exists()
                             && let Err(e) = bind(p, etc_file, true)
@@ -705,6 +817,11 @@ pub fn run_in_isolation(
                             die(e);
                         }
                     }
+                    if let Some(ref proxy) = config.proxy
+                        && let Err(e) = bind(&proxy.ca_cert, PROXY_CA_PATH, true)
+                    {
+                        die(e);
+                    }
 
                     // --- pivot_root ---
 
@@ -732,15 +849,24 @@ pub fn run_in_isolation(
                         unsafe { std::env::remove_var(&key) };
                     }
                     unsafe { std::env::set_var("PATH", "/usr/bin:/bin:/usr/sbin:/sbin") };
-                    unsafe { std::env::set_var("HOME", "/build") };
+                    let work_dir = if config.writable_root { "/" } else { "/build" };
+                    unsafe { std::env::set_var("HOME", work_dir) };
                     unsafe { std::env::set_var("TERM", "xterm") };
+                    if config.proxy.is_some() {
+                        for key in PROXY_VARS {
+                            unsafe { std::env::set_var(key, PROXY_URL) };
+                        }
+                        for key in CA_BUNDLE_VARS {
+                            unsafe { std::env::set_var(key, PROXY_CA_PATH) };
+                        }
+                    }
                     for (key, value) in &config.env {
                         unsafe { std::env::set_var(key, value) };
                     }
 
                     // --- chdir + exec ---
-                    if let Err(e) = chdir("/build") {
-                        die(format!("chdir /build: {e}"));
+                    if let Err(e) = chdir(work_dir) {
+                        die(format!("chdir {work_dir}: {e}"));
                     }
 
                     let c_command = CString::new(command)
@@ -758,10 +884,19 @@ pub fn run_in_isolation(
                         }
                     }
 
-                    // Redirect stdout/stderr to pipes for capture.
-                    unsafe {
-                        libc::dup2(out_write_fd, 1);
-                        libc::dup2(eout_write_fd, 2);
+                    if config.interactive {
+                        // Keep the terminal on stdio and take its foreground
+                        // group so job control and Ctrl-C reach the shell.
+                        unsafe {
+                            libc::setpgid(0, 0);
+                        }
+                        take_terminal();
+                    } else {
+                        // Redirect stdout/stderr to pipes for capture.
+                        unsafe {
+                            libc::dup2(out_write_fd, 1);
+                            libc::dup2(eout_write_fd, 2);
+                        }
                     }
                     // Close all pipe fds (originals no longer needed after dup2).
                     std::mem::forget(out_write);
@@ -786,6 +921,15 @@ pub fn run_in_isolation(
                         apply_cpu_affinity(n);
                     }
 
+                    // Drop capabilities and install the seccomp filter last:
+                    // the steps above need what they take away.
+                    if let Some(ref hardening) = hardening
+                        && let Err(e) = hardening.apply()
+                    {
+                        eprintln!("harden: {e}");
+                        unsafe { libc::_exit(1) }
+                    }
+
                     // Defensive retry for ETXTBUSY: multiple lowerdirs may
                     // kernels or filesystem configurations may briefly report the
                     // file as busy.  A short exponential backoff covers the window.
@@ -815,6 +959,11 @@ pub fn run_in_isolation(
                     let _ = nix::unistd::close(eout_write_fd);
                     std::mem::forget(err_write);
                     let _ = nix::unistd::close(err_write_fd);
+                    // After closing the pipes, so the relay cannot hold the
+                    // command's output open.
+                    if let Some((listener, socket_dir, socket)) = relay {
+                        spawn_proxy_relay(listener, socket_dir, socket);
+                    }
 
                     match wait_for_raw_status(grandchild) {
                         Ok(raw) => unsafe { libc::_exit(raw) },
@@ -865,7 +1014,11 @@ pub fn run_in_isolation(
             let stderr_handle =
                 make_stream_capture(err_file, config.verbose, config.log_stderr.take());
 
-            let status = wait_for_child(child)?;
+            let waited = wait_for_child(child);
+            if config.interactive {
+                take_terminal();
+            }
+            let (status, usage) = waited?;
             if let Some(done) = watchdog {
                 done.store(true, Ordering::Release);
             }
@@ -888,12 +1041,79 @@ pub fn run_in_isolation(
                 status,
                 stdout,
                 stderr,
+                usage,
+                oom_killed: cgroup.as_ref().is_some_and(TaskCgroup::oom_killed),
             })
         }
         Err(e) => Err(WrightError::IsolationError(format!("fork: {e}"))),
     }
 }
 
+/// Run `cmd` in the foreground of the caller's terminal with inherited
+/// stdio; nothing is captured.
+fn run_attached(
+    mut cmd: std::process::Command,
+    config: &IsolationConfig,
+    cgroup: Option<TaskCgroup>,
+    hardening: Option<Prepared>,
+) -> Result<IsolationOutput> {
+    cmd.stdin(Stdio::inherit());
+    cmd.stdout(Stdio::inherit());
+    cmd.stderr(Stdio::inherit());
+    let rlimits = config.rlimits.clone();
+    let cpu_count = config.cpu_count;
+    let cgroup_procs = cgroup.as_ref().map(|c| c.procs().to_owned());
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(ref procs) = cgroup_procs {
+                cgroup::join(procs)?;
+            }
+            libc::setpgid(0, 0);
+            take_terminal();
+            if let Some(n) = cpu_count {
+                apply_cpu_affinity(n);
+            }
+            apply_rlimits(&rlimits).map_err(std::io::Error::other)?;
+            match hardening {
+                Some(ref hardening) => hardening.apply(),
+                None => Ok(()),
+            }
+        });
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| WrightError::IsolationError(format!("failed to execute command: {e}")))?;
+    let _reap_guard = super::reaper::register(child.id(), true);
+    let status = child.wait();
+    take_terminal();
+    let status = status
+        .map_err(|e| WrightError::IsolationError(format!("failed to wait for command: {e}")))?;
+    let empty = || CapturedOutput {
+        file: tempfile::tempfile().unwrap(),
+        tail: String::new(),
+    };
+    Ok(IsolationOutput {
+        status,
+        stdout: empty(),
+        stderr: empty(),
+        usage: ResourceUsage::default(),
+        oom_killed: cgroup.as_ref().is_some_and(TaskCgroup::oom_killed),
+    })
+}
+
+/// Make the calling process group the foreground group of the terminal on
+/// stdin, if stdin is one. SIGTTOU is ignored around the call so that a
+/// background group may take the terminal (and wright may take it back).
+fn take_terminal() {
+    unsafe {
+        if libc::isatty(libc::STDIN_FILENO) == 1 {
+            let prev = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+            libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+            libc::signal(libc::SIGTTOU, prev);
+        }
+    }
+}
+
 /// Wait for a child and return the raw exit code (0-255).
 fn wait_for_raw_status(pid: Pid) -> std::result::Result<i32, ()> {
     loop {
@@ -908,30 +1128,127 @@ fn wait_for_raw_status(pid: Pid) -> std::result::Result<i32, ()> {
     }
 }
 
-/// Wait for a child process and convert the result to `ExitStatus`.
-fn wait_for_child(pid: Pid) -> Result<ExitStatus> {
+/// Wait for a child process and return its `ExitStatus` with the resource
+/// usage of the child and the descendants it reaped.
+fn wait_for_child(pid: Pid) -> Result<(ExitStatus, ResourceUsage)> {
+    use std::os::unix::process::ExitStatusExt;
+    let mut raw: libc::c_int = 0;
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
     loop {
-        match waitpid(pid, None) {
-            Ok(WaitStatus::Exited(_pid, code)) => {
-                use std::os::unix::process::ExitStatusExt;
-                return Ok(ExitStatus::from_raw(code << 8));
-            }
-            Ok(WaitStatus::Signaled(_pid, sig, _core)) => {
-                use std::os::unix::process::ExitStatusExt;
-                return Ok(ExitStatus::from_raw(sig as i32));
-            }
-            Ok(WaitStatus::Stopped(..)) | Ok(WaitStatus::Continued(..)) => {
-                continue;
-            }
-            Ok(_) => continue,
-            Err(nix::errno::Errno::EINTR) => continue,
-            Err(e) => {
-                return Err(WrightError::IsolationError(format!("waitpid: {e}")));
+        if unsafe { libc::wait4(pid.as_raw(), &mut raw, 0, &mut rusage) } >= 0 {
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(WrightError::IsolationError(format!("wait4: {err}")));
+        }
+    }
+    let secs = |tv: libc::timeval| tv.tv_sec as f64 + tv.tv_usec as f64 / 1e6;
+    let usage = ResourceUsage {
+        user_secs: secs(rusage.ru_utime),
+        system_secs: secs(rusage.ru_stime),
+        max_rss_kb: rusage.ru_maxrss.max(0) as u64,
+    };
+    Ok((ExitStatus::from_raw(raw), usage))
+}
+
+/// Proxy variables pointed at [`PROXY_URL`] in proxied stages.
+const PROXY_VARS: &[&str] = &[
+    "http_proxy",
+    "https_proxy",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "npm_config_proxy",
+    "npm_config_https_proxy",
+];
+
+/// CA bundle variables pointed at [`PROXY_CA_PATH`] in proxied stages, so
+/// that OpenSSL, curl, git, cargo, Go, Node, npm, and Python requests trust
+/// the proxy.
+const CA_BUNDLE_VARS: &[&str] = &[
+    "SSL_CERT_FILE",
+    "CURL_CA_BUNDLE",
+    "GIT_SSL_CAINFO",
+    "CARGO_HTTP_CAINFO",
+    "NODE_EXTRA_CA_CERTS",
+    "npm_config_cafile",
+    "REQUESTS_CA_BUNDLE",
+    "PIP_CERT",
+];
+
+/// Bring up `lo` in the caller's network namespace, which starts with it
+/// down.
+fn bring_up_loopback() -> std::io::Result<()> {
+    unsafe {
+        let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut req: libc::ifreq = std::mem::zeroed();
+        for (dst, src) in req.ifr_name.iter_mut().zip(b"lo\0") {
+            *dst = *src as libc::c_char;
+        }
+        let mut result = Ok(());
+        if libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut req) < 0 {
+            result = Err(std::io::Error::last_os_error());
+        } else {
+            req.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+            if libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &req) < 0 {
+                result = Err(std::io::Error::last_os_error());
             }
         }
+        libc::close(fd);
+        result
     }
 }
 
+/// Fork a process that forwards every connection accepted on `listener`,
+/// inside the stage's network namespace, to the proxy's Unix socket
+/// `socket` in `socket_dir`, outside it.  A process rather than a thread
+/// because the caller, having unshared its PID namespace, may not create
+/// threads.  The relay lands in the new namespace and dies with its PID 1,
+/// the grandchild.  It is forked twice so that the grandchild, not the
+/// caller, reaps it: PID 1 cannot finish exiting while a process of its
+/// namespace awaits reaping, and the caller waits only for the grandchild.
+fn spawn_proxy_relay(listener: TcpListener, socket_dir: std::fs::File, socket: OsString) {
+    match unsafe { fork() } {
+        Ok(ForkResult::Parent { child }) => {
+            let _ = waitpid(child, None);
+            return;
+        }
+        Ok(ForkResult::Child) => {}
+        Err(_) => return,
+    }
+    if let Ok(ForkResult::Child) = unsafe { fork() }
+        && nix::unistd::fchdir(socket_dir.as_raw_fd()).is_ok()
+    {
+        for client in listener.incoming().flatten() {
+            let socket = socket.clone();
+            std::thread::spawn(move || {
+                if let Ok(upstream) = UnixStream::connect(&socket) {
+                    relay(client, upstream);
+                }
+            });
+        }
+    }
+    unsafe { libc::_exit(0) }
+}
+
+fn relay(client: TcpStream, upstream: UnixStream) {
+    let (Ok(mut client_read), Ok(mut upstream_write)) = (client.try_clone(), upstream.try_clone())
+    else {
+        return;
+    };
+    let outbound = std::thread::spawn(move || {
+        let _ = std::io::copy(&mut client_read, &mut upstream_write);
+        let _ = upstream_write.shutdown(std::net::Shutdown::Write);
+    });
+    let (mut upstream_read, mut client_write) = (upstream, client);
+    let _ = std::io::copy(&mut upstream_read, &mut client_write);
+    let _ = client_write.shutdown(std::net::Shutdown::Write);
+    let _ = outbound.join();
+}
+
 /// Quick probe: can we create the required namespaces?
 ///
 /// Fork a throwaway child that attempts `unshare(flags)`.
@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::database::{InstalledAlternative, InstalledDb};
+use crate::error::{Result, WrightError};
+use crate::transaction::alternatives::{select_alternative, sync_alternatives};
+use crate::transaction::rollback::RollbackState;
+use crate::util::output::{self, OutputFormat};
+
+#[derive(Serialize)]
+struct AlternativeGroup {
+    name: String,
+    link: String,
+    selected: Option<String>,
+    pinned: Option<String>,
+    candidates: Vec<InstalledAlternative>,
+}
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    alternatives: &'a [AlternativeGroup],
+}
+
+/// Implementation of `wright alternative list`: alternative `name`, or every
+/// alternative with an installed candidate.
+pub async fn execute_list_alternatives(
+    db: &InstalledDb,
+    name: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut groups: Vec<AlternativeGroup> = Vec::new();
+    for candidate in db.list_alternatives(name).await? {
+        match groups.last_mut() {
+            Some(group) if group.name == candidate.name => group.candidates.push(candidate),
+            _ => groups.push(AlternativeGroup {
+                name: candidate.name.clone(),
+                link: String::new(),
+                selected: None,
+                pinned: None,
+                candidates: vec![candidate],
+            }),
+        }
+    }
+    for group in &mut groups {
+        group.pinned = db.get_alternative_pin(&group.name).await?;
+        if let Some(selected) = select_alternative(&group.candidates, group.pinned.as_deref()) {
+            group.link = selected.link.clone();
+            group.selected = Some(selected.part_name.clone());
+        }
+    }
+
+    let state = |group: &AlternativeGroup, candidate: &InstalledAlternative| {
+        if group.selected.as_deref() != Some(candidate.part_name.as_str()) {
+            "candidate"
+        } else if group.pinned == group.selected {
+            "pinned"
+        } else {
+            "selected"
+        }
+    };
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "alternative list",
+                &ListBody {
+                    alternatives: &groups,
+                },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &["name", "part", "link", "target", "priority", "state"],
+                groups.iter().flat_map(|group| {
+                    group.candidates.iter().map(move |c| {
+                        [
+                            c.name.clone(),
+                            c.part_name.clone(),
+                            c.link.clone(),
+                            c.target.clone(),
+                            c.priority.to_string(),
+                            state(group, c).to_string(),
+                        ]
+                    })
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    if groups.is_empty() {
+        match name {
+            Some(name) => println!("no installed part provides alternative '{}'", name),
+            None => println!("no alternatives"),
+        }
+        return Ok(());
+    }
+    for group in &groups {
+        let pinned = if group.pinned.is_some() && group.pinned == group.selected {
+            " (pinned)"
+        } else {
+            ""
+        };
+        println!("{} {}{}", group.name, group.link, pinned);
+        for c in &group.candidates {
+            let mark = if state(group, c) == "candidate" {
+                ' '
+            } else {
+                '*'
+            };
+            println!(
+                "  {} {:<20} {:>5}  {}",
+                mark, c.part_name, c.priority, c.target
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Implementation of `wright alternative pin` (`part` given) and `unpin`:
+/// record the choice and re-point the link of alternative `name`.
+pub async fn execute_pin_alternative(
+    db: &InstalledDb,
+    root_dir: &Path,
+    name: &str,
+    part: Option<&str>,
+) -> Result<()> {
+    let candidates = db.list_alternatives(Some(name)).await?;
+    let previous = db.get_alternative_pin(name).await?;
+    match part {
+        Some(part) => {
+            if candidates.is_empty() {
+                return Err(WrightError::ValidationError(format!(
+                    "no installed part provides alternative '{}'",
+                    name
+                )));
+            }
+            if !candidates.iter().any(|c| c.part_name == part) {
+                let names: Vec<&str> = candidates.iter().map(|c| c.part_name.as_str()).collect();
+                return Err(WrightError::ValidationError(format!(
+                    "'{}' is not a candidate for alternative '{}' (candidates: {})",
+                    part,
+                    name,
+                    names.join(", ")
+                )));
+            }
+            db.pin_alternative(name, part).await?;
+        }
+        None => {
+            if !db.unpin_alternative(name).await? {
+                crate::cli_warn!("alternative '{}' is not pinned", name);
+                return Ok(());
+            }
+        }
+    }
+
+    let mut rollback = RollbackState::new();
+    let names = BTreeSet::from([name.to_string()]);
+    if let Err(e) = sync_alternatives(db, root_dir, &names, &[], &mut rollback).await {
+        rollback.rollback();
+        // The links are back as they were; so must the pin be.
+        match &previous {
+            Some(prev) => db.pin_alternative(name, prev).await?,
+            None => {
+                db.unpin_alternative(name).await?;
+            }
+        }
+        return Err(e);
+    }
+    if let Some(selected) = select_alternative(&candidates, part) {
+        crate::cli_action!(
+            "Selected",
+            "{} for {} ({} -> {})",
+            selected.part_name,
+            name,
+            selected.link,
+            selected.target
+        );
+    }
+    Ok(())
+}
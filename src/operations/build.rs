@@ -10,13 +10,15 @@ use tokio::sync::Semaphore;
 
 use crate::cli::build::BuildArgs;
 use crate::config::GlobalConfig;
-use crate::foundry::{BuildOptions, Foundry};
+use crate::database::{BuildStage, InstalledDb, NewBuildRun};
+use crate::foundry::checkpoint::Checkpoint;
+use crate::foundry::{BuildInputs, BuildOptions, DebugShell, Foundry};
 use crate::operations::drive::{DriveOptions, drive_batches};
 use crate::plan::manifest::PlanManifest;
 use crate::resolve::{BuildExecutionPlan, BuildPlanOptions, DepDomain, create_execution_plan};
 
 pub async fn execute_build(
-    args: BuildArgs,
+    mut args: BuildArgs,
     config: &GlobalConfig,
     db_path: &Path,
     verbose: u8,
@@ -29,8 +31,13 @@ pub async fn execute_build(
     )
     .map_err(|e| WrightError::LockError(format!("failed to acquire build command lock: {}", e)))?;
 
-    let mut all_targets = args.targets;
+    let mut all_targets = std::mem::take(&mut args.targets);
     use std::io::IsTerminal;
+    if (args.shell.is_some() || args.shell_on_failure) && !std::io::stdin().is_terminal() {
+        return Err(WrightError::ValidationError(
+            "--shell and --shell-on-failure need an interactive terminal on stdin".into(),
+        ));
+    }
     if !std::io::stdin().is_terminal() {
         for line in std::io::stdin().lock().lines() {
             let line = line.map_err(WrightError::IoError)?;
@@ -45,6 +52,14 @@ pub async fn execute_build(
         return Err(WrightError::ForgeError("no targets specified".into()));
     }
 
+    if args.verify_reproducible {
+        return verify_reproducible(&all_targets, config, verbose > 0).await;
+    }
+    if let Some(stage) = args.shell.take() {
+        return shell_into_stage(&all_targets, stage, &args, config, db_path, verbose > 0).await;
+    }
+    let on_failure = args.shell_on_failure.then_some(DebugShell::OnFailure);
+
     // Fast path: single target with no dep resolution needed.
     let can_fast_path = all_targets.len() == 1
         && !all_targets[0].starts_with('@')
@@ -64,7 +79,12 @@ pub async fn execute_build(
             if args.mvp {
                 extra_env.insert("WRIGHT_BUILD_PHASE".to_string(), "mvp".to_string());
             }
-            foundry
+            let mut inputs = foundry.build_inputs(&manifest, args.mvp);
+            {
+                let db = InstalledDb::open(db_path).await?;
+                inputs.add_dependencies(&db, &manifest, args.mvp).await?;
+            }
+            let result = foundry
                 .build(
                     &manifest,
                     plan_path,
@@ -82,9 +102,13 @@ pub async fn execute_build(
                         nproc_per_isolation: config.build.nproc_per_isolation,
                         configure_lock: None,
                         compile_lock: None,
+                        inputs: Some(inputs),
+                        build_root: None,
+                        debug_shell: on_failure,
                     },
                 )
                 .await?;
+            record_usage(db_path, &manifest, result.usage.as_deref()).await;
             if args.seal {
                 crate::seal::package_manifest(&manifest, config, true, args.force)
                     .await
@@ -119,6 +143,7 @@ pub async fn execute_build(
 
     let plan = Arc::new(plan);
     let foundry = Arc::new(Foundry::new(config.clone()));
+    let task_inputs = Arc::new(task_inputs(&plan, &foundry, db_path, options.mvp).await?);
     let resources = crate::resolve::summarize_build_resources(config);
     let configure_lock = Arc::new(Semaphore::new(1));
     let compile_lock = Arc::new(Semaphore::new(resources.total_cpus));
@@ -135,7 +160,12 @@ pub async fn execute_build(
             db_path,
             quiet,
         },
-        resources.concurrent_tasks,
+        // A debug shell owns the terminal, so nothing may forge beside it.
+        if on_failure.is_some() {
+            1
+        } else {
+            resources.concurrent_tasks
+        },
         |task| {
             let plan = Arc::clone(&plan);
             let foundry = Arc::clone(&foundry);
@@ -143,6 +173,9 @@ pub async fn execute_build(
             let configure_lock = Arc::clone(&configure_lock);
             let compile_lock = Arc::clone(&compile_lock);
             let config = config.clone();
+            let inputs = task_inputs.get(&task).cloned();
+            let debug_shell = on_failure.clone();
+            let db_path = db_path.to_path_buf();
 
             async move {
                 let plan_path = plan
@@ -184,18 +217,22 @@ pub async fn execute_build(
                     .ok_or_else(|| WrightError::ForgeError("plan path has no parent".into()))?
                     .to_path_buf();
 
-                // Intra-step idempotence: skip when staging/ is already populated.
+                // Intra-step idempotence: skip when staging/ is already
+                // populated by a forge under the same build inputs.
                 let build_root = foundry.build_root(&manifest)?;
                 let can_short_circuit = !force
                     && options.stages.is_empty()
                     && options.until_stage.is_none()
                     && !options.fetch_only;
-                if can_short_circuit && staging_is_populated(&build_root) {
+                if can_short_circuit
+                    && staging_is_populated(&build_root)
+                    && inputs_unchanged(&build_root, inputs.as_ref())
+                {
                     info!(event = "build.short_circuited", plan_name = %base, reason = "staging_populated", "Build short-circuited — staging already populated");
                     return Ok(());
                 }
 
-                foundry
+                let result = foundry
                     .build(
                         &manifest,
                         &plan_dir,
@@ -213,11 +250,15 @@ pub async fn execute_build(
                             nproc_per_isolation: config.build.nproc_per_isolation,
                             configure_lock: Some(configure_lock),
                             compile_lock: Some(compile_lock),
+                            inputs,
+                            build_root: None,
+                            debug_shell,
                         },
                     )
                     .await
-                    .map(|_| ())
-                    .map_err(|e| WrightError::ForgeError(format!("build {}: {}", base, e)))
+                    .map_err(|e| e.in_forge(format!("build {base}")))?;
+                record_usage(&db_path, &manifest, result.usage.as_deref()).await;
+                Ok(())
             }
         },
         cancel_rx,
@@ -253,6 +294,240 @@ pub async fn execute_build(
     Ok(())
 }
 
+/// `wright build --shell <stage>`: forge the single target up to `stage`
+/// and open an interactive shell in that stage's isolation. Dependencies
+/// are not built; the shell sees what is deployed.
+async fn shell_into_stage(
+    targets: &[String],
+    stage: String,
+    args: &BuildArgs,
+    config: &GlobalConfig,
+    db_path: &Path,
+    verbose: bool,
+) -> Result<()> {
+    let plan_dirs = crate::resolve::plan_search_dirs(config);
+    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?;
+    let plan_paths: Vec<_> = crate::resolve::resolve_targets(targets, &index, &plan_dirs)?
+        .into_iter()
+        .collect();
+    let [plan_path] = plan_paths.as_slice() else {
+        return Err(WrightError::ValidationError(format!(
+            "--shell takes a single target, got {} plans",
+            plan_paths.len()
+        )));
+    };
+    let manifest = PlanManifest::from_file(plan_path).map_err(|e| {
+        WrightError::ForgeError(format!("read plan {}: {}", plan_path.display(), e))
+    })?;
+    let plan_dir = plan_path
+        .parent()
+        .ok_or_else(|| WrightError::ForgeError("plan path has no parent".into()))?;
+
+    let foundry = Foundry::new(config.clone());
+    let mut extra_env = HashMap::new();
+    extra_env.insert(
+        "WRIGHT_BUILD_PHASE".to_string(),
+        if args.mvp { "mvp" } else { "full" }.to_string(),
+    );
+    let mut inputs = foundry.build_inputs(&manifest, args.mvp);
+    {
+        let db = InstalledDb::open(db_path).await?;
+        inputs.add_dependencies(&db, &manifest, args.mvp).await?;
+    }
+    foundry
+        .build(
+            &manifest,
+            plan_dir,
+            Path::new("/"),
+            BuildOptions {
+                force_stage: args.force_stage.clone(),
+                skip_check: args.skip_check,
+                force: args.force,
+                clean: args.clean,
+                extra_env,
+                verbose,
+                nproc_per_isolation: config.build.nproc_per_isolation,
+                inputs: Some(inputs),
+                debug_shell: Some(DebugShell::Stage(stage)),
+                ..Default::default()
+            },
+        )
+        .await?;
+    Ok(())
+}
+
+/// `wright build --verify-reproducible`: forge each target twice in fresh
+/// build roots whose names differ (as do the timestamps and isolation task
+/// IDs of the runs), then compare the output trees file by file. Both roots
+/// are removed when the trees match and kept for inspection otherwise.
+async fn verify_reproducible(
+    targets: &[String],
+    config: &GlobalConfig,
+    verbose: bool,
+) -> Result<()> {
+    let plan_dirs = crate::resolve::plan_search_dirs(config);
+    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?;
+    let mut plan_paths: Vec<_> = crate::resolve::resolve_targets(targets, &index, &plan_dirs)?
+        .into_iter()
+        .collect();
+    plan_paths.sort();
+
+    let foundry = Foundry::new(config.clone());
+    let forge_dir = crate::foundry::forge_dir(config)?;
+    let mut unreproducible = Vec::new();
+    for plan_path in &plan_paths {
+        let manifest = PlanManifest::from_file(plan_path).map_err(|e| {
+            WrightError::ForgeError(format!("read plan {}: {}", plan_path.display(), e))
+        })?;
+        let plan_dir = plan_path
+            .parent()
+            .ok_or_else(|| WrightError::ForgeError("plan path has no parent".into()))?;
+        let name = &manifest.metadata.name;
+        let stamp = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
+        let root_name = crate::foundry::build_root_name(&manifest);
+        let roots = crate::foundry::REPRODUCIBLE_ROOT_MARKERS
+            .map(|marker| forge_dir.join(format!("{}{}{:x}", root_name, marker, stamp)));
+
+        for (run, root) in roots.iter().enumerate() {
+            crate::cli_action!("Forging", "{} ({}/2) in {}", name, run + 1, root.display());
+            let mut extra_env = HashMap::new();
+            extra_env.insert("WRIGHT_BUILD_PHASE".to_string(), "full".to_string());
+            if let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") {
+                extra_env.insert("SOURCE_DATE_EPOCH".to_string(), epoch);
+            }
+            foundry
+                .build(
+                    &manifest,
+                    plan_dir,
+                    Path::new("/"),
+                    BuildOptions {
+                        force: true,
+                        extra_env,
+                        verbose,
+                        nproc_per_isolation: config.build.nproc_per_isolation,
+                        build_root: Some(root.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| e.in_forge(format!("build {name}")))?;
+        }
+
+        let cmp = crate::foundry::reproducible::compare_trees(
+            &roots[0],
+            &roots[1],
+            &["staging", "outputs"],
+        )?;
+        if cmp.differences.is_empty() {
+            crate::cli_action!(
+                "Reproducible",
+                "{}: {} path(s) identical",
+                name,
+                cmp.identical
+            );
+            for root in &roots {
+                crate::foundry::layers::force_clean_dir(root).await?;
+            }
+            continue;
+        }
+        crate::cli_warn!(
+            "{}: {} path(s) differ between {} and {}",
+            name,
+            cmp.differences.len(),
+            roots[0].display(),
+            roots[1].display()
+        );
+        for diff in &cmp.differences {
+            if diff.detail.is_empty() {
+                println!("  {:<11} {}", diff.difference.as_str(), diff.path);
+            } else {
+                println!(
+                    "  {:<11} {} ({})",
+                    diff.difference.as_str(),
+                    diff.path,
+                    diff.detail
+                );
+            }
+        }
+        unreproducible.push(name.clone());
+    }
+
+    if !unreproducible.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "not reproducible: {}",
+            unreproducible.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Record what a whole-pipeline forge cost. Accounting is best effort: a
+/// failure to record never fails the build.
+pub(crate) async fn record_usage(
+    db_path: &Path,
+    manifest: &PlanManifest,
+    usage: Option<&[BuildStage]>,
+) {
+    let Some(stages) = usage else {
+        return;
+    };
+    let recorded = async {
+        InstalledDb::open(db_path)
+            .await?
+            .record_build(NewBuildRun {
+                plan_name: &manifest.metadata.name,
+                version: manifest.metadata.version.as_deref().unwrap_or(""),
+                release: manifest.metadata.release,
+                stages,
+            })
+            .await
+    }
+    .await;
+    if let Err(e) = recorded {
+        tracing::warn!(event = "build.usage_record_failed", plan_name = %manifest.metadata.name, error = %e, "Failed to record build resource usage");
+    }
+}
+
+/// Build inputs of every task, dependencies included. The database is read
+/// up front and closed so it is not held for the length of the build.
+async fn task_inputs(
+    plan: &BuildExecutionPlan,
+    foundry: &Foundry,
+    db_path: &Path,
+    mvp: bool,
+) -> Result<HashMap<String, BuildInputs>> {
+    let db = InstalledDb::open(db_path).await?;
+    let mut inputs = HashMap::new();
+    for task in plan.batches().iter().flatten() {
+        let Some(plan_path) = plan.plan_path_for_task(task) else {
+            continue;
+        };
+        let manifest = PlanManifest::from_file(plan_path).map_err(|e| {
+            WrightError::ForgeError(format!(
+                "read plan {}: {}",
+                BuildExecutionPlan::task_base_name(task),
+                e
+            ))
+        })?;
+        let is_mvp = mvp || task.ends_with(":bootstrap");
+        let mut task_inputs = foundry.build_inputs(&manifest, is_mvp);
+        task_inputs.add_dependencies(&db, &manifest, is_mvp).await?;
+        inputs.insert(task.clone(), task_inputs);
+    }
+    Ok(inputs)
+}
+
+/// Whether the build root was last forged under `inputs`.
+fn inputs_unchanged(build_root: &Path, inputs: Option<&BuildInputs>) -> bool {
+    let Some(inputs) = inputs else {
+        return true;
+    };
+    matches!(
+        Checkpoint::read_state(build_root),
+        Ok(Some(state)) if state.build_key == inputs.key()
+    )
+}
+
 fn staging_is_populated(build_root: &std::path::Path) -> bool {
     dir_is_populated(&build_root.join("staging"))
 }
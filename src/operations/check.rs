@@ -3,6 +3,7 @@ use std::time::Instant;
 
 use crate::database::InstalledDb;
 use crate::error::{Result, WrightError};
+use crate::util::output::OutputFormat;
 
 /// Implementation of `wright check`.
 ///
@@ -15,9 +16,10 @@ pub async fn execute_check(
     deep: bool,
     integrity_only: bool,
     check_files: bool,
+    format: OutputFormat,
 ) -> Result<()> {
     let t0 = Instant::now();
-    let issues = super::health::run_standard_checks(
+    let report = super::health::run_standard_checks(
         db,
         root_dir,
         only_part,
@@ -26,7 +28,9 @@ pub async fn execute_check(
         check_files,
     )
     .await?;
+    report.write("check", format)?;
 
+    let issues = report.issue_count();
     if issues == 0 {
         let scope = scope_label(only_part);
         let mode = check_mode_label(deep, check_files, integrity_only);
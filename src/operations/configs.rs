@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::database::{InstalledDb, PendingConfig};
+use crate::error::{Result, WrightError};
+use crate::transaction::config::{base_path, pending_path};
+use crate::util::checksum;
+use crate::util::merge::{merge3, unified_diff};
+use crate::util::output::{self, OutputFormat};
+
+/// How `wright config resolve` settles a pending update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the local file and discard the packaged update.
+    Keep,
+    /// Replace the local file with the packaged update.
+    UseNew,
+    /// Three-way merge the local edits into the packaged update.
+    Merge,
+}
+
+#[derive(Serialize)]
+struct ConfigRow {
+    path: String,
+    part: String,
+    /// `pending`, `modified`, or `missing`.
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    configs: &'a [ConfigRow],
+}
+
+/// Implementation of `wright config list`: pending updates, plus every
+/// locally modified or missing config file with `modified`.
+pub async fn execute_list_configs(
+    db: &InstalledDb,
+    root_dir: &Path,
+    modified: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let pending: HashMap<String, PendingConfig> = db
+        .list_pending_configs()
+        .await?
+        .into_iter()
+        .map(|p| (p.path.clone(), p))
+        .collect();
+
+    let mut rows: Vec<ConfigRow> = pending
+        .values()
+        .map(|p| ConfigRow {
+            path: p.path.clone(),
+            part: p.part_name.clone(),
+            status: "pending",
+        })
+        .collect();
+    if modified {
+        for file in db.list_config_files().await? {
+            if pending.contains_key(&file.path) {
+                continue;
+            }
+            let live = live_path(root_dir, &file.path);
+            let status = match checksum::sha256_file(&live) {
+                Err(_) if !live.exists() => "missing",
+                Ok(hash) if Some(&hash) == file.file_hash.as_ref() => continue,
+                _ => "modified",
+            };
+            rows.push(ConfigRow {
+                path: file.path,
+                part: file.part_name,
+                status,
+            });
+        }
+    }
+    rows.sort_by(|a, b| a.path.cmp(&b.path));
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "config list",
+                &ListBody { configs: &rows },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &["path", "part", "status"],
+                rows.iter()
+                    .map(|r| [r.path.clone(), r.part.clone(), r.status.to_string()]),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    if rows.is_empty() {
+        println!("no pending config updates");
+        return Ok(());
+    }
+    for row in &rows {
+        println!("{:<8} {} ({})", row.status, row.path, row.part);
+    }
+    Ok(())
+}
+
+/// Implementation of `wright config diff`: the local file against its
+/// pending packaged update, for `paths` or every pending update.
+pub async fn execute_diff_configs(
+    db: &InstalledDb,
+    root_dir: &Path,
+    paths: &[String],
+) -> Result<()> {
+    for pending in select_pending(db, paths).await? {
+        let live = live_path(root_dir, &pending.path);
+        let sidecar = pending_path(&live);
+        let (Ok(local), Ok(new)) = (std::fs::read(&live), std::fs::read(&sidecar)) else {
+            crate::cli_warn!(
+                "{}: local file or {} is missing",
+                pending.path,
+                sidecar.display()
+            );
+            continue;
+        };
+        print!(
+            "{}",
+            unified_diff(
+                &local,
+                &format!("{} (local)", pending.path),
+                &new,
+                &format!("{} (new)", pending.path),
+            )?
+        );
+    }
+    Ok(())
+}
+
+/// Implementation of `wright config resolve`.
+pub async fn execute_resolve_configs(
+    db: &InstalledDb,
+    root_dir: &Path,
+    paths: &[String],
+    resolution: Resolution,
+) -> Result<()> {
+    let mut unresolved = Vec::new();
+    for pending in select_pending(db, paths).await? {
+        let live = live_path(root_dir, &pending.path);
+        let sidecar = pending_path(&live);
+        if !sidecar.exists() {
+            // Nothing left to apply; drop the stale record.
+            crate::cli_warn!("{}: {} is missing", pending.path, sidecar.display());
+            db.delete_pending_config(&pending.path).await?;
+            continue;
+        }
+
+        match resolution {
+            Resolution::Keep => {
+                remove(&sidecar)?;
+                crate::cli_action!("Kept", "{}", pending.path);
+            }
+            Resolution::UseNew => {
+                std::fs::rename(&sidecar, &live).map_err(|e| io_error(&live, e))?;
+                crate::cli_action!("Replaced", "{}", pending.path);
+            }
+            Resolution::Merge => {
+                let Some(base) = pending
+                    .base_hash
+                    .as_deref()
+                    .and_then(|h| std::fs::read(base_path(root_dir, h)).ok())
+                else {
+                    crate::cli_warn!(
+                        "{}: no merge base recorded; resolve with --keep or --use-new",
+                        pending.path
+                    );
+                    unresolved.push(pending.path.clone());
+                    continue;
+                };
+                let local = std::fs::read(&live).map_err(|e| io_error(&live, e))?;
+                let new = std::fs::read(&sidecar).map_err(|e| io_error(&sidecar, e))?;
+                let merged = merge3(&base, &local, &new)?;
+                if merged.conflicts > 0 {
+                    // Leave the local file alone; hand over the marked-up result.
+                    let mut name = live.as_os_str().to_owned();
+                    name.push(".wright-merge");
+                    let marked = std::path::PathBuf::from(name);
+                    std::fs::write(&marked, &merged.content).map_err(|e| io_error(&marked, e))?;
+                    crate::cli_warn!(
+                        "{}: {} conflict(s); edit {} and install it by hand, then run \
+                         `wright config resolve --keep {}`",
+                        pending.path,
+                        merged.conflicts,
+                        marked.display(),
+                        pending.path
+                    );
+                    unresolved.push(pending.path.clone());
+                    continue;
+                }
+                std::fs::write(&live, &merged.content).map_err(|e| io_error(&live, e))?;
+                remove(&sidecar)?;
+                crate::cli_action!("Merged", "{}", pending.path);
+            }
+        }
+        db.delete_pending_config(&pending.path).await?;
+    }
+
+    if !unresolved.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "{} config update(s) still pending: {}",
+            unresolved.len(),
+            unresolved.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Pending updates for `paths`, or all of them when `paths` is empty.
+async fn select_pending(db: &InstalledDb, paths: &[String]) -> Result<Vec<PendingConfig>> {
+    if paths.is_empty() {
+        return db.list_pending_configs().await;
+    }
+    let mut selected = Vec::with_capacity(paths.len());
+    for path in paths {
+        let pending = db.get_pending_config(path).await?.ok_or_else(|| {
+            WrightError::ValidationError(format!("no pending config update for '{}'", path))
+        })?;
+        selected.push(pending);
+    }
+    Ok(selected)
+}
+
+fn live_path(root_dir: &Path, path: &str) -> std::path::PathBuf {
+    root_dir.join(path.trim_start_matches('/'))
+}
+
+fn remove(path: &Path) -> Result<()> {
+    std::fs::remove_file(path).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> WrightError {
+    WrightError::IoError(std::io::Error::new(
+        e.kind(),
+        format!("{}: {}", path.display(), e),
+    ))
+}
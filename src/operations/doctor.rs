@@ -6,6 +6,9 @@ use crate::error::{Result, WrightError};
 use crate::part::archive::read_archive_meta;
 use crate::part::soname::SonameIndex;
 use crate::part::version;
+use crate::util::output::OutputFormat;
+
+use super::health::{ClosureMissing, PlanDrift};
 
 /// Run comprehensive system health checks.
 ///
@@ -15,11 +18,12 @@ pub async fn execute_doctor(
     db: &InstalledDb,
     root_dir: &Path,
     config: &GlobalConfig,
+    format: OutputFormat,
 ) -> Result<()> {
     let t0 = std::time::Instant::now();
     crate::cli_action!("Checking", "system health");
 
-    let mut total_issues = super::health::run_standard_checks(
+    let mut report = super::health::run_standard_checks(
         db, root_dir, None,  // only_part
         true,  // deep
         false, // integrity_only
@@ -27,13 +31,15 @@ pub async fn execute_doctor(
     )
     .await?;
 
-    let closure_issues = check_parts_dir_closure(config).await?;
-    total_issues += closure_issues;
+    report.closure_missing = Some(check_parts_dir_closure(config).await?);
 
     // Advisory only (ADR-0023): drift means "rebuild to converge", not a
     // health failure, so it is reported without contributing to the issue
     // count that fails doctor.
-    check_plan_drift(db, config).await?;
+    report.plan_drift = Some(check_plan_drift(db, config).await?);
+
+    report.write("doctor", format)?;
+    let total_issues = report.issue_count();
 
     let elapsed = t0.elapsed().as_secs_f64();
     if total_issues == 0 {
@@ -53,10 +59,10 @@ pub async fn execute_doctor(
 
 /// Scan parts_dir and verify that every archive's runtime_deps can be
 /// resolved to a provider archive in the same directory.
-async fn check_parts_dir_closure(config: &GlobalConfig) -> Result<usize> {
+async fn check_parts_dir_closure(config: &GlobalConfig) -> Result<Vec<ClosureMissing>> {
     let parts_dir = &config.general.parts_dir;
     if !parts_dir.exists() {
-        return Ok(0);
+        return Ok(Vec::new());
     }
 
     let mut archive_count = 0usize;
@@ -75,7 +81,7 @@ async fn check_parts_dir_closure(config: &GlobalConfig) -> Result<usize> {
         }
     }
     if archive_count == 0 {
-        return Ok(0);
+        return Ok(Vec::new());
     }
 
     crate::cli_action!(
@@ -89,7 +95,7 @@ async fn check_parts_dir_closure(config: &GlobalConfig) -> Result<usize> {
         SonameIndex::default()
     });
 
-    let mut missing: Vec<String> = Vec::new();
+    let mut missing: Vec<ClosureMissing> = Vec::new();
 
     for entry in std::fs::read_dir(parts_dir)
         .map_err(|e| WrightError::PartError(format!("read {}: {}", parts_dir.display(), e)))?
@@ -120,33 +126,36 @@ async fn check_parts_dir_closure(config: &GlobalConfig) -> Result<usize> {
             }
             let targets = resolve_dep_targets(dep, &index);
             if targets.is_empty() {
-                missing.push(format!(
-                    "{} needs {} (no provider in parts_dir)",
-                    meta.partinfo.name, dep
-                ));
+                missing.push(ClosureMissing {
+                    part: meta.partinfo.name.clone(),
+                    dependency: dep.to_string(),
+                });
             }
         }
     }
 
     if !missing.is_empty() {
         crate::cli_warn!("{} missing runtime dependencies", missing.len());
-        for line in &missing {
+        for m in &missing {
             // Indent each finding under the warning line; one bullet per
             // missing dep keeps the output scannable.
-            crate::util::progress::term_println(&format!("             - {}", line));
+            crate::util::progress::term_println(&format!(
+                "             - {} needs {} (no provider in parts_dir)",
+                m.part, m.dependency
+            ));
         }
     }
 
-    Ok(missing.len())
+    Ok(missing)
 }
 
 /// Compare each registered plan's recorded provenance checksum against the
 /// current plan source on disk. A mismatch means the plan changed since its
 /// parts were sealed — the installed state no longer reflects plan source.
-async fn check_plan_drift(db: &InstalledDb, config: &GlobalConfig) -> Result<usize> {
+async fn check_plan_drift(db: &InstalledDb, config: &GlobalConfig) -> Result<Vec<PlanDrift>> {
     let plans = db.list_plans().await?;
     if plans.iter().all(|p| p.plan_checksum.is_none()) {
-        return Ok(0);
+        return Ok(Vec::new());
     }
 
     let plan_dirs = crate::resolve::plan_search_dirs(config);
@@ -154,11 +163,11 @@ async fn check_plan_drift(db: &InstalledDb, config: &GlobalConfig) -> Result<usi
         Ok(index) => index,
         Err(e) => {
             crate::cli_warn!("skipping plan drift check: {}", e);
-            return Ok(0);
+            return Ok(Vec::new());
         }
     };
 
-    let mut drifted: Vec<String> = Vec::new();
+    let mut drifted: Vec<PlanDrift> = Vec::new();
     for plan in &plans {
         let Some(ref recorded) = plan.plan_checksum else {
             continue;
@@ -168,12 +177,11 @@ async fn check_plan_drift(db: &InstalledDb, config: &GlobalConfig) -> Result<usi
         };
         match crate::util::checksum::sha256_file(path) {
             Ok(current) if &current != recorded => {
-                drifted.push(format!(
-                    "{} (installed from {}…, source now {}…)",
-                    plan.name,
-                    &recorded[..12.min(recorded.len())],
-                    &current[..12]
-                ));
+                drifted.push(PlanDrift {
+                    plan: plan.name.clone(),
+                    recorded_checksum: recorded.clone(),
+                    current_checksum: current,
+                });
             }
             Ok(_) => {}
             Err(e) => crate::cli_warn!("cannot checksum {}: {}", path.display(), e),
@@ -185,12 +193,17 @@ async fn check_plan_drift(db: &InstalledDb, config: &GlobalConfig) -> Result<usi
             "{} plan(s) changed since their parts were installed (advisory; rebuild to converge)",
             drifted.len()
         );
-        for line in &drifted {
-            crate::util::progress::term_println(&format!("             - {}", line));
+        for d in &drifted {
+            crate::util::progress::term_println(&format!(
+                "             - {} (installed from {}…, source now {}…)",
+                d.plan,
+                &d.recorded_checksum[..12.min(d.recorded_checksum.len())],
+                &d.current_checksum[..12]
+            ));
         }
     }
 
-    Ok(drifted.len())
+    Ok(drifted)
 }
 
 fn resolve_dep_targets(dep: &str, index: &SonameIndex) -> Vec<String> {
@@ -4,6 +4,7 @@
 //! plan fingerprint) and deploy idempotence (database state). This layer
 //! only schedules dependency batches and reports failures.
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -11,9 +12,10 @@ use crate::error::{Result, WrightError};
 use futures_util::stream::{self, StreamExt};
 use tokio::sync::Semaphore;
 use tokio::sync::watch;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::config::GlobalConfig;
+use crate::database::InstalledDb;
 use crate::resolve::BuildExecutionPlan;
 
 pub struct DriveOptions<'a> {
@@ -27,7 +29,9 @@ pub struct DriveOptions<'a> {
 /// No persistent workflow state — resume is handled entirely by the foundry
 /// checkpoints (file-system sentinels keyed by plan fingerprint).
 ///
-/// `concurrency` limits how many tasks within a batch run at once.
+/// `concurrency` limits how many tasks within a batch run at once. Within a
+/// batch, tasks start longest first by the build times recorded for their
+/// plans, so a long forge does not begin last and hold up the next batch.
 pub async fn drive_batches<F, Fut>(
     plan: &BuildExecutionPlan,
     options: &DriveOptions<'_>,
@@ -43,6 +47,7 @@ where
     let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
     let total_batches = plan.batches().len();
     let cancel = cancel;
+    let expected = expected_build_secs(options.db_path).await;
 
     for (batch_idx, batch) in plan.batches().iter().enumerate() {
         if *cancel.borrow() {
@@ -60,7 +65,7 @@ where
             );
         }
 
-        let results: Vec<Result<()>> = stream::iter(batch.iter().cloned())
+        let results: Vec<Result<()>> = stream::iter(longest_first(batch, &expected))
             .map(|task| {
                 let sem = semaphore.clone();
                 let f = task_fn.clone();
@@ -101,3 +106,55 @@ where
 
     Ok(())
 }
+
+/// Expected wall time of each plan with recorded builds; empty when the
+/// database cannot be read.
+async fn expected_build_secs(db_path: &Path) -> HashMap<String, f64> {
+    let expected = async {
+        InstalledDb::open(db_path)
+            .await?
+            .expected_build_secs()
+            .await
+    }
+    .await;
+    expected.unwrap_or_else(|e| {
+        debug!(event = "batch.build_times_unavailable", error = %e, "No recorded build times");
+        HashMap::new()
+    })
+}
+
+/// Order `batch` by expected build time, longest first. Tasks whose plans
+/// have no recorded builds follow in their original order.
+fn longest_first(batch: &[String], expected: &HashMap<String, f64>) -> Vec<String> {
+    let mut tasks = batch.to_vec();
+    let secs = |task: &String| {
+        expected
+            .get(BuildExecutionPlan::task_base_name(task))
+            .copied()
+            .unwrap_or(-1.0)
+    };
+    tasks.sort_by(|a, b| secs(b).total_cmp(&secs(a)));
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_first_orders_by_recorded_time() {
+        let batch: Vec<String> = ["zlib", "new", "gcc:bootstrap", "llvm", "other"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let expected = HashMap::from([
+            ("zlib".to_string(), 12.0),
+            ("gcc".to_string(), 3600.0),
+            ("llvm".to_string(), 5400.0),
+        ]);
+        assert_eq!(
+            longest_first(&batch, &expected),
+            ["llvm", "gcc:bootstrap", "zlib", "new", "other"]
+        );
+    }
+}
@@ -1,7 +1,16 @@
-use crate::database::InstalledDb;
+use serde::Serialize;
+
+use crate::database::{FileEntry, InstalledDb};
 use crate::error::{Result, WrightError};
+use crate::util::output::{self, OutputFormat};
+
+#[derive(Serialize)]
+struct FilesBody<'a> {
+    part: &'a str,
+    files: &'a [FileEntry],
+}
 
-pub async fn execute_files(db: &InstalledDb, part: &str) -> Result<()> {
+pub async fn execute_files(db: &InstalledDb, part: &str, format: OutputFormat) -> Result<()> {
     let installed_part = db
         .get_part(part)
         .await
@@ -12,8 +21,34 @@ pub async fn execute_files(db: &InstalledDb, part: &str) -> Result<()> {
                 .get_files(info.id)
                 .await
                 .map_err(|e| WrightError::DatabaseError(format!("failed to get files: {}", e)))?;
-            for file in &files {
-                println!("{}", file.path);
+            match format {
+                OutputFormat::Json => output::write_json(
+                    &mut std::io::stdout().lock(),
+                    "files",
+                    &FilesBody {
+                        part,
+                        files: &files,
+                    },
+                )?,
+                OutputFormat::Tsv => output::write_tsv(
+                    &mut std::io::stdout().lock(),
+                    &["path", "type", "mode", "size", "hash", "config"],
+                    files.iter().map(|f| {
+                        [
+                            f.path.clone(),
+                            f.file_type.as_str().to_string(),
+                            output::opt(f.file_mode.map(|m| format!("{:o}", m))),
+                            output::opt(f.file_size),
+                            output::opt(f.file_hash.as_deref()),
+                            f.is_config.to_string(),
+                        ]
+                    }),
+                )?,
+                OutputFormat::Human => {
+                    for file in &files {
+                        println!("{}", file.path);
+                    }
+                }
             }
         }
         None => {
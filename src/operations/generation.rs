@@ -0,0 +1,506 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::GlobalConfig;
+use crate::database::{GenerationPart, InstalledDb, Origin, SessionContext};
+use crate::error::{Result, WrightError};
+use crate::operations::rollback::{self, Reversal};
+use crate::part::store::LocalPartStore;
+use crate::util::output::{self, OutputFormat};
+
+/// Name `wright generation diff` uses for the installed registry.
+const CURRENT: &str = "current";
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    generations: &'a [crate::database::Generation],
+}
+
+#[derive(Serialize)]
+struct ChangedPart {
+    name: String,
+    from: GenerationPart,
+    to: GenerationPart,
+}
+
+/// Part-level difference between two registry states.
+#[derive(Serialize, Default)]
+struct GenerationDiff {
+    added: Vec<GenerationPart>,
+    removed: Vec<GenerationPart>,
+    changed: Vec<ChangedPart>,
+}
+
+impl GenerationDiff {
+    fn between(from: &[GenerationPart], to: &[GenerationPart]) -> Self {
+        let from: BTreeMap<&str, &GenerationPart> =
+            from.iter().map(|p| (p.name.as_str(), p)).collect();
+        let to: BTreeMap<&str, &GenerationPart> = to.iter().map(|p| (p.name.as_str(), p)).collect();
+
+        let mut diff = Self::default();
+        for (name, old) in &from {
+            match to.get(name) {
+                None => diff.removed.push((*old).clone()),
+                Some(new) if new != old => diff.changed.push(ChangedPart {
+                    name: name.to_string(),
+                    from: (*old).clone(),
+                    to: (*new).clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, new) in &to {
+            if !from.contains_key(name) {
+                diff.added.push((*new).clone());
+            }
+        }
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn write_human(&self) {
+        for part in &self.added {
+            println!("+ {} {}", part.name, version_label(part));
+        }
+        for part in &self.removed {
+            println!("- {} {}", part.name, version_label(part));
+        }
+        for change in &self.changed {
+            println!(
+                "~ {} {}",
+                change.name,
+                change_label(&change.from, &change.to)
+            );
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DiffBody<'a> {
+    from: &'a str,
+    to: &'a str,
+    #[serde(flatten)]
+    diff: &'a GenerationDiff,
+}
+
+/// Implementation of `wright generation snapshot`.
+pub async fn execute_snapshot(db: &InstalledDb, name: &str) -> Result<()> {
+    validate_name(name)?;
+    let id = db.create_generation(name).await?;
+    let count = db.get_generation_parts(id).await?.len();
+    crate::cli_action!("Snapshot", "generation {} ({} part(s))", name, count);
+    Ok(())
+}
+
+/// Implementation of `wright generation list`.
+pub async fn execute_list_generations(db: &InstalledDb, format: OutputFormat) -> Result<()> {
+    let generations = db.list_generations().await?;
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "generation list",
+                &ListBody {
+                    generations: &generations,
+                },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &["name", "created_at", "parts"],
+                generations.iter().map(|g| {
+                    [
+                        g.name.clone(),
+                        output::opt(g.created_at.as_deref()),
+                        g.part_count.to_string(),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    if generations.is_empty() {
+        println!("no generations recorded");
+        return Ok(());
+    }
+    for g in &generations {
+        println!(
+            "{:<24} {}  {} part(s)",
+            g.name,
+            g.created_at.as_deref().unwrap_or_default(),
+            g.part_count
+        );
+    }
+    Ok(())
+}
+
+/// Implementation of `wright generation diff`. `to` defaults to the
+/// installed registry.
+pub async fn execute_diff(
+    db: &InstalledDb,
+    from: &str,
+    to: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let to = to.unwrap_or(CURRENT);
+    let diff = GenerationDiff::between(&load_state(db, from).await?, &load_state(db, to).await?);
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "generation diff",
+                &DiffBody {
+                    from,
+                    to,
+                    diff: &diff,
+                },
+            );
+        }
+        OutputFormat::Tsv => {
+            let mut rows: Vec<[String; 6]> = Vec::new();
+            for p in &diff.added {
+                rows.push(tsv_row("added", &p.name, None, Some(p)));
+            }
+            for p in &diff.removed {
+                rows.push(tsv_row("removed", &p.name, Some(p), None));
+            }
+            for c in &diff.changed {
+                rows.push(tsv_row("changed", &c.name, Some(&c.from), Some(&c.to)));
+            }
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &[
+                    "change",
+                    "name",
+                    "from_version",
+                    "to_version",
+                    "from_hash",
+                    "to_hash",
+                ],
+                rows,
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    if diff.is_empty() {
+        println!("no differences between {} and {}", from, to);
+    } else {
+        diff.write_human();
+    }
+    Ok(())
+}
+
+/// Implementation of `wright generation delete`.
+pub async fn execute_delete(db: &InstalledDb, name: &str) -> Result<()> {
+    if !db.delete_generation(name).await? {
+        return Err(not_found(name));
+    }
+    crate::cli_action!("Deleted", "generation {}", name);
+    Ok(())
+}
+
+/// Implementation of `wright generation switch`.
+///
+/// Brings the installed registry back to generation `name` with the
+/// smallest change set: parts whose archive hash differs are redeployed
+/// from the archive matching the recorded hash (CAS store, then
+/// `parts_dir`), parts absent from the generation are removed, and
+/// recorded origins are restored.  Runs as one delivery transaction, so a
+/// switch can itself be undone with `wright rollback`.
+pub async fn execute_switch(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    part_store: &LocalPartStore,
+    name: &str,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let t0 = std::time::Instant::now();
+    let target = load_state(db, name).await?;
+    let current = db.current_generation_parts().await?;
+    let diff = GenerationDiff::between(&current, &target);
+
+    let current_by_name: BTreeMap<&str, &GenerationPart> =
+        current.iter().map(|p| (p.name.as_str(), p)).collect();
+    let mut reversal = Reversal::default();
+    let mut provide: Vec<&GenerationPart> = Vec::new();
+    // External placeholders the generation replaces with real parts or
+    // with another version.
+    let mut unprovide: Vec<&GenerationPart> = Vec::new();
+    let mut unrecorded: Vec<&str> = Vec::new();
+
+    for part in diff.added.iter().chain(diff.changed.iter().map(|c| &c.to)) {
+        let now = current_by_name.get(part.name.as_str());
+        let now_external = now.is_some_and(|p| p.origin == Origin::External);
+        if part.origin == Origin::External {
+            if now.is_some() && !now_external {
+                reversal.remove.push(part.name.clone());
+            }
+            if !now_external || now.map(|p| &p.version) != Some(&part.version) {
+                provide.push(part);
+                if let Some(placeholder) = now.filter(|_| now_external) {
+                    unprovide.push(placeholder);
+                }
+            }
+            continue;
+        }
+        let Some(hash) = &part.part_hash else {
+            unrecorded.push(&part.name);
+            continue;
+        };
+        if now.and_then(|p| p.part_hash.as_ref()) != Some(hash) {
+            reversal.restore.push((part.name.clone(), hash.clone()));
+            if now.is_none() || now_external {
+                reversal.reinstated.insert(part.name.clone());
+            }
+            if let Some(placeholder) = now.filter(|_| now_external) {
+                unprovide.push(placeholder);
+            }
+        }
+    }
+    reversal
+        .remove
+        .extend(diff.removed.iter().map(|p| p.name.clone()));
+
+    if !unrecorded.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "generation '{}' has no archive hash recorded for: {}",
+            name,
+            unrecorded.join(", ")
+        )));
+    }
+
+    let located = rollback::locate_archives(config, &reversal)?;
+    let missing: Vec<String> = reversal
+        .restore
+        .iter()
+        .filter(|(_, hash)| !located.contains_key(hash))
+        .map(|(name, hash)| format!("{} ({})", name, &hash[..12.min(hash.len())]))
+        .collect();
+    if !missing.is_empty() {
+        return Err(WrightError::PartNotFound(format!(
+            "no archive in the CAS store or parts_dir for: {}",
+            missing.join(", ")
+        )));
+    }
+
+    if dry_run {
+        println!("[dry-run] switch to generation {}", name);
+        if diff.is_empty() {
+            println!("[dry-run] nothing to do");
+            return Ok(());
+        }
+        diff.write_human();
+        if !reversal.restore.is_empty() {
+            println!(
+                "[dry-run] would redeploy {} part(s):",
+                reversal.restore.len()
+            );
+            for (part, hash) in &reversal.restore {
+                println!("  {} <- {}", part, located[hash].display());
+            }
+        }
+        if !reversal.remove.is_empty() {
+            println!("[dry-run] would remove {} part(s):", reversal.remove.len());
+            for part in &reversal.remove {
+                println!("  {}", part);
+            }
+        }
+        return Ok(());
+    }
+
+    if diff.is_empty() {
+        crate::cli_action!("Finished", "already at generation {}", name);
+        return Ok(());
+    }
+
+    let command_str = format!("generation switch {}", name);
+    let tx_id = crate::delivery::begin_delivery(db, &command_str).await?;
+    let session = SessionContext {
+        id: format!(
+            "{:x}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
+        ),
+        command: command_str,
+        delivery_id: Some(tx_id),
+    };
+    crate::delivery::delivery_ready(db, tx_id).await?;
+    crate::delivery::begin_applying(db, tx_id).await?;
+
+    let result = async {
+        // Journaled, so a failed switch or a later rollback provides it again.
+        for placeholder in &unprovide {
+            let op_id = crate::delivery::begin_placeholder_op(
+                db,
+                tx_id,
+                &placeholder.name,
+                &placeholder.version,
+                rollback::UNPROVIDE_ACTION,
+            )
+            .await?;
+            db.remove_part(&placeholder.name).await?;
+            crate::delivery::op_done(db, op_id).await?;
+        }
+        rollback::apply(
+            db, root_dir, part_store, &reversal, &located, force, &session,
+        )
+        .await?;
+        for part in &provide {
+            crate::cli_action!("Providing", "{} {}", part.name, part.version);
+            let op_id = crate::delivery::begin_placeholder_op(
+                db,
+                tx_id,
+                &part.name,
+                &part.version,
+                rollback::PROVIDE_ACTION,
+            )
+            .await?;
+            db.provide_part(&part.name, &part.version).await?;
+            crate::delivery::op_done(db, op_id).await?;
+        }
+        Ok::<(), WrightError>(())
+    }
+    .await;
+    if let Err(e) = result {
+        rollback::undo_partial(db, config, root_dir, part_store, tx_id, &session).await;
+        let _ = crate::delivery::rollback_delivery(db, tx_id).await;
+        return Err(WrightError::DeployError(format!(
+            "switch to generation {}: {}",
+            name, e
+        )));
+    }
+
+    for part in &target {
+        db.reset_origin(&part.name, part.origin).await?;
+    }
+    crate::transaction::run_triggers(db, tx_id, root_dir).await?;
+    crate::transaction::run_deferred_hooks(db, tx_id, root_dir).await?;
+    crate::delivery::complete_delivery(db, tx_id).await?;
+
+    crate::cli_action!(
+        "Finished",
+        "switch to generation {} in {}: {} redeployed, {} removed, {} provided",
+        name,
+        crate::foundry::logging::format_duration(t0.elapsed().as_secs_f64()),
+        reversal.restore.len(),
+        reversal.remove.len(),
+        provide.len(),
+    );
+    Ok(())
+}
+
+/// Parts of generation `name`, or of the installed registry for `current`.
+async fn load_state(db: &InstalledDb, name: &str) -> Result<Vec<GenerationPart>> {
+    if name == CURRENT {
+        return db.current_generation_parts().await;
+    }
+    let generation = db
+        .get_generation(name)
+        .await?
+        .ok_or_else(|| not_found(name))?;
+    db.get_generation_parts(generation.id).await
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == CURRENT || name.chars().any(char::is_whitespace) {
+        return Err(WrightError::ValidationError(format!(
+            "invalid generation name '{}': must be non-empty, without whitespace, and not '{}'",
+            name, CURRENT
+        )));
+    }
+    Ok(())
+}
+
+fn not_found(name: &str) -> WrightError {
+    WrightError::ValidationError(format!(
+        "generation '{}' not found (see `wright generation list`)",
+        name
+    ))
+}
+
+fn version_label(part: &GenerationPart) -> String {
+    if part.origin == Origin::External {
+        format!("{} (external)", part.version)
+    } else {
+        format!("{}-{}", part.version, part.release)
+    }
+}
+
+fn change_label(from: &GenerationPart, to: &GenerationPart) -> String {
+    let (old, new) = (version_label(from), version_label(to));
+    let mut label = if old != new {
+        format!("{} -> {}", old, new)
+    } else if from.part_hash != to.part_hash {
+        format!("{} (rebuilt)", new)
+    } else {
+        new
+    };
+    if from.origin != to.origin {
+        label.push_str(&format!(" [origin {} -> {}]", from.origin, to.origin));
+    }
+    label
+}
+
+fn tsv_row(
+    change: &str,
+    name: &str,
+    from: Option<&GenerationPart>,
+    to: Option<&GenerationPart>,
+) -> [String; 6] {
+    [
+        change.to_string(),
+        name.to_string(),
+        output::opt(from.map(version_label)),
+        output::opt(to.map(version_label)),
+        output::opt(from.and_then(|p| p.part_hash.clone())),
+        output::opt(to.and_then(|p| p.part_hash.clone())),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(name: &str, version: &str, hash: &str) -> GenerationPart {
+        GenerationPart {
+            name: name.into(),
+            part_hash: Some(hash.into()),
+            origin: Origin::Manual,
+            version: version.into(),
+            release: 1,
+        }
+    }
+
+    #[test]
+    fn test_diff_classifies_added_removed_and_changed() {
+        let from = vec![part("alpha", "1.0", "a1"), part("gamma", "1.0", "g1")];
+        let to = vec![
+            part("alpha", "2.0", "a2"),
+            part("beta", "1.0", "b1"),
+            part("gamma", "1.0", "g1"),
+        ];
+        let diff = GenerationDiff::between(&from, &to);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "beta");
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            change_label(&diff.changed[0].from, &diff.changed[0].to),
+            "1.0-1 -> 2.0-1"
+        );
+
+        let back = GenerationDiff::between(&to, &from);
+        assert_eq!(back.removed.len(), 1);
+        assert_eq!(back.removed[0].name, "beta");
+    }
+}
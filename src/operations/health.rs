@@ -1,14 +1,143 @@
 use std::collections::BTreeMap;
 use std::path::Path;
 
+use serde::Serialize;
+
 use crate::database::{FileType, InstalledDb, InstalledPart, Origin};
 use crate::error::{Result, WrightError};
 use crate::part::elf;
 use crate::query;
+use crate::util::output::{self, OutputFormat};
+
+/// Structured findings of a health-check run. Human output is emitted as
+/// the checks run; this is what `--format json|tsv` renders afterwards.
+#[derive(Default, Serialize)]
+pub(super) struct HealthReport {
+    pub integrity: Vec<String>,
+    pub shadowed: Vec<String>,
+    pub missing_files: Vec<MissingFile>,
+    pub broken_deps: Vec<query::BrokenDep>,
+    pub elf_missing: Vec<DeepMissing>,
+    /// Checks that could not run at all; each counts as one issue.
+    pub errors: Vec<String>,
+    /// `doctor` only: archive runtime deps with no provider in parts_dir.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closure_missing: Option<Vec<ClosureMissing>>,
+    /// `doctor` only: advisory, never counted as an issue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_drift: Option<Vec<PlanDrift>>,
+}
+
+#[derive(Serialize)]
+pub(super) struct MissingFile {
+    pub part: String,
+    pub path: String,
+    /// `missing`, `wrong type`, `expected symlink`, or `expected directory`.
+    pub problem: &'static str,
+}
+
+#[derive(Serialize)]
+pub(super) struct ClosureMissing {
+    pub part: String,
+    pub dependency: String,
+}
+
+#[derive(Serialize)]
+pub(super) struct PlanDrift {
+    pub plan: String,
+    pub recorded_checksum: String,
+    pub current_checksum: String,
+}
+
+#[derive(Serialize)]
+struct HealthBody<'a> {
+    issues: usize,
+    #[serde(flatten)]
+    report: &'a HealthReport,
+}
+
+impl HealthReport {
+    pub fn issue_count(&self) -> usize {
+        self.integrity.len()
+            + self.shadowed.len()
+            + self.missing_files.len()
+            + self.broken_deps.len()
+            + self.elf_missing.len()
+            + self.errors.len()
+            + self.closure_missing.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Render the report for `--format json|tsv`; a no-op for human output,
+    /// which the checks already printed.
+    pub fn write(&self, command: &str, format: OutputFormat) -> Result<()> {
+        let mut out = std::io::stdout().lock();
+        match format {
+            OutputFormat::Human => Ok(()),
+            OutputFormat::Json => output::write_json(
+                &mut out,
+                command,
+                &HealthBody {
+                    issues: self.issue_count(),
+                    report: self,
+                },
+            ),
+            OutputFormat::Tsv => output::write_tsv(
+                &mut out,
+                &["check", "part", "subject", "detail"],
+                self.rows(),
+            ),
+        }
+    }
+
+    fn rows(&self) -> Vec<[String; 4]> {
+        let row = |check: &str, part: &str, subject: &str, detail: &str| {
+            [
+                check.to_string(),
+                part.to_string(),
+                subject.to_string(),
+                detail.to_string(),
+            ]
+        };
+        let mut rows = Vec::new();
+        rows.extend(self.integrity.iter().map(|m| row("integrity", "", "", m)));
+        rows.extend(self.shadowed.iter().map(|m| row("shadowed", "", "", m)));
+        rows.extend(
+            self.missing_files
+                .iter()
+                .map(|m| row("missing_file", &m.part, &m.path, m.problem)),
+        );
+        rows.extend(self.broken_deps.iter().map(|b| {
+            row(
+                "broken_dep",
+                &b.part,
+                &b.required_name,
+                b.version_constraint.as_deref().unwrap_or_default(),
+            )
+        }));
+        rows.extend(
+            self.elf_missing
+                .iter()
+                .map(|m| row("elf_missing", &m.part, &m.binary, &m.soname)),
+        );
+        rows.extend(self.errors.iter().map(|m| row("error", "", "", m)));
+        for m in self.closure_missing.iter().flatten() {
+            rows.push(row("closure_missing", &m.part, &m.dependency, ""));
+        }
+        for d in self.plan_drift.iter().flatten() {
+            rows.push(row(
+                "plan_drift",
+                &d.plan,
+                &d.recorded_checksum,
+                &d.current_checksum,
+            ));
+        }
+        rows
+    }
+}
 
-/// Run the standard suite of system health checks and return the total issue
-/// count. Callers format their own final messages (e.g. `check` vs `doctor`
-/// branding).
+/// Run the standard suite of system health checks and return their findings.
+/// Callers format their own final messages (e.g. `check` vs `doctor`
+/// branding) from `HealthReport::issue_count`.
 pub(super) async fn run_standard_checks(
     db: &InstalledDb,
     root_dir: &Path,
@@ -16,16 +145,16 @@ pub(super) async fn run_standard_checks(
     deep: bool,
     integrity_only: bool,
     check_files: bool,
-) -> Result<usize> {
-    let mut total_issues = 0usize;
+) -> Result<HealthReport> {
+    let mut report = HealthReport::default();
 
-    total_issues += integrity_check(db).await?;
+    integrity_check(db, &mut report).await?;
     if integrity_only {
-        return Ok(total_issues);
+        return Ok(report);
     }
 
     if check_files {
-        total_issues += files_check(db, root_dir, only_part).await?;
+        report.missing_files = files_check(db, root_dir, only_part).await?;
     }
 
     let registry_findings = registry_check(db, only_part).await?;
@@ -40,10 +169,11 @@ pub(super) async fn run_standard_checks(
         report_elf_findings(&elf_findings);
     }
 
-    total_issues +=
-        registry_findings.len() + elf_findings.missing.len() + elf_findings.unmapped.len();
+    report.broken_deps = registry_findings;
+    report.elf_missing = elf_findings.missing;
+    report.elf_missing.extend(elf_findings.unmapped);
 
-    Ok(total_issues)
+    Ok(report)
 }
 
 /// Emit a list of bullet findings indented under a verb line.
@@ -60,20 +190,18 @@ where
 
 // ── integrity ───────────────────────────────────────────────────────────
 
-async fn integrity_check(db: &InstalledDb) -> Result<usize> {
-    let mut issues = 0usize;
-
+async fn integrity_check(db: &InstalledDb, report: &mut HealthReport) -> Result<()> {
     crate::cli_action!("Checking", "database integrity");
     match db.integrity_check().await {
         Ok(list) if list.is_empty() => {}
         Ok(list) => {
             crate::cli_warn!("{} database integrity issue(s)", list.len());
-            issues += list.len();
             emit_bullets(&list);
+            report.integrity = list;
         }
         Err(e) => {
             crate::cli_error!("integrity check failed: {}", e);
-            issues += 1;
+            report.errors.push(format!("integrity check failed: {}", e));
         }
     }
 
@@ -82,16 +210,16 @@ async fn integrity_check(db: &InstalledDb) -> Result<usize> {
         Ok(list) if list.is_empty() => {}
         Ok(list) => {
             crate::cli_warn!("{} shadowed file conflict(s)", list.len());
-            issues += list.len();
             emit_bullets(&list);
+            report.shadowed = list;
         }
         Err(e) => {
             crate::cli_error!("shadow check failed: {}", e);
-            issues += 1;
+            report.errors.push(format!("shadow check failed: {}", e));
         }
     }
 
-    Ok(issues)
+    Ok(())
 }
 
 // ── registry deps ───────────────────────────────────────────────────────
@@ -137,10 +265,11 @@ struct DeepReport {
     unmapped: Vec<DeepMissing>,
 }
 
-struct DeepMissing {
-    part: String,
-    binary: String,
-    soname: String,
+#[derive(Serialize)]
+pub(super) struct DeepMissing {
+    pub part: String,
+    pub binary: String,
+    pub soname: String,
 }
 
 async fn elf_check(
@@ -243,16 +372,11 @@ fn report_elf_findings(report: &DeepReport) {
 
 // ── file existence ──────────────────────────────────────────────────────
 
-struct FilesReport {
-    missing: Vec<PartMissing>,
-}
-
-struct PartMissing {
-    part_name: String,
-    paths: Vec<String>,
-}
-
-async fn files_check(db: &InstalledDb, root_dir: &Path, only_part: Option<&str>) -> Result<usize> {
+async fn files_check(
+    db: &InstalledDb,
+    root_dir: &Path,
+    only_part: Option<&str>,
+) -> Result<Vec<MissingFile>> {
     crate::cli_action!("Checking", "deployed file existence");
 
     let parts: Vec<InstalledPart> = match only_part {
@@ -276,10 +400,7 @@ async fn files_check(db: &InstalledDb, root_dir: &Path, only_part: Option<&str>)
             .collect(),
     };
 
-    let mut report = FilesReport {
-        missing: Vec::new(),
-    };
-    let mut total_missing = 0usize;
+    let mut missing: Vec<MissingFile> = Vec::new();
 
     for part in &parts {
         if part.origin == Origin::External {
@@ -287,78 +408,61 @@ async fn files_check(db: &InstalledDb, root_dir: &Path, only_part: Option<&str>)
         }
 
         let files = db.get_files(part.id).await?;
-        let mut missing_paths: Vec<String> = Vec::new();
 
         for f in &files {
             let abs = root_dir.join(f.path.trim_start_matches('/'));
-            match f.file_type {
-                FileType::File => {
-                    if !abs.is_file() {
-                        let extra = if abs.exists() {
-                            " (wrong type)".to_string()
-                        } else {
-                            String::new()
-                        };
-                        missing_paths.push(format!("{}{}", abs.display(), extra));
-                    }
-                }
-                FileType::Symlink => {
-                    if !abs.is_symlink() {
-                        let extra = if abs.exists() {
-                            " (expected symlink)".to_string()
-                        } else {
-                            String::new()
-                        };
-                        missing_paths.push(format!("{}{}", abs.display(), extra));
-                    }
-                }
-                FileType::Directory => {
-                    if !abs.is_dir() {
-                        let extra = if abs.exists() {
-                            " (expected directory)".to_string()
-                        } else {
-                            String::new()
-                        };
-                        missing_paths.push(format!("{}{}", abs.display(), extra));
-                    }
-                }
+            let (present, wrong_type) = match f.file_type {
+                FileType::File => (abs.is_file(), "wrong type"),
+                FileType::Symlink => (abs.is_symlink(), "expected symlink"),
+                FileType::Directory => (abs.is_dir(), "expected directory"),
+            };
+            if !present {
+                missing.push(MissingFile {
+                    part: part.name.clone(),
+                    path: abs.display().to_string(),
+                    problem: if abs.exists() { wrong_type } else { "missing" },
+                });
             }
         }
-
-        if !missing_paths.is_empty() {
-            total_missing += missing_paths.len();
-            report.missing.push(PartMissing {
-                part_name: part.name.clone(),
-                paths: missing_paths,
-            });
-        }
     }
 
-    if total_missing == 0 {
-        return Ok(0);
+    if missing.is_empty() {
+        return Ok(missing);
     }
 
-    let part_count = report.missing.len();
+    // `missing` is pushed part by part, so each chunk is one part's paths.
+    let by_part: Vec<&[MissingFile]> = missing.chunk_by(|a, b| a.part == b.part).collect();
     crate::cli_warn!(
-        "{} missing file(s) across {} part(s) — run `wright install --force <part>` to repair",
-        total_missing,
-        part_count
+        "{} missing file(s) across {} part(s) — run `wright repair <part>` to restore them",
+        missing.len(),
+        by_part.len()
     );
     let mut lines: Vec<String> = Vec::new();
-    for pm in &report.missing {
-        let count = pm.paths.len();
+    for chunk in &by_part {
+        let paths: Vec<String> = chunk
+            .iter()
+            .map(|m| {
+                if m.problem == "missing" {
+                    m.path.clone()
+                } else {
+                    format!("{} ({})", m.path, m.problem)
+                }
+            })
+            .collect();
+        let part_name = &chunk[0].part;
+        let count = paths.len();
         if count <= 5 {
-            for path in &pm.paths {
-                lines.push(format!("{}: {}", pm.part_name, path));
+            for path in &paths {
+                lines.push(format!("{}: {}", part_name, path));
             }
         } else {
-            for path in pm.paths.iter().take(3) {
-                lines.push(format!("{}: {}", pm.part_name, path));
+            for path in paths.iter().take(3) {
+                lines.push(format!("{}: {}", part_name, path));
             }
-            lines.push(format!("{}: ... and {} more", pm.part_name, count - 3));
+            lines.push(format!("{}: ... and {} more", part_name, count - 3));
         }
     }
     emit_bullets(&lines);
 
-    Ok(total_missing)
+    Ok(missing)
 }
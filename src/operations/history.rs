@@ -1,8 +1,64 @@
-use crate::database::InstalledDb;
+use serde::Serialize;
+
+use crate::database::{HistoryRecord, InstalledDb};
 use crate::error::Result;
+use crate::util::output::{self, OutputFormat};
+
+#[derive(Serialize)]
+struct HistoryBody<'a> {
+    records: &'a [HistoryRecord],
+}
 
-pub async fn execute_history(db: &InstalledDb, part: Option<&str>) -> Result<()> {
+pub async fn execute_history(
+    db: &InstalledDb,
+    part: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
     let records = db.get_history(part).await?;
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "history",
+                &HistoryBody { records: &records },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &[
+                    "timestamp",
+                    "session_id",
+                    "command",
+                    "part",
+                    "action",
+                    "old_version",
+                    "new_version",
+                    "old_hash",
+                    "new_hash",
+                    "status",
+                    "details",
+                ],
+                records.iter().map(|r| {
+                    [
+                        output::opt(r.timestamp.as_deref()),
+                        r.session_id.clone(),
+                        r.command.clone(),
+                        r.part_name.clone(),
+                        r.action.to_string(),
+                        output::opt(r.old_version.as_deref()),
+                        output::opt(r.new_version.as_deref()),
+                        output::opt(r.old_hash.as_deref()),
+                        output::opt(r.new_hash.as_deref()),
+                        r.status.to_string(),
+                        output::opt(r.details.as_deref()),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
     if records.is_empty() {
         println!("no history records found");
     } else {
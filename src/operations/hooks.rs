@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::database::{HookFailure, InstalledDb};
+use crate::error::{Result, WrightError};
+use crate::util::output::{self, OutputFormat};
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    hooks: &'a [HookFailure],
+}
+
+/// Implementation of `wright hook list`: the recorded hook failures of
+/// `parts`, or of every part.
+pub async fn execute_list_hooks(
+    db: &InstalledDb,
+    parts: &[String],
+    format: OutputFormat,
+) -> Result<()> {
+    let failures = select_failures(db, parts).await?;
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "hook list",
+                &ListBody { hooks: &failures },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &["part", "hook", "status", "failed_at", "error"],
+                failures.iter().map(|f| {
+                    [
+                        f.part_name.clone(),
+                        f.hook.clone(),
+                        f.status.clone(),
+                        f.failed_at.clone().unwrap_or_default(),
+                        f.error.clone(),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    if failures.is_empty() {
+        println!("no failed or deferred hooks");
+        return Ok(());
+    }
+    for f in &failures {
+        println!("{:<8} {} {}: {}", f.status, f.part_name, f.hook, f.error);
+    }
+    Ok(())
+}
+
+/// Implementation of `wright hook run`: run the recorded hooks of `parts`
+/// (or of every part) again, only `hook` when given.
+pub async fn execute_run_hooks(
+    db: &InstalledDb,
+    root_dir: &Path,
+    parts: &[String],
+    hook: Option<&str>,
+) -> Result<()> {
+    let selected: Vec<HookFailure> = select_failures(db, parts)
+        .await?
+        .into_iter()
+        .filter(|f| hook.is_none_or(|h| f.hook == h))
+        .collect();
+    if selected.is_empty() {
+        crate::cli_warn!("no failed or deferred hooks to run");
+        return Ok(());
+    }
+
+    let mut still_failing = Vec::new();
+    for failure in &selected {
+        match crate::transaction::rerun_hook(db, failure, root_dir).await {
+            Ok(()) => crate::cli_action!("Ran", "{} {}", failure.part_name, failure.hook),
+            Err(e) => {
+                crate::cli_warn!("{} {}: {}", failure.part_name, failure.hook, e);
+                still_failing.push(format!("{} {}", failure.part_name, failure.hook));
+            }
+        }
+    }
+
+    if !still_failing.is_empty() {
+        return Err(WrightError::ScriptError(format!(
+            "{} hook(s) still failing: {}",
+            still_failing.len(),
+            still_failing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Recorded failures of `parts`, or of every part when `parts` is empty.
+async fn select_failures(db: &InstalledDb, parts: &[String]) -> Result<Vec<HookFailure>> {
+    if parts.is_empty() {
+        return db.list_hook_failures(None).await;
+    }
+    let mut selected = Vec::new();
+    for part in parts {
+        selected.extend(db.list_hook_failures(Some(part)).await?);
+    }
+    Ok(selected)
+}
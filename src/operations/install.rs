@@ -3,14 +3,13 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::error::{Result, WrightError};
-use sha2::{Digest, Sha256};
 use tokio::sync::Semaphore;
 use tracing::{debug, info, trace, warn};
 
 use crate::config::GlobalConfig;
 use crate::database::{InstalledDb, SessionContext};
 use crate::delivery::store::CasStore;
-use crate::foundry::{BuildOptions, Foundry};
+use crate::foundry::{BuildInputs, BuildOptions, Foundry};
 use crate::part::folio;
 use crate::part::store::LocalPartStore;
 use crate::plan::manifest::{OutputConfig, PlanManifest};
@@ -37,73 +36,53 @@ pub struct InstallRequest<'a> {
     pub run_hooks: bool,
 }
 
-/// Pre-computed fingerprint for each plan name in the build set.
+/// Build inputs for each task in the build set, keyed by task name (which
+/// for a full build is also the plan's base name).
 ///
-/// The fingerprint captures the plan's own build key and the fingerprints of
-/// its direct build dependencies, forming a content-addressed identity that
-/// covers the full transitive build closure.
+/// The fingerprint of a task is its closure-aware build key: the plan's own
+/// inputs plus the deployed part hashes of its build and link dependencies,
+/// so a rebuilt or upgraded dependency yields a new fingerprint.
+#[derive(Default)]
 struct PlanFingerprints {
-    /// plan_name -> closure fingerprint
-    fingerprints: HashMap<String, String>,
+    /// task name -> build inputs
+    inputs: HashMap<String, BuildInputs>,
 }
 
 impl PlanFingerprints {
-    /// Compute fingerprints for all plans in the execution plan.
-    fn compute(plan: &BuildExecutionPlan, foundry: &Foundry) -> Result<Self> {
-        let mut fingerprints: HashMap<String, String> = HashMap::new();
-
-        // Process batch by batch so that dependency fingerprints are available
-        // when computing closure fingerprints for later batches.
-        for batch in plan.batches() {
-            for task in batch {
-                let base = BuildExecutionPlan::task_base_name(task);
-                let plan_path = plan
-                    .plan_path_for_task(task)
-                    .ok_or_else(|| WrightError::ForgeError(format!("no path for task {}", task)))?;
-                let manifest = PlanManifest::from_file(plan_path)
-                    .map_err(|e| WrightError::ForgeError(format!("read plan {}: {}", base, e)))?;
-
-                let build_key = foundry.compute_build_key(&manifest)?;
-
-                // Collect fingerprints of build dependencies.
-                let dep_names = plan.deps_for_task(task);
-                let mut dep_fps: HashMap<String, String> = HashMap::new();
-                for dep_name in dep_names {
-                    let dep_base = BuildExecutionPlan::task_base_name(dep_name);
-                    if let Some(fp) = fingerprints.get(dep_base) {
-                        dep_fps.insert(dep_base.to_string(), fp.clone());
-                    }
-                }
-
-                let closure_fp = CasStore::compute_closure_fingerprint(&build_key, &dep_fps);
-                trace!(event = "fingerprint.closure", plan_name = %base, closure_fp = %&closure_fp[..8], "Computed closure fingerprint");
+    /// Compute inputs for every task in `batch`. Runs as each batch comes
+    /// up, so dependencies deployed by earlier batches contribute the part
+    /// hashes they were just deployed with.
+    async fn compute_batch(
+        &mut self,
+        plan: &BuildExecutionPlan,
+        batch: &[String],
+        foundry: &Foundry,
+        db: &InstalledDb,
+        mvp: bool,
+    ) -> Result<()> {
+        for task in batch {
+            let base = BuildExecutionPlan::task_base_name(task);
+            let plan_path = plan
+                .plan_path_for_task(task)
+                .ok_or_else(|| WrightError::ForgeError(format!("no path for task {}", task)))?;
+            let manifest = PlanManifest::from_file(plan_path)
+                .map_err(|e| WrightError::ForgeError(format!("read plan {}: {}", base, e)))?;
 
-                // Insert for both the full task and its bootstrap variant.
-                // Bootstrap tasks get a different fingerprint to distinguish
-                // from full builds (different compilation results).
-                fingerprints.insert(base.to_string(), closure_fp.clone());
-                if task.ends_with(":bootstrap") {
-                    fingerprints.insert(task.clone(), closure_fp);
-                } else {
-                    // Also insert the :bootstrap variant if it exists.
-                    let bootstrap_task = format!("{}:bootstrap", base);
-                    if plan.build_set().contains(&bootstrap_task) {
-                        let mut bp = Sha256::new();
-                        bp.update(closure_fp.as_bytes());
-                        bp.update(b":bootstrap");
-                        let bootstrap_fp = format!("{:x}", bp.finalize());
-                        trace!(event = "fingerprint.bootstrap", plan_name = %base, bootstrap_fp = %&bootstrap_fp[..8], "Computed bootstrap fingerprint");
-                        fingerprints.insert(bootstrap_task, bootstrap_fp);
-                    }
-                }
-            }
+            let is_mvp = mvp || task.ends_with(":bootstrap");
+            let mut inputs = foundry.build_inputs(&manifest, is_mvp);
+            inputs.add_dependencies(db, &manifest, is_mvp).await?;
+            trace!(event = "fingerprint.closure", plan_name = %base, closure_fp = %&inputs.key()[..8], "Computed closure fingerprint");
+            self.inputs.insert(task.clone(), inputs);
         }
+        Ok(())
+    }
 
-        Ok(Self { fingerprints })
+    fn get(&self, name: &str) -> Option<String> {
+        self.inputs.get(name).map(BuildInputs::key)
     }
 
-    fn get(&self, name: &str) -> Option<&String> {
-        self.fingerprints.get(name)
+    fn inputs(&self, name: &str) -> Option<&BuildInputs> {
+        self.inputs.get(name)
     }
 }
 
@@ -303,10 +282,11 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
             chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
         ),
         command: command_str.clone(),
+        delivery_id: Some(tx_id),
     };
 
     // ── Compute plan fingerprints & CAS resolution ──────────────────
-    let plan_fps = PlanFingerprints::compute(&plan, &foundry)?;
+    let mut plan_fps = PlanFingerprints::default();
     let cas_store = CasStore::new(config.general.store_dir.clone());
 
     // Pre-compute every part name that will be deployed across all batches,
@@ -331,6 +311,9 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
 
     for (batch_idx, batch) in plan.batches().iter().enumerate() {
         bail_if_cancelled!();
+        plan_fps
+            .compute_batch(&plan, batch, &foundry, &db, build_opts.mvp)
+            .await?;
 
         if !quiet && total_batches > 1 {
             let bases: Vec<&str> = batch
@@ -370,7 +353,7 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                     .and_then(|p| PlanManifest::from_file(p).ok());
                 if let Some(ref manifest) = plan_path {
                     let fp_key = if let Some(fp) = plan_fps.get(task) {
-                        fp.clone()
+                        fp
                     } else {
                         continue;
                     };
@@ -406,6 +389,8 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
             let config = config.clone();
             let task = task.clone();
             let task_for_handle = task.clone();
+            let inputs = plan_fps.inputs(&task).cloned();
+            let db_path = db_path.to_path_buf();
 
             let handle = tokio::spawn(async move {
                 let plan_path = plan.plan_path_for_task(&task_for_handle).ok_or_else(|| {
@@ -446,7 +431,7 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                     .ok_or_else(|| WrightError::ForgeError("plan path has no parent".into()))?
                     .to_path_buf();
 
-                foundry
+                let result = foundry
                     .build(
                         &manifest,
                         &plan_dir,
@@ -464,11 +449,20 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                             nproc_per_isolation: config.build.nproc_per_isolation,
                             configure_lock: Some(configure_lock),
                             compile_lock: Some(compile_lock),
+                            inputs,
+                            build_root: None,
+                            debug_shell: None,
                         },
                     )
                     .await
-                    .map(|_| ())
-                    .map_err(|e| WrightError::ForgeError(format!("forge {}: {}", base, e)))
+                    .map_err(|e| e.in_forge(format!("forge {base}")))?;
+                crate::operations::build::record_usage(
+                    &db_path,
+                    &manifest,
+                    result.usage.as_deref(),
+                )
+                .await;
+                Ok::<(), WrightError>(())
             });
             build_handles.push((task.clone(), handle));
         }
@@ -484,10 +478,7 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                     if *cancel_rx.borrow() {
                         return Err(WrightError::ForgeError("cancelled by user".into()));
                     }
-                    return Err(WrightError::ForgeError(format!(
-                        "task '{}' failed: {}",
-                        task, e
-                    )));
+                    return Err(e.in_forge(format!("task '{task}' failed")));
                 }
                 Err(e) => {
                     let _ = crate::delivery::rollback_delivery(&db, tx_id).await;
@@ -555,7 +546,7 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                 let part_names = manifest_part_names(&manifest);
                 for pn in &part_names {
                     if let Ok(Some(resolved)) = part_store.resolve(pn).await
-                        && let Err(e) = cas_store.store(&resolved.path, pn, fp)
+                        && let Err(e) = cas_store.store(&resolved.path, pn, &fp)
                     {
                         warn!(event = "cas.store_failed", part_name = %pn, error = %e, "Failed to store part in CAS");
                     }
@@ -583,7 +574,7 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                     };
                     let part_names = manifest_part_names(&manifest);
                     for pn in &part_names {
-                        if let Some(cas_path) = cas_store.resolve(pn, fp) {
+                        if let Some(cas_path) = cas_store.resolve(pn, &fp) {
                             // Copy CAS archive to parts_dir so the deploy
                             // step can find it via the normal part_store.
                             let ver = manifest.metadata.version.as_deref().unwrap_or("");
@@ -742,6 +733,19 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
                 match result {
                     Ok(()) => {}
                     Err(e) => {
+                        // Earlier batches stay committed, so their touched
+                        // paths still need the triggers before the delivery's
+                        // records are dropped.
+                        if run_hooks
+                            && let Err(te) =
+                                crate::transaction::run_triggers(&db, tx_id, root_dir).await
+                        {
+                            warn!(
+                                event = "triggers.failed",
+                                error = %te,
+                                "Could not run triggers for committed batches"
+                            );
+                        }
                         crate::delivery::rollback_delivery(&db, tx_id).await?;
                         let _ = crate::delivery::cleanup_delivery(&db, tx_id).await;
                         return Err(WrightError::DeployError(format!("deploy batch: {}", e)));
@@ -752,8 +756,11 @@ pub async fn execute_install(request: InstallRequest<'_>) -> Result<()> {
     }
 
     // ── Mark delivery as COMPLETED ──────────────────────────────────
+    if run_hooks {
+        crate::transaction::run_triggers(&db, tx_id, root_dir).await?;
+        crate::transaction::run_deferred_hooks(&db, tx_id, root_dir).await?;
+    }
     crate::delivery::complete_delivery(&db, tx_id).await?;
-    let _ = crate::delivery::cleanup_delivery(&db, tx_id).await;
 
     // Rule C: terminal completion line for the entire install workflow.
     if !quiet {
@@ -1,5 +1,13 @@
-use crate::database::InstalledDb;
+use serde::Serialize;
+
+use crate::database::{InstalledDb, PartWithPlan};
 use crate::error::Result;
+use crate::util::output::{self, OutputFormat};
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    parts: &'a [PartWithPlan],
+}
 
 pub async fn execute_list(
     db: &InstalledDb,
@@ -7,6 +15,7 @@ pub async fn execute_list(
     roots: bool,
     provided: bool,
     orphans: bool,
+    format: OutputFormat,
 ) -> Result<()> {
     let parts = if provided {
         db.get_provided_parts().await?
@@ -18,6 +27,46 @@ pub async fn execute_list(
         db.list_parts().await?
     };
 
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "list",
+                &ListBody { parts: &parts },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &[
+                    "name",
+                    "origin",
+                    "plan",
+                    "version",
+                    "release",
+                    "epoch",
+                    "arch",
+                    "installed_at",
+                    "part_hash",
+                ],
+                parts.iter().map(|p| {
+                    [
+                        p.name.clone(),
+                        p.origin.to_string(),
+                        p.plan_name.clone(),
+                        p.version.clone(),
+                        p.release.to_string(),
+                        p.epoch.to_string(),
+                        p.arch.clone(),
+                        output::opt(p.installed_at.as_deref()),
+                        output::opt(p.part_hash.as_deref()),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
     if parts.is_empty() {
         if !provided && !roots && !orphans {
             println!("no parts installed");
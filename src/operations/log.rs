@@ -0,0 +1,447 @@
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::cli::log::LogArgs;
+use crate::config::GlobalConfig;
+use crate::error::{Result, WrightError};
+use crate::foundry::buildlog::{self, BuildLogs, BuildRecord};
+use crate::foundry::checkpoint::{Checkpoint, StageStatus};
+use crate::foundry::logging::format_duration;
+use crate::foundry::{build_root_name, forge_dir};
+use crate::plan::discovery::PlanIndex;
+use crate::plan::manifest::PlanManifest;
+use crate::resolve::plan_search_dirs;
+use crate::util::output::{self, OutputFormat};
+
+/// How often `--follow` polls the log for new output.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One retained build of a build root.
+#[derive(Serialize)]
+struct BuildEntry {
+    root: String,
+    plan: String,
+    version: String,
+    /// 0 for the latest build, counting up into `log-history/`.
+    build: usize,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    outcome: &'static str,
+    duration_secs: Option<f64>,
+    stages: Vec<StageEntry>,
+    logs_dir: PathBuf,
+}
+
+#[derive(Serialize)]
+struct StageEntry {
+    stage: String,
+    /// `ok`, `failed`, `pending`, `ran` (a hook, or no checkpoint record),
+    /// or `cached` (up to date, so not run by this build).
+    outcome: &'static str,
+    duration_secs: Option<f64>,
+    log: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    builds: &'a [BuildEntry],
+}
+
+/// Implementation of `wright log`.
+pub async fn execute_log(config: &GlobalConfig, args: LogArgs, format: OutputFormat) -> Result<()> {
+    let roots = match args.plan {
+        Some(ref plan) => vec![plan_root(config, plan)?],
+        None => all_roots(&forge_dir(config)?)?,
+    };
+
+    if let Some(ref pattern) = args.grep {
+        let re = Regex::new(pattern)
+            .map_err(|e| WrightError::ValidationError(format!("invalid pattern: {}", e)))?;
+        return grep(&roots, &re, args.stage.as_deref(), args.build);
+    }
+
+    if let Some(ref stage) = args.stage {
+        let root = &roots[0];
+        if args.follow {
+            return follow(root, stage).await;
+        }
+        let builds = buildlog::list(root);
+        let index = args.build.unwrap_or(0);
+        let build = builds
+            .get(index)
+            .ok_or_else(|| no_build(root, index, builds.len()))?;
+        let log = build.dir.join(format!("{}.log", stage));
+        if !log.exists() {
+            return Err(WrightError::ValidationError(format!(
+                "build #{} of {} has no log for stage '{}' (has: {})",
+                index,
+                root_label(root),
+                stage,
+                build
+                    .stage_logs()
+                    .into_iter()
+                    .map(|(s, _)| s)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        return print_log(&log, args.tail);
+    }
+
+    // A single plan lists every retained build; the whole workshop lists
+    // the latest build of each root.
+    let mut entries = Vec::new();
+    for root in &roots {
+        let builds = buildlog::list(root);
+        let shown = if args.plan.is_some() { builds.len() } else { 1 };
+        for (index, build) in builds.iter().enumerate().take(shown) {
+            if args.build.is_none_or(|b| b == index) {
+                entries.push(entry(root, index, build));
+            }
+        }
+    }
+    if args.plan.is_some() && entries.is_empty() {
+        return Err(no_build(&roots[0], args.build.unwrap_or(0), 0));
+    }
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "log",
+                &ListBody { builds: &entries },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &[
+                    "root",
+                    "plan",
+                    "version",
+                    "build",
+                    "started_at",
+                    "outcome",
+                    "duration_secs",
+                    "stages",
+                ],
+                entries.iter().map(|e| {
+                    [
+                        e.root.clone(),
+                        e.plan.clone(),
+                        e.version.clone(),
+                        e.build.to_string(),
+                        output::opt(e.started_at.clone()),
+                        e.outcome.to_string(),
+                        output::opt(e.duration_secs),
+                        e.stages
+                            .iter()
+                            .map(|s| format!("{}={}", s.stage, s.outcome))
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    let label = |e: &BuildEntry| {
+        if args.plan.is_some() {
+            format!("#{}", e.build)
+        } else {
+            e.root.clone()
+        }
+    };
+    let width = entries.iter().map(|e| label(e).len()).max().unwrap_or(0);
+    for e in &entries {
+        let started = e
+            .started_at
+            .as_deref()
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let stages: Vec<String> = e
+            .stages
+            .iter()
+            .map(|s| match s.duration_secs {
+                Some(secs) => format!("{} {} {}", s.stage, s.outcome, format_duration(secs)),
+                None => format!("{} {}", s.stage, s.outcome),
+            })
+            .collect();
+        println!(
+            "{:<width$}  {:<16}  {:<8} {:>8}  {}",
+            label(e),
+            started,
+            e.outcome,
+            e.duration_secs.map(format_duration).unwrap_or_default(),
+            stages.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The build root of `plan`, a plan name or a plan directory.
+fn plan_root(config: &GlobalConfig, plan: &str) -> Result<PathBuf> {
+    let path = Path::new(plan);
+    let manifest = if path.join("plan.toml").is_file() {
+        PlanManifest::from_file(&path.join("plan.toml"))?
+    } else {
+        PlanIndex::discover(&plan_search_dirs(config))?
+            .manifest_for(plan)?
+            .ok_or_else(|| WrightError::ValidationError(format!("no plan named '{}'", plan)))?
+    };
+    Ok(forge_dir(config)?.join(build_root_name(&manifest)))
+}
+
+fn all_roots(forge_dir: &Path) -> Result<Vec<PathBuf>> {
+    let listing = match std::fs::read_dir(forge_dir) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(WrightError::IoError(e)),
+    };
+    let mut roots: Vec<PathBuf> = listing
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    roots.sort();
+    Ok(roots)
+}
+
+fn entry(root: &Path, index: usize, build: &BuildLogs) -> BuildEntry {
+    let record = build.record.as_ref();
+    let logs = build.stage_logs();
+    let mut stages: Vec<StageEntry> = logs
+        .iter()
+        .map(|(stage, log)| StageEntry {
+            stage: stage.clone(),
+            outcome: match record.and_then(|r| r.stages.get(stage)).map(|s| s.status) {
+                Some(StageStatus::Completed) => "ok",
+                Some(StageStatus::Failed) => "failed",
+                Some(StageStatus::Pending) => "pending",
+                None => "ran",
+            },
+            duration_secs: buildlog::stage_duration(log),
+            log: Some(log.clone()),
+        })
+        .collect();
+    // Stages run in the order their logs were created.
+    stages.sort_by_key(|s| {
+        s.log.as_ref().and_then(|l| {
+            std::fs::metadata(l)
+                .and_then(|m| m.created().or(m.modified()))
+                .ok()
+        })
+    });
+    if let Some(record) = record {
+        for (stage, rec) in &record.stages {
+            if rec.status == StageStatus::Completed && !logs.iter().any(|(s, _)| s == stage) {
+                stages.push(StageEntry {
+                    stage: stage.clone(),
+                    outcome: "cached",
+                    duration_secs: None,
+                    log: None,
+                });
+            }
+        }
+    }
+
+    let (plan, version) = match record {
+        Some(r) => (r.plan_name.clone(), r.version.clone()),
+        None => Checkpoint::read_state(root)
+            .ok()
+            .flatten()
+            .map(|s| (s.plan_name, s.version))
+            .unwrap_or_default(),
+    };
+    BuildEntry {
+        root: root_label(root),
+        plan,
+        version,
+        build: index,
+        started_at: record.map(|r| r.started_at.to_rfc3339()),
+        finished_at: record.and_then(|r| r.finished_at.map(|t| t.to_rfc3339())),
+        outcome: record.map_or("unknown", BuildRecord::outcome),
+        duration_secs: record.and_then(BuildRecord::duration_secs),
+        stages,
+        logs_dir: build.dir.clone(),
+    }
+}
+
+/// Print the lines matching `re` in the stage logs of `roots`, limited to
+/// `stage` and to build `only` when given.
+fn grep(roots: &[PathBuf], re: &Regex, stage: Option<&str>, only: Option<usize>) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    let mut matches = 0usize;
+    for root in roots {
+        for (index, build) in buildlog::list(root).iter().enumerate() {
+            if only.is_some_and(|b| b != index) {
+                continue;
+            }
+            for (name, log) in build.stage_logs() {
+                if stage.is_some_and(|s| s != name) {
+                    continue;
+                }
+                let Ok(file) = std::fs::File::open(&log) else {
+                    continue;
+                };
+                for (lineno, line) in BufReader::new(file).split(b'\n').enumerate() {
+                    let line = line.map_err(WrightError::IoError)?;
+                    let line = String::from_utf8_lossy(&line);
+                    if re.is_match(&line) {
+                        matches += 1;
+                        writeln!(
+                            out,
+                            "{} #{} {}:{}: {}",
+                            root_label(root),
+                            index,
+                            name,
+                            lineno + 1,
+                            line
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+    if matches == 0 {
+        crate::cli_action!("Finished", "no matches for '{}'", re.as_str());
+    }
+    Ok(())
+}
+
+fn print_log(log: &Path, tail: Option<usize>) -> Result<()> {
+    let raw = std::fs::read(log).map_err(WrightError::IoError)?;
+    let text = String::from_utf8_lossy(&raw);
+    let mut out = std::io::stdout().lock();
+    match tail {
+        Some(n) => {
+            let lines: Vec<&str> = text.lines().collect();
+            for line in &lines[lines.len().saturating_sub(n)..] {
+                writeln!(out, "{}", line)?;
+            }
+        }
+        None => out.write_all(text.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Print the latest build's `stage` log, then whatever is appended to it,
+/// until that build has finished and the log has stopped growing.
+async fn follow(root: &Path, stage: &str) -> Result<()> {
+    let logs_dir = root.join("logs");
+    let log = logs_dir.join(format!("{}.log", stage));
+    let finished = || {
+        buildlog::list(root)
+            .first()
+            .and_then(|b| b.record.as_ref())
+            .is_none_or(|r| r.finished_at.is_some())
+    };
+
+    let mut offset = 0u64;
+    let mut out = std::io::stdout();
+    loop {
+        let done = finished();
+        if let Ok(mut file) = std::fs::File::open(&log) {
+            let len = file.metadata().map_err(WrightError::IoError)?.len();
+            if len < offset {
+                // The build was restarted and the log rewritten.
+                offset = 0;
+            }
+            file.seek(SeekFrom::Start(offset))
+                .map_err(WrightError::IoError)?;
+            let mut chunk = Vec::new();
+            file.read_to_end(&mut chunk).map_err(WrightError::IoError)?;
+            offset += chunk.len() as u64;
+            out.write_all(&chunk)?;
+            out.flush()?;
+        }
+        // `done` was read before the log, so nothing written before the
+        // build finished is missed.
+        if done {
+            if offset == 0 && !log.exists() {
+                return Err(WrightError::ValidationError(format!(
+                    "the latest build of {} has no log for stage '{}'",
+                    root_label(root),
+                    stage
+                )));
+            }
+            return Ok(());
+        }
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+    }
+}
+
+fn no_build(root: &Path, index: usize, count: usize) -> WrightError {
+    WrightError::ValidationError(format!(
+        "no build #{} of {} ({} build(s) retained)",
+        index,
+        root_label(root),
+        count
+    ))
+}
+
+fn root_label(root: &Path) -> String {
+    root.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| root.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_reports_stage_outcomes() {
+        let root = tempfile::tempdir().unwrap();
+        let logs = root.path().join("logs");
+        std::fs::create_dir_all(&logs).unwrap();
+        std::fs::write(
+            logs.join(buildlog::BUILD_RECORD),
+            r#"{
+  "plan_name": "zlib",
+  "version": "1.3",
+  "started_at": "2026-01-01T00:00:00Z",
+  "finished_at": "2026-01-01T00:01:30Z",
+  "succeeded": false,
+  "stages": {
+    "prepare": { "status": "COMPLETED", "input_hash": "a" },
+    "compile": { "status": "FAILED", "input_hash": "b" }
+  }
+}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            logs.join("compile.log"),
+            "=== Exit code: 2 ===\n=== Duration: 4.0s ===\n",
+        )
+        .unwrap();
+
+        let builds = buildlog::list(root.path());
+        let e = entry(root.path(), 0, &builds[0]);
+        assert_eq!(e.plan, "zlib");
+        assert_eq!(e.outcome, "failed");
+        assert_eq!(e.duration_secs, Some(90.0));
+        let stages: Vec<(&str, &str, Option<f64>)> = e
+            .stages
+            .iter()
+            .map(|s| (s.stage.as_str(), s.outcome, s.duration_secs))
+            .collect();
+        assert_eq!(
+            stages,
+            vec![
+                ("compile", "failed", Some(4.0)),
+                ("prepare", "cached", None)
+            ]
+        );
+    }
+}
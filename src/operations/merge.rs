@@ -71,6 +71,7 @@ pub async fn execute_merge(
             chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
         ),
         command: command_str,
+        delivery_id: Some(tx_id),
     };
 
     if path {
@@ -189,8 +190,9 @@ pub async fn execute_merge(
         }
     }
 
+    crate::transaction::run_triggers(&db, tx_id, root_dir).await?;
+    crate::transaction::run_deferred_hooks(&db, tx_id, root_dir).await?;
     crate::delivery::complete_delivery(&db, tx_id).await?;
-    let _ = crate::delivery::cleanup_delivery(&db, tx_id).await;
 
     Ok(())
 }
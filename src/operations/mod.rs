@@ -1,16 +1,29 @@
+pub mod alternatives;
 pub mod build;
 pub mod check;
+pub mod configs;
 pub mod doctor;
 pub mod drive;
 pub mod files;
+pub mod generation;
 pub mod health;
 pub mod history;
+pub mod hooks;
 pub mod install;
 pub mod launch;
 pub mod lint;
 pub mod list;
+pub mod log;
 pub mod merge;
 pub mod owner;
 pub mod provide;
+pub mod prune;
 pub mod remove;
+pub mod repair;
+pub mod rollback;
+pub mod sources;
+pub mod stats;
+pub mod tree;
 pub mod upgrade;
+pub mod verify;
+pub mod workshop;
@@ -1,11 +1,31 @@
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+
 use crate::database::InstalledDb;
 use crate::error::{Result, WrightError};
+use crate::util::output::{self, OutputFormat};
+
+#[derive(Serialize)]
+struct OwnerRecord {
+    path: String,
+    /// Empty when no deployed part owns the path.
+    parts: Vec<String>,
+}
 
-pub async fn execute_owner(db: &InstalledDb, paths: &[PathBuf]) -> Result<()> {
+#[derive(Serialize)]
+struct OwnerBody<'a> {
+    owners: &'a [OwnerRecord],
+}
+
+pub async fn execute_owner(
+    db: &InstalledDb,
+    paths: &[PathBuf],
+    format: OutputFormat,
+) -> Result<()> {
     let multi = paths.len() > 1;
     let mut any_missing = false;
+    let mut records: Vec<OwnerRecord> = Vec::new();
 
     for input in paths {
         let resolved = normalize_path(input);
@@ -18,18 +38,40 @@ pub async fn execute_owner(db: &InstalledDb, paths: &[PathBuf]) -> Result<()> {
         if owners.is_empty() {
             any_missing = true;
             tracing::error!("'{}' is not owned by any deployed part", lookup);
-            continue;
         }
 
-        for owner in &owners {
-            if multi {
-                println!("{}: {}", lookup, owner);
-            } else {
-                println!("{}", owner);
+        if format.is_human() {
+            for owner in &owners {
+                if multi {
+                    println!("{}: {}", lookup, owner);
+                } else {
+                    println!("{}", owner);
+                }
             }
+        } else {
+            records.push(OwnerRecord {
+                path: lookup.into_owned(),
+                parts: owners,
+            });
         }
     }
 
+    match format {
+        OutputFormat::Json => output::write_json(
+            &mut std::io::stdout().lock(),
+            "owner",
+            &OwnerBody { owners: &records },
+        )?,
+        OutputFormat::Tsv => output::write_tsv(
+            &mut std::io::stdout().lock(),
+            &["path", "part"],
+            records
+                .iter()
+                .flat_map(|r| r.parts.iter().map(move |p| [r.path.clone(), p.clone()])),
+        )?,
+        OutputFormat::Human => {}
+    }
+
     if any_missing {
         std::process::exit(1);
     }
@@ -0,0 +1,559 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::GlobalConfig;
+use crate::database::InstalledDb;
+use crate::error::{Result, WrightError};
+use crate::part::archive;
+use crate::part::version::Version;
+use crate::util::units::format_bytes;
+
+/// Retention policy for one `wright prune` run.
+pub struct PrunePolicy {
+    /// Newest plan revisions kept per plan; `0` disables the rule.
+    pub keep_latest: usize,
+    /// How many recent delivery transactions protect the archives they touched.
+    pub keep_deliveries: i64,
+    /// How many recent delivery transactions keep their records; `0` keeps
+    /// all.
+    pub keep_delivery_records: i64,
+    /// Only archives older than this are pruned.
+    pub max_age: Option<Duration>,
+    /// Evict the oldest prunable archives until the inventory fits.
+    pub max_size: Option<u64>,
+}
+
+/// One archive file in `parts_dir` or the CAS store.
+struct Archive {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    /// `(dev, ino)`: CAS entries are usually hard links of `parts_dir`
+    /// archives, so bytes are only reclaimed once every link goes.
+    inode: (u64, u64),
+    hash: String,
+    plan: String,
+    revision: Revision,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Revision {
+    epoch: u32,
+    version: String,
+    release: u32,
+}
+
+impl Revision {
+    fn cmp_newer(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| {
+                match (
+                    Version::parse(&self.version),
+                    Version::parse(&other.version),
+                ) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => self.version.cmp(&other.version),
+                }
+            })
+            .then_with(|| self.release.cmp(&other.release))
+    }
+}
+
+/// Why an archive stays or goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Installed,
+    Generation,
+    Delivery,
+    Latest,
+    /// Unprotected but inside the age and size limits.
+    WithinLimits,
+    Superseded,
+    TooOld,
+    OverSize,
+}
+
+impl Verdict {
+    fn prunes(self) -> bool {
+        matches!(self, Self::Superseded | Self::TooOld | Self::OverSize)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Installed => "installed",
+            Self::Generation => "in a generation",
+            Self::Delivery => "in a recent delivery",
+            Self::Latest => "latest revision",
+            Self::WithinLimits => "within limits",
+            Self::Superseded => "superseded",
+            Self::TooOld => "older than --max-age",
+            Self::OverSize => "over --max-size",
+        }
+    }
+}
+
+/// Archive hashes that must survive, each with the first reason found.
+struct Protected {
+    hashes: HashMap<String, Verdict>,
+}
+
+impl Protected {
+    async fn load(db: &InstalledDb, keep_deliveries: i64) -> Result<Self> {
+        let mut hashes = HashMap::new();
+        for hash in db.installed_part_hashes().await? {
+            hashes.entry(hash).or_insert(Verdict::Installed);
+        }
+        for hash in db.generation_part_hashes().await? {
+            hashes.entry(hash).or_insert(Verdict::Generation);
+        }
+        if keep_deliveries > 0 {
+            for hash in db.recent_delivery_hashes(keep_deliveries).await? {
+                hashes.entry(hash).or_insert(Verdict::Delivery);
+            }
+        }
+        Ok(Self { hashes })
+    }
+}
+
+/// Implementation of `wright prune`.
+///
+/// Walks `parts_dir` and the CAS store, keeps every archive the installed
+/// registry, a generation, or a recent delivery references plus the newest
+/// `keep_latest` revisions of each plan, and reports (or, with `apply`,
+/// deletes) the rest subject to the age and size limits.  Records of
+/// finished deliveries beyond `keep_delivery_records` go the same way.
+pub async fn execute_prune(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    policy: &PrunePolicy,
+    apply: bool,
+) -> Result<()> {
+    let archives = scan(&[
+        config.general.parts_dir.as_path(),
+        config.general.store_dir.as_path(),
+    ])?;
+    let protected = Protected::load(db, policy.keep_deliveries).await?;
+    let verdicts = judge(&archives, &protected, policy, SystemTime::now());
+
+    let doomed: Vec<(&Archive, Verdict)> = archives
+        .iter()
+        .zip(&verdicts)
+        .filter(|(_, v)| v.prunes())
+        .map(|(a, v)| (a, *v))
+        .collect();
+    let total = unique_bytes(archives.iter());
+    let reclaimable = reclaimable_bytes(&archives, &verdicts);
+
+    let mut kept: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for verdict in verdicts.iter().filter(|v| !v.prunes()) {
+        *kept.entry(verdict.label()).or_default() += 1;
+    }
+    let old_deliveries = if policy.keep_delivery_records > 0 {
+        db.deliveries_beyond(policy.keep_delivery_records).await?
+    } else {
+        Vec::new()
+    };
+
+    let kept_summary = kept
+        .iter()
+        .map(|(label, n)| format!("{} {}", n, label))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if let Some(limit) = policy.max_size
+        && total - reclaimable > limit
+    {
+        crate::cli_warn!(
+            "inventory stays at {} after pruning, above --max-size {}: the rest is protected",
+            format_bytes(total - reclaimable),
+            format_bytes(limit)
+        );
+    }
+
+    if !apply {
+        for (archive, verdict) in &doomed {
+            println!(
+                "[dry-run] would remove {} ({}, {})",
+                archive.path.display(),
+                format_bytes(archive.size),
+                verdict.label()
+            );
+        }
+        println!(
+            "[dry-run] {} of {} archive(s) prunable, {} reclaimable of {}; kept: {}",
+            doomed.len(),
+            archives.len(),
+            format_bytes(reclaimable),
+            format_bytes(total),
+            if kept_summary.is_empty() {
+                "none"
+            } else {
+                &kept_summary
+            }
+        );
+        if !old_deliveries.is_empty() {
+            println!(
+                "[dry-run] would drop the records of {} delivery transaction(s)",
+                old_deliveries.len()
+            );
+        }
+        if !doomed.is_empty() || !old_deliveries.is_empty() {
+            println!("[dry-run] pass --apply to delete");
+        }
+        return Ok(());
+    }
+
+    let mut removed: HashSet<&Path> = HashSet::new();
+    for (archive, verdict) in &doomed {
+        match std::fs::remove_file(&archive.path) {
+            Ok(()) => {
+                tracing::debug!(
+                    event = "prune.removed",
+                    path = %archive.path.display(),
+                    reason = verdict.label(),
+                    "Pruned archive"
+                );
+                removed.insert(&archive.path);
+            }
+            Err(e) => crate::cli_warn!("failed to remove {}: {}", archive.path.display(), e),
+        }
+    }
+    // A failed removal keeps its inode, and so any other link's bytes.
+    let gone: Vec<bool> = archives
+        .iter()
+        .map(|a| removed.contains(a.path.as_path()))
+        .collect();
+    crate::cli_action!(
+        "Pruned",
+        "{} archive(s), {} reclaimed",
+        removed.len(),
+        format_bytes(freed_bytes(&archives, &gone))
+    );
+    for tx_id in &old_deliveries {
+        db.cleanup_delivery(*tx_id).await?;
+    }
+    if !old_deliveries.is_empty() {
+        crate::cli_action!(
+            "Pruned",
+            "records of {} delivery transaction(s)",
+            old_deliveries.len()
+        );
+    }
+    Ok(())
+}
+
+/// Read every archive in `dirs`. Unreadable archives are skipped with a
+/// warning and so never pruned.
+fn scan(dirs: &[&Path]) -> Result<Vec<Archive>> {
+    let mut archives = Vec::new();
+    let mut hashes: HashMap<(u64, u64), String> = HashMap::new();
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+
+    for dir in dirs {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(WrightError::PartError(format!(
+                    "read {}: {}",
+                    dir.display(),
+                    e
+                )));
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_archive = path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .is_some_and(|n| n.ends_with(".wright.tar.zst") || n.ends_with(".part"));
+            // store_dir may sit inside parts_dir; count each file once.
+            if !is_archive || !seen.insert(path.clone()) {
+                continue;
+            }
+            let Ok(meta) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            let partinfo = match archive::read_partinfo(&path) {
+                Ok(p) => p,
+                Err(e) => {
+                    crate::cli_warn!("skipping unreadable archive {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            let inode = (meta.dev(), meta.ino());
+            let hash = match hashes.get(&inode) {
+                Some(hash) => hash.clone(),
+                None => {
+                    let hash = crate::util::checksum::sha256_file(&path)?;
+                    hashes.insert(inode, hash.clone());
+                    hash
+                }
+            };
+            archives.push(Archive {
+                path,
+                size: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                inode,
+                hash,
+                plan: partinfo.plan.name,
+                revision: Revision {
+                    epoch: partinfo.plan.epoch,
+                    version: partinfo.plan.version,
+                    release: partinfo.plan.release,
+                },
+            });
+        }
+    }
+    Ok(archives)
+}
+
+/// Decide each archive's fate. The result is parallel to `archives`.
+fn judge(
+    archives: &[Archive],
+    protected: &Protected,
+    policy: &PrunePolicy,
+    now: SystemTime,
+) -> Vec<Verdict> {
+    let latest = latest_revisions(archives, policy.keep_latest);
+    let mut verdicts: Vec<Verdict> = archives
+        .iter()
+        .map(|a| {
+            if let Some(reason) = protected.hashes.get(&a.hash) {
+                *reason
+            } else if latest.contains(&(a.plan.as_str(), &a.revision)) {
+                Verdict::Latest
+            } else if let Some(max_age) = policy.max_age {
+                let age = now.duration_since(a.modified).unwrap_or_default();
+                if age > max_age {
+                    Verdict::TooOld
+                } else {
+                    Verdict::WithinLimits
+                }
+            } else if policy.max_size.is_some() {
+                Verdict::WithinLimits
+            } else {
+                Verdict::Superseded
+            }
+        })
+        .collect();
+
+    if let Some(limit) = policy.max_size {
+        let mut remaining = unique_bytes(
+            archives
+                .iter()
+                .zip(&verdicts)
+                .filter(|(_, v)| !v.prunes())
+                .map(|(a, _)| a),
+        );
+        let mut candidates: Vec<usize> = (0..archives.len())
+            .filter(|&i| verdicts[i] == Verdict::WithinLimits)
+            .collect();
+        candidates.sort_by_key(|&i| archives[i].modified);
+        for i in candidates {
+            if remaining <= limit {
+                break;
+            }
+            verdicts[i] = Verdict::OverSize;
+            let inode = archives[i].inode;
+            let still_linked = archives
+                .iter()
+                .zip(&verdicts)
+                .any(|(a, v)| a.inode == inode && !v.prunes());
+            if !still_linked {
+                remaining -= archives[i].size;
+            }
+        }
+    }
+    verdicts
+}
+
+/// The newest `keep` revisions of every plan.
+fn latest_revisions(archives: &[Archive], keep: usize) -> HashSet<(&str, &Revision)> {
+    let mut by_plan: HashMap<&str, Vec<&Revision>> = HashMap::new();
+    for a in archives {
+        let revisions = by_plan.entry(a.plan.as_str()).or_default();
+        if !revisions.contains(&&a.revision) {
+            revisions.push(&a.revision);
+        }
+    }
+    let mut latest = HashSet::new();
+    for (plan, mut revisions) in by_plan {
+        revisions.sort_by(|a, b| b.cmp_newer(a));
+        for revision in revisions.into_iter().take(keep) {
+            latest.insert((plan, revision));
+        }
+    }
+    latest
+}
+
+/// Bytes on disk used by `archives`, counting hard-linked files once.
+fn unique_bytes<'a>(archives: impl Iterator<Item = &'a Archive>) -> u64 {
+    let mut seen = HashSet::new();
+    archives
+        .filter(|a| seen.insert(a.inode))
+        .map(|a| a.size)
+        .sum()
+}
+
+/// Bytes freed once every archive judged prunable is removed.
+fn reclaimable_bytes(archives: &[Archive], verdicts: &[Verdict]) -> u64 {
+    let doomed: Vec<bool> = verdicts.iter().map(|v| v.prunes()).collect();
+    freed_bytes(archives, &doomed)
+}
+
+/// Bytes freed by removing the archives flagged in `removed`: an inode
+/// only counts when none of its links remains.
+fn freed_bytes(archives: &[Archive], removed: &[bool]) -> u64 {
+    let kept: HashSet<(u64, u64)> = archives
+        .iter()
+        .zip(removed)
+        .filter(|(_, gone)| !**gone)
+        .map(|(a, _)| a.inode)
+        .collect();
+    unique_bytes(
+        archives
+            .iter()
+            .zip(removed)
+            .filter(|(a, gone)| **gone && !kept.contains(&a.inode))
+            .map(|(a, _)| a),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn archive(plan: &str, version: &str, ino: u64, size: u64, days_old: u64) -> Archive {
+        Archive {
+            path: PathBuf::from(format!("/parts/{}-{}-{}", plan, version, ino)),
+            size,
+            modified: SystemTime::UNIX_EPOCH + DAY * (100 - days_old) as u32,
+            inode: (1, ino),
+            hash: format!("hash-{}", ino),
+            plan: plan.into(),
+            revision: Revision {
+                epoch: 0,
+                version: version.into(),
+                release: 1,
+            },
+        }
+    }
+
+    fn policy(keep_latest: usize) -> PrunePolicy {
+        PrunePolicy {
+            keep_latest,
+            keep_deliveries: 0,
+            keep_delivery_records: 0,
+            max_age: None,
+            max_size: None,
+        }
+    }
+
+    fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + DAY * 100
+    }
+
+    #[test]
+    fn test_keeps_latest_and_installed_revisions() {
+        let archives = vec![
+            archive("zlib", "1.2.13", 1, 10, 30),
+            archive("zlib", "1.3.1", 2, 10, 10),
+            archive("zlib", "1.3", 3, 10, 20),
+            archive("zlib", "1.3.1", 4, 10, 10),
+        ];
+        let protected = Protected {
+            hashes: HashMap::from([("hash-1".to_string(), Verdict::Installed)]),
+        };
+        let verdicts = judge(&archives, &protected, &policy(1), now());
+        assert_eq!(
+            verdicts,
+            vec![
+                Verdict::Installed,
+                Verdict::Latest,
+                Verdict::Superseded,
+                Verdict::Latest
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_age_spares_recent_archives() {
+        let archives = vec![
+            archive("zlib", "1.0", 1, 10, 40),
+            archive("zlib", "2.0", 2, 10, 5),
+            archive("zlib", "3.0", 3, 10, 1),
+        ];
+        let mut p = policy(1);
+        p.max_age = Some(DAY * 30);
+        let verdicts = judge(
+            &archives,
+            &Protected {
+                hashes: HashMap::new(),
+            },
+            &p,
+            now(),
+        );
+        assert_eq!(
+            verdicts,
+            vec![Verdict::TooOld, Verdict::WithinLimits, Verdict::Latest]
+        );
+    }
+
+    #[test]
+    fn test_max_size_evicts_oldest_and_counts_hard_links_once() {
+        let mut linked = archive("zlib", "1.0", 1, 100, 50);
+        linked.path = PathBuf::from("/store/zlib-1.0");
+        let archives = vec![
+            archive("zlib", "1.0", 1, 100, 50),
+            linked,
+            archive("zlib", "2.0", 2, 100, 20),
+            archive("zlib", "3.0", 3, 100, 1),
+        ];
+        let mut p = policy(1);
+        p.max_size = Some(150);
+        let verdicts = judge(
+            &archives,
+            &Protected {
+                hashes: HashMap::new(),
+            },
+            &p,
+            now(),
+        );
+        assert_eq!(
+            verdicts,
+            vec![
+                Verdict::OverSize,
+                Verdict::OverSize,
+                Verdict::OverSize,
+                Verdict::Latest
+            ]
+        );
+        assert_eq!(reclaimable_bytes(&archives, &verdicts), 200);
+        // A link that failed to go keeps its inode's bytes.
+        assert_eq!(freed_bytes(&archives, &[true, false, true, false]), 100);
+
+        p.max_size = Some(250);
+        let verdicts = judge(
+            &archives,
+            &Protected {
+                hashes: HashMap::new(),
+            },
+            &p,
+            now(),
+        );
+        assert_eq!(
+            &verdicts[..3],
+            &[Verdict::OverSize, Verdict::OverSize, Verdict::WithinLimits]
+        );
+    }
+}
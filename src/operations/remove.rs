@@ -22,6 +22,7 @@ pub async fn execute_remove(
             chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
         ),
         command: command_str,
+        delivery_id: Some(tx_id),
     };
 
     let batch_targets: HashSet<String> = if recursive {
@@ -133,8 +134,9 @@ pub async fn execute_remove(
         }
     }
 
+    transaction::run_triggers(db, tx_id, root_dir).await?;
+    transaction::run_deferred_hooks(db, tx_id, root_dir).await?;
     crate::delivery::complete_delivery(db, tx_id).await?;
-    let _ = crate::delivery::cleanup_delivery(db, tx_id).await;
 
     let elapsed = workflow_t0.elapsed().as_secs_f64();
     crate::cli_action!(
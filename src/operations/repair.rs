@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::config::GlobalConfig;
+use crate::database::{InstalledDb, InstalledPart, SessionContext};
+use crate::delivery::store::CasStore;
+use crate::error::{Result, WrightError};
+use crate::transaction;
+
+use super::verify::{is_damage, select_parts};
+
+/// A part and the recorded paths of it to rewrite.
+pub(super) struct RepairTarget<'a> {
+    pub part: &'a InstalledPart,
+    pub paths: Vec<String>,
+}
+
+/// Implementation of `wright repair`: audit the named parts (or all) and
+/// re-extract only their damaged files from the archives they were
+/// deployed from.
+pub async fn execute_repair(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    names: &[String],
+    dry_run: bool,
+) -> Result<()> {
+    let t0 = Instant::now();
+    let parts = select_parts(db, names).await?;
+    let mut targets = Vec::new();
+    for part in &parts {
+        let paths: Vec<String> = transaction::audit_part(db, part, root_dir)
+            .await?
+            .into_iter()
+            .filter(is_damage)
+            .map(|f| f.path)
+            .collect();
+        if !paths.is_empty() {
+            targets.push(RepairTarget { part, paths });
+        }
+    }
+
+    if targets.is_empty() {
+        crate::cli_action!("Finished", "repair: {} part(s) intact", parts.len());
+        return Ok(());
+    }
+    if dry_run {
+        for target in &targets {
+            println!(
+                "[dry-run] would repair {} file(s) of {}:",
+                target.paths.len(),
+                target.part.name
+            );
+            for path in &target.paths {
+                println!("  {}", path);
+            }
+        }
+        return Ok(());
+    }
+
+    let unavailable = repair_targets(db, config, root_dir, &targets, "repair").await?;
+    let files: usize = targets
+        .iter()
+        .filter(|t| !unavailable.contains(&t.part.name))
+        .map(|t| t.paths.len())
+        .sum();
+    crate::cli_action!(
+        "Finished",
+        "repair in {}: {} file(s) across {} part(s)",
+        crate::foundry::logging::format_duration(t0.elapsed().as_secs_f64()),
+        files,
+        targets.len() - unavailable.len(),
+    );
+    if !unavailable.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "no archive to repair from for {} part(s); re-merge: {}",
+            unavailable.len(),
+            unavailable.join(" ")
+        )));
+    }
+    Ok(())
+}
+
+/// Repair `targets` as one delivery transaction. Parts whose archive is in
+/// neither the CAS store nor `parts_dir` are skipped and returned. A part
+/// that fails stops the run and its error is returned, after the delivery
+/// completes for the parts repaired before it.
+pub(super) async fn repair_targets(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    targets: &[RepairTarget<'_>],
+    command: &str,
+) -> Result<Vec<String>> {
+    let wanted: HashSet<String> = targets
+        .iter()
+        .filter_map(|t| t.part.part_hash.clone())
+        .collect();
+    let located = CasStore::new(config.general.store_dir.clone())
+        .locate_by_part_hash(&[config.general.parts_dir.as_path()], &wanted)?;
+
+    let mut unavailable = Vec::new();
+    let mut ready = Vec::new();
+    for target in targets {
+        match target.part.part_hash.as_ref().and_then(|h| located.get(h)) {
+            Some(archive) => ready.push((target, archive)),
+            None => {
+                crate::cli_warn!(
+                    "{}: archive {} not found in the CAS store or parts_dir",
+                    target.part.name,
+                    target
+                        .part
+                        .part_hash
+                        .as_deref()
+                        .unwrap_or("(none recorded)")
+                );
+                unavailable.push(target.part.name.clone());
+            }
+        }
+    }
+    if ready.is_empty() {
+        return Ok(unavailable);
+    }
+
+    let tx_id = crate::delivery::begin_delivery(db, command).await?;
+    let session = SessionContext {
+        id: format!(
+            "{:x}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
+        ),
+        command: command.to_string(),
+        delivery_id: Some(tx_id),
+    };
+    crate::delivery::delivery_ready(db, tx_id).await?;
+    crate::delivery::begin_applying(db, tx_id).await?;
+
+    let mut failure = None;
+    for (target, archive) in ready {
+        // Each part is its own filesystem transaction: a failure rolls back
+        // that part, and parts already repaired stay repaired.
+        match transaction::repair_part(
+            db,
+            &target.part.name,
+            archive,
+            &target.paths,
+            root_dir,
+            session.clone(),
+        )
+        .await
+        {
+            Ok(count) => {
+                crate::cli_action!("Repaired", "{} file(s) of {}", count, target.part.name)
+            }
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+    // The parts repaired before a failure are committed, so the delivery
+    // completes with them either way.
+    crate::transaction::run_triggers(db, tx_id, root_dir).await?;
+    crate::delivery::complete_delivery(db, tx_id).await?;
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(unavailable),
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::config::GlobalConfig;
+use crate::database::{
+    DeliveryStatus, HistoryAction, InstalledDb, OpStatus, Origin, SessionContext, TransactionOp,
+};
+use crate::delivery::store::CasStore;
+use crate::error::{Result, WrightError};
+use crate::part::store::LocalPartStore;
+use crate::transaction;
+
+/// How many deliveries `wright rollback --list` shows.
+const LIST_LIMIT: i64 = 20;
+
+/// Action of the op `wright generation switch` records when it drops an
+/// external placeholder, so a real part or another version can take its
+/// name.  The op's `placeholder_version` holds the dropped version.
+pub(super) const UNPROVIDE_ACTION: &str = "unprovide";
+
+/// Action of the op `wright generation switch` records when it provides an
+/// external placeholder.
+pub(super) const PROVIDE_ACTION: &str = "provide";
+
+/// Net effect of a delivery on one part: its archive hash before the
+/// delivery (`before`) and the one the delivery left behind (`after`,
+/// `None` when it ended up removed).
+struct PartChange {
+    name: String,
+    existed_before: bool,
+    /// `None` with `existed_before` means the prior hash was never recorded.
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// What it takes to put a set of parts back to an earlier state.
+/// Also built by `wright generation switch`.
+#[derive(Default)]
+pub(super) struct Reversal {
+    /// `(part, archive hash)` to redeploy.
+    pub(super) restore: Vec<(String, String)>,
+    /// Parts to remove.
+    pub(super) remove: Vec<String>,
+    /// Parts that are not installed now; they come back as explicit installs.
+    pub(super) reinstated: HashSet<String>,
+    /// Parts whose installed hash no longer matches the delivery's end state.
+    drifted: Vec<String>,
+    /// Parts whose prior hash was never recorded.
+    unrecorded: Vec<String>,
+}
+
+impl Reversal {
+    pub(super) fn is_empty(&self) -> bool {
+        self.restore.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// Implementation of `wright rollback --list`.
+pub async fn execute_list_deliveries(db: &InstalledDb) -> Result<()> {
+    let deliveries = db.list_deliveries(LIST_LIMIT).await?;
+    if deliveries.is_empty() {
+        println!("no delivery transactions recorded");
+        return Ok(());
+    }
+    let reverted_by: HashMap<i64, i64> = deliveries
+        .iter()
+        .filter(|tx| tx.status == DeliveryStatus::Completed)
+        .filter_map(|tx| tx.reverts.map(|target| (target, tx.id)))
+        .collect();
+    for tx in &deliveries {
+        let ops = db.get_ops_for_delivery(tx.id).await?;
+        let done = ops.iter().filter(|op| op.status == OpStatus::Done).count();
+        let status = match tx.status {
+            DeliveryStatus::Completed => "completed",
+            DeliveryStatus::RolledBack => "rolled_back",
+            DeliveryStatus::Planning => "planning",
+            DeliveryStatus::Ready => "ready",
+            DeliveryStatus::Applying => "applying",
+        };
+        let reverted = reverted_by
+            .get(&tx.id)
+            .map(|by| format!(" (reverted by {})", by))
+            .unwrap_or_default();
+        println!(
+            "{:>5}  {}  {:<11} {:>3} op(s)  {}{}",
+            tx.id,
+            tx.created_at.as_deref().unwrap_or_default(),
+            status,
+            done,
+            tx.command,
+            reverted
+        );
+    }
+    Ok(())
+}
+
+/// Implementation of `wright rollback <tx-id>`.
+///
+/// Reverses a completed delivery as one new delivery transaction: parts the
+/// delivery upgraded or removed are redeployed from the archive matching
+/// their recorded `old_hash`, and parts it installed are removed.  If any
+/// step fails, the steps already applied are reverted the same way.
+pub async fn execute_rollback(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    part_store: &LocalPartStore,
+    target_tx: i64,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let t0 = std::time::Instant::now();
+    let tx = db.get_delivery(target_tx).await?.ok_or_else(|| {
+        WrightError::ValidationError(format!(
+            "delivery transaction {} not found (see `wright rollback --list`)",
+            target_tx
+        ))
+    })?;
+    if tx.status != DeliveryStatus::Completed {
+        return Err(WrightError::ValidationError(format!(
+            "delivery transaction {} is not completed; only completed deliveries can be rolled back",
+            target_tx
+        )));
+    }
+    if let Some(by) = db.find_reverting_delivery(target_tx).await? {
+        return Err(WrightError::ValidationError(format!(
+            "delivery transaction {} was already rolled back by delivery {}",
+            target_tx, by
+        )));
+    }
+
+    let target_ops = db.get_ops_for_delivery(target_tx).await?;
+    let changes = net_changes(&target_ops);
+    let placeholders = target_ops.iter().any(is_placeholder_op);
+    if changes.is_empty() && !placeholders {
+        return Err(WrightError::ValidationError(format!(
+            "delivery transaction {} recorded no completed operations",
+            target_tx
+        )));
+    }
+
+    let reversal = plan_reversal(db, &changes).await?;
+    if !reversal.unrecorded.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "delivery {} did not record the prior archive hash of: {}",
+            target_tx,
+            reversal.unrecorded.join(", ")
+        )));
+    }
+    // A later delivery touched the same parts; reverting would clobber it.
+    if !reversal.drifted.is_empty() && !force {
+        return Err(WrightError::ValidationError(format!(
+            "parts changed since delivery {}: {} (use --force to roll back anyway)",
+            target_tx,
+            reversal.drifted.join(", ")
+        )));
+    }
+
+    let located = locate_archives(config, &reversal)?;
+    let missing: Vec<String> = reversal
+        .restore
+        .iter()
+        .filter(|(_, hash)| !located.contains_key(hash))
+        .map(|(name, hash)| format!("{} ({})", name, &hash[..12.min(hash.len())]))
+        .collect();
+    if !missing.is_empty() {
+        return Err(WrightError::PartNotFound(format!(
+            "no archive in the CAS store or parts_dir for: {}",
+            missing.join(", ")
+        )));
+    }
+
+    if dry_run {
+        println!(
+            "[dry-run] rollback of delivery {}: {}",
+            target_tx, tx.command
+        );
+        if !reversal.restore.is_empty() {
+            println!(
+                "[dry-run] would redeploy {} part(s):",
+                reversal.restore.len()
+            );
+            for (name, hash) in &reversal.restore {
+                println!("  {} <- {}", name, located[hash].display());
+            }
+        }
+        if !reversal.remove.is_empty() {
+            println!("[dry-run] would remove {} part(s):", reversal.remove.len());
+            for name in &reversal.remove {
+                println!("  {}", name);
+            }
+        }
+        if reversal.is_empty() {
+            println!("[dry-run] nothing to do");
+        }
+        return Ok(());
+    }
+
+    if reversal.is_empty() && !placeholders {
+        crate::cli_action!(
+            "Finished",
+            "rollback of delivery {}: nothing to do",
+            target_tx
+        );
+        return Ok(());
+    }
+
+    let command_str = format!("rollback {}", target_tx);
+    let tx_id = crate::delivery::begin_delivery(db, &command_str).await?;
+    db.set_delivery_reverts(tx_id, target_tx).await?;
+    let session = SessionContext {
+        id: format!(
+            "{:x}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
+        ),
+        command: command_str,
+        delivery_id: Some(tx_id),
+    };
+    crate::delivery::delivery_ready(db, tx_id).await?;
+    crate::delivery::begin_applying(db, tx_id).await?;
+
+    let result = async {
+        unprovide(db, &target_ops).await?;
+        apply(
+            db, root_dir, part_store, &reversal, &located, force, &session,
+        )
+        .await?;
+        reprovide(db, &target_ops).await
+    }
+    .await;
+    if let Err(e) = result {
+        undo_partial(db, config, root_dir, part_store, tx_id, &session).await;
+        let _ = crate::delivery::rollback_delivery(db, tx_id).await;
+        return Err(WrightError::DeployError(format!(
+            "rollback of delivery {}: {}",
+            target_tx, e
+        )));
+    }
+
+    crate::transaction::run_triggers(db, tx_id, root_dir).await?;
+    crate::transaction::run_deferred_hooks(db, tx_id, root_dir).await?;
+    // Completing this delivery marks the target as reverted: it cannot be
+    // rolled back a second time, and keeps its own `completed` status.
+    crate::delivery::complete_delivery(db, tx_id).await?;
+
+    crate::cli_action!(
+        "Finished",
+        "rollback of delivery {} in {}: {} redeployed, {} removed",
+        target_tx,
+        crate::foundry::logging::format_duration(t0.elapsed().as_secs_f64()),
+        reversal.restore.len(),
+        reversal.remove.len(),
+    );
+    Ok(())
+}
+
+/// Compare each part's pre- and post-delivery state with what is installed
+/// now and work out the steps that restore the pre-delivery state.
+async fn plan_reversal(db: &InstalledDb, changes: &[PartChange]) -> Result<Reversal> {
+    let mut reversal = Reversal::default();
+    for change in changes {
+        let current = db.get_part(&change.name).await?.map(|p| p.part_hash);
+        let end_state_holds = match (&change.after, &current) {
+            (None, None) => true,
+            (Some(after), Some(hash)) => hash.as_deref() == Some(after.as_str()),
+            _ => false,
+        };
+        if !end_state_holds {
+            reversal.drifted.push(change.name.clone());
+        }
+
+        let current_hash = current.as_ref().and_then(|h| h.as_deref());
+        match (&change.before, change.existed_before) {
+            (Some(hash), _) if current_hash != Some(hash.as_str()) => {
+                reversal.restore.push((change.name.clone(), hash.clone()));
+                if current.is_none() {
+                    reversal.reinstated.insert(change.name.clone());
+                }
+            }
+            (Some(_), _) => {}
+            (None, true) => reversal.unrecorded.push(change.name.clone()),
+            (None, false) if current.is_some() => reversal.remove.push(change.name.clone()),
+            (None, false) => {}
+        }
+    }
+    Ok(reversal)
+}
+
+pub(super) fn locate_archives(
+    config: &GlobalConfig,
+    reversal: &Reversal,
+) -> Result<HashMap<String, PathBuf>> {
+    let wanted: HashSet<String> = reversal
+        .restore
+        .iter()
+        .map(|(_, hash)| hash.clone())
+        .collect();
+    CasStore::new(config.general.store_dir.clone())
+        .locate_by_part_hash(&[config.general.parts_dir.as_path()], &wanted)
+}
+
+pub(super) async fn apply(
+    db: &InstalledDb,
+    root_dir: &Path,
+    part_store: &LocalPartStore,
+    reversal: &Reversal,
+    located: &HashMap<String, PathBuf>,
+    force: bool,
+    session: &SessionContext,
+) -> Result<()> {
+    if !reversal.restore.is_empty() {
+        let paths: Vec<PathBuf> = reversal
+            .restore
+            .iter()
+            .map(|(_, hash)| located[hash].clone())
+            .collect();
+        // Downgraded parts keep whatever origin they already have.
+        transaction::deploy_parts_with_explicit_targets(
+            db,
+            &paths,
+            &reversal.reinstated,
+            root_dir,
+            part_store,
+            false,
+            true,
+            None,
+            true,
+            session.clone(),
+        )
+        .await?;
+    }
+
+    if !reversal.remove.is_empty() {
+        let order = transaction::order_removal_batch(db, &reversal.remove).await?;
+        let batch: HashSet<String> = reversal.remove.iter().cloned().collect();
+        for name in &order {
+            crate::cli_action!("Removing", "{}", name);
+            let ignored: HashSet<String> = batch.iter().filter(|n| *n != name).cloned().collect();
+            transaction::remove_part_with_ignored_dependents(
+                db,
+                name,
+                root_dir,
+                force,
+                &ignored,
+                session.clone(),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Best effort: revert the ops a failed delivery already applied, so a
+/// failure leaves the system where it started.
+pub(super) async fn undo_partial(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    part_store: &LocalPartStore,
+    tx_id: i64,
+    session: &SessionContext,
+) {
+    let ops = match db.get_ops_for_delivery(tx_id).await {
+        Ok(ops) => ops,
+        Err(e) => {
+            crate::cli_warn!("cannot read ops of delivery {}: {}", tx_id, e);
+            return;
+        }
+    };
+    let changes = net_changes(&ops);
+    if changes.is_empty() && !ops.iter().any(is_placeholder_op) {
+        return;
+    }
+    crate::cli_action!("Reverting", "{} part(s) already changed", changes.len());
+
+    // Undo steps are not part of any delivery: the failed one is marked
+    // rolled back as a whole.
+    let undo_session = SessionContext {
+        delivery_id: None,
+        ..session.clone()
+    };
+    let result = async {
+        unprovide(db, &ops).await?;
+        let reversal = plan_reversal(db, &changes).await?;
+        let located = locate_archives(config, &reversal)?;
+        if let Some((name, _)) = reversal
+            .restore
+            .iter()
+            .find(|(_, hash)| !located.contains_key(hash))
+        {
+            return Err(WrightError::PartNotFound(format!(
+                "no archive to restore {}",
+                name
+            )));
+        }
+        apply(
+            db,
+            root_dir,
+            part_store,
+            &reversal,
+            &located,
+            true,
+            &undo_session,
+        )
+        .await?;
+        reprovide(db, &ops).await
+    }
+    .await;
+    if let Err(e) = result {
+        crate::cli_warn!(
+            "could not revert delivery {} ({}); inspect with `wright rollback --list`",
+            tx_id,
+            e
+        );
+    }
+}
+
+fn is_placeholder_op(op: &TransactionOp) -> bool {
+    op.status == OpStatus::Done
+        && (op.action_type == UNPROVIDE_ACTION || op.action_type == PROVIDE_ACTION)
+}
+
+/// Drop the external placeholders a delivery's provide ops created, unless
+/// a real part took the name since.  Runs before the parts are restored, so
+/// a part the delivery replaced with a placeholder can be redeployed.
+async fn unprovide(db: &InstalledDb, ops: &[TransactionOp]) -> Result<()> {
+    for op in ops
+        .iter()
+        .filter(|op| is_placeholder_op(op) && op.action_type == PROVIDE_ACTION)
+    {
+        if db
+            .get_part(&op.part_name)
+            .await?
+            .is_some_and(|p| p.origin == Origin::External)
+        {
+            crate::cli_action!("Unproviding", "{}", op.part_name);
+            db.remove_part(&op.part_name).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Put back the external placeholders a delivery's unprovide ops dropped,
+/// unless a part of that name is installed again.
+async fn reprovide(db: &InstalledDb, ops: &[TransactionOp]) -> Result<()> {
+    for op in ops
+        .iter()
+        .filter(|op| is_placeholder_op(op) && op.action_type == UNPROVIDE_ACTION)
+    {
+        let Some(version) = &op.placeholder_version else {
+            continue;
+        };
+        if db.get_part(&op.part_name).await?.is_none() {
+            crate::cli_action!("Providing", "{} {}", op.part_name, version);
+            db.provide_part(&op.part_name, version).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Fold a delivery's completed ops into one before/after pair per part.
+/// The first op on a part fixes its prior state; the last fixes its final one.
+fn net_changes(ops: &[TransactionOp]) -> Vec<PartChange> {
+    let mut by_part: BTreeMap<&str, PartChange> = BTreeMap::new();
+    for op in ops
+        .iter()
+        .filter(|op| op.status == OpStatus::Done && !is_placeholder_op(op))
+    {
+        let after = if op.action_type == HistoryAction::Remove.to_string() {
+            None
+        } else {
+            Some(op.part_hash.clone())
+        };
+        by_part
+            .entry(op.part_name.as_str())
+            .and_modify(|c| c.after = after.clone())
+            .or_insert_with(|| PartChange {
+                name: op.part_name.clone(),
+                existed_before: op.action_type != HistoryAction::Install.to_string(),
+                before: op.old_hash.clone(),
+                after,
+            });
+    }
+    by_part.into_values().collect()
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::GlobalConfig;
+use crate::error::{Result, WrightError};
+use crate::foundry::charge::{self, CachedSource};
+use crate::foundry::vendor::VENDOR_DIR;
+use crate::plan::discovery::PlanIndex;
+use crate::resolve::plan_search_dirs;
+use crate::util::output::{self, OutputFormat};
+use crate::util::units::format_bytes;
+
+/// One file, git mirror, or vendored dependency in the source cache.
+#[derive(Serialize)]
+struct CacheEntry {
+    /// Path relative to `general.source_dir`.
+    path: String,
+    kind: &'static str,
+    size: u64,
+    /// Plans whose sources resolve to this entry; empty for orphans.
+    plans: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    source_dir: &'a Path,
+    entries: &'a [&'a CacheEntry],
+}
+
+/// Which plans reference which cache entries.
+struct References {
+    by_path: BTreeMap<PathBuf, Vec<(String, CachedSource)>>,
+    /// Plans that failed to parse: their sources are unknown.
+    unreadable: Vec<String>,
+}
+
+impl References {
+    fn load(config: &GlobalConfig) -> Result<Self> {
+        let index = PlanIndex::discover(&plan_search_dirs(config))?;
+        let mut names: Vec<&String> = index.names().collect();
+        names.sort();
+
+        let mut by_path: BTreeMap<PathBuf, Vec<(String, CachedSource)>> = BTreeMap::new();
+        let mut unreadable = Vec::new();
+        for name in names {
+            let plan_dir = index
+                .path_for(name)
+                .and_then(|p| p.parent())
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            match index
+                .manifest_for(name)
+                .and_then(|m| m.map(|m| charge::cached_sources(&m, &plan_dir)).transpose())
+            {
+                Ok(Some(sources)) => {
+                    for source in sources {
+                        by_path
+                            .entry(source.rel_path.clone())
+                            .or_default()
+                            .push((name.clone(), source));
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    crate::cli_warn!("cannot read plan '{}': {}", name, e);
+                    unreadable.push(name.clone());
+                }
+            }
+        }
+        Ok(Self {
+            by_path,
+            unreadable,
+        })
+    }
+
+    fn plans_for(&self, rel_path: &Path) -> Vec<String> {
+        let mut plans: Vec<String> = self
+            .by_path
+            .get(rel_path)
+            .map(|refs| refs.iter().map(|(plan, _)| plan.clone()).collect())
+            .unwrap_or_default();
+        plans.dedup();
+        plans
+    }
+}
+
+/// Implementation of `wright sources list`.
+pub async fn execute_list_sources(
+    config: &GlobalConfig,
+    orphans_only: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let refs = References::load(config)?;
+    let entries = scan(&config.general.source_dir, &refs)?;
+    let shown: Vec<&CacheEntry> = entries
+        .iter()
+        .filter(|e| !orphans_only || e.plans.is_empty())
+        .collect();
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "sources list",
+                &ListBody {
+                    source_dir: &config.general.source_dir,
+                    entries: &shown,
+                },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &["path", "kind", "size", "plans"],
+                shown.iter().map(|e| {
+                    [
+                        e.path.clone(),
+                        e.kind.to_string(),
+                        e.size.to_string(),
+                        e.plans.join(","),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    for entry in &shown {
+        let owners = if entry.plans.is_empty() {
+            "(orphan)".to_string()
+        } else {
+            entry.plans.join(", ")
+        };
+        println!(
+            "{:>10}  {:<48} {}",
+            format_bytes(entry.size),
+            entry.path,
+            owners
+        );
+    }
+    let orphans: Vec<&CacheEntry> = entries.iter().filter(|e| e.plans.is_empty()).collect();
+    crate::cli_action!(
+        "Summary",
+        "{} cache entries, {}; {} orphan(s), {}",
+        entries.len(),
+        format_bytes(entries.iter().map(|e| e.size).sum()),
+        orphans.len(),
+        format_bytes(orphans.iter().map(|e| e.size).sum()),
+    );
+    Ok(())
+}
+
+/// Implementation of `wright sources verify`: re-hash cached HTTP sources
+/// against the sha256 their plans declare.
+pub async fn execute_verify_sources(config: &GlobalConfig, plans: &[String]) -> Result<()> {
+    let refs = References::load(config)?;
+    let mut checked = 0usize;
+    let mut failed: Vec<String> = Vec::new();
+
+    for (rel_path, sources) in &refs.by_path {
+        let declared: Vec<&(String, CachedSource)> = sources
+            .iter()
+            .filter(|(plan, s)| s.sha256.is_some() && (plans.is_empty() || plans.contains(plan)))
+            .collect();
+        if declared.is_empty() {
+            continue;
+        }
+        let path = config.general.source_dir.join(rel_path);
+        if !path.is_file() {
+            continue;
+        }
+        let actual = crate::util::checksum::sha256_file(&path)?;
+        checked += 1;
+        for (plan, source) in declared {
+            let expected = source.sha256.as_deref().unwrap_or_default();
+            if actual != expected {
+                crate::cli_warn!(
+                    "{} ({}): sha256 mismatch\n  expected: {}\n  actual:   {}",
+                    rel_path.display(),
+                    plan,
+                    expected,
+                    actual
+                );
+                failed.push(rel_path.display().to_string());
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        failed.dedup();
+        return Err(WrightError::ValidationError(format!(
+            "{} of {} cached source(s) failed verification: {}",
+            failed.len(),
+            checked,
+            failed.join(", ")
+        )));
+    }
+    crate::cli_action!("Verified", "{} cached source(s)", checked);
+    Ok(())
+}
+
+/// Implementation of `wright sources clean`: delete cache entries no plan
+/// references. Reports only, unless `apply`.
+pub async fn execute_clean_sources(config: &GlobalConfig, apply: bool) -> Result<()> {
+    let refs = References::load(config)?;
+    // An unreadable plan's sources would all look orphaned.
+    if !refs.unreadable.is_empty() {
+        return Err(WrightError::ValidationError(format!(
+            "refusing to clean the source cache while plans fail to parse: {}",
+            refs.unreadable.join(", ")
+        )));
+    }
+    let entries = scan(&config.general.source_dir, &refs)?;
+    let orphans: Vec<&CacheEntry> = entries.iter().filter(|e| e.plans.is_empty()).collect();
+    let bytes: u64 = orphans.iter().map(|e| e.size).sum();
+
+    if !apply {
+        for entry in &orphans {
+            println!(
+                "[dry-run] would remove {} ({})",
+                entry.path,
+                format_bytes(entry.size)
+            );
+        }
+        println!(
+            "[dry-run] {} orphan(s), {} reclaimable",
+            orphans.len(),
+            format_bytes(bytes)
+        );
+        if !orphans.is_empty() {
+            println!("[dry-run] pass --apply to delete");
+        }
+        return Ok(());
+    }
+
+    let mut removed = 0usize;
+    for entry in &orphans {
+        let path = config.general.source_dir.join(&entry.path);
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => crate::cli_warn!("failed to remove {}: {}", path.display(), e),
+        }
+    }
+    crate::cli_action!(
+        "Cleaned",
+        "{} orphan(s), {} reclaimed",
+        removed,
+        format_bytes(bytes)
+    );
+    Ok(())
+}
+
+/// List the top-level files, `git/` mirrors, and `vendor/<ecosystem>/`
+/// dependencies of the source cache.
+fn scan(source_dir: &Path, refs: &References) -> Result<Vec<CacheEntry>> {
+    let mut dirs = vec![
+        (source_dir.to_path_buf(), "file"),
+        (source_dir.join("git"), "git"),
+    ];
+    if let Ok(ecosystems) = std::fs::read_dir(source_dir.join(VENDOR_DIR)) {
+        for ecosystem in ecosystems.flatten() {
+            if ecosystem.file_type().is_ok_and(|t| t.is_dir()) {
+                dirs.push((ecosystem.path(), "vendor"));
+            }
+        }
+    }
+    let mut entries = Vec::new();
+    for (dir, kind) in &dirs {
+        let (dir, kind) = (dir.as_path(), *kind);
+        let listing = match std::fs::read_dir(dir) {
+            Ok(listing) => listing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(WrightError::IoError(std::io::Error::new(
+                    e.kind(),
+                    format!("read {}: {}", dir.display(), e),
+                )));
+            }
+        };
+        for item in listing.flatten() {
+            let path = item.path();
+            let Ok(file_type) = item.file_type() else {
+                continue;
+            };
+            let wanted = match kind {
+                "git" => file_type.is_dir(),
+                "vendor" => !item.file_name().to_string_lossy().starts_with('.'),
+                _ => file_type.is_file(),
+            };
+            if !wanted {
+                continue;
+            }
+            let rel_path = path.strip_prefix(source_dir).unwrap_or(&path).to_path_buf();
+            entries.push(CacheEntry {
+                path: rel_path.display().to_string(),
+                kind,
+                size: crate::util::disk_usage(&path),
+                plans: refs.plans_for(&rel_path),
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_maps_files_and_mirrors_to_plans() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("zlib-zlib-1.3.tar.gz"), b"tarball").unwrap();
+        std::fs::write(dir.path().join("old-old-0.1.tar.gz"), b"stale").unwrap();
+        std::fs::create_dir_all(dir.path().join("git/repo-1234abcd/objects")).unwrap();
+        std::fs::write(dir.path().join("git/repo-1234abcd/HEAD"), b"ref").unwrap();
+        std::fs::create_dir_all(dir.path().join("vendor/crates")).unwrap();
+        std::fs::write(
+            dir.path().join("vendor/crates/libc-0.2.150.crate"),
+            b"crate",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("vendor/pypi/demo-1.0")).unwrap();
+        std::fs::write(dir.path().join("vendor/pypi/demo-1.0/demo.whl"), b"wheel").unwrap();
+
+        let source = |rel: &str| CachedSource {
+            rel_path: PathBuf::from(rel),
+            locator: String::new(),
+            sha256: None,
+        };
+        let refs = References {
+            by_path: BTreeMap::from([
+                (
+                    PathBuf::from("zlib-zlib-1.3.tar.gz"),
+                    vec![("zlib".to_string(), source("zlib-zlib-1.3.tar.gz"))],
+                ),
+                (
+                    PathBuf::from("git/repo-1234abcd"),
+                    vec![("repo".to_string(), source("git/repo-1234abcd"))],
+                ),
+                (
+                    PathBuf::from("vendor/crates/libc-0.2.150.crate"),
+                    vec![(
+                        "app".to_string(),
+                        source("vendor/crates/libc-0.2.150.crate"),
+                    )],
+                ),
+            ]),
+            unreadable: Vec::new(),
+        };
+
+        let entries = scan(dir.path(), &refs).unwrap();
+        let summary: Vec<(&str, &str, u64, Vec<String>)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.kind, e.size, e.plans.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("git/repo-1234abcd", "git", 3, vec!["repo".to_string()]),
+                ("old-old-0.1.tar.gz", "file", 5, vec![]),
+                (
+                    "vendor/crates/libc-0.2.150.crate",
+                    "vendor",
+                    5,
+                    vec!["app".to_string()]
+                ),
+                ("vendor/pypi/demo-1.0", "vendor", 5, vec![]),
+                ("zlib-zlib-1.3.tar.gz", "file", 7, vec!["zlib".to_string()]),
+            ]
+        );
+    }
+}
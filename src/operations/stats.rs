@@ -0,0 +1,153 @@
+use serde::Serialize;
+
+use crate::cli::stats::StatsArgs;
+use crate::database::{BuildRun, BuildStage, InstalledDb};
+use crate::error::Result;
+use crate::foundry::logging::format_duration;
+use crate::util::output::{self, OutputFormat};
+use crate::util::units::format_bytes;
+
+#[derive(Serialize)]
+struct BuildEntry {
+    #[serde(flatten)]
+    run: BuildRun,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stages: Option<Vec<BuildStage>>,
+}
+
+#[derive(Serialize)]
+struct StatsBody<'a> {
+    builds: &'a [BuildEntry],
+}
+
+pub async fn execute_stats(db: &InstalledDb, args: StatsArgs, format: OutputFormat) -> Result<()> {
+    let mut runs = match args.plan {
+        Some(ref plan) => db.build_runs(plan).await?,
+        None => db.latest_build_runs().await?,
+    };
+    // Change in wall time from the build before, for a plan's history.
+    let changes: Vec<Option<f64>> = match args.plan {
+        Some(_) => (0..runs.len())
+            .map(|i| runs.get(i + 1).map(|prev| change(prev, &runs[i])))
+            .collect(),
+        None => vec![None; runs.len()],
+    };
+    if let Some(limit) = args.limit {
+        runs.truncate(limit);
+    }
+    let mut builds = Vec::with_capacity(runs.len());
+    for run in runs {
+        let stages = if args.stages {
+            Some(db.build_stages(run.id).await?)
+        } else {
+            None
+        };
+        builds.push(BuildEntry { run, stages });
+    }
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "stats",
+                &StatsBody { builds: &builds },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &[
+                    "plan",
+                    "version",
+                    "release",
+                    "built_at",
+                    "stage",
+                    "wall_secs",
+                    "cpu_secs",
+                    "max_rss_kb",
+                    "disk_bytes",
+                ],
+                builds.iter().flat_map(|b| {
+                    let run = &b.run;
+                    let total = [
+                        run.plan_name.clone(),
+                        run.version.clone(),
+                        run.release.to_string(),
+                        output::opt(run.built_at.as_deref()),
+                        String::new(),
+                        format!("{:.3}", run.wall_secs),
+                        format!("{:.3}", run.cpu_secs),
+                        run.max_rss_kb.to_string(),
+                        run.disk_bytes.to_string(),
+                    ];
+                    let stages = b.stages.iter().flatten().map(move |s| {
+                        [
+                            run.plan_name.clone(),
+                            run.version.clone(),
+                            run.release.to_string(),
+                            output::opt(run.built_at.as_deref()),
+                            s.stage.clone(),
+                            format!("{:.3}", s.wall_secs),
+                            format!("{:.3}", s.user_secs + s.system_secs),
+                            s.max_rss_kb.to_string(),
+                            s.disk_bytes.to_string(),
+                        ]
+                    });
+                    std::iter::once(total).chain(stages)
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    if builds.is_empty() {
+        match args.plan {
+            Some(ref plan) => println!("no recorded builds of {}", plan),
+            None => println!("no recorded builds"),
+        }
+        return Ok(());
+    }
+
+    let width = builds
+        .iter()
+        .map(|b| b.run.plan_name.len() + b.run.version.len() + 1)
+        .max()
+        .unwrap_or(0);
+    for (b, change) in builds.iter().zip(changes) {
+        let run = &b.run;
+        let change = change
+            .map(|c| format!("  {:+.0}%", c * 100.0))
+            .unwrap_or_default();
+        println!(
+            "{:<width$}  {:>8}  cpu {:>8}  rss {:>10}  disk {:>10}  {}{}",
+            format!("{}-{}", run.plan_name, run.version),
+            format_duration(run.wall_secs),
+            format_duration(run.cpu_secs),
+            format_bytes(run.max_rss_kb as u64 * 1024),
+            format_bytes(run.disk_bytes as u64),
+            run.built_at.as_deref().unwrap_or_default(),
+            change,
+        );
+        for s in b.stages.iter().flatten() {
+            println!(
+                "  {:<w$}  {:>8}  cpu {:>8}  rss {:>10}  disk {:>10}",
+                s.stage,
+                format_duration(s.wall_secs),
+                format_duration(s.user_secs + s.system_secs),
+                format_bytes(s.max_rss_kb as u64 * 1024),
+                format_bytes(s.disk_bytes as u64),
+                w = width.saturating_sub(2),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Relative change in wall time from `prev` to `run`.
+fn change(prev: &BuildRun, run: &BuildRun) -> f64 {
+    if prev.wall_secs > 0.0 {
+        run.wall_secs / prev.wall_secs - 1.0
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,111 @@
+use std::io::{IsTerminal, Write};
+
+use crate::config::GlobalConfig;
+use crate::database::InstalledDb;
+use crate::error::{Result, WrightError};
+use crate::plan::discovery::PlanIndex;
+use crate::query::{self, PlanDepGraph, PrefixMode, TreeOptions};
+use crate::resolve::{DepDomain, plan_search_dirs};
+
+pub struct TreeRequest<'a> {
+    /// Root of the tree; `None` renders every root part (or plan).
+    pub target: Option<&'a str>,
+    pub reverse: bool,
+    /// Maximum depth; `0` means unlimited.
+    pub depth: usize,
+    /// Domain filter; `None` means the mode's natural default (runtime for
+    /// the installed registry, every domain for plan sources).
+    pub domain: Option<DepDomain>,
+    /// Walk plan sources (`PlanIndex`) instead of the installed registry.
+    pub plans: bool,
+    pub filter: Option<&'a str>,
+    pub prune: &'a [String],
+    pub prefix_mode: PrefixMode,
+    pub stats: bool,
+}
+
+/// Implementation of `wright tree`.
+pub async fn execute_tree(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    request: TreeRequest<'_>,
+) -> Result<()> {
+    if request.reverse && request.target.is_none() {
+        return Err(WrightError::ValidationError(
+            "--reverse needs a part to root the tree at".into(),
+        ));
+    }
+
+    let color = std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal();
+    let opts = TreeOptions {
+        max_depth: if request.depth == 0 {
+            usize::MAX
+        } else {
+            request.depth
+        },
+        filter: request.filter,
+        prefix_mode: request.prefix_mode,
+        prune: request.prune,
+        color,
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    let stats = if request.plans {
+        let domain = request.domain.unwrap_or(DepDomain::ALL);
+        let index = PlanIndex::discover(&plan_search_dirs(config))?;
+        let graph = PlanDepGraph::from_index(&index, domain)?;
+        match request.target {
+            Some(name) => {
+                if !graph.contains(name) {
+                    return Err(WrightError::PartNotFound(format!(
+                        "no plan named '{}' in the plan index",
+                        name
+                    )));
+                }
+                writeln!(out, "{}", name)?;
+                if request.reverse {
+                    query::write_plan_reverse_dep_tree(&graph, name, &opts, &mut out)?
+                } else {
+                    query::write_plan_dep_tree(&graph, name, &opts, &mut out)?
+                }
+            }
+            None => query::write_plan_system_tree(&graph, &opts, &mut out)?,
+        }
+    } else {
+        // Migration 013 dropped the build/link tables: the registry holds
+        // runtime edges only, so any other domain must come from plan source.
+        if let Some(domain) = request.domain
+            && !domain.contains(DepDomain::RUNTIME)
+        {
+            return Err(WrightError::ValidationError(
+                "the installed registry records runtime dependencies only; \
+                 pass --plans to walk build or link edges"
+                    .into(),
+            ));
+        }
+        match request.target {
+            Some(name) => {
+                if db.get_part(name).await?.is_none() {
+                    return Err(WrightError::PartNotFound(format!(
+                        "part '{}' is not deployed (pass --plans to walk plan sources)",
+                        name
+                    )));
+                }
+                writeln!(out, "{}", name)?;
+                if request.reverse {
+                    query::write_reverse_dep_tree(db, name, &opts, &mut out).await?
+                } else {
+                    query::write_dep_tree(db, name, &opts, &mut out).await?
+                }
+            }
+            None => query::write_system_tree(db, &opts, &mut out).await?,
+        }
+    };
+
+    if request.stats {
+        stats.write_summary(&mut out, color)?;
+    }
+    Ok(())
+}
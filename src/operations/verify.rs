@@ -0,0 +1,199 @@
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::config::GlobalConfig;
+use crate::database::{InstalledDb, InstalledPart, Origin};
+use crate::error::{Result, WrightError};
+use crate::transaction::{self, AuditFinding, AuditProblem};
+use crate::util::output::{self, OutputFormat};
+
+use super::repair::{RepairTarget, repair_targets};
+
+#[derive(Serialize)]
+struct PartAudit {
+    part: String,
+    findings: Vec<AuditFinding>,
+}
+
+#[derive(Serialize)]
+struct VerifyBody<'a> {
+    parts: &'a [PartAudit],
+    /// Parts with damage a restore did not (or could not) repair.
+    remerge: Vec<&'a str>,
+}
+
+/// Implementation of `wright verify`: audit deployed files part by part and,
+/// with `restore`, repair damaged files as `wright repair` does.
+///
+/// Exit semantics: returns `WrightError::ValidationError` when any part
+/// still has damaged files, so the CLI dispatch layer maps to a non-zero
+/// exit.
+pub async fn execute_verify(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    names: &[String],
+    restore: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let t0 = Instant::now();
+    let parts = select_parts(db, names).await?;
+    if format.is_human() {
+        crate::cli_action!("Verifying", "{} deployed part(s)", parts.len());
+    }
+
+    let mut audits = Vec::new();
+    for part in &parts {
+        let findings = transaction::audit_part(db, part, root_dir).await?;
+        if !findings.is_empty() {
+            audits.push(PartAudit {
+                part: part.name.clone(),
+                findings,
+            });
+        }
+    }
+
+    if restore {
+        restore_damaged(db, config, root_dir, &parts, &mut audits).await?;
+        audits.retain(|a| !a.findings.is_empty());
+    }
+
+    let remerge: Vec<&str> = audits
+        .iter()
+        .filter(|a| a.findings.iter().any(is_damage))
+        .map(|a| a.part.as_str())
+        .collect();
+    let damaged = remerge.len();
+    match format {
+        OutputFormat::Json => output::write_json(
+            &mut std::io::stdout().lock(),
+            "verify",
+            &VerifyBody {
+                parts: &audits,
+                remerge,
+            },
+        )?,
+        OutputFormat::Tsv => output::write_tsv(
+            &mut std::io::stdout().lock(),
+            &["part", "path", "problem", "expected", "actual"],
+            audits.iter().flat_map(|a| {
+                a.findings.iter().map(|f| {
+                    [
+                        a.part.clone(),
+                        f.path.clone(),
+                        f.problem.as_str().to_string(),
+                        output::opt(f.expected.as_deref()),
+                        output::opt(f.actual.as_deref()),
+                    ]
+                })
+            }),
+        )?,
+        OutputFormat::Human => print_human(&audits, &remerge),
+    }
+
+    if damaged == 0 {
+        if format.is_human() {
+            crate::cli_action!(
+                "Finished",
+                "verify in {}: {} part(s) intact",
+                crate::foundry::logging::format_duration(t0.elapsed().as_secs_f64()),
+                parts.len(),
+            );
+        }
+        return Ok(());
+    }
+    Err(WrightError::ValidationError(format!(
+        "verify found damaged files in {} part(s)",
+        damaged
+    )))
+}
+
+/// Damage is anything but an extra file or a local edit to a config:
+/// both are reported, neither is repaired by re-merging.
+pub(super) fn is_damage(finding: &AuditFinding) -> bool {
+    finding.problem != AuditProblem::Extra && !finding.is_local_edit()
+}
+
+pub(super) async fn select_parts(db: &InstalledDb, names: &[String]) -> Result<Vec<InstalledPart>> {
+    let mut parts = Vec::new();
+    if names.is_empty() {
+        for p in db.list_parts().await? {
+            if p.origin == Origin::External {
+                continue;
+            }
+            parts.push(InstalledPart {
+                id: p.id,
+                name: p.name,
+                plan_id: p.plan_id,
+                installed_at: p.installed_at,
+                part_hash: p.part_hash,
+                deploy_scripts: p.deploy_scripts,
+                origin: p.origin,
+            });
+        }
+        return Ok(parts);
+    }
+    for name in names {
+        let part = db
+            .get_part(name)
+            .await?
+            .ok_or_else(|| WrightError::PartNotFound(name.clone()))?;
+        parts.push(part);
+    }
+    Ok(parts)
+}
+
+async fn restore_damaged(
+    db: &InstalledDb,
+    config: &GlobalConfig,
+    root_dir: &Path,
+    parts: &[InstalledPart],
+    audits: &mut [PartAudit],
+) -> Result<()> {
+    let targets: Vec<RepairTarget> = parts
+        .iter()
+        .filter_map(|part| {
+            let audit = audits.iter().find(|a| a.part == part.name)?;
+            let paths: Vec<String> = audit
+                .findings
+                .iter()
+                .filter(|f| is_damage(f))
+                .map(|f| f.path.clone())
+                .collect();
+            (!paths.is_empty()).then_some(RepairTarget { part, paths })
+        })
+        .collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    repair_targets(db, config, root_dir, &targets, "verify --restore").await?;
+    for target in &targets {
+        if let Some(audit) = audits.iter_mut().find(|a| a.part == target.part.name) {
+            audit.findings = transaction::audit_part(db, target.part, root_dir).await?;
+        }
+    }
+    Ok(())
+}
+
+fn print_human(audits: &[PartAudit], remerge: &[&str]) {
+    for audit in audits {
+        println!("{}:", audit.part);
+        for f in &audit.findings {
+            let detail = match (&f.expected, &f.actual) {
+                (Some(expected), Some(actual)) => {
+                    format!(" (expected {}, found {})", expected, actual)
+                }
+                (None, Some(actual)) => format!(" ({})", actual),
+                _ => String::new(),
+            };
+            let note = if f.is_local_edit() { " [config]" } else { "" };
+            println!("  {:<10} {}{}{}", f.problem.as_str(), f.path, detail, note);
+        }
+    }
+    if !remerge.is_empty() {
+        println!("parts to re-merge: {}", remerge.join(" "));
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::config::GlobalConfig;
+use crate::error::{Result, WrightError};
+use crate::foundry::checkpoint::{
+    CHECKPOINT_FILE, Checkpoint, ForgeState, STAGE_ORDER, StageStatus,
+};
+use crate::foundry::{build_root_name, forge_dir, is_reproducible_root, layers};
+use crate::plan::discovery::PlanIndex;
+use crate::resolve::plan_search_dirs;
+use crate::util::output::{self, OutputFormat};
+use crate::util::units::format_bytes;
+
+/// Scratch directory the native isolation backend leaves behind on a crash.
+const ISOLATION_SCRATCH: &str = ".wright-isolation";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum RootStatus {
+    /// The build root of a plan at its current version.
+    Current,
+    /// The plan exists, but at a different version.
+    Outdated,
+    /// No current plan owns this root.
+    Orphan,
+}
+
+impl RootStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            RootStatus::Current => "current",
+            RootStatus::Outdated => "outdated",
+            RootStatus::Orphan => "orphan",
+        }
+    }
+}
+
+/// One directory under `build.forge_dir`.
+#[derive(Serialize)]
+struct BuildRoot {
+    name: String,
+    plan: Option<String>,
+    version: Option<String>,
+    /// Checkpoint progress, e.g. `compile done` or `failed at check`.
+    state: String,
+    #[serde(serialize_with = "serialize_time")]
+    last_used: SystemTime,
+    size: u64,
+    status: RootStatus,
+    /// Mount points still attached below the root.
+    mounts: usize,
+    /// Whether isolation scratch directories survived a crash.
+    stale_scratch: bool,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct ListBody<'a> {
+    forge_dir: &'a Path,
+    roots: &'a [BuildRoot],
+}
+
+/// Current plans, keyed for matching build roots.
+struct PlanSet {
+    /// Build root directory name → plan name, for every readable plan.
+    current: HashMap<String, String>,
+    /// All plan names, including ones that failed to parse.
+    names: HashSet<String>,
+    /// Plans that failed to parse: their roots are never removed.
+    unreadable: HashSet<String>,
+}
+
+impl PlanSet {
+    fn load(config: &GlobalConfig) -> Result<Self> {
+        let index = PlanIndex::discover(&plan_search_dirs(config))?;
+        let mut set = Self {
+            current: HashMap::new(),
+            names: index.names().cloned().collect(),
+            unreadable: HashSet::new(),
+        };
+        for name in index.names() {
+            match index.manifest_for(name) {
+                Ok(Some(manifest)) => {
+                    set.current.insert(build_root_name(&manifest), name.clone());
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    crate::cli_warn!("cannot read plan '{}': {}", name, e);
+                    set.unreadable.insert(name.clone());
+                }
+            }
+        }
+        Ok(set)
+    }
+
+    /// Plan a root belongs to: the checkpoint's record, or else the longest
+    /// plan name the directory name starts with.
+    fn owner(&self, dir_name: &str, state: Option<&ForgeState>) -> Option<String> {
+        if let Some(state) = state {
+            return Some(state.plan_name.clone());
+        }
+        if let Some(plan) = self.current.get(dir_name) {
+            return Some(plan.clone());
+        }
+        self.names
+            .iter()
+            .filter(|name| {
+                dir_name
+                    .strip_prefix(name.as_str())
+                    .is_some_and(|rest| rest.starts_with('-'))
+            })
+            .max_by_key(|name| name.len())
+            .cloned()
+    }
+
+    fn status(&self, dir_name: &str, plan: Option<&str>) -> RootStatus {
+        if self.current.contains_key(dir_name) {
+            return RootStatus::Current;
+        }
+        match plan {
+            // Without a parsed manifest the current version is unknown.
+            Some(plan) if self.unreadable.contains(plan) => RootStatus::Current,
+            Some(plan) if self.names.contains(plan) => RootStatus::Outdated,
+            _ => RootStatus::Orphan,
+        }
+    }
+}
+
+/// Implementation of `wright workshop list`.
+pub async fn execute_list_workshop(config: &GlobalConfig, format: OutputFormat) -> Result<()> {
+    let forge_dir = forge_dir(config)?;
+    let plans = PlanSet::load(config)?;
+    let roots = scan(&forge_dir, &plans)?;
+
+    match format {
+        OutputFormat::Json => {
+            return output::write_json(
+                &mut std::io::stdout().lock(),
+                "workshop list",
+                &ListBody {
+                    forge_dir: &forge_dir,
+                    roots: &roots,
+                },
+            );
+        }
+        OutputFormat::Tsv => {
+            return output::write_tsv(
+                &mut std::io::stdout().lock(),
+                &[
+                    "name",
+                    "plan",
+                    "version",
+                    "state",
+                    "last_used",
+                    "size",
+                    "status",
+                    "mounts",
+                ],
+                roots.iter().map(|r| {
+                    [
+                        r.name.clone(),
+                        r.plan.clone().unwrap_or_default(),
+                        r.version.clone().unwrap_or_default(),
+                        r.state.clone(),
+                        rfc3339(r.last_used),
+                        r.size.to_string(),
+                        r.status.as_str().to_string(),
+                        r.mounts.to_string(),
+                    ]
+                }),
+            );
+        }
+        OutputFormat::Human => {}
+    }
+
+    for root in &roots {
+        let mut notes = Vec::new();
+        if root.status != RootStatus::Current {
+            notes.push(root.status.as_str().to_string());
+        }
+        if root.mounts > 0 {
+            notes.push(format!("{} mount(s)", root.mounts));
+        }
+        if root.stale_scratch {
+            notes.push("stale scratch".to_string());
+        }
+        println!(
+            "{:>10}  {:<36} {:<18} {}  {}",
+            format_bytes(root.size),
+            root.name,
+            root.state,
+            chrono::DateTime::<chrono::Local>::from(root.last_used).format("%Y-%m-%d %H:%M"),
+            notes.join(", ")
+        );
+    }
+    crate::cli_action!(
+        "Summary",
+        "{} build root(s), {}; {} outdated or orphaned",
+        roots.len(),
+        format_bytes(roots.iter().map(|r| r.size).sum()),
+        roots
+            .iter()
+            .filter(|r| r.status != RootStatus::Current)
+            .count(),
+    );
+    Ok(())
+}
+
+/// Implementation of `wright workshop clean`: remove build roots of plans
+/// that no longer exist or have moved to another version, plus roots
+/// unused for longer than `older_than`. Roots that stay have their stale
+/// mounts detached and isolation scratch removed. Reports only, unless
+/// `apply`.
+pub async fn execute_clean_workshop(
+    config: &GlobalConfig,
+    older_than: Option<Duration>,
+    apply: bool,
+) -> Result<()> {
+    let forge_dir = forge_dir(config)?;
+    let plans = PlanSet::load(config)?;
+    let roots = scan(&forge_dir, &plans)?;
+    let now = SystemTime::now();
+
+    let mut removed = 0usize;
+    let mut reclaimed = 0u64;
+    for root in &roots {
+        match removal_reason(root, older_than, now) {
+            Some(reason) => {
+                if !apply {
+                    println!(
+                        "[dry-run] would remove {} ({}, {})",
+                        root.name,
+                        reason,
+                        format_bytes(root.size)
+                    );
+                    removed += 1;
+                    reclaimed += root.size;
+                    continue;
+                }
+                layers::detach_stale_mounts(&root.path).await?;
+                match layers::force_clean_dir(&root.path).await {
+                    Ok(()) => {
+                        crate::cli_action!("Removed", "{} ({})", root.name, reason);
+                        removed += 1;
+                        reclaimed += root.size;
+                    }
+                    Err(e) => crate::cli_warn!("{}", e),
+                }
+            }
+            None => {
+                if root.mounts == 0 && !root.stale_scratch {
+                    continue;
+                }
+                if !apply {
+                    println!(
+                        "[dry-run] would detach {} mount(s) and clear scratch in {}",
+                        root.mounts, root.name
+                    );
+                    continue;
+                }
+                layers::detach_stale_mounts(&root.path).await?;
+                let scratch = root.path.join(ISOLATION_SCRATCH);
+                if root.stale_scratch
+                    && let Err(e) = layers::force_clean_dir(&scratch).await
+                {
+                    crate::cli_warn!("{}", e);
+                }
+                crate::cli_action!("Tidied", "{}", root.name);
+            }
+        }
+    }
+
+    if apply {
+        crate::cli_action!(
+            "Cleaned",
+            "{} build root(s), {} reclaimed",
+            removed,
+            format_bytes(reclaimed)
+        );
+    } else {
+        println!(
+            "[dry-run] {} build root(s), {} reclaimable",
+            removed,
+            format_bytes(reclaimed)
+        );
+        if removed > 0 {
+            println!("[dry-run] pass --apply to delete");
+        }
+    }
+    Ok(())
+}
+
+fn removal_reason(
+    root: &BuildRoot,
+    older_than: Option<Duration>,
+    now: SystemTime,
+) -> Option<String> {
+    match root.status {
+        RootStatus::Orphan => return Some("plan no longer exists".to_string()),
+        RootStatus::Outdated => return Some("plan version changed".to_string()),
+        RootStatus::Current => {}
+    }
+    let threshold = older_than?;
+    let idle = now.duration_since(root.last_used).unwrap_or_default();
+    (idle > threshold).then(|| format!("unused for {}d", idle.as_secs() / 86_400))
+}
+
+fn scan(forge_dir: &Path, plans: &PlanSet) -> Result<Vec<BuildRoot>> {
+    let listing = match std::fs::read_dir(forge_dir) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(WrightError::IoError(std::io::Error::new(
+                e.kind(),
+                format!("read {}: {}", forge_dir.display(), e),
+            )));
+        }
+    };
+
+    let mut roots = Vec::new();
+    for item in listing.flatten() {
+        if !item.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let path = item.path();
+        let name = item.file_name().to_string_lossy().into_owned();
+        if is_reproducible_root(&name) {
+            continue;
+        }
+        let state = Checkpoint::read_state(&path).unwrap_or_else(|e| {
+            crate::cli_warn!("{}", e);
+            None
+        });
+        let plan = plans.owner(&name, state.as_ref());
+        let status = plans.status(&name, plan.as_deref());
+        roots.push(BuildRoot {
+            plan,
+            version: state.as_ref().map(|s| s.version.clone()),
+            state: progress(state.as_ref()),
+            last_used: last_used(&path),
+            size: crate::util::disk_usage(&path),
+            status,
+            mounts: layers::mounts_under(&path).len(),
+            stale_scratch: path.join(ISOLATION_SCRATCH).exists(),
+            name,
+            path,
+        });
+    }
+    roots.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(roots)
+}
+
+fn progress(state: Option<&ForgeState>) -> String {
+    let Some(state) = state else {
+        return "-".to_string();
+    };
+    if let Some((stage, _)) = state
+        .stages
+        .iter()
+        .find(|(_, r)| r.status == StageStatus::Failed)
+    {
+        return format!("failed at {}", stage);
+    }
+    let completed = STAGE_ORDER.iter().rev().find(|stage| {
+        state
+            .stages
+            .get(**stage)
+            .is_some_and(|r| r.status == StageStatus::Completed)
+    });
+    match completed {
+        Some(&"staging") => "complete".to_string(),
+        Some(stage) => format!("{} done", stage),
+        None => "pending".to_string(),
+    }
+}
+
+/// Every build rewrites `staging/` and `logs/` at the top of the root and
+/// updates the checkpoint, so the newer of the two mtimes tracks last use.
+fn last_used(root: &Path) -> SystemTime {
+    [root.to_path_buf(), root.join(CHECKPOINT_FILE)]
+        .iter()
+        .filter_map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+fn serialize_time<S: serde::Serializer>(
+    time: &SystemTime,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&rfc3339(*time))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::foundry::checkpoint::StageRecord;
+
+    fn plans() -> PlanSet {
+        PlanSet {
+            current: HashMap::from([("zlib-1.3".to_string(), "zlib".to_string())]),
+            names: HashSet::from(["zlib".to_string(), "zlib-ng".to_string()]),
+            unreadable: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_status_matches_roots_to_plans() {
+        let plans = plans();
+        let owner = |dir: &str| plans.owner(dir, None);
+        assert_eq!(owner("zlib-1.2"), Some("zlib".to_string()));
+        assert_eq!(owner("zlib-ng-2.1"), Some("zlib-ng".to_string()));
+        assert_eq!(owner("gone-1.0"), None);
+
+        assert_eq!(plans.status("zlib-1.3", Some("zlib")), RootStatus::Current);
+        assert_eq!(plans.status("zlib-1.2", Some("zlib")), RootStatus::Outdated);
+        assert_eq!(plans.status("gone-1.0", None), RootStatus::Orphan);
+    }
+
+    #[test]
+    fn test_progress_summarizes_checkpoint() {
+        let record = |status| StageRecord {
+            status,
+            input_hash: String::new(),
+            completed_at: None,
+            error: None,
+        };
+        let mut state = ForgeState {
+            plan_name: "zlib".to_string(),
+            version: "1.3".to_string(),
+            stages: BTreeMap::new(),
+            build_key: String::new(),
+            inputs: BTreeMap::new(),
+        };
+        assert_eq!(progress(None), "-");
+        assert_eq!(progress(Some(&state)), "pending");
+
+        state
+            .stages
+            .insert("prepare".to_string(), record(StageStatus::Completed));
+        state
+            .stages
+            .insert("configure".to_string(), record(StageStatus::Completed));
+        assert_eq!(progress(Some(&state)), "configure done");
+
+        state
+            .stages
+            .insert("compile".to_string(), record(StageStatus::Failed));
+        assert_eq!(progress(Some(&state)), "failed at compile");
+    }
+}
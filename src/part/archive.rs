@@ -5,7 +5,7 @@ use chrono::Utc;
 use walkdir::WalkDir;
 
 use crate::error::{Result, WrightError};
-use crate::plan::manifest::{PlanManifest, Source};
+use crate::plan::manifest::{Alternative, Patch, PlanManifest, Source};
 
 /// Plan-level metadata extracted from the `[plan]` section of `.PARTINFO`.
 /// All outputs of a plan share these fields; they are stored in the `plans` table.
@@ -39,6 +39,9 @@ pub struct Provenance {
     pub wright_version: String,
     /// Weakest isolation level declared across the plan's pipeline stages.
     pub isolation: String,
+    /// What `network = "proxy"` stages fetched, one
+    /// `<METHOD> <url> <status> sha256=<body hash>` line per request.
+    pub fetches: Vec<String>,
 }
 
 /// Metadata extracted from a .PARTINFO file.
@@ -53,7 +56,9 @@ pub struct PartInfo {
     pub runtime_deps: Vec<String>,
     pub replaces: Vec<String>,
     pub conflicts: Vec<String>,
+    pub provides: Vec<String>,
     pub backup_files: Vec<String>,
+    pub alternatives: Vec<Alternative>,
     pub plan: PlanMetadata,
     pub provenance: Option<Provenance>,
 }
@@ -74,16 +79,20 @@ fn purge_excluded_files(part_dir: &Path) {
 }
 
 /// Create a .wright.tar.zst binary part archive.
+///
+/// `fetches` are the proxied-stage fetch records of the build, sealed into
+/// `[provenance]`.
 pub fn create_part(
     part_dir: &Path,
     manifest: &PlanManifest,
     output_path: &Path,
     source_plan: Option<&PlanManifest>,
+    fetches: &[String],
 ) -> Result<PathBuf> {
     purge_excluded_files(part_dir);
 
     // Generate .PARTINFO
-    let partinfo = generate_partinfo(manifest, source_plan);
+    let partinfo = generate_partinfo(manifest, source_plan, source_date_epoch(), fetches);
 
     // Generate .FILELIST
     let filelist = generate_filelist(part_dir)?;
@@ -100,6 +109,18 @@ pub fn create_part(
         )));
     }
 
+    // An alternative whose target is not in the part would install a
+    // dangling link.
+    for alt in &manifest.alternatives {
+        let target = part_dir.join(alt.target.trim_start_matches('/'));
+        if target.symlink_metadata().is_err() {
+            return Err(WrightError::PartError(format!(
+                "refusing to seal '{}': alternative '{}' targets {}, which the part does not contain",
+                manifest.metadata.name, alt.name, alt.target
+            )));
+        }
+    }
+
     // Write metadata files into part_dir
     std::fs::write(part_dir.join(".PARTINFO"), &partinfo)
         .map_err(|e| WrightError::PartError(format!("failed to write .PARTINFO: {}", e)))?;
@@ -120,7 +141,7 @@ pub fn create_part(
     let archive_name = manifest.part_filename();
     let part_path = output_path.join(&archive_name);
 
-    crate::util::compress::create_tar_zst(part_dir, &part_path)?;
+    crate::util::compress::create_tar_zst(part_dir, &part_path, source_date_epoch())?;
 
     // Clean up metadata files from part_dir
     let _ = std::fs::remove_file(part_dir.join(".PARTINFO"));
@@ -257,8 +278,25 @@ pub fn read_partinfo(part_path: &Path) -> Result<PartInfo> {
     )))
 }
 
-fn generate_partinfo(manifest: &PlanManifest, source_plan: Option<&PlanManifest>) -> String {
-    let build_date = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+/// `SOURCE_DATE_EPOCH` from the environment, when it holds a timestamp.
+/// Setting it makes sealed archives bit-identical for identical trees.
+fn source_date_epoch() -> Option<u64> {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+}
+
+fn generate_partinfo(
+    manifest: &PlanManifest,
+    source_plan: Option<&PlanManifest>,
+    epoch: Option<u64>,
+    fetches: &[String],
+) -> String {
+    let build_date = epoch
+        .and_then(|e| chrono::DateTime::from_timestamp(e as i64, 0))
+        .unwrap_or_else(Utc::now)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
 
     // Determine plan-level metadata: either from the original plan manifest
     // or from the current manifest itself (single-output plans).
@@ -277,7 +315,10 @@ fn generate_partinfo(manifest: &PlanManifest, source_plan: Option<&PlanManifest>
     }
 
     let mut relations_toml = String::new();
-    if !manifest.relations.replaces.is_empty() || !manifest.relations.conflicts.is_empty() {
+    if !manifest.relations.replaces.is_empty()
+        || !manifest.relations.conflicts.is_empty()
+        || !manifest.relations.provides.is_empty()
+    {
         relations_toml.push_str("\n[relations]\n");
         if !manifest.relations.replaces.is_empty() {
             relations_toml.push_str("replaces = [");
@@ -299,6 +340,16 @@ fn generate_partinfo(manifest: &PlanManifest, source_plan: Option<&PlanManifest>
             }
             relations_toml.push_str("]\n");
         }
+        if !manifest.relations.provides.is_empty() {
+            relations_toml.push_str("provides = [");
+            for (i, dep) in manifest.relations.provides.iter().enumerate() {
+                if i > 0 {
+                    relations_toml.push_str(", ");
+                }
+                relations_toml.push_str(&format!("\"{}\"", dep));
+            }
+            relations_toml.push_str("]\n");
+        }
     }
 
     let mut backup_toml = String::new();
@@ -315,6 +366,14 @@ fn generate_partinfo(manifest: &PlanManifest, source_plan: Option<&PlanManifest>
         backup_toml.push_str("]\n");
     }
 
+    let mut alternatives_toml = String::new();
+    for alt in &manifest.alternatives {
+        alternatives_toml.push_str(&format!(
+            "\n[[alternatives]]\nname = \"{}\"\nlink = \"{}\"\ntarget = \"{}\"\npriority = {}\n",
+            alt.name, alt.link, alt.target, alt.priority
+        ));
+    }
+
     let mut plan_toml = String::new();
     plan_toml.push_str("\n[plan]\n");
     plan_toml.push_str(&format!("name = \"{}\"\n", plan.metadata.name));
@@ -334,7 +393,7 @@ fn generate_partinfo(manifest: &PlanManifest, source_plan: Option<&PlanManifest>
 name = "{name}"
 build_date = "{build_date}"
 packager = "wright {wright_version}"
-{runtime_deps}{relations}{backup}{plan}{provenance}
+{runtime_deps}{relations}{backup}{alternatives}{plan}{provenance}
 "#,
         name = manifest.metadata.name,
         build_date = build_date,
@@ -342,24 +401,35 @@ packager = "wright {wright_version}"
         runtime_deps = runtime_deps_toml,
         relations = relations_toml,
         backup = backup_toml,
+        alternatives = alternatives_toml,
         plan = plan_toml,
-        provenance = generate_provenance_toml(plan),
+        provenance = generate_provenance_toml(plan, fetches),
     )
 }
 
 /// Render the `[provenance]` section from the plan-level manifest (ADR-0023).
-fn generate_provenance_toml(plan: &PlanManifest) -> String {
+fn generate_provenance_toml(plan: &PlanManifest, fetches: &[String]) -> String {
     let mut toml = String::from("\n[provenance]\n");
     if let Some(ref sum) = plan.plan_checksum {
         toml.push_str(&format!("plan_checksum = \"{}\"\n", sum));
     }
-    if !plan.sources.entries.is_empty() {
+    let version = plan.metadata.version.as_deref();
+    let lines: Vec<String> = plan
+        .sources
+        .entries
+        .iter()
+        .map(|source| source_provenance_line(source, plan))
+        .chain(
+            plan.patches
+                .iter()
+                .filter(|patch| patch.applies_to(version))
+                .map(|patch| patch_provenance_line(patch, plan)),
+        )
+        .collect();
+    if !lines.is_empty() {
         toml.push_str("source_checksums = [\n");
-        for source in &plan.sources.entries {
-            toml.push_str(&format!(
-                "    \"{}\",\n",
-                source_provenance_line(source, plan)
-            ));
+        for line in lines {
+            toml.push_str(&format!("    \"{}\",\n", line));
         }
         toml.push_str("]\n");
     }
@@ -371,9 +441,22 @@ fn generate_provenance_toml(plan: &PlanManifest) -> String {
         "isolation = \"{}\"\n",
         weakest_isolation_level(plan)
     ));
+    if !fetches.is_empty() {
+        toml.push_str("fetches = [\n");
+        for fetch in fetches {
+            toml.push_str(&format!("    \"{}\",\n", toml_escape(fetch)));
+        }
+        toml.push_str("]\n");
+    }
     toml
 }
 
+/// Escape a string for a TOML basic string. Fetched URLs are whatever a
+/// build tool asked for, so unlike plan fields they may hold quotes.
+fn toml_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 /// One provenance line per source: kind, expanded locator, and the
 /// verification the charge step applied to it.
 fn source_provenance_line(source: &Source, plan: &PlanManifest) -> String {
@@ -393,9 +476,41 @@ fn source_provenance_line(source: &Source, plan: &PlanManifest) -> String {
                 .unwrap_or_else(|| "HEAD".to_string())
         ),
         Source::Local(local) => format!("local {}", process_uri(&local.path, plan)),
+        Source::CargoVendor(cargo) => format!(
+            "cargo_vendor {} registry={}",
+            process_uri(&cargo.lockfile, plan),
+            process_uri(&cargo.registry, plan)
+        ),
+        Source::GoMod(go) => format!(
+            "go_mod {} proxy={}",
+            process_uri(&go.lockfile, plan),
+            process_uri(&go.proxy, plan)
+        ),
+        Source::PipRequirements(pip) => format!(
+            "pip_requirements {} index={}",
+            process_uri(&pip.lockfile, plan),
+            process_uri(&pip.index, plan)
+        ),
     }
 }
 
+/// One provenance line per applied patch: its locator, the checksum it
+/// was pinned to (or, for an unpinned local patch, hashed at load), and
+/// its strip level.
+fn patch_provenance_line(patch: &Patch, plan: &PlanManifest) -> String {
+    use crate::foundry::variables::process_uri;
+    format!(
+        "patch {} sha256={} strip={}",
+        process_uri(patch.locator(), plan),
+        patch
+            .sha256
+            .as_deref()
+            .or(patch.file_checksum.as_deref())
+            .unwrap_or("SKIP"),
+        patch.strip
+    )
+}
+
 /// The weakest isolation level any pipeline stage declares — the
 /// security-relevant fact about the build that produced the part.
 fn weakest_isolation_level(plan: &PlanManifest) -> &'static str {
@@ -411,11 +526,7 @@ fn weakest_isolation_level(plan: &PlanManifest) -> &'static str {
         .values()
         .filter_map(|stage| stage.isolation.parse::<IsolationLevel>().ok())
         .min_by_key(|level| rank(*level))
-        .map(|level| match level {
-            IsolationLevel::None => "none",
-            IsolationLevel::Relaxed => "relaxed",
-            IsolationLevel::Strict => "strict",
-        })
+        .map(IsolationLevel::as_str)
         .unwrap_or("strict")
 }
 
@@ -444,44 +555,67 @@ fn generate_filelist(part_dir: &Path) -> Result<String> {
 /// ```toml
 /// [hooks]
 /// post_install = "ldconfig"
-/// post_upgrade = "systemctl reload nginx"
 /// pre_remove = "systemctl stop nginx"
-/// post_remove = "userdel nginx"
+///
+/// [hooks.post_upgrade]
+/// script = "systemctl reload nginx"
+/// isolation = "none"
+/// on_failure = "warn"
 /// ```
+///
+/// Hooks with the default isolation, timeout, and failure policy keep the
+/// plain string form.
 fn generate_hooks_toml(scripts: &crate::plan::manifest::DeployScripts) -> String {
-    let has_any = scripts.pre_install.is_some()
-        || scripts.post_install.is_some()
-        || scripts.post_upgrade.is_some()
-        || scripts.pre_remove.is_some()
-        || scripts.post_remove.is_some();
-    if !has_any {
-        return String::new();
-    }
-
-    let mut content = String::from("[hooks]\n");
-    for (key, value) in [
+    let hooks = [
         ("pre_install", &scripts.pre_install),
         ("post_install", &scripts.post_install),
         ("post_upgrade", &scripts.post_upgrade),
         ("pre_remove", &scripts.pre_remove),
         ("post_remove", &scripts.post_remove),
-    ] {
-        if let Some(s) = value {
-            let trimmed = s.trim();
-            if trimmed.contains('\n') {
-                content.push_str(&format!("{} = \"\"\"\n{}\n\"\"\"\n", key, trimmed));
-            } else {
-                content.push_str(&format!(
-                    "{} = \"{}\"\n",
-                    key,
-                    trimmed.replace('\\', "\\\\").replace('"', "\\\"")
-                ));
+    ];
+    if hooks.iter().all(|(_, hook)| hook.is_none()) {
+        return String::new();
+    }
+
+    let is_plain = |hook: &crate::plan::manifest::Hook| {
+        hook.isolation == crate::isolation::IsolationLevel::Strict
+            && hook.timeout.is_none()
+            && hook.on_failure == crate::plan::manifest::HookFailurePolicy::Warn
+    };
+    let mut content = String::from("[hooks]\n");
+    for (key, hook) in hooks {
+        if let Some(hook) = hook
+            && is_plain(hook)
+        {
+            content.push_str(&format!("{} = {}\n", key, toml_script(&hook.script)));
+        }
+    }
+    for (key, hook) in hooks {
+        if let Some(hook) = hook
+            && !is_plain(hook)
+        {
+            content.push_str(&format!("\n[hooks.{}]\n", key));
+            content.push_str(&format!("script = {}\n", toml_script(&hook.script)));
+            content.push_str(&format!("isolation = \"{}\"\n", hook.isolation.as_str()));
+            if let Some(timeout) = hook.timeout {
+                content.push_str(&format!("timeout = {}\n", timeout));
             }
+            content.push_str(&format!("on_failure = \"{}\"\n", hook.on_failure.as_str()));
         }
     }
     content
 }
 
+/// A trimmed script as a TOML string: multi-line scripts as a `"""` block.
+fn toml_script(script: &str) -> String {
+    let trimmed = script.trim();
+    if trimmed.contains('\n') {
+        format!("\"\"\"\n{}\n\"\"\"", trimmed)
+    } else {
+        format!("\"{}\"", trimmed.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
 fn parse_partinfo(path: &Path) -> Result<PartInfo> {
     let content = std::fs::read_to_string(path).map_err(|e| {
         WrightError::PartError(format!(
@@ -504,6 +638,8 @@ fn parse_partinfo_str(content: &str, source: &str) -> Result<PartInfo> {
         #[serde(default)]
         backup: Option<PartInfoBackup>,
         #[serde(default)]
+        alternatives: Vec<Alternative>,
+        #[serde(default)]
         provenance: Option<PartInfoProvenance>,
     }
 
@@ -517,6 +653,8 @@ fn parse_partinfo_str(content: &str, source: &str) -> Result<PartInfo> {
         wright_version: String,
         #[serde(default)]
         isolation: String,
+        #[serde(default)]
+        fetches: Vec<String>,
     }
 
     #[derive(serde::Deserialize)]
@@ -545,6 +683,8 @@ fn parse_partinfo_str(content: &str, source: &str) -> Result<PartInfo> {
         replaces: Vec<String>,
         #[serde(default)]
         conflicts: Vec<String>,
+        #[serde(default)]
+        provides: Vec<String>,
     }
 
     #[derive(serde::Deserialize)]
@@ -571,7 +711,9 @@ fn parse_partinfo_str(content: &str, source: &str) -> Result<PartInfo> {
         runtime_deps: parsed.part.runtime_deps,
         replaces: relations.replaces,
         conflicts: relations.conflicts,
+        provides: relations.provides,
         backup_files: parsed.backup.map(|b| b.files).unwrap_or_default(),
+        alternatives: parsed.alternatives,
         plan: PlanMetadata {
             name: plan_section.name,
             version: plan_section.version,
@@ -584,6 +726,7 @@ fn parse_partinfo_str(content: &str, source: &str) -> Result<PartInfo> {
             source_checksums: p.source_checksums,
             wright_version: p.wright_version,
             isolation: p.isolation,
+            fetches: p.fetches,
         }),
     })
 }
@@ -611,17 +754,95 @@ arch = "x86_64"
 
         // An empty staging tree must not seal (regression: wright 5.0.2
         // packed metadata-only parts that deployed zero files).
-        let err = super::create_part(staging.path(), &manifest, out.path(), None).unwrap_err();
+        let err = super::create_part(staging.path(), &manifest, out.path(), None, &[]).unwrap_err();
         assert!(err.to_string().contains("contains no files"), "{err}");
         assert!(!out.path().join(manifest.part_filename()).exists());
 
         // The same tree with payload seals fine.
         std::fs::create_dir_all(staging.path().join("usr/bin")).unwrap();
         std::fs::write(staging.path().join("usr/bin/demo"), "x").unwrap();
-        let part = super::create_part(staging.path(), &manifest, out.path(), None).unwrap();
+        let part = super::create_part(staging.path(), &manifest, out.path(), None, &[]).unwrap();
         assert!(part.exists());
     }
 
+    #[test]
+    fn hooks_toml_roundtrips_hook_tables() {
+        use crate::isolation::IsolationLevel;
+        use crate::plan::manifest::{DeployScripts, FabricateHooks, Hook, HookFailurePolicy};
+
+        let scripts = DeployScripts {
+            pre_install: None,
+            post_install: Some(Hook::new("ldconfig")),
+            post_upgrade: Some(Hook {
+                script: "echo \"reloading\"\nsystemctl reload demo".to_string(),
+                isolation: IsolationLevel::None,
+                timeout: Some(30),
+                on_failure: HookFailurePolicy::Defer,
+            }),
+            pre_remove: None,
+            post_remove: None,
+        };
+        let content = super::generate_hooks_toml(&scripts);
+        assert!(content.contains("post_install = \"ldconfig\""), "{content}");
+
+        #[derive(serde::Deserialize)]
+        struct HooksFile {
+            hooks: FabricateHooks,
+        }
+        let parsed: HooksFile = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.hooks.post_install, scripts.post_install);
+        let post_upgrade = parsed.hooks.post_upgrade.unwrap();
+        assert_eq!(
+            post_upgrade.script.trim_end(),
+            "echo \"reloading\"\nsystemctl reload demo"
+        );
+        assert_eq!(post_upgrade.isolation, IsolationLevel::None);
+        assert_eq!(post_upgrade.timeout, Some(30));
+        assert_eq!(post_upgrade.on_failure, HookFailurePolicy::Defer);
+        assert_eq!(parsed.hooks.pre_remove, None);
+    }
+
+    #[test]
+    fn partinfo_roundtrips_alternatives() {
+        let plan = crate::plan::manifest::PlanManifest::parse(
+            r#"
+name = "neovim"
+version = "0.10.0"
+release = 1
+description = "Vim-fork focused on extensibility"
+license = "Apache-2.0"
+arch = "x86_64"
+
+[[output]]
+
+[[output.alternatives]]
+name = "vi"
+link = "/usr/bin/vi"
+target = "/usr/bin/nvim"
+priority = 50
+"#,
+        )
+        .unwrap();
+        let (name, output) = plan.catchall_part().unwrap();
+        let manifest = output.to_manifest(name, &plan);
+
+        let info = parse_partinfo_str(
+            &generate_partinfo(&manifest, Some(&plan), Some(0), &[]),
+            "test",
+        )
+        .unwrap();
+        assert_eq!(info.alternatives, manifest.alternatives);
+        assert_eq!(info.alternatives[0].priority, 50);
+
+        // The target must be part of the sealed tree.
+        let staging = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(staging.path().join("usr/bin")).unwrap();
+        std::fs::write(staging.path().join("usr/bin/vim"), "x").unwrap();
+        let err = super::create_part(staging.path(), &manifest, out.path(), None, &[]).unwrap_err();
+        assert!(err.to_string().contains("/usr/bin/nvim"), "{err}");
+    }
+
     #[test]
     fn parse_partinfo_accepts_runtime_dependencies() {
         let info = parse_partinfo_str(
@@ -719,6 +940,14 @@ type = "git"
 url = "https://example.org/demo.git"
 ref = "v${VERSION}"
 
+[[patches]]
+url = "https://example.org/fix-${VERSION}.patch"
+sha256 = "def456"
+
+[[patches]]
+path = "old.patch"
+versions = ["< 1.0"]
+
 [pipeline.compile]
 executor = "shell"
 isolation = "none"
@@ -732,8 +961,13 @@ script = "true"
         let mut manifest = crate::plan::manifest::PlanManifest::parse(toml_str).unwrap();
         manifest.plan_checksum = Some("deadbeef".to_string());
 
-        let partinfo = generate_partinfo(&manifest, None);
+        let fetches = vec![
+            "GET https://index.crates.io/config.json 200 sha256=abc".to_string(),
+            "GET http://example.org/\"q\" 404 sha256=def".to_string(),
+        ];
+        let partinfo = generate_partinfo(&manifest, None, Some(1_700_000_000), &fetches);
         let info = parse_partinfo_str(&partinfo, "test").unwrap();
+        assert_eq!(info.build_date, "2023-11-14T22:13:20Z");
 
         let provenance = info.provenance.expect("generated .PARTINFO has provenance");
         assert_eq!(provenance.plan_checksum.as_deref(), Some("deadbeef"));
@@ -742,11 +976,13 @@ script = "true"
             vec![
                 "http https://example.org/demo-1.2.3.tar.gz sha256=abc123",
                 "git https://example.org/demo.git ref=v1.2.3",
+                "patch https://example.org/fix-1.2.3.patch sha256=def456 strip=1",
             ]
         );
         assert_eq!(provenance.wright_version, env!("CARGO_PKG_VERSION"));
         // Weakest of {none, strict} is none.
         assert_eq!(provenance.isolation, "none");
+        assert_eq!(provenance.fetches, fetches);
     }
 
     #[test]
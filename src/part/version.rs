@@ -324,6 +324,35 @@ pub fn parse_dependency(dep: &str) -> Result<(String, Option<VersionConstraint>)
     Ok((dep.to_string(), None))
 }
 
+/// Parse a `provides` entry: a virtual name, optionally pinned to the
+/// version it provides (`http-server=2.4`).
+pub fn parse_provide(entry: &str) -> Result<(String, Option<Version>)> {
+    let entry = entry.trim();
+    let (name, version) = match entry.split_once('=') {
+        Some((name, version)) => (name.trim(), Some(Version::parse(version.trim())?)),
+        None => (entry, None),
+    };
+    if !is_valid_dep_component(name) {
+        return Err(WrightError::ValidationError(format!(
+            "provides entry '{}': invalid name '{}'",
+            entry, name
+        )));
+    }
+    Ok((name.to_string(), version))
+}
+
+/// Whether a provide of `version` satisfies a dependency's `constraint`.
+/// An unversioned provide only satisfies unconstrained dependencies.
+pub fn provide_satisfies(
+    version: Option<&Version>,
+    constraint: Option<&VersionConstraint>,
+) -> bool {
+    match constraint {
+        None => true,
+        Some(constraint) => version.is_some_and(|v| constraint.satisfies(v)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -448,6 +477,20 @@ mod tests {
         assert!(constraint.is_none());
     }
 
+    #[test]
+    fn test_parse_provide() {
+        let (name, version) = parse_provide("http-server=2.4").unwrap();
+        assert_eq!(name, "http-server");
+        assert_eq!(version, Some(Version::parse("2.4").unwrap()));
+        assert_eq!(parse_provide("sh").unwrap(), ("sh".to_string(), None));
+        assert!(parse_provide("Bad Name").is_err());
+
+        let ge = VersionConstraint::parse(">= 2.0").unwrap();
+        assert!(provide_satisfies(version.as_ref(), Some(&ge)));
+        assert!(!provide_satisfies(None, Some(&ge)));
+        assert!(provide_satisfies(None, None));
+    }
+
     #[test]
     fn test_parse_dep_ref() {
         assert_eq!(
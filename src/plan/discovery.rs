@@ -1,8 +1,9 @@
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use crate::error::{Result, WrightError};
+use crate::part::version::{self, VersionConstraint};
 use crate::plan::manifest::PlanManifest;
 
 /// Walk plan directories and collect every `plan.toml` path found.
@@ -41,6 +42,9 @@ pub fn discover_plan_paths(plan_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
 pub struct PlanIndex {
     entries: HashMap<String, PathBuf>,
     cache: RefCell<HashMap<String, PlanManifest>>,
+    /// `[providers]` from `wright.toml`: virtual name → preferred plans or
+    /// outputs, most preferred first.
+    provider_prefs: BTreeMap<String, Vec<String>>,
 }
 
 impl PlanIndex {
@@ -60,6 +64,7 @@ impl PlanIndex {
         Ok(Self {
             entries,
             cache: RefCell::new(HashMap::new()),
+            provider_prefs: BTreeMap::new(),
         })
     }
 
@@ -68,9 +73,16 @@ impl PlanIndex {
         Self {
             entries,
             cache: RefCell::new(HashMap::new()),
+            provider_prefs: BTreeMap::new(),
         }
     }
 
+    /// Set the preferred providers of virtual dependencies (`[providers]`).
+    pub fn with_provider_preferences(mut self, prefs: BTreeMap<String, Vec<String>>) -> Self {
+        self.provider_prefs = prefs;
+        self
+    }
+
     /// Look up the filesystem path for a plan by its declared name.
     pub fn path_for(&self, name: &str) -> Option<&PathBuf> {
         self.entries.get(name)
@@ -110,6 +122,95 @@ impl PlanIndex {
         Ok(results)
     }
 
+    /// `(plan, output)` pairs whose `provides` name `name` with a version
+    /// satisfying `constraint`, sorted.
+    pub fn providers_of(
+        &self,
+        name: &str,
+        constraint: Option<&VersionConstraint>,
+    ) -> Result<Vec<(String, String)>> {
+        let mut found = Vec::new();
+        for (plan, manifest) in self.load_all()? {
+            let mut outputs: Vec<(String, &[String])> = manifest
+                .output_parts()
+                .map(|(output, sub)| (output.to_string(), sub.provides.as_slice()))
+                .collect();
+            if manifest.outputs.is_none() {
+                outputs.push((plan.clone(), manifest.relations.provides.as_slice()));
+            }
+            for (output, provides) in outputs {
+                let provides_name = provides.iter().any(|entry| {
+                    version::parse_provide(entry).is_ok_and(|(provided, v)| {
+                        provided == name && version::provide_satisfies(v.as_ref(), constraint)
+                    })
+                });
+                if provides_name {
+                    found.push((plan.clone(), output));
+                }
+            }
+        }
+        found.sort();
+        Ok(found)
+    }
+
+    /// Pick the plan and output that satisfy a dependency on virtual name
+    /// `name`, or `None` when no plan provides it.
+    ///
+    /// Providers in `installed` (output names) win, since the choice has
+    /// already been made on the target.  Among several plans the first
+    /// `[providers]` preference that matches a plan or output wins;
+    /// without one the choice is ambiguous and an error lists the
+    /// candidates.
+    pub fn resolve_provider(
+        &self,
+        name: &str,
+        constraint: Option<&VersionConstraint>,
+        installed: &HashSet<String>,
+    ) -> Result<Option<(String, String)>> {
+        let mut candidates = self.providers_of(name, constraint)?;
+        if candidates
+            .iter()
+            .any(|(_, output)| installed.contains(output))
+        {
+            candidates.retain(|(_, output)| installed.contains(output));
+        }
+        let Some(first) = candidates.first() else {
+            return Ok(None);
+        };
+        if candidates.iter().all(|(plan, _)| plan == &first.0) {
+            return Ok(Some(first.clone()));
+        }
+
+        let prefs = self.provider_prefs.get(name).map(Vec::as_slice);
+        for pref in prefs.unwrap_or_default() {
+            if let Some(chosen) = candidates
+                .iter()
+                .find(|(plan, output)| plan == pref || output == pref)
+            {
+                return Ok(Some(chosen.clone()));
+            }
+        }
+
+        let listed: Vec<String> = candidates
+            .iter()
+            .map(|(plan, output)| {
+                if plan == output {
+                    plan.clone()
+                } else {
+                    format!("{plan}:{output}")
+                }
+            })
+            .collect();
+        Err(WrightError::DependencyError(format!(
+            "'{}' is provided by several plans ({}); choose one with \
+             `[providers] {} = [\"{}\"]` in wright.toml",
+            name,
+            listed.join(", "),
+            name,
+            candidates[0].0
+        )))
+    }
+
     /// Iterate over all known plan names.
     pub fn names(&self) -> impl Iterator<Item = &String> {
         self.entries.keys()
@@ -158,3 +259,73 @@ impl PlanIndex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_plan(root: &Path, name: &str, provides: &str) {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("plan.toml"),
+            format!(
+                r#"
+name = "{name}"
+version = "1.0.0"
+release = 1
+description = "{name}"
+license = "MIT"
+arch = "x86_64"
+
+[pipeline.staging]
+script = "true"
+
+[[output]]
+provides = [{provides}]
+"#
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_resolve_provider_prefers_configured_then_fails_when_ambiguous() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plan(dir.path(), "apache", r#""http-server=2.4""#);
+        write_plan(dir.path(), "nginx", r#""http-server=1.25""#);
+        write_plan(dir.path(), "dash", r#""sh""#);
+        let index = PlanIndex::discover(&[dir.path().to_path_buf()]).unwrap();
+        let none = HashSet::new();
+
+        let sh = index.resolve_provider("sh", None, &none).unwrap();
+        assert_eq!(sh, Some(("dash".to_string(), "dash".to_string())));
+        assert_eq!(index.resolve_provider("awk", None, &none).unwrap(), None);
+
+        // Only apache's provide satisfies the constraint.
+        let constraint = VersionConstraint::parse(">= 2.0").unwrap();
+        let chosen = index
+            .resolve_provider("http-server", Some(&constraint), &none)
+            .unwrap();
+        assert_eq!(chosen.unwrap().0, "apache");
+
+        let err = index
+            .resolve_provider("http-server", None, &none)
+            .unwrap_err();
+        assert!(err.to_string().contains("(apache, nginx)"), "{err}");
+        assert!(err.to_string().contains("[providers]"), "{err}");
+
+        let installed = HashSet::from(["nginx".to_string()]);
+        let chosen = index
+            .resolve_provider("http-server", None, &installed)
+            .unwrap();
+        assert_eq!(chosen.unwrap().0, "nginx");
+
+        let index = index.with_provider_preferences(BTreeMap::from([(
+            "http-server".to_string(),
+            vec!["lighttpd".to_string(), "nginx".to_string()],
+        )]));
+        let chosen = index.resolve_provider("http-server", None, &none).unwrap();
+        assert_eq!(chosen.unwrap().0, "nginx");
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::{Component, Path};
 
 use serde::Deserialize;
 
@@ -11,18 +12,149 @@ mod parse;
 // Fabricate output types
 // ---------------------------------------------------------------------------
 
+/// Wall-clock limit of a hook that does not set `timeout`, in seconds.
+pub const DEFAULT_HOOK_TIMEOUT: u64 = 600;
+
+/// What a failing deploy hook does to its operation (`on_failure`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HookFailurePolicy {
+    /// Fail the operation, and with it the delivery.
+    Abort,
+    /// Report the failure, record it, and carry on.
+    #[default]
+    Warn,
+    /// Record the failure and run the hook again once the rest of the
+    /// delivery is done.
+    Defer,
+}
+
+impl HookFailurePolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Abort => "abort",
+            Self::Warn => "warn",
+            Self::Defer => "defer",
+        }
+    }
+}
+
+impl std::str::FromStr for HookFailurePolicy {
+    type Err = WrightError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "abort" => Ok(Self::Abort),
+            "warn" => Ok(Self::Warn),
+            "defer" => Ok(Self::Defer),
+            _ => Err(WrightError::ValidationError(format!(
+                "invalid hook on_failure '{}' (valid: abort, warn, defer)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A deploy hook: a bare script, or a table that also says how it runs.
+///
+/// ```toml
+/// post_install = "ldconfig"
+/// post_upgrade = { script = "systemctl reload nginx", isolation = "none" }
+/// post_remove = { script = "mandb -q", on_failure = "defer" }
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(try_from = "HookToml")]
+pub struct Hook {
+    pub script: String,
+    /// `strict` (the default) runs the script in its own namespaces with
+    /// the target root mounted writable as `/` and no network; `relaxed`
+    /// keeps the host network; `none` runs it directly on the host,
+    /// chrooted into the target root when that is not `/`.
+    pub isolation: crate::isolation::IsolationLevel,
+    /// Wall-clock limit in seconds; `None` means [`DEFAULT_HOOK_TIMEOUT`].
+    pub timeout: Option<u64>,
+    pub on_failure: HookFailurePolicy,
+}
+
+impl Hook {
+    /// A hook running `script` with the default isolation and timeout.
+    pub fn new(script: impl Into<String>) -> Self {
+        Self {
+            script: script.into(),
+            isolation: crate::isolation::IsolationLevel::Strict,
+            timeout: None,
+            on_failure: HookFailurePolicy::Warn,
+        }
+    }
+
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HookToml {
+    Script(String),
+    Table(HookTable),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HookTable {
+    script: String,
+    #[serde(default)]
+    isolation: Option<String>,
+    #[serde(default)]
+    timeout: Option<u64>,
+    #[serde(default)]
+    on_failure: Option<String>,
+}
+
+impl TryFrom<HookToml> for Hook {
+    type Error = WrightError;
+
+    fn try_from(value: HookToml) -> Result<Self> {
+        match value {
+            HookToml::Script(script) => Ok(Self::new(script)),
+            HookToml::Table(table) => {
+                let isolation = match table.isolation {
+                    Some(level) => level.parse().map_err(|e| {
+                        WrightError::ValidationError(format!("invalid hook isolation: {e}"))
+                    })?,
+                    None => crate::isolation::IsolationLevel::Strict,
+                };
+                if table.timeout == Some(0) {
+                    return Err(WrightError::ValidationError(
+                        "hook timeout must be at least 1 second".to_string(),
+                    ));
+                }
+                let on_failure = match table.on_failure {
+                    Some(policy) => policy.parse()?,
+                    None => HookFailurePolicy::Warn,
+                };
+                Ok(Self {
+                    script: table.script,
+                    isolation,
+                    timeout: table.timeout,
+                    on_failure,
+                })
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct FabricateHooks {
     #[serde(default)]
-    pub pre_install: Option<String>,
+    pub pre_install: Option<Hook>,
     #[serde(default)]
-    pub post_install: Option<String>,
+    pub post_install: Option<Hook>,
     #[serde(default)]
-    pub post_upgrade: Option<String>,
+    pub post_upgrade: Option<Hook>,
     #[serde(default)]
-    pub pre_remove: Option<String>,
+    pub pre_remove: Option<Hook>,
     #[serde(default)]
-    pub post_remove: Option<String>,
+    pub post_remove: Option<Hook>,
 }
 
 /// Additional output mode.
@@ -45,7 +177,8 @@ pub struct SubFabricateOutput {
     /// Parts that cannot coexist with this output.
     #[serde(default)]
     pub conflicts: Vec<String>,
-    /// Virtual part names this output satisfies.
+    /// Virtual part names this output satisfies, each optionally with the
+    /// version it provides (`http-server=2.4`).
     #[serde(default)]
     pub provides: Vec<String>,
     #[serde(default)]
@@ -56,6 +189,25 @@ pub struct SubFabricateOutput {
     pub hooks: Option<FabricateHooks>,
     #[serde(default)]
     pub backup: Option<Vec<String>>,
+    /// Alternative names this output can be selected for.
+    #[serde(default)]
+    pub alternatives: Vec<Alternative>,
+}
+
+/// One `[[output.alternatives]]` entry: this output is a candidate for the
+/// alternative `name`, whose `link` points at `target` while it is selected.
+/// The installed candidate with the highest `priority` is selected unless
+/// `wright alternative pin` says otherwise.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Alternative {
+    pub name: String,
+    /// Absolute path of the managed symlink, e.g. `/usr/bin/vi`.
+    pub link: String,
+    /// Absolute path shipped by this output, e.g. `/usr/bin/nvim`.
+    pub target: String,
+    #[serde(default)]
+    pub priority: i32,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -84,15 +236,15 @@ pub enum OutputConfig {
 #[derive(Debug, Deserialize, Clone)]
 pub struct DeployScripts {
     #[serde(default)]
-    pub pre_install: Option<String>,
+    pub pre_install: Option<Hook>,
     #[serde(default)]
-    pub post_install: Option<String>,
+    pub post_install: Option<Hook>,
     #[serde(default)]
-    pub post_upgrade: Option<String>,
+    pub post_upgrade: Option<Hook>,
     #[serde(default)]
-    pub pre_remove: Option<String>,
+    pub pre_remove: Option<Hook>,
     #[serde(default)]
-    pub post_remove: Option<String>,
+    pub post_remove: Option<Hook>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -113,7 +265,8 @@ pub struct BackupConfig {
 /// - **`conflicts`**: Mutual exclusion — installation is refused while a
 ///   conflicting part is present. Use when two parts cannot coexist.
 /// - **`provides`**: Virtual names — allows this part to satisfy dependencies on
-///   an abstract capability (e.g. `http-server`).
+///   an abstract capability (e.g. `http-server`), versioned as
+///   `http-server=2.4` to satisfy constrained dependencies.
 ///
 /// Declared per-output in `[[output]]`.
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -134,6 +287,9 @@ pub enum Source {
     Http(HttpSource),
     Git(GitSource),
     Local(LocalSource),
+    CargoVendor(CargoVendorSource),
+    GoMod(GoModSource),
+    PipRequirements(PipRequirementsSource),
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -170,6 +326,109 @@ pub struct LocalSource {
     pub extract_to: Option<String>,
 }
 
+/// Every crates.io crate a `Cargo.lock` pins, vendored for offline builds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CargoVendorSource {
+    /// `Cargo.lock` path relative to the plan directory.
+    pub lockfile: String,
+    /// Base URL crates are downloaded from as `<name>/<name>-<version>.crate`.
+    #[serde(default = "default_crate_registry")]
+    pub registry: String,
+    /// Optional subdirectory under WORKDIR to place `vendor/` and `.cargo/` in.
+    pub extract_to: Option<String>,
+}
+
+/// Every module version a `go.sum` pins, served as a local module proxy.
+#[derive(Debug, Deserialize, Clone)]
+pub struct GoModSource {
+    /// `go.sum` path relative to the plan directory.
+    pub lockfile: String,
+    /// Module proxy the modules are downloaded from.
+    #[serde(default = "default_go_proxy")]
+    pub proxy: String,
+    /// Optional subdirectory under WORKDIR to place `vendor/goproxy/` in.
+    pub extract_to: Option<String>,
+}
+
+/// Every requirement of a hash-pinned `requirements.txt`, as a wheelhouse.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PipRequirementsSource {
+    /// `requirements.txt` path relative to the plan directory.
+    pub lockfile: String,
+    /// Base URL of the index's JSON API (`<index>/<name>/<version>/json`).
+    #[serde(default = "default_pip_index")]
+    pub index: String,
+    /// Optional subdirectory under WORKDIR to place `vendor/pypi/` in.
+    pub extract_to: Option<String>,
+}
+
+/// A patch `Charge` applies to the extracted sources (`[[patches]]`), in
+/// declaration order.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Patch {
+    /// Patch file relative to the plan directory. Exclusive with `url`.
+    pub path: Option<String>,
+    /// Remote patch file. Exclusive with `path`.
+    pub url: Option<String>,
+    /// SHA-256 of the patch file; required with `url`, checked when set
+    /// with `path`.
+    pub sha256: Option<String>,
+    /// Leading path components to drop from the file names in the patch.
+    #[serde(default = "default_patch_strip")]
+    pub strip: u32,
+    /// Subdirectory under WORKDIR the patch applies in.
+    pub target: Option<String>,
+    /// Version constraints (`">= 1.2"`) that must all hold for the patch
+    /// to apply.
+    #[serde(default)]
+    pub versions: Vec<String>,
+    /// SHA-256 of the `path` file, resolved by [`PlanManifest::from_file`]
+    /// so the build key follows edits to the patch.
+    #[serde(skip)]
+    pub file_checksum: Option<String>,
+}
+
+impl Patch {
+    /// `path` or `url`, as written in the plan.
+    pub fn locator(&self) -> &str {
+        self.path
+            .as_deref()
+            .or(self.url.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Whether the plan version satisfies every `versions` constraint.
+    pub fn applies_to(&self, version: Option<&str>) -> bool {
+        if self.versions.is_empty() {
+            return true;
+        }
+        let Some(Ok(version)) = version.map(crate::part::version::Version::parse) else {
+            return false;
+        };
+        self.versions.iter().all(|c| {
+            crate::part::version::VersionConstraint::parse(c)
+                .is_ok_and(|constraint| constraint.satisfies(&version))
+        })
+    }
+}
+
+fn default_patch_strip() -> u32 {
+    1
+}
+
+fn default_crate_registry() -> String {
+    "https://static.crates.io/crates".to_string()
+}
+
+fn default_go_proxy() -> String {
+    "https://proxy.golang.org".to_string()
+}
+
+fn default_pip_index() -> String {
+    "https://pypi.org/pypi".to_string()
+}
+
 fn default_skip() -> String {
     "SKIP".to_string()
 }
@@ -191,6 +450,8 @@ pub struct PlanManifest {
     pub runtime_deps: Vec<String>,
     pub relations: Relations,
     pub sources: Sources,
+    /// Patches applied after extraction, in order.
+    pub patches: Vec<Patch>,
     pub options: PlanBuildOptions,
     pub pipeline: HashMap<String, PipelineStage>,
     pub pipeline_order: Option<PipelineOrder>,
@@ -202,6 +463,7 @@ pub struct PlanManifest {
     /// Derived archive metadata populated from outputs.
     pub deploy_scripts: Option<DeployScripts>,
     pub backup: Option<BackupConfig>,
+    pub alternatives: Vec<Alternative>,
     /// For sub-outputs, the original plan name. Used to write plan-level
     /// metadata into the pack archive.
     pub source_plan: Option<String>,
@@ -257,6 +519,24 @@ pub struct PlanBuildOptions {
     pub cpu_time_limit: Option<u64>,
     #[serde(default)]
     pub timeout: Option<u64>,
+    /// cgroup v2 limits for each stage's isolation, overriding the global
+    /// ones: `memory_max` (MB), `pids_max`, `cpu_max` (CPUs), `io_max`.
+    #[serde(default)]
+    pub memory_max: Option<u64>,
+    #[serde(default)]
+    pub pids_max: Option<u64>,
+    #[serde(default)]
+    pub cpu_max: Option<f64>,
+    #[serde(default)]
+    pub io_max: Option<Vec<String>>,
+    /// Syscalls blocked in strict isolation that this plan's stages may
+    /// make anyway, e.g. `["ptrace"]` for a test suite that debugs itself.
+    #[serde(default)]
+    pub allow_syscalls: Vec<String>,
+    /// Capabilities this plan's strict stages keep beyond the defaults,
+    /// e.g. `["CAP_NET_RAW"]`.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
     /// Skip FHS validation after the final output stage.
     /// Set to `true` only for parts with a deliberate reason to install
     /// outside the standard FHS paths (e.g. kernel modules, legacy compat layers).
@@ -280,12 +560,28 @@ impl Default for PlanBuildOptions {
             memory_limit: None,
             cpu_time_limit: None,
             timeout: None,
+            memory_max: None,
+            pids_max: None,
+            cpu_max: None,
+            io_max: None,
+            allow_syscalls: Vec::new(),
+            capabilities: Vec::new(),
             skip_fhs_check: false,
             skip_elf_lint: false,
         }
     }
 }
 
+impl PlanBuildOptions {
+    /// What strict isolation lets this plan's stages keep.
+    pub fn hardening(&self) -> crate::isolation::harden::Hardening {
+        crate::isolation::harden::Hardening {
+            allow_syscalls: self.allow_syscalls.clone(),
+            capabilities: self.capabilities.clone(),
+        }
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -296,6 +592,10 @@ pub struct PipelineStage {
     pub executor: String,
     #[serde(default = "default_isolation_level")]
     pub isolation: String,
+    /// `"proxy"` confines the stage's network access to a wright-run
+    /// HTTP(S) proxy that records every fetch; see [`crate::foundry::proxy`].
+    #[serde(default = "default_network_mode")]
+    pub network: String,
     #[serde(default)]
     pub env: HashMap<String, String>,
     #[serde(default)]
@@ -310,6 +610,10 @@ fn default_isolation_level() -> String {
     "strict".to_string()
 }
 
+fn default_network_mode() -> String {
+    "isolation".to_string()
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct PipelineOrder {
     pub stages: Vec<String>,
@@ -409,6 +713,42 @@ impl PlanManifest {
 
         // Each source entry is self-contained (uri + sha256), no positional check needed
 
+        for (i, patch) in self.patches.iter().enumerate() {
+            match (&patch.path, &patch.url) {
+                (Some(_), None) => {}
+                (None, Some(_)) if patch.sha256.is_some() => {}
+                (None, Some(url)) => {
+                    return Err(WrightError::ValidationError(format!(
+                        "[[patches]] entry {i} ({url}): 'url' requires 'sha256'"
+                    )));
+                }
+                _ => {
+                    return Err(WrightError::ValidationError(format!(
+                        "[[patches]] entry {i}: set exactly one of 'path' and 'url'"
+                    )));
+                }
+            }
+            if !patch.versions.is_empty() && self.metadata.version.is_none() {
+                return Err(WrightError::ValidationError(format!(
+                    "[[patches]] entry {i} ({}): 'versions' needs a plan version",
+                    patch.locator()
+                )));
+            }
+            for constraint in &patch.versions {
+                crate::part::version::VersionConstraint::parse(constraint)?;
+            }
+            if let Some(ref target) = patch.target
+                && !Path::new(target)
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(WrightError::ValidationError(format!(
+                    "[[patches]] entry {i} ({}): 'target' must be a relative path inside WORKDIR",
+                    patch.locator()
+                )));
+            }
+        }
+
         // Validate output config
         if let Some(ref part) = self.outputs {
             match part {
@@ -458,6 +798,41 @@ impl PlanManifest {
                                 sub_name
                             )));
                         }
+                        for entry in &sub_part.provides {
+                            crate::part::version::parse_provide(entry).map_err(|e| {
+                                WrightError::ValidationError(format!(
+                                    "output '{}': {}",
+                                    sub_name, e
+                                ))
+                            })?;
+                        }
+                        let mut alternative_names = std::collections::HashSet::new();
+                        for alt in &sub_part.alternatives {
+                            if !name_re.is_match(&alt.name) {
+                                return Err(WrightError::ValidationError(format!(
+                                    "output '{}': invalid alternative name '{}': must match [a-z0-9][a-z0-9_+.-]*",
+                                    sub_name, alt.name
+                                )));
+                            }
+                            if !alternative_names.insert(alt.name.as_str()) {
+                                return Err(WrightError::ValidationError(format!(
+                                    "output '{}': duplicate alternative '{}'",
+                                    sub_name, alt.name
+                                )));
+                            }
+                            if !alt.link.starts_with('/') || !alt.target.starts_with('/') {
+                                return Err(WrightError::ValidationError(format!(
+                                    "output '{}': alternative '{}': link and target must be absolute paths",
+                                    sub_name, alt.name
+                                )));
+                            }
+                            if alt.link == alt.target {
+                                return Err(WrightError::ValidationError(format!(
+                                    "output '{}': alternative '{}': link and target must differ",
+                                    sub_name, alt.name
+                                )));
+                            }
+                        }
                     }
                 }
             }
@@ -488,8 +863,31 @@ impl PlanManifest {
                     name, stage.isolation, e
                 )));
             }
+            match stage.network.parse::<crate::isolation::NetworkMode>() {
+                Err(e) => {
+                    return Err(WrightError::ValidationError(format!(
+                        "stage '{}': invalid network mode '{}': {}",
+                        name, stage.network, e
+                    )));
+                }
+                Ok(crate::isolation::NetworkMode::Proxy)
+                    if stage
+                        .isolation
+                        .parse::<crate::isolation::IsolationLevel>()
+                        .ok()
+                        == Some(crate::isolation::IsolationLevel::None) =>
+                {
+                    return Err(WrightError::ValidationError(format!(
+                        "stage '{}': network = \"proxy\" needs isolation relaxed or strict",
+                        name
+                    )));
+                }
+                Ok(_) => {}
+            }
         }
 
+        self.options.hardening().validate()?;
+
         // Validate deps: plan:output syntax, optional constraints, no duplicates.
         // Existence of the referenced local plan/output is checked by
         // `wright lint`, where the full local plan index is available.
@@ -1316,9 +1714,9 @@ pre_remove = "systemctl stop test"
             Some(OutputConfig::Multi(ref outputs)) => {
                 let (_, output) = outputs.iter().find(|(name, _)| name == "test").unwrap();
                 let hooks = output.hooks.as_ref().unwrap();
-                assert_eq!(hooks.pre_install.as_deref(), Some("echo pre"));
-                assert_eq!(hooks.post_install.as_deref(), Some("ldconfig"));
-                assert_eq!(hooks.pre_remove.as_deref(), Some("systemctl stop test"));
+                assert_eq!(hooks.pre_install, Some(Hook::new("echo pre")));
+                assert_eq!(hooks.post_install, Some(Hook::new("ldconfig")));
+                assert_eq!(hooks.pre_remove, Some(Hook::new("systemctl stop test")));
                 assert_eq!(output.backup.as_ref().unwrap(), &["/etc/test.conf"]);
             }
             _ => panic!("expected Multi output config"),
@@ -1327,6 +1725,52 @@ pre_remove = "systemctl stop test"
         assert!(manifest.backup.is_some());
     }
 
+    #[test]
+    fn hook_table_sets_isolation_and_timeout() {
+        let toml_str = r#"
+name = "test"
+version = "1.0.0"
+release = 1
+description = "test"
+license = "MIT"
+arch = "x86_64"
+
+[[output]]
+
+[output.hooks]
+post_install = "ldconfig"
+post_upgrade = { script = "systemctl reload test", isolation = "none", timeout = 30 }
+post_remove = { script = "mandb -q", on_failure = "defer" }
+"#;
+        let manifest = PlanManifest::parse(toml_str).unwrap();
+        let scripts = manifest.deploy_scripts.unwrap();
+        let post_install = scripts.post_install.unwrap();
+        assert_eq!(
+            post_install.isolation,
+            crate::isolation::IsolationLevel::Strict
+        );
+        assert_eq!(post_install.timeout_secs(), DEFAULT_HOOK_TIMEOUT);
+        let post_upgrade = scripts.post_upgrade.unwrap();
+        assert_eq!(post_upgrade.script, "systemctl reload test");
+        assert_eq!(
+            post_upgrade.isolation,
+            crate::isolation::IsolationLevel::None
+        );
+        assert_eq!(post_upgrade.timeout_secs(), 30);
+        assert_eq!(post_upgrade.on_failure, HookFailurePolicy::Warn);
+        let post_remove = scripts.post_remove.unwrap();
+        assert_eq!(post_remove.on_failure, HookFailurePolicy::Defer);
+        assert_eq!(
+            post_remove.isolation,
+            crate::isolation::IsolationLevel::Strict
+        );
+
+        let bad = toml_str.replace("isolation = \"none\"", "isolation = \"jail\"");
+        assert!(PlanManifest::parse(&bad).is_err());
+        let bad = toml_str.replace("\"defer\"", "\"retry\"");
+        assert!(PlanManifest::parse(&bad).is_err());
+    }
+
     #[test]
     fn test_main_package_in_multi_inherits_description() {
         let toml_str = r#"
@@ -1442,6 +1886,34 @@ skip_fhs_check = true
         assert!(manifest.options.skip_fhs_check);
     }
 
+    #[test]
+    fn test_stage_network_mode() {
+        let plan = |isolation: &str, network: &str| {
+            format!(
+                r#"
+name = "vendored"
+version = "1.0.0"
+release = 1
+description = "vendored deps"
+license = "MIT"
+arch = "x86_64"
+
+[pipeline.prepare]
+isolation = "{isolation}"
+network = "{network}"
+script = "cargo vendor"
+"#
+            )
+        };
+        let manifest = PlanManifest::parse(&plan("strict", "proxy")).unwrap();
+        assert_eq!(manifest.pipeline["prepare"].network, "proxy");
+
+        let err = PlanManifest::parse(&plan("none", "proxy")).unwrap_err();
+        assert!(err.to_string().contains("needs isolation"), "{err}");
+        let err = PlanManifest::parse(&plan("strict", "host")).unwrap_err();
+        assert!(err.to_string().contains("invalid network mode"), "{err}");
+    }
+
     #[test]
     fn test_skip_elf_lint_option() {
         let toml_str = r#"
@@ -1478,6 +1950,38 @@ provides = ["http-server"]
         assert_eq!(manifest.relations.replaces, vec!["old-nginx"]);
         assert_eq!(manifest.relations.conflicts, vec!["apache"]);
         assert_eq!(manifest.relations.provides, vec!["http-server"]);
+
+        let err = PlanManifest::parse(&toml_str.replace("\"http-server\"", "\"Http-Server=2.4\""))
+            .unwrap_err();
+        assert!(err.to_string().contains("output 'nginx'"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_output_alternatives() {
+        let toml_str = r#"
+name = "vim"
+version = "9.1.0"
+release = 1
+description = "test"
+license = "MIT"
+arch = "x86_64"
+
+[[output]]
+
+[[output.alternatives]]
+name = "vi"
+link = "/usr/bin/vi"
+target = "/usr/bin/vim"
+priority = 40
+"#;
+        let manifest = PlanManifest::parse(toml_str).unwrap();
+        assert_eq!(manifest.alternatives.len(), 1);
+        assert_eq!(manifest.alternatives[0].name, "vi");
+        assert_eq!(manifest.alternatives[0].priority, 40);
+
+        let err = PlanManifest::parse(&toml_str.replace("\"/usr/bin/vi\"", "\"usr/bin/vi\""))
+            .unwrap_err();
+        assert!(err.to_string().contains("must be absolute"), "{err}");
     }
 
     #[test]
@@ -1528,6 +2032,114 @@ ref = "v1.0"
         }
     }
 
+    #[test]
+    fn test_parse_patches() {
+        let plan = |patches: &str| {
+            PlanManifest::parse(&format!(
+                r#"
+name = "test"
+version = "1.4.0"
+release = 1
+description = "test"
+license = "MIT"
+arch = "x86_64"
+
+{patches}
+"#
+            ))
+        };
+        let manifest = plan(
+            r#"
+[[patches]]
+path = "patches/fix.patch"
+
+[[patches]]
+url = "https://example.com/cve.patch"
+sha256 = "abc"
+strip = 0
+target = "test-${VERSION}"
+versions = [">= 1.2", "< 2.0"]
+
+[[patches]]
+path = "patches/old.patch"
+versions = ["< 1.2"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.patches.len(), 3);
+        assert_eq!(manifest.patches[0].strip, 1);
+        assert_eq!(
+            manifest.patches[1].locator(),
+            "https://example.com/cve.patch"
+        );
+        let version = manifest.metadata.version.as_deref();
+        assert!(manifest.patches[1].applies_to(version));
+        assert!(!manifest.patches[2].applies_to(version));
+
+        for (patches, message) in [
+            (
+                "[[patches]]\nurl = \"https://example.com/a.patch\"",
+                "requires 'sha256'",
+            ),
+            (
+                "[[patches]]\npath = \"a.patch\"\nurl = \"https://example.com/a.patch\"",
+                "exactly one",
+            ),
+            (
+                "[[patches]]\npath = \"a.patch\"\nversions = [\"~1\"]",
+                "constraint",
+            ),
+            (
+                "[[patches]]\npath = \"a.patch\"\ntarget = \"../etc\"",
+                "inside WORKDIR",
+            ),
+        ] {
+            let err = plan(patches).unwrap_err().to_string();
+            assert!(err.contains(message), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_vendor_sources() {
+        let toml_str = r#"
+name = "test"
+version = "1.0.0"
+release = 1
+description = "test"
+license = "MIT"
+arch = "x86_64"
+
+[[sources]]
+type = "cargo_vendor"
+lockfile = "Cargo.lock"
+
+[[sources]]
+type = "go_mod"
+lockfile = "go.sum"
+proxy = "https://goproxy.example.com"
+
+[[sources]]
+type = "pip_requirements"
+lockfile = "requirements.txt"
+extract_to = "py"
+"#;
+        let manifest = PlanManifest::parse(toml_str).unwrap();
+        match &manifest.sources.entries[..] {
+            [
+                Source::CargoVendor(cargo),
+                Source::GoMod(go),
+                Source::PipRequirements(pip),
+            ] => {
+                assert_eq!(cargo.lockfile, "Cargo.lock");
+                assert_eq!(cargo.registry, "https://static.crates.io/crates");
+                assert_eq!(go.proxy, "https://goproxy.example.com");
+                assert_eq!(pip.index, "https://pypi.org/pypi");
+                assert_eq!(pip.extract_to.as_deref(), Some("py"));
+            }
+            other => panic!("unexpected sources: {other:?}"),
+        }
+    }
+
     #[test]
     fn test_parse_epoch() {
         let toml_str = r#"
@@ -1587,13 +2199,13 @@ post_install = "ldconfig"
             Some(OutputConfig::Multi(ref outputs)) => {
                 let (_, output) = outputs.iter().find(|(name, _)| name == "test").unwrap();
                 let hooks = output.hooks.as_ref().unwrap();
-                assert_eq!(hooks.pre_install.as_deref(), Some("echo preparing"));
-                assert_eq!(hooks.post_install.as_deref(), Some("ldconfig"));
+                assert_eq!(hooks.pre_install, Some(Hook::new("echo preparing")));
+                assert_eq!(hooks.post_install, Some(Hook::new("ldconfig")));
             }
             _ => panic!("expected Multi"),
         }
         let scripts = manifest.deploy_scripts.as_ref().unwrap();
-        assert_eq!(scripts.pre_install.as_deref(), Some("echo preparing"));
+        assert_eq!(scripts.pre_install, Some(Hook::new("echo preparing")));
     }
 
     #[test]
@@ -55,6 +55,7 @@ impl SubFabricateOutput {
                 provides: self.provides.clone(),
             },
             sources: Sources::default(),
+            patches: Vec::new(),
             options: PlanBuildOptions::default(),
             pipeline: HashMap::new(),
             pipeline_order: None,
@@ -63,6 +64,7 @@ impl SubFabricateOutput {
             discard: Vec::new(),
             deploy_scripts,
             backup,
+            alternatives: self.alternatives.clone(),
             source_plan: Some(parent.metadata.name.clone()),
             plan_checksum: parent.plan_checksum.clone(),
         }
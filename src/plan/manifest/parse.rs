@@ -7,8 +7,9 @@ use crate::error::{Result, WrightError};
 
 use super::PlanBuildOptions;
 use super::{
-    BackupConfig, DeployScripts, DiscardRule, FabricateHooks, OutputConfig, PhaseConfig,
-    PipelineOrder, PipelineStage, PlanManifest, PlanMetadata, Relations, Source, Sources,
+    Alternative, BackupConfig, DeployScripts, DiscardRule, FabricateHooks, OutputConfig, Patch,
+    PhaseConfig, PipelineOrder, PipelineStage, PlanManifest, PlanMetadata, Relations, Source,
+    Sources,
 };
 
 #[derive(Deserialize)]
@@ -36,6 +37,8 @@ struct RawManifest {
     pub output: Option<toml::Value>,
     #[serde(default)]
     pub discard: Vec<DiscardRule>,
+    #[serde(default)]
+    pub patches: Vec<Patch>,
 }
 
 #[derive(Deserialize, Default)]
@@ -71,6 +74,7 @@ struct OutputSection {
     outputs: Option<OutputConfig>,
     deploy_scripts: Option<DeployScripts>,
     backup: Option<BackupConfig>,
+    alternatives: Vec<Alternative>,
     relations: Relations,
     runtime_deps: Vec<String>,
 }
@@ -146,6 +150,7 @@ fn parse_output_section(
                     outputs: Some(OutputConfig::Multi(parts)),
                     deploy_scripts: None,
                     backup: None,
+                    alternatives: Vec::new(),
                     relations: Relations::default(),
                     runtime_deps: all_runtime_deps,
                 }),
@@ -166,11 +171,13 @@ fn parse_output_section(
                     let backup_cfg = catchall.backup.as_ref().map(|files| BackupConfig {
                         files: files.clone(),
                     });
+                    let alternatives = catchall.alternatives.clone();
 
                     Ok(OutputSection {
                         outputs: Some(OutputConfig::Multi(parts)),
                         deploy_scripts,
                         backup: backup_cfg,
+                        alternatives,
                         relations,
                         runtime_deps: all_runtime_deps,
                     })
@@ -201,6 +208,7 @@ fn parse_output_section(
             },
             deploy_scripts: None,
             backup: None,
+            alternatives: Vec::new(),
             relations: Relations::default(),
             runtime_deps: Vec::new(),
         }),
@@ -226,6 +234,7 @@ impl PlanManifest {
             hooks,
             output,
             discard,
+            patches,
         } = raw;
 
         let metadata = if let Some(plan) = section_plan {
@@ -301,6 +310,7 @@ impl PlanManifest {
             outputs,
             deploy_scripts,
             backup,
+            alternatives,
             relations,
             runtime_deps,
         } = output_section;
@@ -312,6 +322,7 @@ impl PlanManifest {
             runtime_deps,
             relations,
             sources,
+            patches,
             options,
             pipeline: pipeline_stages,
             pipeline_order,
@@ -320,6 +331,7 @@ impl PlanManifest {
             discard,
             deploy_scripts,
             backup,
+            alternatives,
             source_plan: None,
             plan_checksum: None,
         };
@@ -345,6 +357,13 @@ impl PlanManifest {
             other => other,
         })?;
         manifest.plan_checksum = Some(crate::util::checksum::sha256_bytes(content.as_bytes()));
+        // An unreadable patch file is reported when the build applies it.
+        let plan_dir = path.parent().unwrap_or(Path::new("."));
+        for patch in &mut manifest.patches {
+            if let Some(ref rel) = patch.path {
+                patch.file_checksum = crate::util::checksum::sha256_file(&plan_dir.join(rel)).ok();
+            }
+        }
 
         if path.file_name().and_then(|s| s.to_str()) == Some("plan.toml") {
             let mvp_path = path.with_file_name("mvp.toml");
@@ -1,8 +1,13 @@
 //! Query and analysis operations — dependency tree rendering, etc.
 
+use std::collections::{BTreeMap, HashSet};
+
 use crate::error::{Result, WrightError};
 
 use crate::database::InstalledDb;
+use crate::part::version;
+use crate::plan::discovery::PlanIndex;
+use crate::resolve::DepDomain;
 
 use owo_colors::OwoColorize;
 
@@ -488,11 +493,328 @@ pub async fn write_system_tree(
     Ok(combined_stats)
 }
 
+// ─── plan-source trees ───────────────────────────────────────────────────────
+
+/// One dependency edge read from plan source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanEdge {
+    pub plan: String,
+    pub version_constraint: Option<String>,
+}
+
+/// Dependency graph built from plan sources instead of the installed registry.
+///
+/// The installed registry only records runtime edges (see migration 013);
+/// build and link relationships live in plan source alone, so answering
+/// "what pulls this in at build time" has to walk the `PlanIndex`. Nodes are
+/// plan names — `plan:output` references collapse onto their plan.
+pub struct PlanDepGraph {
+    edges: BTreeMap<String, Vec<PlanEdge>>,
+    reverse: BTreeMap<String, Vec<String>>,
+}
+
+impl PlanDepGraph {
+    /// Parse every plan in the index and collect edges for `domain`.
+    pub fn from_index(index: &PlanIndex, domain: DepDomain) -> Result<Self> {
+        let mut edges: BTreeMap<String, Vec<PlanEdge>> = BTreeMap::new();
+        for (name, manifest) in index.load_all()? {
+            let mut raw: Vec<&String> = Vec::new();
+            if domain.contains(DepDomain::BUILD) {
+                raw.extend(manifest.build_deps.iter());
+            }
+            if domain.contains(DepDomain::LINK) {
+                raw.extend(manifest.link_deps.iter());
+            }
+            if domain.contains(DepDomain::RUNTIME) {
+                raw.extend(manifest.runtime_deps.iter());
+            }
+
+            let mut seen = HashSet::new();
+            let mut plan_edges = Vec::new();
+            for dep in raw {
+                let (dep_name, constraint) = version::parse_dependency(dep)
+                    .unwrap_or_else(|_| (dep.trim().to_string(), None));
+                let plan = version::parse_dep_ref(&dep_name).plan().to_string();
+                if plan.is_empty() || plan == name || !seen.insert(plan.clone()) {
+                    continue;
+                }
+                plan_edges.push(PlanEdge {
+                    plan,
+                    version_constraint: constraint.map(|c| c.to_string()),
+                });
+            }
+            plan_edges.sort_by(|a, b| a.plan.cmp(&b.plan));
+            edges.insert(name, plan_edges);
+        }
+        Ok(Self::from_edges(edges))
+    }
+
+    /// Build a graph from pre-computed edges.  Useful for tests.
+    pub fn from_edges(edges: BTreeMap<String, Vec<PlanEdge>>) -> Self {
+        let mut reverse: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, deps) in &edges {
+            for dep in deps {
+                reverse
+                    .entry(dep.plan.clone())
+                    .or_default()
+                    .push(name.clone());
+            }
+        }
+        for dependents in reverse.values_mut() {
+            dependents.sort();
+            dependents.dedup();
+        }
+        Self { edges, reverse }
+    }
+
+    /// Whether a plan with this name exists in the index.
+    pub fn contains(&self, name: &str) -> bool {
+        self.edges.contains_key(name)
+    }
+
+    /// Plans that no other plan depends on within the selected domain.
+    pub fn roots(&self) -> Vec<&str> {
+        self.edges
+            .keys()
+            .filter(|name| !self.reverse.contains_key(name.as_str()))
+            .map(|name| name.as_str())
+            .collect()
+    }
+
+    fn children(&self, name: &str, reverse: bool) -> Vec<PlanEdge> {
+        if reverse {
+            self.reverse
+                .get(name)
+                .map(|names| {
+                    names
+                        .iter()
+                        .map(|n| PlanEdge {
+                            plan: n.clone(),
+                            version_constraint: None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            self.edges.get(name).cloned().unwrap_or_default()
+        }
+    }
+}
+
+/// Render the forward dependency tree of a plan from plan sources.
+pub fn write_plan_dep_tree(
+    graph: &PlanDepGraph,
+    name: &str,
+    opts: &TreeOptions<'_>,
+    out: &mut dyn std::io::Write,
+) -> Result<TreeStats> {
+    write_plan_tree_root(graph, name, false, opts, out)
+}
+
+/// Render the reverse dependency tree of a plan from plan sources.
+pub fn write_plan_reverse_dep_tree(
+    graph: &PlanDepGraph,
+    name: &str,
+    opts: &TreeOptions<'_>,
+    out: &mut dyn std::io::Write,
+) -> Result<TreeStats> {
+    write_plan_tree_root(graph, name, true, opts, out)
+}
+
+/// Render every root plan and its dependencies from plan sources.
+pub fn write_plan_system_tree(
+    graph: &PlanDepGraph,
+    opts: &TreeOptions<'_>,
+    out: &mut dyn std::io::Write,
+) -> Result<TreeStats> {
+    let roots = graph.roots();
+    if roots.is_empty() {
+        if graph.edges.is_empty() {
+            writeln!(out, "No plans found.")?;
+        } else {
+            writeln!(
+                out,
+                "No root plans found; the plan graph may be fully cyclic."
+            )?;
+        }
+        return Ok(TreeStats::default());
+    }
+
+    let mut visited = HashSet::new();
+    let mut combined_stats = TreeStats::default();
+    for (i, root) in roots.iter().enumerate() {
+        writeln!(out, "{}", root)?;
+        combined_stats.total += 1;
+        let mut ancestors = HashSet::new();
+        visited.insert(root.to_string());
+        ancestors.insert(root.to_string());
+        write_plan_tree_inner(
+            graph,
+            root,
+            false,
+            "",
+            1,
+            opts,
+            &mut visited,
+            &mut ancestors,
+            &mut combined_stats,
+            out,
+        )?;
+        if i < roots.len() - 1 {
+            writeln!(out)?;
+        }
+    }
+    Ok(combined_stats)
+}
+
+fn write_plan_tree_root(
+    graph: &PlanDepGraph,
+    name: &str,
+    reverse: bool,
+    opts: &TreeOptions<'_>,
+    out: &mut dyn std::io::Write,
+) -> Result<TreeStats> {
+    if !graph.contains(name) {
+        return Err(WrightError::PartNotFound(format!(
+            "no plan named '{}' in the plan index",
+            name
+        )));
+    }
+    let mut visited = HashSet::new();
+    let mut ancestors = HashSet::new();
+    let mut stats = TreeStats {
+        total: 1,
+        ..TreeStats::default()
+    };
+    visited.insert(name.to_string());
+    ancestors.insert(name.to_string());
+    write_plan_tree_inner(
+        graph,
+        name,
+        reverse,
+        "",
+        1,
+        opts,
+        &mut visited,
+        &mut ancestors,
+        &mut stats,
+        out,
+    )?;
+    Ok(stats)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_plan_tree_inner(
+    graph: &PlanDepGraph,
+    name: &str,
+    reverse: bool,
+    prefix: &str,
+    current_depth: usize,
+    opts: &TreeOptions<'_>,
+    visited: &mut HashSet<String>,
+    ancestors: &mut HashSet<String>,
+    stats: &mut TreeStats,
+    out: &mut dyn std::io::Write,
+) -> Result<()> {
+    if current_depth > opts.max_depth {
+        return Ok(());
+    }
+
+    let deps = graph.children(name, reverse);
+    let children: Vec<_> = if let Some(f) = opts.filter {
+        deps.iter().filter(|d| d.plan.contains(f)).collect()
+    } else {
+        deps.iter().collect()
+    };
+
+    for (i, dep) in children.iter().enumerate() {
+        let is_last_child = i == children.len() - 1;
+
+        if opts.prune.contains(&dep.plan) {
+            stats.total += 1;
+            write_line_prefix(out, opts, prefix, is_last_child, current_depth)?;
+            write_part_name(out, &dep.plan, opts.color)?;
+            if let Some(c) = &dep.version_constraint {
+                write_version_constraint(out, c, opts.color)?;
+            }
+            write_pruned_tag(out, opts.color)?;
+            writeln!(out)?;
+            continue;
+        }
+
+        let is_cycle = ancestors.contains(&dep.plan);
+        let is_dup = !is_cycle && visited.contains(&dep.plan);
+        if is_dup && opts.prefix_mode == PrefixMode::None {
+            continue;
+        }
+
+        stats.total += 1;
+        if current_depth > stats.max_depth_seen {
+            stats.max_depth_seen = current_depth;
+        }
+        let known = graph.contains(&dep.plan);
+        write_line_prefix(out, opts, prefix, is_last_child, current_depth)?;
+        write_part_name(out, &dep.plan, opts.color)?;
+        if let Some(c) = &dep.version_constraint {
+            write_version_constraint(out, c, opts.color)?;
+        }
+        if !known {
+            // Counted under `not_installed`: in plan-source mode the
+            // equivalent gap is a reference no plan in the index satisfies.
+            write_no_plan(out, opts.color)?;
+            stats.not_installed += 1;
+        }
+        if is_cycle {
+            stats.cycles += 1;
+            write_cycle_tag(out, opts.color)?;
+        } else if is_dup {
+            write_dup_tag(out, opts.color)?;
+        }
+        writeln!(out)?;
+
+        if is_cycle || is_dup || !known {
+            continue;
+        }
+
+        visited.insert(dep.plan.clone());
+        ancestors.insert(dep.plan.clone());
+        let new_prefix = match opts.prefix_mode {
+            PrefixMode::Indent => {
+                format!("{}{}", prefix, if is_last_child { "    " } else { "│   " })
+            }
+            _ => String::new(),
+        };
+        write_plan_tree_inner(
+            graph,
+            &dep.plan,
+            reverse,
+            &new_prefix,
+            current_depth + 1,
+            opts,
+            visited,
+            ancestors,
+            stats,
+            out,
+        )?;
+        ancestors.remove(&dep.plan);
+    }
+
+    Ok(())
+}
+
+fn write_no_plan(out: &mut dyn std::io::Write, color: bool) -> std::io::Result<()> {
+    if color {
+        write!(out, " {}", "[no plan]".red())
+    } else {
+        write!(out, " [no plan]")
+    }
+}
+
 // ─── health-check functions (unchanged) ──────────────────────────────────────
 
 /// One unsatisfied runtime dependency edge — a part declared a need
 /// the registry cannot resolve (directly or via replaces).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct BrokenDep {
     pub part: String,
     pub required_name: String,
@@ -503,8 +825,9 @@ pub struct BrokenDep {
 ///
 /// Per ADR-0016 resolution walks `parts.name` first and falls through
 /// to `replaces.name` so renamed targets stay satisfied across plan
-/// migrations. The advisory model is read-only — this function reports;
-/// callers decide how to react.
+/// migrations, then to `provides.name` for virtual dependencies. The
+/// advisory model is read-only — this function reports; callers decide
+/// how to react.
 pub async fn check_dependencies_structured(db: &InstalledDb) -> Result<Vec<BrokenDep>> {
     let all_parts = db.list_parts().await?;
     let mut broken = Vec::new();
@@ -512,7 +835,7 @@ pub async fn check_dependencies_structured(db: &InstalledDb) -> Result<Vec<Broke
     for part in all_parts {
         let deps = db.get_dependencies(part.id).await?;
         for dep in deps {
-            if !is_dep_satisfied(db, &dep.name).await? {
+            if !is_dep_satisfied(db, &dep.name, dep.version_constraint.as_deref()).await? {
                 broken.push(BrokenDep {
                     part: part.name.clone(),
                     required_name: dep.name,
@@ -535,19 +858,35 @@ pub async fn check_dependencies(db: &InstalledDb) -> Result<Vec<String>> {
                 .map(|c| format!(" ({})", c))
                 .unwrap_or_default();
             format!(
-                "Part '{}' has a broken dependency: '{}'{} not found",
+                "Part '{}' has a broken dependency: '{}'{} not satisfied",
                 b.part, b.required_name, vc
             )
         })
         .collect())
 }
 
-async fn is_dep_satisfied(db: &InstalledDb, required: &str) -> Result<bool> {
+async fn is_dep_satisfied(
+    db: &InstalledDb,
+    required: &str,
+    constraint: Option<&str>,
+) -> Result<bool> {
     // The required_name may be "plan:output" or just "output". Names are
     // globally unique, so the output is what we look up.
     let target = required.split(':').next_back().unwrap_or(required);
-    if db.get_part(target).await?.is_some() {
-        return Ok(true);
+    // A constraint that no longer parses is not held against the part.
+    let constraint = constraint.and_then(|c| version::VersionConstraint::parse(c).ok());
+    if let Some(part) = db.get_part(target).await? {
+        let Some(constraint) = &constraint else {
+            return Ok(true);
+        };
+        let plan_version = match db.get_plan_by_id(part.plan_id).await? {
+            Some(plan) => plan.version,
+            None => String::new(),
+        };
+        return Ok(match version::Version::parse(&plan_version) {
+            Ok(v) => constraint.satisfies(&v),
+            Err(_) => true,
+        });
     }
     // Replaces fallback: any deployed part declaring `replaces = [target]`
     // covers the old name. Walk parts → check their replaces list.
@@ -558,6 +897,13 @@ async fn is_dep_satisfied(db: &InstalledDb, required: &str) -> Result<bool> {
             return Ok(true);
         }
     }
+    // Provides fallback: a versioned provide takes part in the constraint.
+    for (_, provided) in db.find_providers(target).await? {
+        let provided = provided.and_then(|v| version::Version::parse(&v).ok());
+        if version::provide_satisfies(provided.as_ref(), constraint.as_ref()) {
+            return Ok(true);
+        }
+    }
     Ok(false)
 }
 
@@ -589,3 +935,86 @@ pub async fn check_file_ownership_conflicts(db: &InstalledDb) -> Result<Vec<Stri
 pub async fn check_shadowed_files(db: &InstalledDb) -> Result<Vec<String>> {
     db.get_shadowed_conflicts().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(plan: &str) -> PlanEdge {
+        PlanEdge {
+            plan: plan.to_string(),
+            version_constraint: None,
+        }
+    }
+
+    fn graph(edges: &[(&str, &[&str])]) -> PlanDepGraph {
+        PlanDepGraph::from_edges(
+            edges
+                .iter()
+                .map(|(name, deps)| (name.to_string(), deps.iter().map(|d| edge(d)).collect()))
+                .collect(),
+        )
+    }
+
+    fn render(graph: &PlanDepGraph, name: Option<&str>, reverse: bool) -> (String, TreeStats) {
+        let opts = TreeOptions {
+            max_depth: usize::MAX,
+            filter: None,
+            prefix_mode: PrefixMode::Indent,
+            prune: &[],
+            color: false,
+        };
+        let mut out = Vec::new();
+        let stats = match (name, reverse) {
+            (Some(n), false) => write_plan_dep_tree(graph, n, &opts, &mut out).unwrap(),
+            (Some(n), true) => write_plan_reverse_dep_tree(graph, n, &opts, &mut out).unwrap(),
+            (None, _) => write_plan_system_tree(graph, &opts, &mut out).unwrap(),
+        };
+        (String::from_utf8(out).unwrap(), stats)
+    }
+
+    #[test]
+    fn test_plan_tree_forward_and_reverse() {
+        let g = graph(&[
+            ("app", &["libfoo", "zlib"]),
+            ("libfoo", &["zlib"]),
+            ("zlib", &[]),
+        ]);
+
+        let (text, stats) = render(&g, Some("app"), false);
+        assert_eq!(text, "├── libfoo\n│   └── zlib\n└── zlib (*)\n");
+        assert_eq!(stats.total, 4);
+
+        let (text, _) = render(&g, Some("zlib"), true);
+        assert_eq!(text, "├── app\n└── libfoo\n    └── app (*)\n");
+    }
+
+    #[test]
+    fn test_plan_tree_marks_cycles_and_missing_plans() {
+        let g = graph(&[("a", &["b"]), ("b", &["a", "ghost"])]);
+        let (text, stats) = render(&g, Some("a"), false);
+        assert!(text.contains("a (cycle)"), "{text}");
+        assert!(text.contains("ghost [no plan]"), "{text}");
+        assert_eq!(stats.cycles, 1);
+        assert_eq!(stats.not_installed, 1);
+    }
+
+    #[test]
+    fn test_plan_system_tree_lists_roots_only() {
+        let g = graph(&[("app", &["zlib"]), ("tool", &[]), ("zlib", &[])]);
+        assert_eq!(g.roots(), vec!["app".to_string(), "tool".to_string()]);
+    }
+
+    #[test]
+    fn test_plan_tree_unknown_root_is_error() {
+        let g = graph(&[("app", &[])]);
+        let opts = TreeOptions {
+            max_depth: usize::MAX,
+            filter: None,
+            prefix_mode: PrefixMode::Indent,
+            prune: &[],
+            color: false,
+        };
+        assert!(write_plan_dep_tree(&g, "nope", &opts, &mut Vec::new()).is_err());
+    }
+}
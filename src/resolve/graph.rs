@@ -56,7 +56,10 @@ pub(super) async fn expand_missing_dependencies(
                 .unwrap_or_else(|_| (dep.clone(), None))
                 .0;
             let (dep_plan_name, dep_output_name) =
-                version::parse_dep_ref(&dep_name).to_plan_output();
+                match resolve_virtual_dependency(dep, index, db).await? {
+                    Some(provider) => provider,
+                    None => version::parse_dep_ref(&dep_name).to_plan_output(),
+                };
             let dep_depth = depth + 1;
 
             if dep_depth > max_depth {
@@ -94,7 +97,10 @@ pub(super) async fn expand_missing_dependencies(
                     .unwrap_or_else(|_| (build_dep.clone(), None))
                     .0;
                 let build_dep_plan_name =
-                    version::parse_dep_ref(&build_dep_name).plan().to_string();
+                    match resolve_virtual_dependency(build_dep, index, db).await? {
+                        Some((plan, _)) => plan,
+                        None => version::parse_dep_ref(&build_dep_name).plan().to_string(),
+                    };
                 let build_dep_depth = depth + 1;
                 if build_dep_depth >= max_depth {
                     continue;
@@ -116,7 +122,10 @@ pub(super) async fn expand_missing_dependencies(
                             .unwrap_or_else(|_| (rdep.clone(), None))
                             .0;
                         let (rdep_plan_name, rdep_output_name) =
-                            version::parse_dep_ref(&rdep_name).to_plan_output();
+                            match resolve_virtual_dependency(rdep, index, db).await? {
+                                Some(provider) => provider,
+                                None => version::parse_dep_ref(&rdep_name).to_plan_output(),
+                            };
                         if !runtime_seen.insert(rdep_plan_name.clone()) {
                             continue;
                         }
@@ -159,6 +168,34 @@ pub(super) async fn expand_missing_dependencies(
     Ok(resolved_count)
 }
 
+/// The `(plan, output)` providing `dep` when it names no plan or installed
+/// part, only a virtual name some plan `provides`; `None` otherwise.
+async fn resolve_virtual_dependency(
+    dep: &str,
+    index: &PlanIndex,
+    db: &InstalledDb,
+) -> Result<Option<(String, String)>> {
+    let Ok((name, constraint)) = version::parse_dependency(dep) else {
+        return Ok(None);
+    };
+    if name.contains(':') || index.path_for(&name).is_some() || db.get_part(&name).await?.is_some()
+    {
+        return Ok(None);
+    }
+    let mut installed = HashSet::new();
+    for (part, provided) in db.find_providers(&name).await? {
+        let provided = provided.and_then(|v| version::Version::parse(&v).ok());
+        if version::provide_satisfies(provided.as_ref(), constraint.as_ref()) {
+            installed.insert(part);
+        }
+    }
+    let provider = index.resolve_provider(&name, constraint.as_ref(), &installed)?;
+    if let Some((plan, output)) = &provider {
+        debug!(event = "graph.virtual_resolved", dependency = %name, plan_name = %plan, output = %output, "Virtual dependency resolved to provider");
+    }
+    Ok(provider)
+}
+
 /// Returns the match reason label when the dependency matches any policy, or `None` if it doesn't.
 /// Combines the match check and label derivation into a single database round-trip.
 async fn dependency_match_label(
@@ -366,7 +403,7 @@ pub(super) fn construction_plan_label(
     } else {
         match rebuild_reasons.get(name) {
             Some(RebuildReason::LinkDependency) => "relink",
-            Some(RebuildReason::Transitive) => "rebuild",
+            Some(RebuildReason::Transitive | RebuildReason::InputsChanged(_)) => "rebuild",
             Some(RebuildReason::Explicit) | None => "build",
         }
     }
@@ -554,10 +591,21 @@ pub(super) fn build_dep_map(
             all_deps.extend(manifest.link_deps.iter());
         }
         for dep_raw in &all_deps {
-            let dep_name = version::parse_dependency(dep_raw)
-                .unwrap_or_else(|_| (dep_raw.to_string(), None))
-                .0;
+            let (dep_name, constraint) =
+                version::parse_dependency(dep_raw).unwrap_or_else(|_| (dep_raw.to_string(), None));
             let dep_plan_name = version::parse_dep_ref(&dep_name).plan().to_string();
+            // A virtual name orders after the provider being built with it.
+            if !dep_name.contains(':')
+                && !part_to_plan.contains_key(&dep_plan_name)
+                && index.path_for(&dep_plan_name).is_none()
+                && let Some((provider, _)) = index
+                    .providers_of(&dep_name, constraint.as_ref())?
+                    .into_iter()
+                    .find(|(plan, _)| build_set.contains(plan))
+            {
+                part_to_plan.insert(dep_name.clone(), provider);
+                continue;
+            }
             if !part_to_plan.contains_key(&dep_plan_name)
                 && let Some(dep_path) = index.path_for(&dep_plan_name)
                 && let Ok(dep_manifest) = PlanManifest::from_file(dep_path)
@@ -202,7 +202,8 @@ pub async fn resolve_build_set(
     opts: ResolveOptions,
 ) -> Result<Vec<String>> {
     let plan_dirs = plan_search_dirs(config);
-    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?;
+    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?
+        .with_provider_preferences(config.providers.clone());
     let plans_to_build = resolve_targets(&targets, &index, &plan_dirs)?;
 
     if plans_to_build.is_empty() {
@@ -326,7 +327,8 @@ pub fn create_execution_plan(
     dep_domain: DepDomain,
 ) -> Result<BuildExecutionPlan> {
     let plan_dirs = plan_search_dirs(config);
-    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?;
+    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?
+        .with_provider_preferences(config.providers.clone());
     let plans_to_build = resolve_targets(&targets, &index, &plan_dirs)?;
 
     if plans_to_build.is_empty() {
@@ -459,7 +461,8 @@ pub fn describe_batch_actions(
 
 pub fn lint_dependency_graph_for_targets(config: &GlobalConfig, targets: &[String]) -> Result<()> {
     let plan_dirs = plan_search_dirs(config);
-    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?;
+    let index = crate::plan::discovery::PlanIndex::discover(&plan_dirs)?
+        .with_provider_preferences(config.providers.clone());
     let plans_to_build = resolve_targets(targets, &index, &plan_dirs)?;
 
     if plans_to_build.is_empty() {
@@ -533,11 +536,31 @@ fn lint_dependency_graph(graph: &crate::foundry::mvp::PlanGraph) -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RebuildReason {
     Explicit,
     LinkDependency,
     Transitive,
+    /// The build inputs differ from those the build root was last forged
+    /// under; holds the labels of the inputs that changed (`dep:zlib`,
+    /// `env`, `executor:shell`, ...).
+    InputsChanged(Vec<String>),
+}
+
+impl std::fmt::Display for RebuildReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RebuildReason::Explicit => write!(f, "requested"),
+            RebuildReason::LinkDependency => write!(f, "link dependency rebuilt"),
+            RebuildReason::Transitive => write!(f, "dependency rebuilt"),
+            RebuildReason::InputsChanged(changed) if changed.is_empty() => {
+                write!(f, "previous build inputs were not recorded")
+            }
+            RebuildReason::InputsChanged(changed) => {
+                write!(f, "changed {}", changed.join(", "))
+            }
+        }
+    }
 }
 
 use std::ops::BitOr;
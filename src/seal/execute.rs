@@ -17,6 +17,7 @@ pub async fn package_outputs(
         .await
         .map_err(WrightError::IoError)?;
     let output_dir = config.general.parts_dir.clone();
+    let fetches = crate::foundry::proxy::recorded_fetches(&result.build_root);
 
     match manifest.outputs {
         Some(OutputConfig::Multi(ref parts)) => {
@@ -32,8 +33,13 @@ pub async fn package_outputs(
                     fhs::validate(part_dir, sub_name)?;
                 }
                 let sub_manifest = sub_part.to_manifest(sub_name, manifest);
-                let sub_part_path =
-                    archive::create_part(part_dir, &sub_manifest, &output_dir, Some(manifest))?;
+                let sub_part_path = archive::create_part(
+                    part_dir,
+                    &sub_manifest,
+                    &output_dir,
+                    Some(manifest),
+                    &fetches,
+                )?;
                 let file_name = sub_part_path
                     .file_name()
                     .and_then(|n| n.to_str())
@@ -56,7 +62,8 @@ pub async fn package_outputs(
             if !manifest.options.skip_fhs_check {
                 fhs::validate(&result.staging_dir, &manifest.metadata.name)?;
             }
-            let part_path = archive::create_part(&result.staging_dir, manifest, &output_dir, None)?;
+            let part_path =
+                archive::create_part(&result.staging_dir, manifest, &output_dir, None, &fetches)?;
             let file_name = part_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
             info!(
                 event = "seal.packed",
@@ -105,6 +112,7 @@ pub async fn package_manifest(
             build_root: build_root.clone(),
             logs_dir: build_root.join("logs"),
             output_dirs: mold_result.split_dirs,
+            usage: None,
         }
     } else {
         let mut output_dirs = std::collections::HashMap::new();
@@ -121,6 +129,7 @@ pub async fn package_manifest(
             build_root: build_root.clone(),
             logs_dir: build_root.join("logs"),
             output_dirs,
+            usage: None,
         }
     };
 
@@ -0,0 +1,203 @@
+//! Alternatives — one symlink switched between installed implementations.
+//!
+//! An output declares itself a candidate for an alternative name with
+//! `[[output.alternatives]]`:
+//!
+//! ```toml
+//! [[output.alternatives]]
+//! name = "vi"
+//! link = "/usr/bin/vi"
+//! target = "/usr/bin/nvim"
+//! priority = 50
+//! ```
+//!
+//! While at least one candidate is installed, `link` points at the target
+//! of the pinned candidate (`wright alternative pin`), or else of the one
+//! with the highest priority.  Installs, upgrades and removals re-select
+//! the names their part is a candidate for, inside the op's transaction.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use tracing::{info, warn};
+
+use crate::database::{InstalledAlternative, InstalledDb};
+use crate::error::{Result, WrightError};
+use crate::transaction::rollback::RollbackState;
+
+/// The candidate alternative `name` should point at: the pinned part when it
+/// is a candidate, else the first of `candidates` (highest priority first).
+pub fn select_alternative<'a>(
+    candidates: &'a [InstalledAlternative],
+    pin: Option<&str>,
+) -> Option<&'a InstalledAlternative> {
+    pin.and_then(|part| candidates.iter().find(|c| c.part_name == part))
+        .or_else(|| candidates.first())
+}
+
+/// Point the link of each alternative in `names` at its selected candidate
+/// and drop links no candidate is selected for any more.  `released` are
+/// candidacies the caller just took away (by removing or upgrading their
+/// part), so their links can be told from unmanaged files.
+///
+/// A link path that is a part's file or not a symlink is left alone with a
+/// warning.  Returns the links that changed.
+pub async fn sync_alternatives(
+    db: &InstalledDb,
+    root_dir: &Path,
+    names: &BTreeSet<String>,
+    released: &[InstalledAlternative],
+    rollback: &mut RollbackState,
+) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+    for name in names {
+        let candidates = db.list_alternatives(Some(name)).await?;
+        let pin = db.get_alternative_pin(name).await?;
+        let selected = select_alternative(&candidates, pin.as_deref());
+
+        let known: Vec<&InstalledAlternative> = candidates
+            .iter()
+            .chain(released.iter().filter(|r| &r.name == name))
+            .collect();
+        let stale_links: BTreeSet<&str> = known
+            .iter()
+            .map(|c| c.link.as_str())
+            .filter(|link| selected.is_none_or(|s| s.link != *link))
+            .collect();
+        for link in stale_links {
+            let full = root_dir.join(link.trim_start_matches('/'));
+            let Ok(current) = tokio::fs::read_link(&full).await else {
+                continue;
+            };
+            let current = current.to_string_lossy().to_string();
+            if !known.iter().any(|c| c.target == current) || owned_by_part(db, link).await? {
+                continue;
+            }
+            tokio::fs::remove_file(&full).await.map_err(|e| {
+                WrightError::DeployError(format!("failed to remove {}: {}", full.display(), e))
+            })?;
+            rollback.record_symlink_backup(full, current);
+            info!(
+                event = "alternatives.removed",
+                alternative = name,
+                link,
+                "Removed alternative link"
+            );
+            changed.push(link.to_string());
+        }
+
+        if let Some(selected) = selected
+            && set_link(db, root_dir, selected, rollback).await?
+        {
+            changed.push(selected.link.clone());
+        }
+    }
+    Ok(changed)
+}
+
+/// Point `alt.link` at `alt.target`; false when it already did or the link
+/// path is not wright's to manage.
+async fn set_link(
+    db: &InstalledDb,
+    root_dir: &Path,
+    alt: &InstalledAlternative,
+    rollback: &mut RollbackState,
+) -> Result<bool> {
+    let full = root_dir.join(alt.link.trim_start_matches('/'));
+    if owned_by_part(db, &alt.link).await? {
+        warn!(
+            event = "alternatives.link_owned",
+            alternative = alt.name,
+            link = alt.link,
+            "Alternative link is a file of a part; leaving it alone"
+        );
+        return Ok(false);
+    }
+
+    match tokio::fs::symlink_metadata(&full).await {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            let current = tokio::fs::read_link(&full).await?;
+            if current == Path::new(&alt.target) {
+                return Ok(false);
+            }
+            tokio::fs::remove_file(&full).await.map_err(|e| {
+                WrightError::DeployError(format!("failed to replace {}: {}", full.display(), e))
+            })?;
+            rollback.record_symlink_backup(full.clone(), current.to_string_lossy().to_string());
+        }
+        Ok(_) => {
+            warn!(
+                event = "alternatives.link_blocked",
+                alternative = alt.name,
+                link = alt.link,
+                "Alternative link path is not a symlink; leaving it alone"
+            );
+            return Ok(false);
+        }
+        Err(_) => {
+            if let Some(parent) = full.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            rollback.record_file_created(full.clone());
+        }
+    }
+
+    tokio::fs::symlink(&alt.target, &full).await.map_err(|e| {
+        WrightError::DeployError(format!(
+            "failed to link {} -> {}: {}",
+            full.display(),
+            alt.target,
+            e
+        ))
+    })?;
+    info!(
+        event = "alternatives.selected",
+        alternative = alt.name,
+        plan_name = alt.part_name,
+        link = alt.link,
+        target = alt.target,
+        "Selected alternative"
+    );
+    Ok(true)
+}
+
+async fn owned_by_part(db: &InstalledDb, path: &str) -> Result<bool> {
+    Ok(!db.find_owners_batch(&[path]).await?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(part_name: &str, priority: i64) -> InstalledAlternative {
+        InstalledAlternative {
+            name: "vi".to_string(),
+            part_name: part_name.to_string(),
+            link: "/usr/bin/vi".to_string(),
+            target: format!("/usr/bin/{part_name}"),
+            priority,
+        }
+    }
+
+    #[test]
+    fn pin_overrides_priority_while_the_part_is_a_candidate() {
+        let candidates = [candidate("nvim", 50), candidate("vim", 40)];
+        assert_eq!(
+            select_alternative(&candidates, None).unwrap().part_name,
+            "nvim"
+        );
+        assert_eq!(
+            select_alternative(&candidates, Some("vim"))
+                .unwrap()
+                .part_name,
+            "vim"
+        );
+        assert_eq!(
+            select_alternative(&candidates, Some("busybox"))
+                .unwrap()
+                .part_name,
+            "nvim"
+        );
+        assert!(select_alternative(&[], Some("vim")).is_none());
+    }
+}
@@ -0,0 +1,163 @@
+//! Config file protection for `[backup]` files.
+//!
+//! A packaged config is installed over the live file only when the live
+//! file still matches the packaged version it was installed from. When the
+//! admin has edited it, the new version goes beside it as
+//! `<path>.wright-new` and is recorded as a pending config update for
+//! `wright config` to resolve. A pristine copy of every packaged config is
+//! kept by hash under `var/lib/wright/config-base`, so a later three-way
+//! merge has a base to start from.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info, warn};
+
+use crate::database::{FileEntry, FileType, InstalledDb};
+use crate::error::Result;
+use crate::util::checksum;
+
+/// Suffix of the sidecar holding a pending packaged config.
+pub const PENDING_SUFFIX: &str = ".wright-new";
+
+/// Directory, relative to the target root, of pristine packaged configs.
+pub const CONFIG_BASE_DIR: &str = "var/lib/wright/config-base";
+
+/// `<path>.wright-new` for a live config path.
+pub fn pending_path(live: &Path) -> PathBuf {
+    let mut name = live.as_os_str().to_owned();
+    name.push(PENDING_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Pristine copy of the packaged config with content hash `hash`.
+pub fn base_path(root_dir: &Path, hash: &str) -> PathBuf {
+    root_dir.join(CONFIG_BASE_DIR).join(hash)
+}
+
+/// How the incoming config files of one part are to be deployed.
+#[derive(Debug, Default)]
+pub(super) struct ConfigUpdates {
+    /// Paths whose live file carries local edits: deploy beside it.
+    pub sidecar: HashSet<String>,
+    /// Pending records to write: path → (new hash, merge base hash).
+    pub pending: Vec<(String, String, Option<String>)>,
+    /// Paths deployed in place whose earlier pending record is now moot.
+    pub settled: Vec<String>,
+}
+
+/// Decide, for each incoming config file, whether the live copy may be
+/// replaced. `recorded` maps paths to the hash recorded for the currently
+/// installed version (empty on a fresh install); `diverted` paths belong
+/// to another part and are diverted instead.
+pub(super) async fn plan_config_updates(
+    db: &InstalledDb,
+    entries: &[FileEntry],
+    recorded: &HashMap<&str, Option<&str>>,
+    diverted: &HashSet<String>,
+    root_dir: &Path,
+) -> Result<ConfigUpdates> {
+    let mut updates = ConfigUpdates::default();
+    for entry in entries
+        .iter()
+        .filter(|e| e.is_config && e.file_type == FileType::File)
+        .filter(|e| !diverted.contains(&e.path))
+    {
+        let live = root_dir.join(entry.path.trim_start_matches('/'));
+        let pending = db.get_pending_config(&entry.path).await?;
+        if !live.is_file() {
+            if pending.is_some() {
+                updates.settled.push(entry.path.clone());
+            }
+            continue;
+        }
+        let Some(new_hash) = entry.file_hash.clone() else {
+            continue;
+        };
+        let live_hash = checksum::sha256_file(&live).ok();
+        // The local edits started from the pending base if one is recorded,
+        // else from the version installed now.
+        let base = pending
+            .as_ref()
+            .and_then(|p| p.base_hash.clone())
+            .or_else(|| {
+                recorded
+                    .get(entry.path.as_str())
+                    .copied()
+                    .flatten()
+                    .map(str::to_string)
+            });
+
+        let unmodified = live_hash.is_some()
+            && (live_hash.as_deref() == Some(new_hash.as_str()) || live_hash == base);
+        if unmodified {
+            if pending.is_some() {
+                updates.settled.push(entry.path.clone());
+            }
+            continue;
+        }
+        updates.sidecar.insert(entry.path.clone());
+        updates.pending.push((entry.path.clone(), new_hash, base));
+    }
+    Ok(updates)
+}
+
+/// Keep a pristine copy of every packaged config in `extract_dir`. Must run
+/// before the files are moved into the root.
+pub(super) async fn store_config_bases(entries: &[FileEntry], extract_dir: &Path, root_dir: &Path) {
+    for entry in entries
+        .iter()
+        .filter(|e| e.is_config && e.file_type == FileType::File)
+    {
+        let Some(ref hash) = entry.file_hash else {
+            continue;
+        };
+        let dest = base_path(root_dir, hash);
+        if dest.exists() {
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        let src = extract_dir.join(entry.path.trim_start_matches('/'));
+        if let Err(e) = tokio::fs::copy(&src, &dest).await {
+            debug!(event = "config.base_store_failed", path = entry.path, error = %e, "Failed to keep config merge base");
+        }
+    }
+}
+
+/// Record the pending updates of a deployed part and drop settled ones.
+pub(super) async fn record_config_updates(
+    db: &InstalledDb,
+    part_id: i64,
+    updates: &ConfigUpdates,
+) -> Result<()> {
+    for (path, new_hash, base) in &updates.pending {
+        db.upsert_pending_config(path, part_id, new_hash, base.as_deref())
+            .await?;
+        warn!(
+            event = "config.pending",
+            path,
+            sidecar = %format!("{}{}", path, PENDING_SUFFIX),
+            "Config modified locally; new version saved beside it (see `wright config`)"
+        );
+    }
+    for path in &updates.settled {
+        db.delete_pending_config(path).await?;
+    }
+    Ok(())
+}
+
+/// Remove the sidecars of settled updates; runs once the deployment is
+/// committed, since the sidecar is not part of the rollback state.
+pub(super) async fn remove_settled_sidecars(updates: &ConfigUpdates, root_dir: &Path) {
+    for path in &updates.settled {
+        let sidecar = pending_path(&root_dir.join(path.trim_start_matches('/')));
+        if tokio::fs::remove_file(&sidecar).await.is_ok() {
+            info!(
+                event = "config.sidecar_removed",
+                path, "Removed superseded config update"
+            );
+        }
+    }
+}
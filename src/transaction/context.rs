@@ -16,6 +16,10 @@ pub struct TransactionContext<'a> {
     db: &'a InstalledDb,
     rollback: RollbackState,
     tx_id: i64,
+    /// `transaction_ops` row in the session's delivery, if it has one.
+    op_id: Option<i64>,
+    /// Paths the op changed, recorded with it on commit for triggers.
+    touched: Vec<String>,
     part_name: String,
     finalized: bool,
 }
@@ -51,10 +55,28 @@ impl<'a> TransactionContext<'a> {
             )
             .await?;
 
+        // A remove has no incoming artifact; its op points at the one leaving.
+        let op_id = match session.delivery_id {
+            Some(delivery_id) => Some(
+                crate::delivery::begin_op(
+                    db,
+                    delivery_id,
+                    part_name,
+                    new_hash.or(old_hash).unwrap_or_default(),
+                    &action.to_string(),
+                    old_hash,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
         Ok(Self {
             db,
             rollback,
             tx_id,
+            op_id,
+            touched: Vec::new(),
             part_name: part_name.to_string(),
             finalized: false,
         })
@@ -64,10 +86,22 @@ impl<'a> TransactionContext<'a> {
         &mut self.rollback
     }
 
+    /// Note paths this op installed, replaced, or removed, so the
+    /// delivery's transaction triggers can match them.
+    pub fn touch(&mut self, paths: impl IntoIterator<Item = String>) {
+        self.touched.extend(paths);
+    }
+
     pub async fn commit(mut self) -> Result<()> {
         self.db
             .update_history_status(self.tx_id, HistoryStatus::Completed)
             .await?;
+        if let Some(op_id) = self.op_id {
+            if !self.touched.is_empty() {
+                self.db.insert_op_paths(op_id, &self.touched).await?;
+            }
+            crate::delivery::op_done(self.db, op_id).await?;
+        }
         self.rollback.commit();
         self.finalized = true;
         Ok(())
@@ -78,6 +112,9 @@ impl<'a> TransactionContext<'a> {
         self.db
             .update_history_status(self.tx_id, HistoryStatus::RolledBack)
             .await?;
+        if let Some(op_id) = self.op_id {
+            crate::delivery::op_failed(self.db, op_id, "rolled back").await?;
+        }
         self.finalized = true;
         Ok(())
     }
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use std::time::Instant;
@@ -13,9 +13,13 @@ use crate::part::archive;
 use crate::part::archive::PartInfo;
 use crate::part::store::LocalPartStore;
 use crate::part::version::{self, Version};
+use crate::transaction::alternatives::sync_alternatives;
+use crate::transaction::config::{
+    plan_config_updates, record_config_updates, remove_settled_sidecars, store_config_bases,
+};
 use crate::transaction::context::TransactionContext;
 use crate::transaction::fs::{collect_file_entries, copy_entries_to_root};
-use crate::transaction::hooks::{log_running_hook, read_hooks, run_deploy_script};
+use crate::transaction::hooks::{read_hooks, run_part_hook};
 
 use super::{log_debug_timing, remove_part, upgrade_part};
 
@@ -353,6 +357,28 @@ async fn warn_about_runtime_dependencies(
                 continue;
             }
 
+            let providers = db.find_providers(&output_name).await?;
+            if providers.iter().any(|(_, provided)| {
+                version::provide_satisfies(
+                    provided
+                        .as_deref()
+                        .and_then(|v| Version::parse(v).ok())
+                        .as_ref(),
+                    constraint.as_ref(),
+                )
+            }) {
+                continue;
+            }
+            if !providers.is_empty() {
+                warn!(
+                    event = "deploy.version_constraint_unsatisfied",
+                    dependency = output_name,
+                    dependent = name,
+                    "No installed provider satisfies the runtime dependency; continuing deploy"
+                );
+                continue;
+            }
+
             warn!(
                 event = "deploy.missing_dependency",
                 dependency = output_name,
@@ -535,6 +561,7 @@ pub async fn deploy_part_with_origin(
         phase_start.elapsed(),
     );
 
+    let delivery_id = session.delivery_id;
     let mut tx = TransactionContext::begin(
         db,
         HistoryAction::Install,
@@ -550,11 +577,22 @@ pub async fn deploy_part_with_origin(
     let backup_dir = tempfile::tempdir()
         .map_err(|e| WrightError::DeployError(format!("failed to create backup dir: {}", e)))?;
 
-    if run_hooks && let Some(ref script) = hooks.pre_install {
-        log_running_hook(&partinfo.name, "pre_install");
+    let content = hooks_content.as_deref().unwrap_or_default();
+    if run_hooks && let Some(ref hook) = hooks.pre_install {
         phase_start = Instant::now();
-        if let Err(e) = run_deploy_script(script, root_dir, &partinfo.name, "pre_install").await {
-            warn!(event = "deploy.hook_failed", plan_name = partinfo.name, hook = "pre_install", error = %e, "Hook failed");
+        let ran = run_part_hook(
+            db,
+            hook,
+            content,
+            root_dir,
+            &partinfo.name,
+            "pre_install",
+            delivery_id,
+        )
+        .await;
+        if let Err(e) = ran {
+            tx.rollback().await?;
+            return Err(e);
         }
         log_debug_timing(
             "install",
@@ -564,6 +602,11 @@ pub async fn deploy_part_with_origin(
         );
     }
 
+    // A config left behind by an earlier removal may carry local edits.
+    let config_updates =
+        plan_config_updates(db, &file_entries, &HashMap::new(), &divert_paths, root_dir).await?;
+    store_config_bases(&file_entries, temp_dir.path(), root_dir).await;
+
     phase_start = Instant::now();
     match copy_entries_to_root(
         &file_entries,
@@ -571,7 +614,7 @@ pub async fn deploy_part_with_origin(
         root_dir,
         tx.rollback_state(),
         Some(backup_dir.path()),
-        &HashSet::new(),
+        &config_updates.sidecar,
         &divert_paths,
     )
     .await
@@ -628,6 +671,13 @@ pub async fn deploy_part_with_origin(
     }
 
     db.insert_files(part_id, &file_entries).await?;
+    record_config_updates(db, part_id, &config_updates).await?;
+    tx.touch(
+        file_entries
+            .iter()
+            .filter(|e| e.file_type != FileType::Directory)
+            .map(|e| e.path.clone()),
+    );
 
     let mut deps = Vec::new();
     for d in &partinfo.runtime_deps {
@@ -648,8 +698,23 @@ pub async fn deploy_part_with_origin(
     if !partinfo.replaces.is_empty() {
         db.insert_replaces(part_id, &partinfo.replaces).await?;
     }
+    if !partinfo.provides.is_empty() {
+        db.insert_provides(part_id, &partinfo.provides).await?;
+    }
+    if !partinfo.alternatives.is_empty() {
+        db.replace_alternatives(part_id, &partinfo.alternatives)
+            .await?;
+        let names: BTreeSet<String> = partinfo
+            .alternatives
+            .iter()
+            .map(|alt| alt.name.clone())
+            .collect();
+        let linked = sync_alternatives(db, root_dir, &names, &[], tx.rollback_state()).await?;
+        tx.touch(linked);
+    }
 
     tx.commit().await?;
+    remove_settled_sidecars(&config_updates, root_dir).await;
     log_debug_timing(
         "deploy",
         &partinfo.name,
@@ -657,12 +722,18 @@ pub async fn deploy_part_with_origin(
         phase_start.elapsed(),
     );
 
-    if run_hooks && let Some(ref script) = hooks.post_install {
-        log_running_hook(&partinfo.name, "post_install");
+    if run_hooks && let Some(ref hook) = hooks.post_install {
         phase_start = Instant::now();
-        if let Err(e) = run_deploy_script(script, root_dir, &partinfo.name, "post_install").await {
-            warn!(event = "deploy.hook_failed", plan_name = partinfo.name, hook = "post_install", error = %e, "Hook failed");
-        }
+        run_part_hook(
+            db,
+            hook,
+            content,
+            root_dir,
+            &partinfo.name,
+            "post_install",
+            delivery_id,
+        )
+        .await?;
         log_debug_timing(
             "install",
             &partinfo.name,
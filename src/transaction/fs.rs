@@ -4,7 +4,7 @@ use crate::part::archive::PartInfo;
 use crate::transaction::rollback::RollbackState;
 use crate::util::checksum;
 use std::collections::HashSet;
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -75,20 +75,16 @@ pub(super) fn collect_file_entries(
             file_type,
             file_mode: Some(metadata.permissions().mode() as i64),
             is_config,
+            // Directories are often shared and predate the part; only the
+            // owner of what the part itself put on disk is meaningful.
+            file_uid: (file_type != FileType::Directory).then_some(metadata.uid() as i64),
+            file_gid: (file_type != FileType::Directory).then_some(metadata.gid() as i64),
         });
     }
 
     Ok(entries)
 }
 
-pub(super) fn collect_config_paths(new_entries: &[FileEntry]) -> HashSet<String> {
-    new_entries
-        .iter()
-        .filter(|e| e.is_config && e.file_type == FileType::File)
-        .map(|e| e.path.clone())
-        .collect()
-}
-
 /// Move `src` to `dst`, using rename(2) when possible (same filesystem) and
 /// falling back to copy+delete when crossing filesystem boundaries (EXDEV).
 async fn move_or_copy(src: &Path, dst: &Path) -> std::io::Result<()> {
@@ -211,9 +207,7 @@ pub(super) async fn copy_entries_to_root(
             if config_paths.contains(&entry.path)
                 && tokio::fs::symlink_metadata(&dest_path).await.is_ok()
             {
-                let mut new_name = dest_path.as_os_str().to_owned();
-                new_name.push(".wnew");
-                let side_path = PathBuf::from(new_name);
+                let side_path = super::config::pending_path(&dest_path);
                 move_or_copy(&src_path, &side_path).await.map_err(|e| {
                     WrightError::DeployError(format!(
                         "failed to write {}: {}",
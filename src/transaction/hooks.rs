@@ -1,7 +1,10 @@
 use std::path::Path;
 use std::process::Stdio;
 
+use crate::database::{HookFailure, InstalledDb};
 use crate::error::{Result, WrightError};
+use crate::isolation::{IsolationConfig, IsolationLevel, ResourceLimits};
+use crate::plan::manifest::{Hook, HookFailurePolicy};
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 
 #[derive(Debug, Clone, Default, serde::Deserialize)]
@@ -13,15 +16,15 @@ struct HooksFile {
 #[derive(Debug, Clone, Default, serde::Deserialize)]
 pub(super) struct Hooks {
     #[serde(default)]
-    pub pre_install: Option<String>,
+    pub pre_install: Option<Hook>,
     #[serde(default)]
-    pub post_install: Option<String>,
+    pub post_install: Option<Hook>,
     #[serde(default)]
-    pub post_upgrade: Option<String>,
+    pub post_upgrade: Option<Hook>,
     #[serde(default)]
-    pub pre_remove: Option<String>,
+    pub pre_remove: Option<Hook>,
     #[serde(default)]
-    pub post_remove: Option<String>,
+    pub post_remove: Option<Hook>,
 }
 
 pub(super) fn read_hooks(extract_dir: &Path) -> (Option<String>, Hooks) {
@@ -42,7 +45,7 @@ fn parse_hooks_from_db(content: &str) -> Hooks {
     Hooks::default()
 }
 
-pub fn get_hook(content: &str, hook_name: &str) -> Option<String> {
+pub fn get_hook(content: &str, hook_name: &str) -> Option<Hook> {
     let hooks = parse_hooks_from_db(content);
     match hook_name {
         "pre_install" => hooks.pre_install,
@@ -67,6 +70,30 @@ fn normalize_hook_output_line(line: &str) -> &str {
     line.strip_prefix(":: ").unwrap_or(line)
 }
 
+fn log_hook_line(part_name: &str, hook_name: &str, line: &str, stderr: bool) {
+    let line = normalize_hook_output_line(line.trim_end());
+    if line.is_empty() {
+        return;
+    }
+    if stderr {
+        tracing::warn!(
+            event = "hooks.stderr",
+            hook_name,
+            part_name,
+            line,
+            "Hook stderr output"
+        );
+    } else {
+        tracing::info!(
+            event = "hooks.stdout",
+            hook_name,
+            part_name,
+            line,
+            "Hook stdout output"
+        );
+    }
+}
+
 async fn log_hook_output<R>(
     reader: R,
     part_name: String,
@@ -78,27 +105,7 @@ where
 {
     let mut lines = BufReader::new(reader).lines();
     while let Some(line) = lines.next_line().await? {
-        let line = normalize_hook_output_line(line.trim_end());
-        if line.is_empty() {
-            continue;
-        }
-        if stderr {
-            tracing::warn!(
-                event = "hooks.stderr",
-                hook_name,
-                part_name,
-                line,
-                "Hook stderr output"
-            );
-        } else {
-            tracing::info!(
-                event = "hooks.stdout",
-                hook_name,
-                part_name,
-                line,
-                "Hook stdout output"
-            );
-        }
+        log_hook_line(&part_name, &hook_name, &line, stderr);
     }
     Ok(())
 }
@@ -115,11 +122,219 @@ async fn join_hook_output(
     Ok(())
 }
 
+/// Run hook `hook_name` of `part_name`, taken from the part's `.HOOKS`
+/// `content`, and apply its `on_failure` policy.  Only the failure of an
+/// `abort` hook is returned; the failure of a `warn` or `defer` hook is
+/// recorded for `wright hook run`, and a success clears an earlier one.
+/// A failing `abort` post hook is recorded too: its operation already
+/// happened, so the hook is left to re-run.
+pub(super) async fn run_part_hook(
+    db: &InstalledDb,
+    hook: &Hook,
+    content: &str,
+    root_dir: &Path,
+    part_name: &str,
+    hook_name: &str,
+    delivery_id: Option<i64>,
+) -> Result<()> {
+    log_running_hook(part_name, hook_name);
+    let e = match run_deploy_script(hook, root_dir, part_name, hook_name).await {
+        Ok(()) => {
+            db.delete_hook_failure(part_name, hook_name).await?;
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+    tracing::warn!(
+        event = "hooks.failed",
+        part_name,
+        hook_name,
+        on_failure = hook.on_failure.as_str(),
+        error = %e,
+        "Hook failed"
+    );
+    let status = match hook.on_failure {
+        HookFailurePolicy::Abort => {
+            if hook_name.starts_with("post_") {
+                db.record_hook_failure(
+                    part_name,
+                    hook_name,
+                    "failed",
+                    content,
+                    &e.to_string(),
+                    delivery_id,
+                )
+                .await?;
+            }
+            return Err(WrightError::ScriptError(format!(
+                "{} hook of '{}' failed: {}",
+                hook_name, part_name, e
+            )));
+        }
+        HookFailurePolicy::Warn => "failed",
+        HookFailurePolicy::Defer => "deferred",
+    };
+    db.record_hook_failure(
+        part_name,
+        hook_name,
+        status,
+        content,
+        &e.to_string(),
+        delivery_id,
+    )
+    .await
+}
+
+/// Run the hooks deferred during delivery `tx_id` once more, now that the
+/// rest of the delivery is in place.  Those that fail again stay recorded.
+pub async fn run_deferred_hooks(db: &InstalledDb, tx_id: i64, root_dir: &Path) -> Result<()> {
+    for deferred in db.get_deferred_hooks(tx_id).await? {
+        if let Err(e) = rerun_hook(db, &deferred, root_dir).await {
+            tracing::warn!(
+                event = "hooks.deferred_failed",
+                part_name = deferred.part_name,
+                hook_name = deferred.hook,
+                error = %e,
+                "Deferred hook failed again"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Run the hook of a recorded failure again.  A success clears the record;
+/// a failure refreshes it, keeping its status, and is returned.
+pub async fn rerun_hook(db: &InstalledDb, failure: &HookFailure, root_dir: &Path) -> Result<()> {
+    let Some(hook) = get_hook(&failure.hooks, &failure.hook) else {
+        // The recorded hooks no longer parse; nothing left to run.
+        db.delete_hook_failure(&failure.part_name, &failure.hook)
+            .await?;
+        return Ok(());
+    };
+    log_running_hook(&failure.part_name, &failure.hook);
+    match run_deploy_script(&hook, root_dir, &failure.part_name, &failure.hook).await {
+        Ok(()) => {
+            db.delete_hook_failure(&failure.part_name, &failure.hook)
+                .await?;
+            Ok(())
+        }
+        Err(e) => {
+            db.record_hook_failure(
+                &failure.part_name,
+                &failure.hook,
+                &failure.status,
+                &failure.hooks,
+                &e.to_string(),
+                failure.delivery_id,
+            )
+            .await?;
+            Err(e)
+        }
+    }
+}
+
+/// Run a deploy hook against `root_dir`, isolated unless it opts out with
+/// `isolation = "none"`, and killed once it outlives its timeout.
 pub(super) async fn run_deploy_script(
+    hook: &Hook,
+    root_dir: &Path,
+    part_name: &str,
+    hook_name: &str,
+) -> Result<()> {
+    let timeout = std::time::Duration::from_secs(hook.timeout_secs());
+    if hook.isolation == IsolationLevel::None {
+        return run_script_with_timeout(
+            &hook.script,
+            root_dir,
+            part_name,
+            hook_name,
+            None,
+            timeout,
+        )
+        .await;
+    }
+    run_isolated_script(hook, root_dir, part_name, hook_name).await
+}
+
+/// Run `hook` through the isolation machinery with `root_dir` mounted
+/// writable as `/`: its own mount, PID, and IPC namespaces, no network
+/// under `strict`, and the isolation watchdog enforcing the timeout.
+async fn run_isolated_script(
+    hook: &Hook,
+    root_dir: &Path,
+    part_name: &str,
+    hook_name: &str,
+) -> Result<()> {
+    let root_dir = root_dir.canonicalize().map_err(|e| {
+        WrightError::ScriptError(format!(
+            "cannot resolve deploy root {}: {}",
+            root_dir.display(),
+            e
+        ))
+    })?;
+    // Scratch space for the mount point of the new root, off the target.
+    let scratch = tempfile::tempdir().map_err(|e| {
+        WrightError::ScriptError(format!("failed to create hook scratch dir: {}", e))
+    })?;
+    let mut config = IsolationConfig::new(
+        hook.isolation,
+        scratch.path().join("src"),
+        scratch.path().join("output"),
+        format!("hook-{}-{}", part_name, hook_name),
+    );
+    config.base_root = root_dir;
+    config.writable_root = true;
+    config.env = vec![("ROOT".to_string(), "/".to_string())];
+    config.rlimits = ResourceLimits {
+        timeout_secs: Some(hook.timeout_secs()),
+        ..ResourceLimits::default()
+    };
+
+    let script = hook.script.clone();
+    let started = std::time::Instant::now();
+    let output = tokio::task::spawn_blocking(move || {
+        crate::isolation::run_in_isolation(
+            &mut config,
+            "/bin/sh",
+            &["-e".to_string(), "-c".to_string(), script],
+        )
+    })
+    .await
+    .map_err(|e| WrightError::ScriptError(format!("hook task failed: {}", e)))??;
+
+    for (captured, stderr) in [(output.stdout, false), (output.stderr, true)] {
+        use std::io::BufRead;
+        for line in std::io::BufReader::new(captured.file).lines() {
+            let Ok(line) = line else { break };
+            log_hook_line(part_name, hook_name, &line, stderr);
+        }
+    }
+
+    if !output.status.success() {
+        if started.elapsed().as_secs() >= hook.timeout_secs() {
+            return Err(WrightError::ScriptError(format!(
+                "script timed out after {}s",
+                hook.timeout_secs()
+            )));
+        }
+        return Err(WrightError::ScriptError(format!(
+            "script exited with status {}",
+            output.status
+        )));
+    }
+    Ok(())
+}
+
+/// Run `script` under `/bin/sh -e` in `root_dir`, chrooted into it when it
+/// is not `/` and we are root, writing `input` to its stdin when given, and
+/// kill the script's process group once it has run for `timeout`.
+pub(super) async fn run_script_with_timeout(
     script: &str,
     root_dir: &Path,
     part_name: &str,
     hook_name: &str,
+    input: Option<&str>,
+    timeout: std::time::Duration,
 ) -> Result<()> {
     // The chroot child runs with cwd `/`, so a relative `--root` (e.g.
     // `build/rootfs`) would resolve against `/` and chroot would exit 125.
@@ -144,17 +359,32 @@ pub(super) async fn run_deploy_script(
     let root_env = if use_chroot { Path::new("/") } else { root_dir };
     let current_dir = if use_chroot { Path::new("/") } else { root_dir };
 
-    let mut child = command
+    command
         .arg("-e")
         .arg("-c")
         .arg(script)
         .env("ROOT", root_env)
         .current_dir(current_dir)
+        .process_group(0)
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+        .stderr(Stdio::piped());
+    if input.is_some() {
+        command.stdin(Stdio::piped());
+    }
+    let mut child = command
         .spawn()
         .map_err(|e| WrightError::ScriptError(format!("failed to execute script: {}", e)))?;
 
+    // Fed from a task so a script that ignores its input cannot block us;
+    // dropping the handle closes the pipe.
+    let stdin_task = child.stdin.take().zip(input).map(|(mut stdin, input)| {
+        let input = input.to_string();
+        tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            let _ = stdin.write_all(input.as_bytes()).await;
+        })
+    });
+
     let stdout_task = child.stdout.take().map(|stdout| {
         tokio::spawn(log_hook_output(
             stdout,
@@ -172,14 +402,34 @@ pub(super) async fn run_deploy_script(
         ))
     });
 
-    let status = child
-        .wait()
-        .await
-        .map_err(|e| WrightError::ScriptError(format!("failed to wait for script: {}", e)))?;
+    let waited = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(waited) => Some(waited),
+        Err(_) => {
+            if let Some(pid) = child.id() {
+                let _ = nix::sys::signal::killpg(
+                    nix::unistd::Pid::from_raw(pid as i32),
+                    nix::sys::signal::Signal::SIGKILL,
+                );
+            }
+            let _ = child.wait().await;
+            None
+        }
+    };
 
     join_hook_output(stdout_task).await?;
     join_hook_output(stderr_task).await?;
+    if let Some(task) = stdin_task {
+        let _ = task.await;
+    }
 
+    let Some(waited) = waited else {
+        return Err(WrightError::ScriptError(format!(
+            "script timed out after {}s",
+            timeout.as_secs()
+        )));
+    };
+    let status = waited
+        .map_err(|e| WrightError::ScriptError(format!("failed to wait for script: {}", e)))?;
     if !status.success() {
         return Err(WrightError::ScriptError(format!(
             "script exited with status {}",
@@ -1,9 +1,13 @@
+pub mod alternatives;
+pub mod config;
 mod context;
 mod deploy;
 mod fs;
 mod hooks;
 mod remove;
+mod repair;
 pub mod rollback;
+pub mod triggers;
 mod upgrade;
 mod verify;
 
@@ -18,12 +22,14 @@ pub use context::TransactionContext;
 pub use deploy::{
     deploy_part, deploy_part_with_origin, deploy_parts, deploy_parts_with_explicit_targets,
 };
-pub use hooks::get_hook;
+pub use hooks::{get_hook, rerun_hook, run_deferred_hooks};
 pub use remove::{
     cascade_remove_list, order_removal_batch, remove_part, remove_part_with_ignored_dependents,
 };
+pub use repair::repair_part;
+pub use triggers::run_triggers;
 pub use upgrade::upgrade_part;
-pub use verify::verify_part;
+pub use verify::{AuditFinding, AuditProblem, audit_part, verify_part};
 
 /// Derive journal path from the database path.
 pub(super) fn journal_path_from_db(db: &InstalledDb) -> Option<PathBuf> {
@@ -48,6 +54,11 @@ pub(super) async fn self_replace_relations(
         .execute(&db.pool)
         .await
         .map_err(|e| WrightError::DatabaseError(format!("failed to delete old replaces: {}", e)))?;
+    sqlx::query("DELETE FROM provides WHERE part_id = ?")
+        .bind(part_id)
+        .execute(&db.pool)
+        .await
+        .map_err(|e| WrightError::DatabaseError(format!("failed to delete old provides: {}", e)))?;
 
     if !partinfo.conflicts.is_empty() {
         db.insert_conflicts(part_id, &partinfo.conflicts).await?;
@@ -55,6 +66,9 @@ pub(super) async fn self_replace_relations(
     if !partinfo.replaces.is_empty() {
         db.insert_replaces(part_id, &partinfo.replaces).await?;
     }
+    if !partinfo.provides.is_empty() {
+        db.insert_provides(part_id, &partinfo.provides).await?;
+    }
     Ok(())
 }
 
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::Path;
 
 use tracing::{info, warn};
@@ -7,8 +7,10 @@ use crate::database::{FileType, HistoryAction, InstalledDb, SessionContext};
 use crate::error::{Result, WrightError};
 
 use super::get_hook;
+use crate::transaction::alternatives::sync_alternatives;
+use crate::transaction::config::pending_path;
 use crate::transaction::context::TransactionContext;
-use crate::transaction::hooks::{log_running_hook, run_deploy_script};
+use crate::transaction::hooks::run_part_hook;
 
 use futures_util::FutureExt;
 use futures_util::future::BoxFuture;
@@ -65,19 +67,20 @@ pub async fn remove_part_with_ignored_dependents(
         }
     }
 
+    let delivery_id = session.delivery_id;
     if let Some(ref content) = part.deploy_scripts
-        && let Some(script) = get_hook(content, "pre_remove")
+        && let Some(hook) = get_hook(content, "pre_remove")
     {
-        log_running_hook(name, "pre_remove");
-        if let Err(e) = run_deploy_script(&script, root_dir, name, "pre_remove").await {
-            warn!(
-                event = "remove.hook_failed",
-                plan_name = name,
-                hook = "pre_remove",
-                error = %e,
-                "Hook failed, continuing removal"
-            );
-        }
+        run_part_hook(
+            db,
+            &hook,
+            content,
+            root_dir,
+            name,
+            "pre_remove",
+            delivery_id,
+        )
+        .await?;
     }
 
     // Create backup directory BEFORE transaction context so it outlives the tx
@@ -192,9 +195,39 @@ pub async fn remove_part_with_ignored_dependents(
 
     // Phase 3: Database mutation.
     let diversions = db.get_all_diverted_files(part.id).await.unwrap_or_default();
-
+    let pending_configs: Vec<String> = db
+        .list_pending_configs()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.part_name == name)
+        .map(|p| p.path)
+        .collect();
+
+    let released = db.get_part_alternatives(name).await?;
     db.remove_part(name).await?;
+    // Failures of the hooks of the installed part no longer need a re-run.
+    for hook in ["pre_install", "post_install", "post_upgrade"] {
+        db.delete_hook_failure(name, hook).await?;
+    }
     let _ = db.remove_shadowed_records(part.id).await;
+    tx.touch(
+        files
+            .iter()
+            .filter(|f| f.file_type != FileType::Directory && !f.is_config)
+            .map(|f| f.path.clone()),
+    );
+
+    // Another candidate takes over the links this part was selected for.
+    let names: BTreeSet<String> = released.iter().map(|alt| alt.name.clone()).collect();
+    let linked = sync_alternatives(db, root_dir, &names, &released, tx.rollback_state()).await?;
+    tx.touch(linked);
+
+    // The live config stays; its unmerged packaged update goes with the part.
+    for path in pending_configs {
+        let sidecar = pending_path(&root_dir.join(path.trim_start_matches('/')));
+        let _ = tokio::fs::remove_file(&sidecar).await;
+    }
 
     // Phase 4: Restore diverted files.
     for (original_path, diverted_path) in diversions {
@@ -214,22 +247,26 @@ pub async fn remove_part_with_ignored_dependents(
         }
     }
 
+    // The part is gone from the registry: an aborting post_remove cannot
+    // undo the removal, only stop the delivery after it.
+    let mut post_remove = Ok(());
     if let Some(ref content) = part.deploy_scripts
-        && let Some(script) = get_hook(content, "post_remove")
+        && let Some(hook) = get_hook(content, "post_remove")
     {
-        log_running_hook(name, "post_remove");
-        if let Err(e) = run_deploy_script(&script, root_dir, name, "post_remove").await {
-            warn!(
-                event = "remove.hook_failed",
-                plan_name = name,
-                hook = "post_remove",
-                error = %e,
-                "Hook failed, continuing removal"
-            );
-        }
+        post_remove = run_part_hook(
+            db,
+            &hook,
+            content,
+            root_dir,
+            name,
+            "post_remove",
+            delivery_id,
+        )
+        .await;
     }
 
     tx.commit().await?;
+    post_remove?;
 
     info!(event = "remove.completed", plan_name = name, "Removed");
     Ok(())
@@ -0,0 +1,216 @@
+//! In-place repair of a deployed part from the archive it came from.
+//!
+//! Only the given paths are rewritten; hooks do not run and the registry is
+//! left as it is. Every overwrite is journaled through the transaction's
+//! `RollbackState`, so a failed or interrupted repair puts back what was on
+//! disk before it started.
+
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::database::{FileEntry, FileType, HistoryAction, InstalledDb, SessionContext};
+use crate::error::{Result, WrightError};
+use crate::part::archive;
+use crate::transaction::context::TransactionContext;
+
+/// Put `paths` of part `name` back as recorded, taking content from
+/// `archive_path`, which must be the archive the part was deployed from.
+/// Returns the number of paths rewritten.
+pub async fn repair_part(
+    db: &InstalledDb,
+    name: &str,
+    archive_path: &Path,
+    paths: &[String],
+    root_dir: &Path,
+    session: SessionContext,
+) -> Result<usize> {
+    let part = db
+        .get_part(name)
+        .await?
+        .ok_or_else(|| WrightError::PartNotFound(name.to_string()))?;
+    let files: HashMap<String, FileEntry> = db
+        .get_files(part.id)
+        .await?
+        .into_iter()
+        .map(|f| (f.path.clone(), f))
+        .collect();
+    let shadowed: HashMap<String, Option<String>> =
+        db.get_shadowed_paths(part.id).await?.into_iter().collect();
+
+    let staging_dir = root_dir.join("var/lib/wright/staging");
+    let _ = tokio::fs::create_dir_all(&staging_dir).await;
+    let temp_dir = tempfile::tempdir_in(&staging_dir)
+        .or_else(|_| tempfile::tempdir())
+        .map_err(|e| WrightError::DeployError(format!("failed to create temp dir: {}", e)))?;
+    let (partinfo, archive_hash) = archive::extract_part(archive_path, temp_dir.path())?;
+    if part.part_hash.as_deref() != Some(archive_hash.as_str()) {
+        return Err(WrightError::ValidationError(format!(
+            "{} is not the archive '{}' was deployed from",
+            archive_path.display(),
+            name
+        )));
+    }
+    let backup_dir = tempfile::tempdir()
+        .map_err(|e| WrightError::DeployError(format!("failed to create backup dir: {}", e)))?;
+
+    let version = partinfo.plan.version.as_str();
+    let mut tx = TransactionContext::begin(
+        db,
+        HistoryAction::Repair,
+        name,
+        Some(version),
+        Some(version),
+        session,
+        Some(&archive_hash),
+        Some(&archive_hash),
+    )
+    .await?;
+
+    // Sorted, so a directory is restored before what it contains.
+    let mut wanted: Vec<&String> = paths.iter().collect();
+    wanted.sort();
+    wanted.dedup();
+
+    let mut repaired = 0;
+    for path in wanted {
+        let Some(file) = files.get(path) else {
+            continue;
+        };
+        // A file shadowed by another part now lives at its diverted path.
+        let on_disk = match shadowed.get(path) {
+            Some(Some(diverted)) => diverted.as_str(),
+            Some(None) => continue,
+            None => path.as_str(),
+        };
+        let src = temp_dir.path().join(path.trim_start_matches('/'));
+        let dest = root_dir.join(on_disk.trim_start_matches('/'));
+        let backup = backup_dir.path().join(path.trim_start_matches('/'));
+
+        let result = journal_existing(&mut tx, &dest, &backup)
+            .and_then(|existed| restore_entry(file, &src, &dest).map(|()| existed));
+        match result {
+            Ok(existed) => {
+                if !existed {
+                    let rollback = tx.rollback_state();
+                    match file.file_type {
+                        FileType::Directory => rollback.record_dir_created(dest),
+                        _ => rollback.record_file_created(dest),
+                    }
+                }
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                return Err(WrightError::DeployError(format!(
+                    "failed to repair {}: {}",
+                    on_disk, e
+                )));
+            }
+        }
+        info!(
+            event = "repair.restored",
+            part = name,
+            path,
+            "Restored file"
+        );
+        if file.file_type != FileType::Directory {
+            tx.touch([path.clone()]);
+        }
+        repaired += 1;
+    }
+
+    tx.commit().await?;
+    Ok(repaired)
+}
+
+/// Record what is at `dest` so a rollback can put it back. Returns whether
+/// anything was there.
+fn journal_existing(
+    tx: &mut TransactionContext<'_>,
+    dest: &Path,
+    backup: &Path,
+) -> std::io::Result<bool> {
+    let Ok(meta) = std::fs::symlink_metadata(dest) else {
+        return Ok(false);
+    };
+    if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(dest)?;
+        tx.rollback_state()
+            .record_symlink_backup(dest.to_path_buf(), target.to_string_lossy().to_string());
+    } else if meta.is_file() {
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // The repaired file is renamed into place, so a hard link keeps the
+        // old inode intact without copying it.
+        if std::fs::hard_link(dest, backup).is_err() {
+            std::fs::copy(dest, backup)?;
+        }
+        tx.rollback_state()
+            .record_backup(dest.to_path_buf(), backup.to_path_buf());
+    }
+    Ok(true)
+}
+
+fn restore_entry(file: &FileEntry, src: &Path, dest: &Path) -> std::io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let existing = std::fs::symlink_metadata(dest).ok();
+    if file.file_type != FileType::Directory && existing.as_ref().is_some_and(|m| m.is_dir()) {
+        return Err(std::io::Error::other("a directory is in the way"));
+    }
+
+    match file.file_type {
+        FileType::Directory => {
+            if !existing.as_ref().is_some_and(|m| m.is_dir()) {
+                if existing.is_some() {
+                    std::fs::remove_file(dest)?;
+                }
+                std::fs::create_dir(dest)?;
+            }
+            set_mode(dest, file.file_mode)?;
+        }
+        FileType::File => {
+            let mut tmp = dest.as_os_str().to_owned();
+            tmp.push(".wright-restore");
+            let tmp = PathBuf::from(tmp);
+            std::fs::copy(src, &tmp)?;
+            // chown clears setuid/setgid, so the mode goes on last.
+            set_owner(&tmp, file)?;
+            set_mode(&tmp, file.file_mode)?;
+            std::fs::rename(&tmp, dest)?;
+        }
+        FileType::Symlink => {
+            let target = std::fs::read_link(src)?;
+            if existing.is_some() {
+                std::fs::remove_file(dest)?;
+            }
+            std::os::unix::fs::symlink(target, dest)?;
+            set_owner(dest, file)?;
+        }
+    }
+    Ok(())
+}
+
+fn set_owner(path: &Path, file: &FileEntry) -> std::io::Result<()> {
+    if file.file_uid.is_none() && file.file_gid.is_none() {
+        return Ok(());
+    }
+    std::os::unix::fs::lchown(
+        path,
+        file.file_uid.map(|uid| uid as u32),
+        file.file_gid.map(|gid| gid as u32),
+    )
+}
+
+fn set_mode(path: &Path, mode: Option<i64>) -> std::io::Result<()> {
+    match mode {
+        Some(mode) => {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode as u32 & 0o7777))
+        }
+        None => Ok(()),
+    }
+}
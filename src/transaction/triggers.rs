@@ -0,0 +1,245 @@
+//! Transaction triggers — system-wide scripts that run once per delivery.
+//!
+//! A trigger is a TOML file in `<root>/etc/wright/triggers/`:
+//!
+//! ```toml
+//! [trigger]
+//! description = "Refresh the shared library cache"
+//! paths = ["/usr/lib/*.so*", "/usr/lib/**/*.so*"]
+//! on = ["install", "upgrade", "remove"]
+//! script = "ldconfig"
+//! ```
+//!
+//! Every op of a delivery records the files and symlinks it installed,
+//! replaced, or removed.  Once the delivery's ops are all done, each
+//! trigger whose `paths` globs match any of those paths runs once, in file
+//! name order, with the matched paths on stdin, one per line, and is
+//! killed once it outlives its `timeout` (the hook default, 600 seconds).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use crate::database::InstalledDb;
+use crate::error::{Result, WrightError};
+use crate::plan::manifest::DEFAULT_HOOK_TIMEOUT;
+use crate::transaction::hooks::run_script_with_timeout;
+
+/// Where triggers are read from, relative to the target root.
+pub const TRIGGERS_DIR: &str = "etc/wright/triggers";
+
+/// Op actions a trigger can be limited to with `on`.
+const ACTIONS: &[&str] = &["install", "upgrade", "remove", "repair"];
+
+#[derive(Debug, Deserialize)]
+struct TriggerFile {
+    trigger: TriggerConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriggerConfig {
+    #[serde(default)]
+    description: Option<String>,
+    /// Globs over absolute paths; `*` stops at `/`, `**` does not.
+    paths: Vec<String>,
+    /// Op actions whose paths count; empty means all of them.
+    #[serde(default)]
+    on: Vec<String>,
+    script: String,
+    /// Wall-clock limit in seconds; unset means [`DEFAULT_HOOK_TIMEOUT`].
+    #[serde(default)]
+    timeout: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct Trigger {
+    /// The file stem of the trigger's definition.
+    pub name: String,
+    pub description: Option<String>,
+    on: Vec<String>,
+    script: String,
+    timeout: u64,
+    matcher: GlobSet,
+}
+
+impl Trigger {
+    /// Parse one trigger definition.
+    pub fn parse(name: &str, content: &str) -> Result<Self> {
+        let file: TriggerFile = toml::from_str(content)
+            .map_err(|e| WrightError::ParseError(format!("trigger {name}: {e}")))?;
+        let config = file.trigger;
+        if config.paths.is_empty() {
+            return Err(WrightError::ValidationError(format!(
+                "trigger {name}: 'paths' must not be empty"
+            )));
+        }
+        if let Some(action) = config.on.iter().find(|a| !ACTIONS.contains(&a.as_str())) {
+            return Err(WrightError::ValidationError(format!(
+                "trigger {name}: unknown action '{action}' in 'on' (expected one of {})",
+                ACTIONS.join(", ")
+            )));
+        }
+        if config.timeout == Some(0) {
+            return Err(WrightError::ValidationError(format!(
+                "trigger {name}: timeout must be at least 1 second"
+            )));
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &config.paths {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    WrightError::ValidationError(format!("trigger {name}: path '{pattern}': {e}"))
+                })?;
+            builder.add(glob);
+        }
+        let matcher = builder
+            .build()
+            .map_err(|e| WrightError::ValidationError(format!("trigger {name}: {e}")))?;
+        Ok(Self {
+            name: name.to_string(),
+            description: config.description,
+            on: config.on,
+            script: config.script,
+            timeout: config.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT),
+            matcher,
+        })
+    }
+
+    /// Whether a path touched by an op of `action` fires this trigger.
+    pub fn matches(&self, action: &str, path: &str) -> bool {
+        (self.on.is_empty() || self.on.iter().any(|a| a == action)) && self.matcher.is_match(path)
+    }
+}
+
+/// Load the `*.toml` triggers in `dir`, sorted by file name.  A missing
+/// directory means no triggers; a definition that does not parse is
+/// skipped with a warning, so one broken file cannot hold back the rest.
+pub fn load_triggers(dir: &Path) -> Result<Vec<Trigger>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(WrightError::IoError)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("toml"))
+        .collect();
+    files.sort();
+
+    let mut triggers = Vec::new();
+    for path in files {
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(WrightError::IoError)
+            .and_then(|content| Trigger::parse(&name, &content));
+        match parsed {
+            Ok(trigger) => triggers.push(trigger),
+            Err(e) => warn!(
+                event = "triggers.invalid",
+                path = %path.display(),
+                error = %e,
+                "Skipping invalid trigger"
+            ),
+        }
+    }
+    Ok(triggers)
+}
+
+/// Run the triggers of `root_dir` that match the paths touched by the done
+/// ops of delivery `tx_id`, then drop the recorded paths.
+///
+/// The ops are already committed, so a failing trigger is reported and
+/// the rest still run.
+pub async fn run_triggers(db: &InstalledDb, tx_id: i64, root_dir: &Path) -> Result<()> {
+    let touched = db.get_delivery_paths(tx_id).await?;
+    if !touched.is_empty() {
+        for trigger in load_triggers(&root_dir.join(TRIGGERS_DIR))? {
+            let matched: BTreeSet<&str> = touched
+                .iter()
+                .filter(|(action, path)| trigger.matches(action, path))
+                .map(|(_, path)| path.as_str())
+                .collect();
+            if matched.is_empty() {
+                debug!(
+                    event = "triggers.skipped",
+                    trigger = trigger.name,
+                    "No touched path matches trigger"
+                );
+                continue;
+            }
+            info!(
+                event = "triggers.running",
+                trigger = trigger.name,
+                path_count = matched.len(),
+                "Running trigger"
+            );
+            let input: String = matched.iter().map(|path| format!("{path}\n")).collect();
+            if let Err(e) = run_script_with_timeout(
+                &trigger.script,
+                root_dir,
+                &trigger.name,
+                "trigger",
+                Some(&input),
+                std::time::Duration::from_secs(trigger.timeout),
+            )
+            .await
+            {
+                warn!(
+                    event = "triggers.failed",
+                    trigger = trigger.name,
+                    error = %e,
+                    "Trigger failed"
+                );
+            }
+        }
+    }
+    db.clear_delivery_paths(tx_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_matches_globs_and_actions() {
+        let trigger = Trigger::parse(
+            "ldconfig",
+            r#"
+[trigger]
+paths = ["/usr/lib/*.so*"]
+on = ["install", "upgrade"]
+script = "ldconfig"
+"#,
+        )
+        .unwrap();
+        assert!(trigger.matches("install", "/usr/lib/libz.so.1"));
+        assert!(!trigger.matches("install", "/usr/lib/gio/modules/libgio.so"));
+        assert!(!trigger.matches("remove", "/usr/lib/libz.so.1"));
+        assert!(!trigger.matches("install", "/usr/bin/zcat"));
+    }
+
+    #[test]
+    fn trigger_rejects_unknown_actions() {
+        let err = Trigger::parse(
+            "bad",
+            "[trigger]\npaths = [\"/usr/lib/*\"]\non = [\"purge\"]\nscript = \"true\"\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown action 'purge'"), "{err}");
+
+        let err = Trigger::parse(
+            "bad",
+            "[trigger]\npaths = [\"/usr/lib/*\"]\nscript = \"true\"\ntimeout = 0\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("at least 1 second"), "{err}");
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 use tracing::{debug, info, warn};
@@ -7,9 +7,13 @@ use crate::database::{Dependency, FileType, HistoryAction, InstalledDb, NewPart,
 use crate::error::{Result, WrightError};
 use crate::part::archive;
 use crate::part::version::{self, Version};
+use crate::transaction::alternatives::sync_alternatives;
+use crate::transaction::config::{
+    plan_config_updates, record_config_updates, remove_settled_sidecars, store_config_bases,
+};
 use crate::transaction::context::TransactionContext;
-use crate::transaction::fs::{collect_config_paths, collect_file_entries, copy_entries_to_root};
-use crate::transaction::hooks::{log_running_hook, read_hooks, run_deploy_script};
+use crate::transaction::fs::{collect_file_entries, copy_entries_to_root};
+use crate::transaction::hooks::{read_hooks, run_part_hook};
 
 use super::{log_debug_timing, self_replace_relations};
 
@@ -164,6 +168,7 @@ pub async fn upgrade_part(
         phase_start.elapsed(),
     );
 
+    let delivery_id = session.delivery_id;
     let mut tx = TransactionContext::begin(
         db,
         HistoryAction::Upgrade,
@@ -232,11 +237,22 @@ pub async fn upgrade_part(
         }
     }
 
-    if run_hooks && let Some(ref script) = hooks.pre_install {
-        log_running_hook(&partinfo.name, "pre_install");
+    let content = hooks_content.as_deref().unwrap_or_default();
+    if run_hooks && let Some(ref hook) = hooks.pre_install {
         phase_start = Instant::now();
-        if let Err(e) = run_deploy_script(script, root_dir, &partinfo.name, "pre_install").await {
-            warn!(event = "upgrade.hook_failed", plan_name = partinfo.name, hook = "pre_install", error = %e, "Hook failed");
+        let ran = run_part_hook(
+            db,
+            hook,
+            content,
+            root_dir,
+            &partinfo.name,
+            "pre_install",
+            delivery_id,
+        )
+        .await;
+        if let Err(e) = ran {
+            tx.rollback().await?;
+            return Err(e);
         }
         log_debug_timing(
             "upgrade",
@@ -246,24 +262,33 @@ pub async fn upgrade_part(
         );
     }
 
-    let config_paths = collect_config_paths(&new_entries);
+    // Configs the admin edited since they were installed get the new
+    // version beside them instead of over them.
+    let recorded: HashMap<&str, Option<&str>> = existing_files
+        .iter()
+        .filter(|f| f.is_config)
+        .map(|f| (f.path.as_str(), f.file_hash.as_deref()))
+        .collect();
+    let config_updates =
+        plan_config_updates(db, &new_entries, &recorded, &divert_paths, root_dir).await?;
+    store_config_bases(&new_entries, temp_dir.path(), root_dir).await;
     phase_start = Instant::now();
 
     // copy_entries_to_root should probably also be async.
     // For now I'll assume it's still sync and wraps internal tokio calls if needed,
     // but better to refactor it to async too.
-    let preserved_configs = match copy_entries_to_root(
+    match copy_entries_to_root(
         &new_entries,
         temp_dir.path(),
         root_dir,
         tx.rollback_state(),
         None,
-        &config_paths,
+        &config_updates.sidecar,
         &divert_paths,
     )
     .await
     {
-        Ok(paths) => paths,
+        Ok(_) => {}
         Err(e) => {
             warn!(event = "upgrade.failed_rollback", plan_name = partinfo.name, error = %e, "Upgrade failed, rolling back");
             tx.rollback().await?;
@@ -277,9 +302,6 @@ pub async fn upgrade_part(
         "filesystem copy into target root",
         phase_start.elapsed(),
     );
-    for path in preserved_configs {
-        info!(event = "upgrade.config_preserved", path, "Preserved config");
-    }
 
     let to_delete_paths: Vec<&str> = existing_files
         .iter()
@@ -378,6 +400,19 @@ pub async fn upgrade_part(
     }
 
     db.replace_files(updated_part.id, &new_entries).await?;
+    record_config_updates(db, updated_part.id, &config_updates).await?;
+    let dropped = existing_files
+        .iter()
+        .filter(|f| !new_paths.contains(f.path.as_str()) && !f.is_config)
+        .map(|f| (&f.path, f.file_type));
+    tx.touch(
+        new_entries
+            .iter()
+            .map(|e| (&e.path, e.file_type))
+            .chain(dropped)
+            .filter(|(_, file_type)| *file_type != FileType::Directory)
+            .map(|(path, _)| path.clone()),
+    );
 
     let mut deps = Vec::new();
     for d in &partinfo.runtime_deps {
@@ -391,7 +426,19 @@ pub async fn upgrade_part(
 
     self_replace_relations(db, updated_part.id, &partinfo).await?;
 
+    let released = db.get_part_alternatives(&partinfo.name).await?;
+    db.replace_alternatives(updated_part.id, &partinfo.alternatives)
+        .await?;
+    let names: BTreeSet<String> = released
+        .iter()
+        .map(|alt| alt.name.clone())
+        .chain(partinfo.alternatives.iter().map(|alt| alt.name.clone()))
+        .collect();
+    let linked = sync_alternatives(db, root_dir, &names, &released, tx.rollback_state()).await?;
+    tx.touch(linked);
+
     tx.commit().await?;
+    remove_settled_sidecars(&config_updates, root_dir).await;
     log_debug_timing(
         "upgrade",
         &partinfo.name,
@@ -399,12 +446,18 @@ pub async fn upgrade_part(
         phase_start.elapsed(),
     );
 
-    if run_hooks && let Some(ref script) = hooks.post_upgrade {
-        log_running_hook(&partinfo.name, "post_upgrade");
+    if run_hooks && let Some(ref hook) = hooks.post_upgrade {
         phase_start = Instant::now();
-        if let Err(e) = run_deploy_script(script, root_dir, &partinfo.name, "post_upgrade").await {
-            warn!(event = "upgrade.hook_failed", plan_name = partinfo.name, hook = "post_upgrade", error = %e, "Hook failed");
-        }
+        run_part_hook(
+            db,
+            hook,
+            content,
+            root_dir,
+            &partinfo.name,
+            "post_upgrade",
+            delivery_id,
+        )
+        .await?;
         log_debug_timing(
             "upgrade",
             &partinfo.name,
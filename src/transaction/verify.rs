@@ -1,6 +1,10 @@
-use crate::database::{FileType, InstalledDb};
+use crate::database::{FileEntry, FileType, InstalledDb, InstalledPart};
 use crate::error::{Result, WrightError};
+use crate::transaction::config::PENDING_SUFFIX;
 use crate::util::checksum;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 
 pub async fn verify_part(db: &InstalledDb, name: &str, root_dir: &Path) -> Result<Vec<String>> {
@@ -53,3 +57,231 @@ pub async fn verify_part(db: &InstalledDb, name: &str, root_dir: &Path) -> Resul
 
     Ok(issues)
 }
+
+/// One way a deployed file departs from what was recorded for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditProblem {
+    Missing,
+    WrongType,
+    Content,
+    Size,
+    Mode,
+    Owner,
+    Target,
+    Unreadable,
+    /// Not recorded for any part, in a directory only this part owns.
+    Extra,
+}
+
+impl AuditProblem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::WrongType => "wrong_type",
+            Self::Content => "content",
+            Self::Size => "size",
+            Self::Mode => "mode",
+            Self::Owner => "owner",
+            Self::Target => "target",
+            Self::Unreadable => "unreadable",
+            Self::Extra => "extra",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFinding {
+    /// Path as recorded for the part.
+    pub path: String,
+    pub problem: AuditProblem,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    /// `backup` files are expected to carry local edits.
+    pub is_config: bool,
+}
+
+impl AuditFinding {
+    /// Local edits to a config are not damage; leave them to `wright config`.
+    pub fn is_local_edit(&self) -> bool {
+        self.is_config && matches!(self.problem, AuditProblem::Content | AuditProblem::Size)
+    }
+}
+
+/// Audit every file recorded for `part` against the disk: presence, type,
+/// size, content, mode, owner and symlink target, plus unrecorded files in
+/// directories only this part owns.
+pub async fn audit_part(
+    db: &InstalledDb,
+    part: &InstalledPart,
+    root_dir: &Path,
+) -> Result<Vec<AuditFinding>> {
+    let files = db.get_files(part.id).await?;
+    let shadowed: HashMap<String, Option<String>> =
+        db.get_shadowed_paths(part.id).await?.into_iter().collect();
+
+    let mut findings = Vec::new();
+    for file in &files {
+        // A file shadowed by another part now lives at its diverted path.
+        let on_disk = match shadowed.get(&file.path) {
+            Some(Some(diverted)) => diverted.as_str(),
+            Some(None) => continue,
+            None => file.path.as_str(),
+        };
+        audit_file(
+            file,
+            &root_dir.join(on_disk.trim_start_matches('/')),
+            &mut findings,
+        );
+    }
+    findings.extend(extra_files(db, part.id, &files, root_dir).await?);
+    findings.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(findings)
+}
+
+fn audit_file(file: &FileEntry, full_path: &Path, findings: &mut Vec<AuditFinding>) {
+    let mut push = |problem, expected: Option<String>, actual: Option<String>| {
+        findings.push(AuditFinding {
+            path: file.path.clone(),
+            problem,
+            expected,
+            actual,
+            is_config: file.is_config,
+        })
+    };
+
+    let meta = match std::fs::symlink_metadata(full_path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            push(AuditProblem::Missing, None, None);
+            return;
+        }
+        Err(e) => {
+            push(AuditProblem::Unreadable, None, Some(e.to_string()));
+            return;
+        }
+    };
+    let actual_type = if meta.is_dir() {
+        FileType::Directory
+    } else if meta.file_type().is_symlink() {
+        FileType::Symlink
+    } else {
+        FileType::File
+    };
+    if actual_type != file.file_type {
+        push(
+            AuditProblem::WrongType,
+            Some(file.file_type.as_str().to_string()),
+            Some(actual_type.as_str().to_string()),
+        );
+        return;
+    }
+
+    match file.file_type {
+        FileType::File => {
+            if let Some(size) = file.file_size
+                && size != meta.len() as i64
+            {
+                push(
+                    AuditProblem::Size,
+                    Some(size.to_string()),
+                    Some(meta.len().to_string()),
+                );
+            } else if let Some(ref expected) = file.file_hash {
+                match checksum::sha256_file(full_path) {
+                    Ok(actual) if &actual != expected => {
+                        push(AuditProblem::Content, Some(expected.clone()), Some(actual));
+                    }
+                    Ok(_) => {}
+                    Err(e) => push(AuditProblem::Unreadable, None, Some(e.to_string())),
+                }
+            }
+            if let Some(mode) = file.file_mode
+                && (mode as u32) & 0o7777 != meta.mode() & 0o7777
+            {
+                push(
+                    AuditProblem::Mode,
+                    Some(format!("{:o}", mode & 0o7777)),
+                    Some(format!("{:o}", meta.mode() & 0o7777)),
+                );
+            }
+        }
+        FileType::Symlink => {
+            if let Some(ref expected) = file.file_hash {
+                match std::fs::read_link(full_path) {
+                    Ok(target) if target.to_string_lossy() != expected.as_str() => push(
+                        AuditProblem::Target,
+                        Some(expected.clone()),
+                        Some(target.to_string_lossy().into_owned()),
+                    ),
+                    Ok(_) => {}
+                    Err(e) => push(AuditProblem::Unreadable, None, Some(e.to_string())),
+                }
+            }
+        }
+        // Directory modes and owners are left alone: shared directories
+        // usually predate the part that records them.
+        FileType::Directory => return,
+    }
+
+    let uid_differs = file.file_uid.is_some_and(|uid| uid != meta.uid() as i64);
+    let gid_differs = file.file_gid.is_some_and(|gid| gid != meta.gid() as i64);
+    if uid_differs || gid_differs {
+        push(
+            AuditProblem::Owner,
+            Some(format!(
+                "{}:{}",
+                id_or_dash(file.file_uid),
+                id_or_dash(file.file_gid)
+            )),
+            Some(format!("{}:{}", meta.uid(), meta.gid())),
+        );
+    }
+}
+
+fn id_or_dash(id: Option<i64>) -> String {
+    id.map_or_else(|| "-".to_string(), |id| id.to_string())
+}
+
+/// Unrecorded entries in directories no other part records. Shared
+/// directories such as `/usr/bin` are skipped: their strays cannot be
+/// attributed to a part.
+async fn extra_files(
+    db: &InstalledDb,
+    part_id: i64,
+    files: &[FileEntry],
+    root_dir: &Path,
+) -> Result<Vec<AuditFinding>> {
+    let recorded: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let mut candidates = Vec::new();
+    for dir in db.get_exclusive_dirs(part_id).await? {
+        let Ok(entries) = std::fs::read_dir(root_dir.join(dir.trim_start_matches('/'))) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = format!(
+                "{}/{}",
+                dir.trim_end_matches('/'),
+                entry.file_name().to_string_lossy()
+            );
+            // Sidecars of pending config updates belong to `wright config`.
+            if !recorded.contains(path.as_str()) && !path.ends_with(PENDING_SUFFIX) {
+                candidates.push(path);
+            }
+        }
+    }
+
+    let paths: Vec<&str> = candidates.iter().map(String::as_str).collect();
+    let owned = db.find_owners_batch(&paths).await?;
+    Ok(candidates
+        .into_iter()
+        .filter(|path| !owned.contains_key(path))
+        .map(|path| AuditFinding {
+            path,
+            problem: AuditProblem::Extra,
+            expected: None,
+            actual: None,
+            is_config: false,
+        })
+        .collect())
+}
@@ -38,7 +38,16 @@ use std::os::unix::fs::{FileTypeExt, MetadataExt};
 
 /// Create a tar.zst archive from a directory.
 /// Handles symlinks by archiving them as symlinks (not following them).
-pub fn create_tar_zst(source_dir: &Path, output_path: &Path) -> Result<()> {
+///
+/// With `mtime_clamp` (a `SOURCE_DATE_EPOCH`), the archive is reproducible:
+/// entry mtimes are clamped to it and every entry is owned by root, so the
+/// same tree always yields the same bytes. Entries are in file-name order
+/// either way.
+pub fn create_tar_zst(
+    source_dir: &Path,
+    output_path: &Path,
+    mtime_clamp: Option<u64>,
+) -> Result<()> {
     let file = std::fs::File::create(output_path).map_err(|e| {
         WrightError::PartError(format!("failed to create {}: {}", output_path.display(), e))
     })?;
@@ -88,23 +97,35 @@ pub fn create_tar_zst(source_dir: &Path, output_path: &Path) -> Result<()> {
             header.set_entry_type(tar::EntryType::Symlink);
             header.set_size(0);
             header.set_mode(0o777);
-            header.set_mtime(
-                metadata
-                    .modified()
-                    .map(|t| {
-                        t.duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs()
-                    })
-                    .unwrap_or(0),
-            );
+            header.set_mtime(entry_mtime(&metadata, mtime_clamp));
             tar_builder
                 .append_link(&mut header, &rel_path, &target)
                 .map_err(|e| WrightError::PartError(format!("tar append symlink failed: {}", e)))?;
         } else if metadata.is_dir() {
+            if let Some(clamp) = mtime_clamp {
+                let mut header = reproducible_header(&metadata, clamp);
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_size(0);
+                tar_builder
+                    .append_data(&mut header, &rel_path, std::io::empty())
+                    .map_err(|e| WrightError::PartError(format!("tar append dir failed: {}", e)))?;
+            } else {
+                tar_builder
+                    .append_dir(&rel_path, full_path)
+                    .map_err(|e| WrightError::PartError(format!("tar append dir failed: {}", e)))?;
+            }
+        } else if let Some(clamp) = mtime_clamp
+            && metadata.is_file()
+        {
+            let mut header = reproducible_header(&metadata, clamp);
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(metadata.len());
+            let file = std::fs::File::open(full_path).map_err(|e| {
+                WrightError::PartError(format!("failed to open {}: {}", full_path.display(), e))
+            })?;
             tar_builder
-                .append_dir(&rel_path, full_path)
-                .map_err(|e| WrightError::PartError(format!("tar append dir failed: {}", e)))?;
+                .append_data(&mut header, &rel_path, file)
+                .map_err(|e| WrightError::PartError(format!("tar append file failed: {}", e)))?;
         } else {
             // The tar crate's append_path_with_name passes the absolute source path
             // to append_special for device/FIFO entries, ignoring the archive name.
@@ -132,19 +153,12 @@ pub fn create_tar_zst(source_dir: &Path, output_path: &Path) -> Result<()> {
                         ))
                     })?;
                     header.set_mode(metadata.mode());
-                    header.set_uid(metadata.uid() as u64);
-                    header.set_gid(metadata.gid() as u64);
+                    if mtime_clamp.is_none() {
+                        header.set_uid(metadata.uid() as u64);
+                        header.set_gid(metadata.gid() as u64);
+                    }
                     header.set_size(0);
-                    header.set_mtime(
-                        metadata
-                            .modified()
-                            .map(|t| {
-                                t.duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap_or_default()
-                                    .as_secs()
-                            })
-                            .unwrap_or(0),
-                    );
+                    header.set_mtime(entry_mtime(&metadata, mtime_clamp));
                     if file_type.is_fifo() {
                         header.set_entry_type(tar::EntryType::Fifo);
                     } else {
@@ -202,6 +216,30 @@ pub fn create_tar_zst(source_dir: &Path, output_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Modification time of an entry in seconds, clamped to `clamp` when set.
+fn entry_mtime(metadata: &std::fs::Metadata, clamp: Option<u64>) -> u64 {
+    let mtime = metadata
+        .modified()
+        .map(|t| {
+            t.duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        })
+        .unwrap_or(0);
+    clamp.map_or(mtime, |clamp| mtime.min(clamp))
+}
+
+/// A header carrying `metadata`'s permission bits, owned by root, with
+/// its mtime clamped to `clamp`.
+fn reproducible_header(metadata: &std::fs::Metadata, clamp: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_mtime(entry_mtime(metadata, Some(clamp)));
+    header
+}
+
 /// Normalize a filesystem path into a safe, relative path for archive entry names.
 fn normalize_part_path(path: &Path) -> Option<std::path::PathBuf> {
     let mut normalized = std::path::PathBuf::new();
@@ -602,4 +640,21 @@ mod tests {
         let result = extract_tar_lz(archive.path(), dest.path());
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_create_with_mtime_clamp_is_reproducible() {
+        let build = |content: &[u8]| {
+            let tree = tempfile::tempdir().unwrap();
+            std::fs::create_dir_all(tree.path().join("usr/bin")).unwrap();
+            std::fs::write(tree.path().join("usr/bin/hello"), content).unwrap();
+            std::os::unix::fs::symlink("hello", tree.path().join("usr/bin/hi")).unwrap();
+            let out = tempfile::NamedTempFile::new().unwrap();
+            create_tar_zst(tree.path(), out.path(), Some(1_000_000)).unwrap();
+            std::fs::read(out.path()).unwrap()
+        };
+        let first = build(b"hello");
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        assert_eq!(first, build(b"hello"));
+        assert_ne!(first, build(b"hellp"));
+    }
 }
@@ -31,7 +31,7 @@ pub fn download_file(url: &str, dest: &Path, timeout: u64, scope: &str) -> Resul
 
         let _span = crate::cli_span!("Fetching", "{} ({})", label, scope);
         if src_path.is_dir() {
-            compress::create_tar_zst(src_path, dest)?;
+            compress::create_tar_zst(src_path, dest, None)?;
         } else {
             std::fs::copy(src_path, dest).map_err(WrightError::IoError)?;
         }
@@ -134,6 +134,7 @@ fn is_wright_error_prefix(seg: &str) -> bool {
             | "script error"
             | "validation error"
             | "isolation error"
+            | "out of memory"
             | "network error"
             | "TOML deserialization error"
             | "SQLite error"
@@ -0,0 +1,208 @@
+//! Line-based text diff and three-way merge, as used to review and settle
+//! pending config updates. The line diff itself comes from libgit2.
+
+use git2::{DiffOptions, Patch};
+
+use crate::error::{Result, WrightError};
+
+/// Outcome of [`merge3`]: the merged text, with conflict markers around
+/// every region both sides changed differently.
+pub struct Merged {
+    pub content: Vec<u8>,
+    pub conflicts: usize,
+}
+
+/// A change of base lines `start..end` into `lines` on one side.
+struct Edit<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a [u8]>,
+}
+
+/// Unified diff from `old` to `new`, empty when they are equal.
+pub fn unified_diff(old: &[u8], old_label: &str, new: &[u8], new_label: &str) -> Result<String> {
+    let mut patch = Patch::from_buffers(
+        old,
+        Some(std::path::Path::new(old_label)),
+        new,
+        Some(std::path::Path::new(new_label)),
+        None,
+    )
+    .map_err(git_error)?;
+    let buf = patch.to_buf().map_err(git_error)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Three-way merge of `ours` and `theirs`, both derived from `base`.
+/// Changes on one side only are taken as they are; overlapping changes
+/// that differ become a conflict marked `<<<<<<< local` / `>>>>>>> new`.
+pub fn merge3(base: &[u8], ours: &[u8], theirs: &[u8]) -> Result<Merged> {
+    let base_lines = split_lines(base);
+    let ours_edits = edits(base, ours)?;
+    let theirs_edits = edits(base, theirs)?;
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut conflicts = 0;
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < ours_edits.len() || j < theirs_edits.len() {
+        // Grow a cluster of edits from either side that overlap or touch.
+        let start = match (ours_edits.get(i), theirs_edits.get(j)) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(a), None) => a.start,
+            (None, Some(b)) => b.start,
+            (None, None) => unreachable!(),
+        };
+        let mut end = start;
+        let (first_ours, first_theirs) = (i, j);
+        loop {
+            if let Some(a) = ours_edits.get(i).filter(|a| a.start <= end) {
+                end = end.max(a.end);
+                i += 1;
+            } else if let Some(b) = theirs_edits.get(j).filter(|b| b.start <= end) {
+                end = end.max(b.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        for line in &base_lines[pos..start] {
+            out.extend_from_slice(line);
+        }
+        let ours_region = apply(&base_lines, start, end, &ours_edits[first_ours..i]);
+        let theirs_region = apply(&base_lines, start, end, &theirs_edits[first_theirs..j]);
+        if first_theirs == j || ours_region == theirs_region {
+            out.extend(ours_region);
+        } else if first_ours == i {
+            out.extend(theirs_region);
+        } else {
+            conflicts += 1;
+            out.extend_from_slice(b"<<<<<<< local\n");
+            push_block(&mut out, &ours_region);
+            out.extend_from_slice(b"=======\n");
+            push_block(&mut out, &theirs_region);
+            out.extend_from_slice(b">>>>>>> new\n");
+        }
+        pos = end;
+    }
+    for line in &base_lines[pos..] {
+        out.extend_from_slice(line);
+    }
+    Ok(Merged {
+        content: out,
+        conflicts,
+    })
+}
+
+fn edits<'a>(base: &[u8], other: &'a [u8]) -> Result<Vec<Edit<'a>>> {
+    let other_lines = split_lines(other);
+    let mut opts = DiffOptions::new();
+    opts.context_lines(0);
+    let patch = Patch::from_buffers(base, None, other, None, Some(&mut opts)).map_err(git_error)?;
+    let mut edits = Vec::with_capacity(patch.num_hunks());
+    for idx in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(idx).map_err(git_error)?;
+        // A pure insertion's old_start names the line it follows.
+        let (old_start, old_lines) = (hunk.old_start() as usize, hunk.old_lines() as usize);
+        let start = if old_lines == 0 {
+            old_start
+        } else {
+            old_start - 1
+        };
+        let (new_start, new_lines) = (hunk.new_start() as usize, hunk.new_lines() as usize);
+        let lines = if new_lines == 0 {
+            Vec::new()
+        } else {
+            other_lines[new_start - 1..new_start - 1 + new_lines].to_vec()
+        };
+        edits.push(Edit {
+            start,
+            end: start + old_lines,
+            lines,
+        });
+    }
+    Ok(edits)
+}
+
+/// Base lines `start..end` with `edits` (all inside that range) applied.
+fn apply<'a>(base: &[&'a [u8]], start: usize, end: usize, edits: &[Edit<'a>]) -> Vec<u8> {
+    let mut region = Vec::new();
+    let mut cursor = start;
+    for edit in edits {
+        for line in &base[cursor..edit.start] {
+            region.extend_from_slice(line);
+        }
+        for line in &edit.lines {
+            region.extend_from_slice(line);
+        }
+        cursor = edit.end;
+    }
+    for line in &base[cursor..end] {
+        region.extend_from_slice(line);
+    }
+    region
+}
+
+/// Append a conflict block, terminating its last line so the marker after
+/// it starts a line of its own.
+fn push_block(out: &mut Vec<u8>, block: &[u8]) {
+    out.extend_from_slice(block);
+    if !block.is_empty() && !block.ends_with(b"\n") {
+        out.push(b'\n');
+    }
+}
+
+fn split_lines(text: &[u8]) -> Vec<&[u8]> {
+    text.split_inclusive(|&b| b == b'\n').collect()
+}
+
+fn git_error(e: git2::Error) -> WrightError {
+    WrightError::IoError(std::io::Error::other(format!("text diff failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &[u8] = b"listen 80\nworkers 4\nlog info\n";
+
+    #[test]
+    fn test_merge3_takes_changes_from_both_sides() {
+        let ours = b"listen 8080\nworkers 4\nlog info\n";
+        let theirs = b"listen 80\nworkers 4\nlog info\ntimeout 30\n";
+        let merged = merge3(BASE, ours, theirs).unwrap();
+        assert_eq!(merged.conflicts, 0);
+        assert_eq!(
+            merged.content,
+            b"listen 8080\nworkers 4\nlog info\ntimeout 30\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_merge3_marks_conflicting_changes() {
+        let ours = b"listen 80\nworkers 8\nlog info\n";
+        let theirs = b"listen 80\nworkers 2\nlog info\n";
+        let merged = merge3(BASE, ours, theirs).unwrap();
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            String::from_utf8(merged.content).unwrap(),
+            "listen 80\n<<<<<<< local\nworkers 8\n=======\nworkers 2\n>>>>>>> new\nlog info\n"
+        );
+    }
+
+    #[test]
+    fn test_merge3_identical_changes_do_not_conflict() {
+        let both = b"listen 80\nworkers 4\nlog debug\n";
+        let merged = merge3(BASE, both, both).unwrap();
+        assert_eq!(merged.conflicts, 0);
+        assert_eq!(merged.content, both.to_vec());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff(BASE, "a/app.conf", b"listen 80\n", "b/app.conf").unwrap();
+        assert!(diff.contains("-workers 4\n"), "{diff}");
+        assert!(unified_diff(BASE, "a", BASE, "b").unwrap().is_empty());
+    }
+}
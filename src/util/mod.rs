@@ -3,8 +3,12 @@ pub mod compress;
 pub mod download;
 pub mod lock;
 pub mod logging;
+pub mod merge;
+pub mod output;
 pub mod progress;
 pub mod stdin;
+pub mod units;
+pub mod x509;
 
 /// Compact a file path for logging by replacing middle segments with `…`
 /// when the path exceeds 45 characters.
@@ -20,6 +24,18 @@ pub fn compact_path(path: &str) -> String {
     format!("/{}/.../{}/{}", parts[0], parts[n - 2], parts[n - 1])
 }
 
+/// Bytes used by the regular files under `path` (or by `path` itself when
+/// it is a file). Unreadable entries are skipped; symlinks are not followed.
+pub fn disk_usage(path: &std::path::Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
 /// Strip path separators and dangerous components from a filename derived from a URL.
 pub fn sanitize_filename(raw: &str) -> String {
     let name = raw.rsplit('/').next().unwrap_or(raw);
@@ -0,0 +1,128 @@
+//! Machine-readable rendering for query commands (`--format json|tsv`).
+//!
+//! Human output stays on the span-driven stderr path in `util::logging`;
+//! the structured formats write only to stdout so they can be piped
+//! while progress lines still reach the terminal.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// Version of the JSON envelope and TSV column layout. Bump on any
+/// change that removes, renames, or retypes a field; adding a field is
+/// not a breaking change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub fn is_human(self) -> bool {
+        self == Self::Human
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    schema_version: u32,
+    command: &'a str,
+    #[serde(flatten)]
+    body: &'a T,
+}
+
+/// Write `body` wrapped in the versioned envelope:
+/// `{"schema_version": N, "command": "<command>", ...body}`.
+///
+/// `body` must serialize to a JSON object; its fields are flattened into
+/// the envelope.
+pub fn write_json<T: Serialize>(out: &mut dyn Write, command: &str, body: &T) -> Result<()> {
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        command,
+        body,
+    };
+    serde_json::to_writer_pretty(&mut *out, &envelope).map_err(std::io::Error::other)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Write a TSV table: a `# schema_version=N` line, a column header, then
+/// one line per row. Fields are escaped with [`tsv_escape`].
+pub fn write_tsv<R, I>(out: &mut dyn Write, columns: &[&str], rows: I) -> Result<()>
+where
+    I: IntoIterator<Item = R>,
+    R: AsRef<[String]>,
+{
+    writeln!(out, "# schema_version={}", SCHEMA_VERSION)?;
+    writeln!(out, "{}", columns.join("\t"))?;
+    for row in rows {
+        let fields: Vec<String> = row.as_ref().iter().map(|f| tsv_escape(f)).collect();
+        writeln!(out, "{}", fields.join("\t"))?;
+    }
+    Ok(())
+}
+
+/// Escape backslash, tab, and newline so every record stays on one line.
+pub fn tsv_escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render an optional value as a TSV field; `None` is the empty string.
+pub fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Body {
+        parts: Vec<&'static str>,
+    }
+
+    #[test]
+    fn test_json_envelope_carries_schema_version() {
+        let mut out = Vec::new();
+        write_json(
+            &mut out,
+            "list",
+            &Body {
+                parts: vec!["zlib"],
+            },
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["command"], "list");
+        assert_eq!(value["parts"][0], "zlib");
+    }
+
+    #[test]
+    fn test_tsv_header_and_escaping() {
+        let mut out = Vec::new();
+        let rows = vec![vec!["a\tb".to_string(), "line\nbreak\\".to_string()]];
+        write_tsv(&mut out, &["x", "y"], rows).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# schema_version=1\nx\ty\na\\tb\tline\\nbreak\\\\\n"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Parsing and formatting of human-written sizes and ages, as used by the
+//! retention flags of maintenance commands (`--max-size 50G`, `--max-age 30d`).
+
+use std::time::Duration;
+
+use crate::error::{Result, WrightError};
+
+/// Parse a byte size: a number with an optional binary suffix
+/// (`K`, `M`, `G`, `T`; `KiB`/`KB`-style spellings are accepted too).
+pub fn parse_size(raw: &str) -> Result<u64> {
+    let s = raw.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, suffix) = s.split_at(split);
+    let shift = match suffix
+        .trim()
+        .to_ascii_uppercase()
+        .trim_end_matches('B')
+        .trim_end_matches('I')
+    {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(invalid("size", raw, "50G, 512M, 1T")),
+    };
+    let value: f64 = number
+        .parse()
+        .map_err(|_| invalid("size", raw, "50G, 512M, 1T"))?;
+    Ok((value * (1u64 << shift) as f64) as u64)
+}
+
+/// Parse an age: a whole number followed by `s`, `m`, `h`, `d`, or `w`.
+pub fn parse_age(raw: &str) -> Result<Duration> {
+    let s = raw.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: u64 = number
+        .parse()
+        .map_err(|_| invalid("age", raw, "30d, 12h, 2w"))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return Err(invalid("age", raw, "30d, 12h, 2w")),
+    };
+    Ok(Duration::from_secs(value * secs))
+}
+
+/// Format a byte count with a binary unit, e.g. `1.5 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn invalid(what: &str, raw: &str, examples: &str) -> WrightError {
+    WrightError::ValidationError(format!(
+        "invalid {} '{}' (examples: {})",
+        what, raw, examples
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_suffixes() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("4K").unwrap(), 4096);
+        assert_eq!(parse_size("50G").unwrap(), 50 << 30);
+        assert_eq!(parse_size("1.5GiB").unwrap(), 3 << 29);
+        assert_eq!(parse_size("2 tb").unwrap(), 2 << 40);
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn test_parse_age_units() {
+        assert_eq!(parse_age("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_age("30d").unwrap(), Duration::from_secs(30 * 86_400));
+        assert_eq!(parse_age("2w").unwrap(), Duration::from_secs(14 * 86_400));
+        assert!(parse_age("3y").is_err());
+        assert!(parse_age("d").is_err());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(50 << 30), "50.0 GiB");
+    }
+}
@@ -0,0 +1,262 @@
+//! Minimal X.509 issuing for the build network proxy.
+//!
+//! The proxy terminates the TLS of every HTTPS request a stage makes, so it
+//! needs a certificate authority the stage trusts and a leaf certificate for
+//! each host the stage connects to.  Both are short-lived ECDSA P-256
+//! certificates generated in memory; the DER encoding below covers exactly
+//! the fields that TLS clients (OpenSSL, Go, rustls, Node) check.
+
+use base64::Engine;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, WrightError};
+
+/// How long issued certificates are valid, in days either side of now.
+const VALIDITY_DAYS: i64 = 7;
+
+const OID_ECDSA_SHA256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+const OID_PRIME256V1: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_SUBJECT_KEY_ID: &[u8] = &[0x55, 0x1D, 0x0E];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x0F];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1D, 0x11];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1D, 0x13];
+const OID_AUTHORITY_KEY_ID: &[u8] = &[0x55, 0x1D, 0x23];
+const OID_EXT_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x25];
+const OID_SERVER_AUTH: &[u8] = &[0x2B, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01];
+
+/// A key pair and its PKCS#8 encoding.
+struct Key {
+    pair: EcdsaKeyPair,
+    pkcs8: Vec<u8>,
+}
+
+impl Key {
+    fn generate(rng: &SystemRandom) -> Result<Self> {
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, rng)
+            .map_err(|_| WrightError::NetworkError("failed to generate key".into()))?;
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), rng)
+            .map_err(|_| WrightError::NetworkError("failed to load generated key".into()))?;
+        Ok(Self {
+            pair,
+            pkcs8: pkcs8.as_ref().to_vec(),
+        })
+    }
+
+    fn id(&self) -> Vec<u8> {
+        Sha256::digest(self.pair.public_key().as_ref())[..20].to_vec()
+    }
+}
+
+/// A throwaway certificate authority.
+pub struct CertAuthority {
+    rng: SystemRandom,
+    key: Key,
+    name: String,
+    cert: Vec<u8>,
+    /// Shared by every leaf: issuing is per host, key generation need not be.
+    leaf_key: Key,
+}
+
+/// A leaf certificate and its private key, both DER.
+pub struct IssuedCert {
+    pub cert: Vec<u8>,
+    pub pkcs8_key: Vec<u8>,
+}
+
+impl CertAuthority {
+    /// Generate a self-signed CA named `name`.
+    pub fn generate(name: &str) -> Result<Self> {
+        let rng = SystemRandom::new();
+        let key = Key::generate(&rng)?;
+        let leaf_key = Key::generate(&rng)?;
+        let extensions = [
+            extension(OID_BASIC_CONSTRAINTS, true, &seq(&[der(0x01, &[0xFF])])),
+            // keyCertSign and cRLSign.
+            extension(OID_KEY_USAGE, true, &der(0x03, &[0x01, 0x06])),
+            extension(OID_SUBJECT_KEY_ID, false, &der(0x04, &key.id())),
+        ];
+        let cert = sign(&rng, &key, name, name, &key, &extensions)?;
+        Ok(Self {
+            rng,
+            key,
+            name: name.to_string(),
+            cert,
+            leaf_key,
+        })
+    }
+
+    /// The CA certificate, PEM-encoded.
+    pub fn pem(&self) -> String {
+        let body = base64::engine::general_purpose::STANDARD.encode(&self.cert);
+        let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+        for line in body.as_bytes().chunks(64) {
+            pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+            pem.push('\n');
+        }
+        pem.push_str("-----END CERTIFICATE-----\n");
+        pem
+    }
+
+    /// Issue a server certificate for `host`, a DNS name or an IP address.
+    pub fn issue(&self, host: &str) -> Result<IssuedCert> {
+        let san = match host.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(ip)) => der(0x87, &ip.octets()),
+            Ok(std::net::IpAddr::V6(ip)) => der(0x87, &ip.octets()),
+            Err(_) => der(0x82, host.as_bytes()),
+        };
+        let extensions = [
+            extension(OID_BASIC_CONSTRAINTS, true, &seq(&[])),
+            // digitalSignature.
+            extension(OID_KEY_USAGE, true, &der(0x03, &[0x07, 0x80])),
+            extension(OID_EXT_KEY_USAGE, false, &seq(&[oid(OID_SERVER_AUTH)])),
+            extension(OID_SUBJECT_ALT_NAME, false, &seq(&[san])),
+            extension(
+                OID_AUTHORITY_KEY_ID,
+                false,
+                &seq(&[der(0x80, &self.key.id())]),
+            ),
+        ];
+        let cert = sign(
+            &self.rng,
+            &self.key,
+            &self.name,
+            host,
+            &self.leaf_key,
+            &extensions,
+        )?;
+        Ok(IssuedCert {
+            cert,
+            pkcs8_key: self.leaf_key.pkcs8.clone(),
+        })
+    }
+}
+
+/// Build and sign a certificate for `subject_key`, issued by `issuer_key`.
+fn sign(
+    rng: &SystemRandom,
+    issuer_key: &Key,
+    issuer: &str,
+    subject: &str,
+    subject_key: &Key,
+    extensions: &[Vec<u8>],
+) -> Result<Vec<u8>> {
+    let mut serial = [0u8; 16];
+    rng.fill(&mut serial)
+        .map_err(|_| WrightError::NetworkError("failed to generate serial".into()))?;
+    serial[0] &= 0x7F;
+    serial[0] |= 0x40;
+
+    let now = chrono::Utc::now();
+    let days = chrono::Duration::days(VALIDITY_DAYS);
+    let algorithm = seq(&[oid(OID_ECDSA_SHA256)]);
+    let mut public_key = vec![0x00];
+    public_key.extend_from_slice(subject_key.pair.public_key().as_ref());
+
+    let tbs = seq(&[
+        der(0xA0, &der(0x02, &[0x02])),
+        der(0x02, &serial),
+        algorithm.clone(),
+        name(issuer),
+        seq(&[utc_time(now - days), utc_time(now + days)]),
+        name(subject),
+        seq(&[
+            seq(&[oid(OID_EC_PUBLIC_KEY), oid(OID_PRIME256V1)]),
+            der(0x03, &public_key),
+        ]),
+        der(0xA3, &seq(extensions)),
+    ]);
+    let signature = issuer_key
+        .pair
+        .sign(rng, &tbs)
+        .map_err(|_| WrightError::NetworkError("failed to sign certificate".into()))?;
+    let mut signature_bits = vec![0x00];
+    signature_bits.extend_from_slice(signature.as_ref());
+    Ok(seq(&[tbs, algorithm, der(0x03, &signature_bits)]))
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn seq(items: &[Vec<u8>]) -> Vec<u8> {
+    der(0x30, &items.concat())
+}
+
+fn oid(encoded: &[u8]) -> Vec<u8> {
+    der(0x06, encoded)
+}
+
+fn name(common_name: &str) -> Vec<u8> {
+    seq(&[der(
+        0x31,
+        &seq(&[oid(OID_COMMON_NAME), der(0x0C, common_name.as_bytes())]),
+    )])
+}
+
+fn utc_time(time: chrono::DateTime<chrono::Utc>) -> Vec<u8> {
+    der(0x17, time.format("%y%m%d%H%M%SZ").to_string().as_bytes())
+}
+
+fn extension(id: &[u8], critical: bool, value: &[u8]) -> Vec<u8> {
+    let mut parts = vec![oid(id)];
+    if critical {
+        parts.push(der(0x01, &[0xFF]));
+    }
+    parts.push(der(0x04, value));
+    seq(&parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::client::danger::ServerCertVerifier;
+
+    #[test]
+    fn test_der_lengths() {
+        assert_eq!(der(0x04, &[1, 2]), vec![0x04, 0x02, 1, 2]);
+        let long = der(0x04, &[0; 300]);
+        assert_eq!(&long[..4], &[0x04, 0x82, 0x01, 0x2C]);
+        assert_eq!(long.len(), 304);
+    }
+
+    #[test]
+    fn test_issued_cert_verifies_against_ca() {
+        let ca = CertAuthority::generate("wright test CA").unwrap();
+        assert!(ca.pem().starts_with("-----BEGIN CERTIFICATE-----\n"));
+        let leaf = ca.issue("example.org").unwrap();
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(ca.cert.clone().into()).unwrap();
+        let verifier = rustls::client::WebPkiServerVerifier::builder_with_provider(
+            roots.into(),
+            rustls::crypto::ring::default_provider().into(),
+        )
+        .build()
+        .unwrap();
+        let name = rustls::pki_types::ServerName::try_from("example.org").unwrap();
+        verifier
+            .verify_server_cert(
+                &leaf.cert.into(),
+                &[],
+                &name,
+                &[],
+                rustls::pki_types::UnixTime::now(),
+            )
+            .unwrap();
+    }
+}
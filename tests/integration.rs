@@ -1,11 +1,24 @@
 mod integration {
+    mod common;
+
+    mod alternatives_test;
     mod build_test;
+    mod config_test;
     mod diversion_test;
     mod force_test;
+    mod generation_test;
+    mod hook_test;
     mod install_pipeline_test;
     mod install_test;
     mod isolation_test;
     mod launch_test;
+    mod provides_test;
+    mod prune_test;
+    mod rollback_test;
+    mod sources_test;
+    mod trigger_test;
+    mod verify_test;
+    mod workshop_test;
 
     mod migration_test;
 }
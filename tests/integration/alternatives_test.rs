@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use wright::database::InstalledDb;
+use wright::operations::alternatives::execute_pin_alternative;
+use wright::transaction;
+
+use super::common::{TestPart, session};
+
+fn build_archive(name: &str, priority: i32, out_dir: &Path) -> PathBuf {
+    TestPart::new(name)
+        .output(&format!(
+            r#"
+[[output.alternatives]]
+name = "vi"
+link = "/usr/bin/vi"
+target = "/usr/bin/{name}"
+priority = {priority}"#
+        ))
+        .file(&format!("usr/bin/{name}"), name)
+        .build(out_dir)
+}
+
+fn vi_target(root: &Path) -> Option<PathBuf> {
+    std::fs::read_link(root.join("usr/bin/vi")).ok()
+}
+
+#[tokio::test]
+async fn test_alternative_follows_priority_pin_and_removal() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let vim = build_archive("vim", 40, parts_dir.path());
+    let nvim = build_archive("nvim", 50, parts_dir.path());
+
+    transaction::deploy_part(&db, &vim, root.path(), false, session(None))
+        .await
+        .unwrap();
+    assert_eq!(vi_target(root.path()), Some("/usr/bin/vim".into()));
+
+    // A higher priority candidate takes over.
+    transaction::deploy_part(&db, &nvim, root.path(), false, session(None))
+        .await
+        .unwrap();
+    assert_eq!(vi_target(root.path()), Some("/usr/bin/nvim".into()));
+
+    execute_pin_alternative(&db, root.path(), "vi", Some("vim"))
+        .await
+        .unwrap();
+    assert_eq!(vi_target(root.path()), Some("/usr/bin/vim".into()));
+    let err = execute_pin_alternative(&db, root.path(), "vi", Some("nano"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("candidates: nvim, vim"), "{err}");
+
+    // Without the pinned part, priority decides again.
+    transaction::remove_part(&db, "vim", root.path(), false, session(None))
+        .await
+        .unwrap();
+    assert_eq!(vi_target(root.path()), Some("/usr/bin/nvim".into()));
+
+    transaction::remove_part(&db, "nvim", root.path(), false, session(None))
+        .await
+        .unwrap();
+    assert_eq!(vi_target(root.path()), None);
+    assert!(db.list_alternatives(None).await.unwrap().is_empty());
+}
@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use wright::config::GlobalConfig;
+use wright::foundry::buildlog;
 use wright::foundry::mold::Mold;
 use wright::foundry::{BuildOptions, Foundry};
 use wright::part::archive;
@@ -45,6 +46,50 @@ async fn test_build_hello_fixture() {
     assert!(result.staging_dir.join("usr/bin/hello").exists());
 }
 
+#[tokio::test]
+async fn test_full_build_reports_stage_usage() {
+    let (manifest, plan_dir) = load_manifest_without_isolation("hello");
+
+    let mut config = GlobalConfig::default();
+    let build_tmp = tempfile::tempdir().unwrap();
+    config.build.forge_dir = build_tmp.path().to_path_buf();
+
+    let foundry = Foundry::new(config);
+    let result = foundry
+        .build(
+            &manifest,
+            plan_dir.as_ref(),
+            Path::new("/"),
+            BuildOptions::default(),
+        )
+        .await
+        .unwrap();
+    let usage = result.usage.expect("a full forge records its usage");
+    let compile = usage.iter().find(|s| s.stage == "compile").unwrap();
+    assert!(compile.wall_secs > 0.0);
+    assert!(compile.max_rss_kb > 0);
+    assert!(usage.iter().any(|s| s.disk_bytes > 0));
+
+    // Every stage is up to date: nothing was forged, nothing to record.
+    let result = foundry
+        .build(
+            &manifest,
+            plan_dir.as_ref(),
+            Path::new("/"),
+            BuildOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert!(result.usage.is_none());
+    let builds = buildlog::list(&result.build_root);
+    assert_eq!(
+        builds.len(),
+        1,
+        "an up-to-date forge keeps the logs as they are"
+    );
+    assert_eq!(builds[0].record.as_ref().unwrap().outcome(), "success");
+}
+
 #[tokio::test]
 async fn test_build_and_archive_hello() {
     let (manifest, plan_dir) = load_manifest_without_isolation("hello");
@@ -66,7 +111,7 @@ async fn test_build_and_archive_hello() {
 
     let output_dir = tempfile::tempdir().unwrap();
     let archive_path =
-        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None).unwrap();
+        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None, &[]).unwrap();
 
     // Verify archive exists
     assert!(archive_path.exists());
@@ -218,7 +263,7 @@ install -Dm755 /bin/sh ${STAGING_DIR}/usr/bin/runtime-link-overlap
 
     let output_dir = tempfile::tempdir().unwrap();
     let archive_path =
-        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None).unwrap();
+        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None, &[]).unwrap();
     let partinfo = archive::read_partinfo(&archive_path).unwrap();
 
     assert_eq!(partinfo.runtime_deps, vec!["openssl", "zlib"]);
@@ -1029,3 +1074,123 @@ retry_count = 3
         "resume should skip the already completed dependency build"
     );
 }
+
+#[test]
+fn test_verify_reproducible_reports_differing_paths() {
+    let root = tempfile::tempdir().unwrap();
+    let plans_dir = root.path().join("plans");
+    let build_dir = root.path().join("build");
+    for (name, script) in [
+        (
+            "repro-stable",
+            "install -Dm644 /dev/null ${STAGING_DIR}/usr/share/repro/empty",
+        ),
+        (
+            "repro-stamped",
+            "mkdir -p ${STAGING_DIR}/usr/share/repro && date +%s%N > ${STAGING_DIR}/usr/share/repro/stamp",
+        ),
+    ] {
+        let plan_dir = plans_dir.join(name);
+        std::fs::create_dir_all(&plan_dir).unwrap();
+        std::fs::write(
+            plan_dir.join("plan.toml"),
+            format!(
+                r#"
+name = "{name}"
+version = "1.0.0"
+release = 1
+description = "reproducibility check"
+license = "MIT"
+arch = "x86_64"
+
+[pipeline.staging]
+executor = "shell"
+isolation = "none"
+script = "{script}"
+"#
+            ),
+        )
+        .unwrap();
+    }
+
+    let config_path = root.path().join("wright.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            r#"[general]
+plans_dir = "{}"
+parts_dir = "{}"
+source_dir = "{}"
+db_path = "{}"
+logs_dir = "{}"
+executors_dir = "/etc/wright/executors"
+
+[build]
+forge_dir = "{}"
+ccache = false
+"#,
+            plans_dir.display(),
+            root.path().join("parts").display(),
+            root.path().join("sources").display(),
+            root.path().join("db/wright.db").display(),
+            root.path().join("logs").display(),
+            build_dir.display(),
+        ),
+    )
+    .unwrap();
+    let verify = |plan: &str| {
+        Command::new(env!("CARGO_BIN_EXE_wright"))
+            .arg("--config")
+            .arg(&config_path)
+            .args(["build", "--verify-reproducible", plan])
+            .output()
+            .unwrap()
+    };
+
+    let stable = verify("repro-stable");
+    assert!(
+        stable.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&stable.stderr)
+    );
+    assert_eq!(std::fs::read_dir(&build_dir).unwrap().count(), 0);
+
+    let stamped = verify("repro-stamped");
+    assert!(!stamped.status.success());
+    let stdout = String::from_utf8_lossy(&stamped.stdout);
+    assert!(
+        stdout.contains("content     staging/usr/share/repro/stamp ("),
+        "stdout: {stdout}"
+    );
+    // Both build roots are kept for inspection.
+    assert_eq!(std::fs::read_dir(&build_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn test_debug_shell_requires_interactive_terminal() {
+    let root = tempfile::tempdir().unwrap();
+    let config_path = root.path().join("wright.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "[general]\ndb_path = \"{}\"\n\n[build]\nforge_dir = \"{}\"\n",
+            root.path().join("db/wright.db").display(),
+            root.path().join("build").display(),
+        ),
+    )
+    .unwrap();
+
+    for flags in [&["--shell", "configure"][..], &["--shell-on-failure"][..]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_wright"))
+            .arg("--config")
+            .arg(&config_path)
+            .arg("build")
+            .args(flags)
+            .arg(fixture_path("hello"))
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("interactive terminal"), "stderr: {stderr}");
+    }
+}
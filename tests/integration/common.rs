@@ -0,0 +1,166 @@
+//! Fixtures shared by the integration tests: part archives built straight
+//! from a manifest, and deliveries of them into a temporary root.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use wright::database::{InstalledDb, SessionContext};
+use wright::part::archive;
+use wright::part::store::LocalPartStore;
+use wright::plan::manifest::PlanManifest;
+use wright::transaction;
+
+enum Entry {
+    File { content: String, mode: u32 },
+    Symlink(String),
+}
+
+/// A part archive to build.  Without any `file`, the part ships
+/// `/usr/bin/<name>` printing its name and version.
+pub struct TestPart {
+    name: String,
+    version: String,
+    output: Option<String>,
+    entries: Vec<(String, Entry)>,
+}
+
+impl TestPart {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            output: None,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    /// TOML for the part's `[[output]]`, including any sub-tables.
+    pub fn output(mut self, toml: &str) -> Self {
+        self.output = Some(toml.to_string());
+        self
+    }
+
+    /// A file at `path`, relative to the root.
+    pub fn file(self, path: &str, content: &str) -> Self {
+        self.entry(path, content, 0o644)
+    }
+
+    pub fn executable(self, path: &str, content: &str) -> Self {
+        self.entry(path, content, 0o755)
+    }
+
+    pub fn symlink(mut self, path: &str, target: &str) -> Self {
+        self.entries
+            .push((path.to_string(), Entry::Symlink(target.to_string())));
+        self
+    }
+
+    fn entry(mut self, path: &str, content: &str, mode: u32) -> Self {
+        self.entries.push((
+            path.to_string(),
+            Entry::File {
+                content: content.to_string(),
+                mode,
+            },
+        ));
+        self
+    }
+
+    /// Seal the part into `out_dir` and return the archive's path.
+    pub fn build(&self, out_dir: &Path) -> PathBuf {
+        let (name, version) = (&self.name, &self.version);
+        let output = self
+            .output
+            .as_deref()
+            .map(|toml| format!("\n[[output]]\n{toml}\n"))
+            .unwrap_or_default();
+        let manifest = PlanManifest::parse(&format!(
+            r#"
+name = "{name}"
+version = "{version}"
+release = 1
+description = "{name}"
+license = "MIT"
+arch = "x86_64"
+
+[pipeline.staging]
+executor = "shell"
+isolation = "none"
+script = "true"
+{output}"#
+        ))
+        .unwrap();
+
+        let part_dir = tempfile::tempdir().unwrap();
+        let default_bin;
+        let entries = if self.entries.is_empty() {
+            default_bin = [(
+                format!("usr/bin/{name}"),
+                Entry::File {
+                    content: format!("#!/bin/sh\necho {name} {version}\n"),
+                    mode: 0o755,
+                },
+            )];
+            &default_bin[..]
+        } else {
+            &self.entries[..]
+        };
+        for (path, entry) in entries {
+            let path = part_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            match entry {
+                Entry::File { content, mode } => {
+                    std::fs::write(&path, content).unwrap();
+                    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(*mode))
+                        .unwrap();
+                }
+                Entry::Symlink(target) => std::os::unix::fs::symlink(target, &path).unwrap(),
+            }
+        }
+        archive::create_part(part_dir.path(), &manifest, out_dir, None, &[]).unwrap()
+    }
+}
+
+pub fn session(delivery_id: Option<i64>) -> SessionContext {
+    SessionContext {
+        id: format!("session-{}", delivery_id.unwrap_or_default()),
+        command: "merge".into(),
+        delivery_id,
+    }
+}
+
+/// Deploy `archives` as one batch, with hooks, outside any delivery
+/// unless `delivery_id` is given.
+pub async fn deploy(
+    db: &InstalledDb,
+    archives: &[PathBuf],
+    root: &Path,
+    delivery_id: Option<i64>,
+) -> wright::error::Result<()> {
+    transaction::deploy_parts(
+        db,
+        archives,
+        root,
+        &LocalPartStore::new(),
+        false,
+        true,
+        true,
+        session(delivery_id),
+    )
+    .await
+}
+
+/// Deploy `archives` as one completed delivery and return its id.
+pub async fn deliver(db: &InstalledDb, root: &Path, archives: &[PathBuf]) -> i64 {
+    let tx_id = wright::delivery::begin_delivery(db, "merge").await.unwrap();
+    deploy(db, archives, root, Some(tx_id)).await.unwrap();
+    wright::delivery::complete_delivery(db, tx_id)
+        .await
+        .unwrap();
+    tx_id
+}
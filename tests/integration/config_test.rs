@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use wright::database::InstalledDb;
+use wright::operations::configs::{Resolution, execute_resolve_configs};
+use wright::transaction;
+
+use super::common::{TestPart, deploy, session};
+
+const BASE: &str = "listen 80\nworkers 4\nlog info\n";
+
+fn build_archive(version: &str, app_conf: &str, out_dir: &Path) -> PathBuf {
+    TestPart::new("app")
+        .version(version)
+        .output(r#"backup = ["/etc/app.conf", "/etc/app-defaults.conf"]"#)
+        .file("etc/app.conf", app_conf)
+        .file("etc/app-defaults.conf", &format!("version {version}\n"))
+        .build(out_dir)
+}
+
+#[tokio::test]
+async fn test_upgrade_keeps_modified_config_and_merges_update() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+
+    let v1 = build_archive("1.0.0", BASE, parts_dir.path());
+    let v2 = build_archive(
+        "2.0.0",
+        "listen 80\nworkers 4\nlog info\ntimeout 30\n",
+        parts_dir.path(),
+    );
+    deploy(&db, std::slice::from_ref(&v1), root.path(), None)
+        .await
+        .unwrap();
+
+    let live = root.path().join("etc/app.conf");
+    std::fs::write(&live, "listen 8080\nworkers 4\nlog info\n").unwrap();
+    transaction::upgrade_part(&db, &v2, root.path(), false, false, session(None))
+        .await
+        .unwrap();
+
+    // The edited file stays; the untouched one follows the package.
+    assert_eq!(
+        std::fs::read_to_string(&live).unwrap(),
+        "listen 8080\nworkers 4\nlog info\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("etc/app-defaults.conf")).unwrap(),
+        "version 2.0.0\n"
+    );
+    assert!(
+        !root
+            .path()
+            .join("etc/app-defaults.conf.wright-new")
+            .exists()
+    );
+    let sidecar = root.path().join("etc/app.conf.wright-new");
+    assert!(sidecar.exists());
+    let pending = db.list_pending_configs().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].path, "/etc/app.conf");
+    assert_eq!(pending[0].part_name, "app");
+
+    execute_resolve_configs(&db, root.path(), &[], Resolution::Merge)
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&live).unwrap(),
+        "listen 8080\nworkers 4\nlog info\ntimeout 30\n"
+    );
+    assert!(!sidecar.exists());
+    assert!(db.list_pending_configs().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_remove_drops_pending_config_sidecar() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+
+    let v1 = build_archive("1.0.0", BASE, parts_dir.path());
+    let v2 = build_archive("2.0.0", "listen 443\n", parts_dir.path());
+    deploy(&db, std::slice::from_ref(&v1), root.path(), None)
+        .await
+        .unwrap();
+    std::fs::write(root.path().join("etc/app.conf"), "listen 8080\n").unwrap();
+    transaction::upgrade_part(&db, &v2, root.path(), false, false, session(None))
+        .await
+        .unwrap();
+    let sidecar = root.path().join("etc/app.conf.wright-new");
+    assert!(sidecar.exists());
+
+    transaction::remove_part(&db, "app", root.path(), false, session(None))
+        .await
+        .unwrap();
+    assert!(!sidecar.exists());
+    assert!(db.list_pending_configs().await.unwrap().is_empty());
+}
@@ -52,7 +52,7 @@ script = "mkdir -p ${{STAGING_DIR}}/usr/bin && echo -n '{}' > ${{STAGING_DIR}}/u
 
     let output_dir = tempfile::tempdir().unwrap();
     let archive =
-        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None).unwrap();
+        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None, &[]).unwrap();
 
     let persistent = std::env::temp_dir().join(format!(
         "test-diversion-{}-{}.wright.tar.zst",
@@ -80,6 +80,7 @@ async fn test_file_diversion_and_restoration() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -96,6 +97,7 @@ async fn test_file_diversion_and_restoration() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -130,6 +132,7 @@ async fn test_file_diversion_and_restoration() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -0,0 +1,136 @@
+use wright::config::GlobalConfig;
+use wright::database::{InstalledDb, Origin};
+use wright::operations::generation::{execute_snapshot, execute_switch};
+use wright::operations::rollback::execute_rollback;
+use wright::part::store::LocalPartStore;
+use wright::transaction;
+
+use super::common::{TestPart, deliver, session};
+
+#[tokio::test]
+async fn test_switch_restores_snapshotted_generation() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.path().to_path_buf();
+    config.general.store_dir = parts_dir.path().join("store");
+
+    let alpha_v1 = TestPart::new("alpha").build(parts_dir.path());
+    let gamma = TestPart::new("gamma").build(parts_dir.path());
+    let later = tempfile::tempdir().unwrap();
+    let alpha_v2 = TestPart::new("alpha").version("2.0.0").build(later.path());
+    let beta = TestPart::new("beta").build(later.path());
+
+    deliver(&db, root.path(), &[alpha_v1, gamma]).await;
+    let snapshot_alpha = db.get_part("alpha").await.unwrap().unwrap();
+    db.reset_origin("gamma", Origin::Dependency).await.unwrap();
+    execute_snapshot(&db, "weekly").await.unwrap();
+    assert!(execute_snapshot(&db, "weekly").await.is_err());
+
+    deliver(&db, root.path(), &[alpha_v2, beta]).await;
+    db.reset_origin("gamma", Origin::Manual).await.unwrap();
+
+    execute_switch(
+        &db,
+        &config,
+        root.path(),
+        &LocalPartStore::new(),
+        "weekly",
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let alpha = db.get_part("alpha").await.unwrap().unwrap();
+    assert_eq!(alpha.part_hash, snapshot_alpha.part_hash);
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("usr/bin/alpha")).unwrap(),
+        "#!/bin/sh\necho alpha 1.0.0\n"
+    );
+    assert!(db.get_part("beta").await.unwrap().is_none());
+    assert!(!root.path().join("usr/bin/beta").exists());
+    assert_eq!(
+        db.get_part("gamma").await.unwrap().unwrap().origin,
+        Origin::Dependency
+    );
+
+    let current = db.current_generation_parts().await.unwrap();
+    let weekly = db.get_generation("weekly").await.unwrap().unwrap();
+    assert_eq!(current, db.get_generation_parts(weekly.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_rollback_of_switch_restores_external_placeholder() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.path().to_path_buf();
+    config.general.store_dir = parts_dir.path().join("store");
+
+    let alpha = TestPart::new("alpha").build(parts_dir.path());
+    deliver(&db, root.path(), &[alpha]).await;
+    execute_snapshot(&db, "built").await.unwrap();
+    transaction::remove_part(&db, "alpha", root.path(), false, session(None))
+        .await
+        .unwrap();
+    db.provide_part("alpha", "9.0").await.unwrap();
+
+    let store = LocalPartStore::new();
+    execute_switch(&db, &config, root.path(), &store, "built", false, false)
+        .await
+        .unwrap();
+    assert_ne!(
+        db.get_part("alpha").await.unwrap().unwrap().origin,
+        Origin::External
+    );
+
+    let switch_tx = db.list_deliveries(1).await.unwrap()[0].id;
+    execute_rollback(&db, &config, root.path(), &store, switch_tx, false, false)
+        .await
+        .unwrap();
+    let placeholder = db.get_part("alpha").await.unwrap().unwrap();
+    assert_eq!(placeholder.origin, Origin::External);
+    assert!(!root.path().join("usr/bin/alpha").exists());
+}
+
+#[tokio::test]
+async fn test_rollback_of_switch_restores_placeholder_version() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = root.path().join("parts");
+    config.general.store_dir = root.path().join("store");
+    let placeholder_version = async |db: &InstalledDb| {
+        db.current_generation_parts()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|p| p.name == "beta")
+            .map(|p| (p.version, p.origin))
+    };
+
+    db.provide_part("beta", "1.0").await.unwrap();
+    execute_snapshot(&db, "old").await.unwrap();
+    db.provide_part("beta", "2.0").await.unwrap();
+
+    let store = LocalPartStore::new();
+    execute_switch(&db, &config, root.path(), &store, "old", false, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        placeholder_version(&db).await,
+        Some(("1.0".to_string(), Origin::External))
+    );
+
+    let switch_tx = db.list_deliveries(1).await.unwrap()[0].id;
+    execute_rollback(&db, &config, root.path(), &store, switch_tx, false, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        placeholder_version(&db).await,
+        Some(("2.0".to_string(), Origin::External))
+    );
+}
@@ -0,0 +1,123 @@
+use wright::database::InstalledDb;
+use wright::transaction;
+
+use super::common::{TestPart, deploy, session};
+
+#[tokio::test]
+async fn test_warn_and_defer_hooks_are_recorded_and_removal_clears_them() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let warned = TestPart::new("fontconfig")
+        .output(
+            r#"[output.hooks]
+post_install = { script = "exit 1", isolation = "none" }"#,
+        )
+        .build(parts_dir.path());
+    let deferred = TestPart::new("man-db")
+        .output(
+            r#"[output.hooks]
+post_install = { script = "exit 1", isolation = "none", on_failure = "defer" }"#,
+        )
+        .build(parts_dir.path());
+
+    let tx_id = wright::delivery::begin_delivery(&db, "merge")
+        .await
+        .unwrap();
+    deploy(&db, &[warned], root.path(), Some(tx_id))
+        .await
+        .unwrap();
+    deploy(&db, &[deferred], root.path(), Some(tx_id))
+        .await
+        .unwrap();
+    assert!(db.get_part("fontconfig").await.unwrap().is_some());
+    assert!(db.get_part("man-db").await.unwrap().is_some());
+
+    let failures = db.list_hook_failures(None).await.unwrap();
+    let statuses: Vec<(&str, &str, &str)> = failures
+        .iter()
+        .map(|f| (f.part_name.as_str(), f.hook.as_str(), f.status.as_str()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("fontconfig", "post_install", "failed"),
+            ("man-db", "post_install", "deferred"),
+        ]
+    );
+    let deferred = db.get_deferred_hooks(tx_id).await.unwrap();
+    assert_eq!(deferred.len(), 1);
+    assert_eq!(deferred[0].part_name, "man-db");
+
+    // The retry at the end of the delivery fails again; the record stays.
+    transaction::run_deferred_hooks(&db, tx_id, root.path())
+        .await
+        .unwrap();
+    assert_eq!(db.get_deferred_hooks(tx_id).await.unwrap().len(), 1);
+
+    transaction::remove_part(&db, "man-db", root.path(), false, session(Some(tx_id)))
+        .await
+        .unwrap();
+    let remaining = db.list_hook_failures(None).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].part_name, "fontconfig");
+}
+
+#[tokio::test]
+async fn test_aborting_pre_install_hook_leaves_part_uninstalled() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let part = TestPart::new("nginx")
+        .output(
+            r#"[output.hooks]
+pre_install = { script = "exit 1", isolation = "none", on_failure = "abort" }"#,
+        )
+        .build(parts_dir.path());
+
+    let tx_id = wright::delivery::begin_delivery(&db, "merge")
+        .await
+        .unwrap();
+    let err = deploy(&db, &[part], root.path(), Some(tx_id))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("pre_install hook of 'nginx' failed"),
+        "{err}"
+    );
+    assert!(db.get_part("nginx").await.unwrap().is_none());
+    assert!(!root.path().join("usr/bin/nginx").exists());
+    assert!(db.list_hook_failures(None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_aborting_post_install_hook_is_recorded_for_rerun() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let part = TestPart::new("fontconfig")
+        .output(
+            r#"[output.hooks]
+post_install = { script = "exit 1", isolation = "none", on_failure = "abort" }"#,
+        )
+        .build(parts_dir.path());
+
+    let tx_id = wright::delivery::begin_delivery(&db, "merge")
+        .await
+        .unwrap();
+    let err = deploy(&db, &[part], root.path(), Some(tx_id))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("post_install hook of 'fontconfig' failed"),
+        "{err}"
+    );
+    // The install is committed; the hook waits for `wright hook run`.
+    assert!(db.get_part("fontconfig").await.unwrap().is_some());
+    let failures = db.list_hook_failures(None).await.unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].hook, "post_install");
+    assert_eq!(failures[0].status, "failed");
+}
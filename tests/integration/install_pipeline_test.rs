@@ -85,6 +85,7 @@ runtime_deps = ["wayland"]
         root_dir: root.clone(),
         verbose: 2,
         quiet: false,
+        format: wright::util::output::OutputFormat::Human,
     };
     let result = install::run(cmd, &ctx).await;
 
@@ -42,7 +42,7 @@ async fn build_hello_archive() -> PathBuf {
 
     let output_dir = tempfile::tempdir().unwrap();
     let archive =
-        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None).unwrap();
+        archive::create_part(&result.staging_dir, &manifest, output_dir.path(), None, &[]).unwrap();
 
     // Copy to persistent temp location
     use std::sync::atomic::{AtomicUsize, Ordering};
@@ -128,8 +128,14 @@ fn create_split_archive_from_manifest(
 
     let temp_output_dir = tempfile::tempdir().unwrap();
     let output_dir = output_dir.unwrap_or(temp_output_dir.path());
-    let archive =
-        archive::create_part(part_dir.path(), &sub_manifest, output_dir, Some(manifest)).unwrap();
+    let archive = archive::create_part(
+        part_dir.path(),
+        &sub_manifest,
+        output_dir,
+        Some(manifest),
+        &[],
+    )
+    .unwrap();
 
     if output_dir == temp_output_dir.path() {
         let version = manifest.metadata.version.as_deref().unwrap_or("noversion");
@@ -194,6 +200,7 @@ async fn test_end_to_end_install_query_remove() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -231,6 +238,7 @@ async fn test_end_to_end_install_query_remove() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -265,6 +273,7 @@ async fn test_install_accepts_same_revision_split_outputs() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -327,6 +336,7 @@ async fn test_install_command_resolves_plan_name_to_all_outputs() {
         root_dir: root.clone(),
         verbose: 0,
         quiet: false,
+        format: wright::util::output::OutputFormat::Human,
     };
     install::run(cmd, &ctx).await.unwrap();
 
@@ -357,6 +367,7 @@ async fn test_install_rejects_mixed_split_plan_revisions() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -388,6 +399,7 @@ async fn test_install_rejects_revision_change_that_leaves_installed_outputs() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -404,6 +416,7 @@ async fn test_install_rejects_revision_change_that_leaves_installed_outputs() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -435,6 +448,7 @@ async fn test_successful_install_removes_rollback_journal() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -464,6 +478,7 @@ async fn test_file_conflict_detection() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -478,6 +493,7 @@ async fn test_file_conflict_detection() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     );
     assert!(result.await.is_err());
@@ -499,6 +515,7 @@ async fn test_verify_detects_modification() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -530,6 +547,7 @@ async fn test_verify_detects_missing_file() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
@@ -561,6 +579,7 @@ async fn test_list_installed_parts() {
         SessionContext {
             id: "test".into(),
             command: "test".into(),
+            delivery_id: None,
         },
     )
     .await
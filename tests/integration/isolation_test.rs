@@ -2,6 +2,8 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 
 use wright::error::WrightError;
+use wright::foundry::proxy::{Proxy, ProxyOptions};
+use wright::isolation::harden::Hardening;
 use wright::isolation::{IsolationConfig, IsolationLevel, run_in_isolation};
 
 fn should_skip_isolation_test(err: &WrightError) -> bool {
@@ -68,3 +70,169 @@ async fn isolation_executes_shebang_script_from_var_tmp_build_mount() {
     assert_eq!(stdout, "isolation-ok");
     assert!(stderr.is_empty(), "unexpected stderr: {stderr:?}");
 }
+
+fn run_strict(script: &str, hardening: Hardening) -> Result<String, WrightError> {
+    let src = tempfile::tempdir().unwrap();
+    let part = tempfile::tempdir().unwrap();
+    let mut config = IsolationConfig::new(
+        IsolationLevel::Strict,
+        src.path().to_path_buf(),
+        part.path().to_path_buf(),
+        "isolation-hardening".to_string(),
+    );
+    config.hardening = hardening;
+    let args = vec!["-c".to_string(), script.to_string()];
+    let output = run_in_isolation(&mut config, "/bin/sh", &args)?;
+    assert!(output.status.success(), "stderr: {}", output.stderr.tail);
+    Ok(output.stdout.tail)
+}
+
+#[tokio::test]
+async fn strict_isolation_drops_capabilities_and_filters_syscalls() {
+    let script = "grep -E '^(NoNewPrivs|Seccomp):' /proc/self/status; \
+                  unshare -U true 2>/dev/null && echo unshare-allowed || echo unshare-denied";
+    let stdout = match run_strict(script, Hardening::default()) {
+        Ok(stdout) => stdout,
+        Err(err) if should_skip_isolation_test(&err) => return,
+        Err(err) => panic!("isolation run failed unexpectedly: {err}"),
+    };
+    if !Path::new("/usr/bin/unshare").exists() {
+        return;
+    }
+    assert!(stdout.contains("NoNewPrivs:\t1"), "{stdout}");
+    assert!(stdout.contains("Seccomp:\t2"), "{stdout}");
+    assert!(stdout.contains("unshare-denied"), "{stdout}");
+
+    let allowed = Hardening {
+        allow_syscalls: vec!["unshare".to_string()],
+        capabilities: vec!["CAP_SYS_ADMIN".to_string()],
+    };
+    let stdout = run_strict(script, allowed).unwrap();
+    assert!(stdout.contains("unshare-allowed"), "{stdout}");
+}
+
+/// Serve `body` to every request on a host-side loopback port.
+fn serve_http(body: &'static str) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut stream = stream;
+            let mut buf = [0u8; 4096];
+            let _ = std::io::Read::read(&mut stream, &mut buf);
+            let _ = std::io::Write::write_all(
+                &mut stream,
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .as_bytes(),
+            );
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn proxied_isolation_reaches_network_only_through_the_proxy() {
+    let port = serve_http("proxied-ok");
+    let url = format!("http://127.0.0.1:{port}/file");
+    let cache = tempfile::tempdir().unwrap();
+    let src = tempfile::tempdir().unwrap();
+    let part = tempfile::tempdir().unwrap();
+    let mut config = IsolationConfig::new(
+        IsolationLevel::Strict,
+        src.path().to_path_buf(),
+        part.path().to_path_buf(),
+        "isolation-proxy".to_string(),
+    );
+
+    // The host's loopback is refused unless allowed.
+    let refusing = Proxy::start(&ProxyOptions {
+        cache_dir: cache.path().to_path_buf(),
+        offline: false,
+        timeout: 10,
+        allow: Vec::new(),
+    })
+    .unwrap();
+    config.proxy = Some(refusing.endpoint());
+    let args = vec!["-c".to_string(), format!("curl -sS {url}")];
+    let output = match run_in_isolation(&mut config, "/bin/sh", &args) {
+        Ok(output) => output,
+        Err(err) if should_skip_isolation_test(&err) => return,
+        Err(err) => panic!("isolation run failed unexpectedly: {err}"),
+    };
+    if !Path::new("/usr/bin/curl").exists() {
+        return;
+    }
+    assert!(
+        output
+            .stdout
+            .tail
+            .contains("loopback, link-local, or private address"),
+        "stdout: {}, stderr: {}",
+        output.stdout.tail,
+        output.stderr.tail
+    );
+    let fetches = refusing.finish();
+    assert!(
+        fetches
+            .iter()
+            .any(|f| f.starts_with(&format!("GET {url} 403 sha256="))),
+        "{fetches:?}"
+    );
+
+    let options = ProxyOptions {
+        cache_dir: cache.path().to_path_buf(),
+        offline: false,
+        timeout: 10,
+        allow: vec!["127.0.0.1".to_string()],
+    };
+    let proxy = Proxy::start(&options).unwrap();
+    config.proxy = Some(proxy.endpoint());
+    let script = format!(
+        "curl -sS http://127.0.0.1:{port}/file; echo; \
+         curl -sS --noproxy '*' http://127.0.0.1:{port}/file >/dev/null 2>&1 || echo direct-blocked; \
+         curl -sS https://127.0.0.1:1/tls"
+    );
+    let args = vec!["-c".to_string(), script];
+    let output = run_in_isolation(&mut config, "/bin/sh", &args).unwrap();
+    let stdout = output.stdout.tail;
+    assert!(
+        stdout.contains("proxied-ok"),
+        "stdout: {stdout}, stderr: {}",
+        output.stderr.tail
+    );
+    assert!(stdout.contains("direct-blocked"), "{stdout}");
+    // The TLS handshake with the proxy succeeds; the upstream does not.
+    assert!(
+        stdout.contains("wright proxy:"),
+        "stdout: {stdout}, stderr: {}",
+        output.stderr.tail
+    );
+
+    let fetches = proxy.finish();
+    assert!(
+        fetches
+            .iter()
+            .any(|f| f.starts_with(&format!("GET {url} 200 sha256="))),
+        "{fetches:?}"
+    );
+    assert!(
+        fetches
+            .iter()
+            .any(|f| f.starts_with("GET https://127.0.0.1:1/tls 502 ")),
+        "{fetches:?}"
+    );
+
+    // Cached for offline replay.
+    let offline = Proxy::start(&ProxyOptions {
+        offline: true,
+        ..options
+    })
+    .unwrap();
+    config.proxy = Some(offline.endpoint());
+    let args = vec!["-c".to_string(), format!("curl -sS {url}")];
+    let output = run_in_isolation(&mut config, "/bin/sh", &args).unwrap();
+    assert_eq!(output.stdout.tail, "proxied-ok");
+}
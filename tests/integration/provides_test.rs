@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use wright::database::InstalledDb;
+use wright::query::check_dependencies_structured;
+use wright::transaction;
+
+use super::common::{TestPart, session};
+
+fn build_archive(name: &str, output: &str, out_dir: &Path) -> PathBuf {
+    TestPart::new(name)
+        .output(output)
+        .file(&format!("usr/share/{name}"), name)
+        .build(out_dir)
+}
+
+#[tokio::test]
+async fn test_versioned_provide_satisfies_constrained_dependency() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let apache = build_archive(
+        "apache",
+        r#"provides = ["http-server=2.4", "webroot"]"#,
+        parts_dir.path(),
+    );
+    let site = build_archive(
+        "site",
+        r#"runtime_deps = ["http-server >= 2.0", "webroot"]"#,
+        parts_dir.path(),
+    );
+    let legacy = build_archive(
+        "legacy",
+        r#"runtime_deps = ["http-server >= 3.0", "webroot >= 1.0"]"#,
+        parts_dir.path(),
+    );
+
+    for part in [&apache, &site, &legacy] {
+        transaction::deploy_part(&db, part, root.path(), false, session(None))
+            .await
+            .unwrap();
+    }
+
+    let providers = db.find_providers("http-server").await.unwrap();
+    assert_eq!(
+        providers,
+        vec![("apache".to_string(), Some("2.4".to_string()))]
+    );
+
+    // An unversioned provide only satisfies an unconstrained dependency.
+    let broken = check_dependencies_structured(&db).await.unwrap();
+    let mut edges: Vec<(&str, &str)> = broken
+        .iter()
+        .map(|b| (b.part.as_str(), b.required_name.as_str()))
+        .collect();
+    edges.sort();
+    assert_eq!(
+        edges,
+        vec![("legacy", "http-server"), ("legacy", "webroot")]
+    );
+
+    transaction::remove_part(&db, "apache", root.path(), true, session(None))
+        .await
+        .unwrap();
+    assert!(db.find_providers("http-server").await.unwrap().is_empty());
+}
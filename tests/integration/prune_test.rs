@@ -0,0 +1,81 @@
+use wright::config::GlobalConfig;
+use wright::database::InstalledDb;
+use wright::operations::prune::{PrunePolicy, execute_prune};
+
+use super::common::{TestPart, deliver, deploy};
+
+#[tokio::test]
+async fn test_prune_keeps_installed_and_latest_archives() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.path().to_path_buf();
+    config.general.store_dir = parts_dir.path().join("store");
+
+    let v1 = TestPart::new("alpha").build(parts_dir.path());
+    let v2 = TestPart::new("alpha")
+        .version("2.0.0")
+        .build(parts_dir.path());
+    let v3 = TestPart::new("alpha")
+        .version("3.0.0")
+        .build(parts_dir.path());
+    deploy(&db, std::slice::from_ref(&v1), root.path(), None)
+        .await
+        .unwrap();
+
+    let policy = PrunePolicy {
+        keep_latest: 1,
+        keep_deliveries: 0,
+        keep_delivery_records: 0,
+        max_age: None,
+        max_size: None,
+    };
+    execute_prune(&db, &config, &policy, false).await.unwrap();
+    assert!(v2.exists());
+
+    execute_prune(&db, &config, &policy, true).await.unwrap();
+    assert!(v1.exists(), "installed archive must be kept");
+    assert!(!v2.exists(), "superseded archive must be pruned");
+    assert!(v3.exists(), "latest archive must be kept");
+}
+
+#[tokio::test]
+async fn test_prune_drops_old_delivery_records() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.path().to_path_buf();
+    config.general.store_dir = parts_dir.path().join("store");
+
+    let mut deliveries = Vec::new();
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        let archive = TestPart::new("alpha")
+            .version(version)
+            .build(parts_dir.path());
+        deliveries.push(deliver(&db, root.path(), &[archive]).await);
+    }
+
+    let policy = PrunePolicy {
+        keep_latest: 0,
+        keep_deliveries: 10,
+        keep_delivery_records: 2,
+        max_age: None,
+        max_size: None,
+    };
+    execute_prune(&db, &config, &policy, false).await.unwrap();
+    assert!(db.get_delivery(deliveries[0]).await.unwrap().is_some());
+
+    execute_prune(&db, &config, &policy, true).await.unwrap();
+    assert!(db.get_delivery(deliveries[0]).await.unwrap().is_none());
+    assert!(
+        db.get_ops_for_delivery(deliveries[0])
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    for tx_id in &deliveries[1..] {
+        assert!(db.get_delivery(*tx_id).await.unwrap().is_some());
+    }
+}
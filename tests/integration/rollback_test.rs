@@ -0,0 +1,108 @@
+use wright::config::GlobalConfig;
+use wright::database::{DeliveryStatus, InstalledDb};
+use wright::operations::rollback::execute_rollback;
+use wright::part::store::LocalPartStore;
+
+use super::common::{TestPart, deliver};
+
+async fn installed_hash(db: &InstalledDb, name: &str) -> Option<String> {
+    db.get_part(name).await.unwrap().and_then(|p| p.part_hash)
+}
+
+#[tokio::test]
+async fn test_rollback_restores_previous_archives_and_removes_new_parts() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let store_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.path().to_path_buf();
+    config.general.store_dir = store_dir.path().to_path_buf();
+
+    let v2_dir = tempfile::tempdir().unwrap();
+    let alpha_v1 = TestPart::new("alpha").build(parts_dir.path());
+    let alpha_v2 = TestPart::new("alpha").version("2.0.0").build(v2_dir.path());
+    let beta = TestPart::new("beta").build(v2_dir.path());
+
+    deliver(&db, root.path(), std::slice::from_ref(&alpha_v1)).await;
+    let v1_hash = installed_hash(&db, "alpha").await;
+    let upgrade_tx = deliver(&db, root.path(), &[alpha_v2, beta]).await;
+    assert_ne!(installed_hash(&db, "alpha").await, v1_hash);
+    assert!(db.get_part("beta").await.unwrap().is_some());
+
+    execute_rollback(
+        &db,
+        &config,
+        root.path(),
+        &LocalPartStore::new(),
+        upgrade_tx,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(installed_hash(&db, "alpha").await, v1_hash);
+    assert!(db.get_part("beta").await.unwrap().is_none());
+    assert!(!root.path().join("usr/bin/beta").exists());
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("usr/bin/alpha")).unwrap(),
+        "#!/bin/sh\necho alpha 1.0.0\n"
+    );
+
+    let reverted = db.get_delivery(upgrade_tx).await.unwrap().unwrap();
+    assert_eq!(reverted.status, DeliveryStatus::Completed);
+    let rollback_tx = db
+        .find_reverting_delivery(upgrade_tx)
+        .await
+        .unwrap()
+        .unwrap();
+    let rollback = db.get_delivery(rollback_tx).await.unwrap().unwrap();
+    assert_eq!(rollback.status, DeliveryStatus::Completed);
+    assert_eq!(rollback.reverts, Some(upgrade_tx));
+    let again = execute_rollback(
+        &db,
+        &config,
+        root.path(),
+        &LocalPartStore::new(),
+        upgrade_tx,
+        false,
+        false,
+    )
+    .await;
+    let err = again.unwrap_err().to_string();
+    assert!(err.contains("already rolled back"), "{err}");
+}
+
+#[tokio::test]
+async fn test_rollback_refuses_when_later_delivery_changed_parts() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.path().to_path_buf();
+    config.general.store_dir = parts_dir.path().join("store");
+
+    let v1 = TestPart::new("alpha").build(parts_dir.path());
+    let v2_dir = tempfile::tempdir().unwrap();
+    let v2 = TestPart::new("alpha").version("2.0.0").build(v2_dir.path());
+    let v3_dir = tempfile::tempdir().unwrap();
+    let v3 = TestPart::new("alpha").version("3.0.0").build(v3_dir.path());
+
+    deliver(&db, root.path(), &[v1]).await;
+    let upgrade_tx = deliver(&db, root.path(), &[v2]).await;
+    deliver(&db, root.path(), &[v3]).await;
+
+    let err = execute_rollback(
+        &db,
+        &config,
+        root.path(),
+        &LocalPartStore::new(),
+        upgrade_tx,
+        false,
+        false,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("changed since delivery"));
+}
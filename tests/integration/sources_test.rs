@@ -0,0 +1,62 @@
+use wright::config::GlobalConfig;
+use wright::operations::sources::{execute_clean_sources, execute_verify_sources};
+use wright::util::checksum::sha256_file;
+
+#[tokio::test]
+async fn test_sources_verify_and_clean_orphans() {
+    let plans_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.plans_dir = plans_dir.path().to_path_buf();
+    config.general.source_dir = source_dir.path().to_path_buf();
+
+    let tarball = source_dir.path().join("zlib.tar.gz");
+    std::fs::write(&tarball, b"zlib tarball").unwrap();
+    let orphan = source_dir.path().join("old-old-0.1.tar.gz");
+    std::fs::write(&orphan, b"stale").unwrap();
+    let mirror = source_dir.path().join("git/gone-0123abcd");
+    std::fs::create_dir_all(&mirror).unwrap();
+    std::fs::write(mirror.join("HEAD"), b"ref: refs/heads/main\n").unwrap();
+
+    std::fs::create_dir_all(plans_dir.path().join("zlib")).unwrap();
+    std::fs::write(
+        plans_dir.path().join("zlib/plan.toml"),
+        format!(
+            r#"
+name = "zlib"
+version = "1.3"
+release = 1
+description = "zlib"
+license = "Zlib"
+arch = "x86_64"
+
+[[sources]]
+type = "http"
+url = "https://example.invalid/zlib-1.3.tar.gz"
+sha256 = "{}"
+as = "zlib.tar.gz"
+"#,
+            sha256_file(&tarball).unwrap()
+        ),
+    )
+    .unwrap();
+
+    execute_verify_sources(&config, &[]).await.unwrap();
+
+    execute_clean_sources(&config, false).await.unwrap();
+    assert!(orphan.exists(), "dry run must not delete");
+
+    execute_clean_sources(&config, true).await.unwrap();
+    assert!(tarball.exists(), "referenced download must be kept");
+    assert!(!orphan.exists(), "orphaned download must be removed");
+    assert!(!mirror.exists(), "orphaned git mirror must be removed");
+
+    std::fs::write(&tarball, b"tampered").unwrap();
+    let err = execute_verify_sources(&config, &["zlib".to_string()])
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("zlib.tar.gz"),
+        "unexpected error: {err}"
+    );
+}
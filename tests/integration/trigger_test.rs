@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use wright::database::InstalledDb;
+use wright::transaction;
+
+use super::common::{TestPart, deploy, session};
+
+fn build_archive(name: &str, files: &[&str], out_dir: &Path) -> PathBuf {
+    files
+        .iter()
+        .fold(TestPart::new(name), |part, file| part.file(file, name))
+        .build(out_dir)
+}
+
+#[tokio::test]
+async fn test_delivery_records_touched_paths_until_triggers_run() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let zlib = build_archive("zlib", &["usr/lib/libz.so.1"], parts_dir.path());
+    let zcat = build_archive("zcat", &["usr/bin/zcat"], parts_dir.path());
+
+    let tx_id = wright::delivery::begin_delivery(&db, "merge")
+        .await
+        .unwrap();
+    deploy(&db, &[zlib, zcat], root.path(), Some(tx_id))
+        .await
+        .unwrap();
+    transaction::remove_part(&db, "zcat", root.path(), false, session(Some(tx_id)))
+        .await
+        .unwrap();
+
+    let touched = db.get_delivery_paths(tx_id).await.unwrap();
+    for expected in [
+        ("install", "/usr/lib/libz.so.1"),
+        ("install", "/usr/bin/zcat"),
+        ("remove", "/usr/bin/zcat"),
+    ] {
+        assert!(
+            touched
+                .iter()
+                .any(|(action, path)| (action.as_str(), path.as_str()) == expected),
+            "{expected:?} not in {touched:?}"
+        );
+    }
+    assert!(
+        touched.iter().all(|(_, path)| path != "/usr/lib"),
+        "directories are not recorded: {touched:?}"
+    );
+
+    let triggers = root.path().join(transaction::triggers::TRIGGERS_DIR);
+    std::fs::create_dir_all(&triggers).unwrap();
+    std::fs::write(
+        triggers.join("ldconfig.toml"),
+        "[trigger]\npaths = [\"/usr/lib/*.so*\"]\nscript = \"ldconfig\"\n",
+    )
+    .unwrap();
+    std::fs::write(triggers.join("broken.toml"), "[trigger]\nscript = 1\n").unwrap();
+    let loaded = transaction::triggers::load_triggers(&triggers).unwrap();
+    assert_eq!(
+        loaded.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+        vec!["ldconfig"]
+    );
+
+    transaction::run_triggers(&db, tx_id, root.path())
+        .await
+        .unwrap();
+    assert!(db.get_delivery_paths(tx_id).await.unwrap().is_empty());
+    wright::delivery::complete_delivery(&db, tx_id)
+        .await
+        .unwrap();
+    assert_eq!(db.get_ops_for_delivery(tx_id).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_recovery_drops_touched_paths_of_interrupted_delivery() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let zlib = build_archive("zlib", &["usr/lib/libz.so.1"], parts_dir.path());
+
+    let tx_id = wright::delivery::begin_delivery(&db, "install")
+        .await
+        .unwrap();
+    wright::delivery::begin_applying(&db, tx_id).await.unwrap();
+    deploy(&db, &[zlib], root.path(), Some(tx_id))
+        .await
+        .unwrap();
+    assert!(!db.get_delivery_paths(tx_id).await.unwrap().is_empty());
+
+    assert!(wright::delivery::recover_if_needed(&db).await.unwrap());
+    assert!(db.get_active_delivery().await.unwrap().is_none());
+    assert!(db.get_delivery_paths(tx_id).await.unwrap().is_empty());
+}
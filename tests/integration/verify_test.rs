@@ -0,0 +1,180 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use wright::config::GlobalConfig;
+use wright::database::{DeliveryStatus, HistoryAction, HistoryStatus, InstalledDb};
+use wright::operations::repair::execute_repair;
+use wright::operations::verify::execute_verify;
+use wright::transaction::{self, AuditProblem};
+use wright::util::output::OutputFormat;
+
+use super::common::{TestPart, deploy};
+
+fn build_archive(out_dir: &Path) -> PathBuf {
+    TestPart::new("tool")
+        .executable("usr/bin/tool", "#!/bin/sh\necho tool\n")
+        .file("usr/share/tool/data.txt", "payload\n")
+        .symlink("usr/share/tool/current", "data.txt")
+        .build(out_dir)
+}
+
+fn test_config(parts_dir: &Path) -> GlobalConfig {
+    let mut config = GlobalConfig::default();
+    config.general.parts_dir = parts_dir.to_path_buf();
+    config.general.store_dir = parts_dir.join("store");
+    config
+}
+
+#[tokio::test]
+async fn test_verify_reports_and_restores_damaged_files() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let config = test_config(parts_dir.path());
+    let archive = build_archive(parts_dir.path());
+    deploy(&db, std::slice::from_ref(&archive), root.path(), None)
+        .await
+        .unwrap();
+
+    let part = db.get_part("tool").await.unwrap().unwrap();
+    assert!(
+        transaction::audit_part(&db, &part, root.path())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let share = root.path().join("usr/share/tool");
+    let bin = root.path().join("usr/bin/tool");
+    std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o777)).unwrap();
+    std::fs::write(share.join("data.txt"), "garbage\n").unwrap();
+    std::fs::remove_file(share.join("current")).unwrap();
+    std::os::unix::fs::symlink("elsewhere", share.join("current")).unwrap();
+    std::fs::write(share.join("stray"), "").unwrap();
+
+    let findings = transaction::audit_part(&db, &part, root.path())
+        .await
+        .unwrap();
+    let problems: Vec<(&str, AuditProblem)> = findings
+        .iter()
+        .map(|f| (f.path.as_str(), f.problem))
+        .collect();
+    assert_eq!(
+        problems,
+        vec![
+            ("/usr/bin/tool", AuditProblem::Mode),
+            ("/usr/share/tool/current", AuditProblem::Target),
+            ("/usr/share/tool/data.txt", AuditProblem::Content),
+            ("/usr/share/tool/stray", AuditProblem::Extra),
+        ]
+    );
+
+    // An audit alone reports damage without touching anything.
+    let names = vec!["tool".to_string()];
+    assert!(
+        execute_verify(&db, &config, root.path(), &names, false, OutputFormat::Json)
+            .await
+            .is_err()
+    );
+
+    // Extra files are reported but are not damage.
+    execute_verify(&db, &config, root.path(), &names, true, OutputFormat::Json)
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(share.join("data.txt")).unwrap(),
+        "payload\n"
+    );
+    assert_eq!(
+        std::fs::read_link(share.join("current")).unwrap(),
+        Path::new("data.txt")
+    );
+    assert_eq!(
+        std::fs::metadata(&bin).unwrap().permissions().mode() & 0o7777,
+        0o755
+    );
+    assert!(share.join("stray").exists());
+}
+
+#[tokio::test]
+async fn test_repair_rewrites_only_damaged_files() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let config = test_config(parts_dir.path());
+    let archive = build_archive(parts_dir.path());
+    deploy(&db, std::slice::from_ref(&archive), root.path(), None)
+        .await
+        .unwrap();
+
+    let bin = root.path().join("usr/bin/tool");
+    let data = root.path().join("usr/share/tool/data.txt");
+    std::fs::remove_file(&bin).unwrap();
+    let untouched = std::fs::metadata(&data).unwrap().modified().unwrap();
+
+    execute_repair(&db, &config, root.path(), &[], false)
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&bin).unwrap(),
+        "#!/bin/sh\necho tool\n"
+    );
+    assert_eq!(
+        std::fs::metadata(&data).unwrap().modified().unwrap(),
+        untouched
+    );
+
+    let history = db.get_history(Some("tool")).await.unwrap();
+    assert!(
+        history
+            .iter()
+            .any(|h| h.action == HistoryAction::Repair && h.status == HistoryStatus::Completed)
+    );
+}
+
+#[tokio::test]
+async fn test_failed_repair_rolls_back_part() {
+    let db = InstalledDb::open_in_memory().await.unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let parts_dir = tempfile::tempdir().unwrap();
+    let config = test_config(parts_dir.path());
+    let archive = build_archive(parts_dir.path());
+    // Sorts before `tool`, so it is repaired before the failure.
+    let awk = TestPart::new("awk")
+        .executable(
+            "usr/bin/awk",
+            "#!/bin/sh
+echo awk
+",
+        )
+        .build(parts_dir.path());
+    deploy(&db, &[archive, awk], root.path(), None)
+        .await
+        .unwrap();
+    let awk_bin = root.path().join("usr/bin/awk");
+    std::fs::remove_file(&awk_bin).unwrap();
+
+    // `current` sorts first and is repaired; the directory squatting on
+    // `data.txt` then makes the repair fail.
+    let share = root.path().join("usr/share/tool");
+    std::fs::remove_file(share.join("current")).unwrap();
+    std::os::unix::fs::symlink("elsewhere", share.join("current")).unwrap();
+    std::fs::remove_file(share.join("data.txt")).unwrap();
+    std::fs::create_dir(share.join("data.txt")).unwrap();
+
+    assert!(
+        execute_repair(&db, &config, root.path(), &[], false)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        std::fs::read_link(share.join("current")).unwrap(),
+        Path::new("elsewhere")
+    );
+    assert_eq!(
+        std::fs::read_to_string(&awk_bin).unwrap(),
+        "#!/bin/sh\necho awk\n"
+    );
+    let deliveries = db.list_deliveries(1).await.unwrap();
+    assert_eq!(deliveries[0].status, DeliveryStatus::Completed);
+}
@@ -0,0 +1,51 @@
+use wright::config::GlobalConfig;
+use wright::operations::workshop::execute_clean_workshop;
+
+#[tokio::test]
+async fn test_workshop_clean_removes_stale_roots() {
+    let plans_dir = tempfile::tempdir().unwrap();
+    let forge_dir = tempfile::tempdir().unwrap();
+    let mut config = GlobalConfig::default();
+    config.general.plans_dir = plans_dir.path().to_path_buf();
+    config.build.forge_dir = forge_dir.path().to_path_buf();
+
+    std::fs::create_dir_all(plans_dir.path().join("zlib")).unwrap();
+    std::fs::write(
+        plans_dir.path().join("zlib/plan.toml"),
+        r#"
+name = "zlib"
+version = "1.3"
+release = 1
+description = "zlib"
+license = "Zlib"
+arch = "x86_64"
+"#,
+    )
+    .unwrap();
+
+    let current = forge_dir.path().join("zlib-1.3");
+    let outdated = forge_dir.path().join("zlib-1.2");
+    let orphan = forge_dir.path().join("gone-1.0");
+    let repro = forge_dir.path().join("zlib-1.2.repro-18f0c3a1");
+    for root in [&current, &outdated, &orphan, &repro] {
+        std::fs::create_dir_all(root.join("logs")).unwrap();
+        std::fs::write(root.join("logs/build.log"), b"log").unwrap();
+    }
+    std::fs::create_dir_all(current.join(".wright-isolation/task-1")).unwrap();
+
+    execute_clean_workshop(&config, None, false).await.unwrap();
+    assert!(orphan.exists(), "dry run must not delete");
+
+    execute_clean_workshop(&config, None, true).await.unwrap();
+    assert!(current.join("logs/build.log").exists());
+    assert!(
+        !current.join(".wright-isolation").exists(),
+        "stale isolation scratch must be cleared"
+    );
+    assert!(!outdated.exists(), "root of an old version must be removed");
+    assert!(!orphan.exists(), "root of a removed plan must be removed");
+    assert!(
+        repro.exists(),
+        "kept reproducibility roots must be left alone"
+    );
+}